@@ -0,0 +1,194 @@
+//! Benchmarks of the inventory's throughput under a mixed workload of reads
+//! and writes, comparing the designs its storage could be locked with: a
+//! single mutex, a single read-write lock, and read-write locked storage split
+//! across shards as the inventory does. Every design reads and writes the
+//! same in-memory storage in the same way, so only the locking differs.
+//!
+//! Run them with `cargo bench --bench storage`, criterion reports the
+//! throughput of each design as operations per second ("elem/s") for 1, 8
+//! and 64 concurrent tasks.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio::sync::{Mutex, RwLock};
+
+use demo::storage::{MemoryStorage, Storage};
+use demo::store::{Item, ItemIdentifier, ItemStock};
+
+// the number of items in the inventory when the workload starts.
+const ITEMS: usize = 10_000;
+
+// the number of operations each task performs per iteration.
+const OPERATIONS_PER_TASK: usize = 1_000;
+
+// the share of operations which change an item rather than read it.
+const WRITE_RATIO: f64 = 0.1;
+
+// the numbers of tasks the workload is spread across.
+const CONCURRENCY: [usize; 3] = [1, 8, 64];
+
+// the number of shards the sharded design is split across.
+const SHARDS: usize = 16;
+
+// -----------------------------------------------------------------------------
+// Storage Designs
+// -----------------------------------------------------------------------------
+
+// a candidate design for storing and locking the items. Each wraps the same
+// storage in its locks, and reads and writes it the way the inventory's
+// handlers do: an item is read under a read lock where there is one, and
+// read then replaced under a write lock.
+#[derive(Clone)]
+enum Design {
+    Mutex(Arc<Mutex<MemoryStorage>>),
+    Sharded(Arc<Vec<RwLock<MemoryStorage>>>),
+}
+
+impl Design {
+    // every design, with the items the workload operates on loaded.
+    async fn all() -> Vec<(String, Design)> {
+        vec![
+            (
+                "mutex".into(),
+                Design::Mutex(Arc::new(Mutex::new(storage(0, 1).await))),
+            ),
+            ("rwlock".into(), Design::sharded(1).await),
+            (
+                format!("rwlock/{}-shards", SHARDS),
+                Design::sharded(SHARDS).await,
+            ),
+        ]
+    }
+
+    async fn sharded(shards: usize) -> Design {
+        let mut locked = Vec::with_capacity(shards);
+        for shard in 0..shards {
+            locked.push(RwLock::new(storage(shard, shards).await));
+        }
+        Design::Sharded(Arc::new(locked))
+    }
+
+    async fn read(&self, sku: String) {
+        match self {
+            Design::Mutex(storage) => {
+                storage.lock().await.get(&sku).await.unwrap();
+            }
+            Design::Sharded(shards) => {
+                let shard = &shards[shard_index(shards.len(), &sku)];
+                shard.read().await.get(&sku).await.unwrap();
+            }
+        }
+    }
+
+    // sets the quantity of an item, which unlike changing it can't fail no
+    // matter how many times the workload is repeated.
+    async fn write(&self, sku: String, quantity: u32) {
+        match self {
+            Design::Mutex(storage) => {
+                let mut storage = storage.lock().await;
+                let item = set_quantity(storage.get(&sku).await.unwrap(), quantity);
+                storage.update(&sku, item).await.unwrap();
+            }
+            Design::Sharded(shards) => {
+                let shard = &shards[shard_index(shards.len(), &sku)];
+                let mut storage = shard.write().await;
+                let item = set_quantity(storage.get(&sku).await.unwrap(), quantity);
+                storage.update(&sku, item).await.unwrap();
+            }
+        }
+    }
+}
+
+// the shard a SKU's item is kept in, hashed as the inventory hashes it.
+fn shard_index(shards: usize, sku: &str) -> usize {
+    let mut hasher = DefaultHasher::new();
+    sku.hash(&mut hasher);
+    hasher.finish() as usize % shards
+}
+
+// storage holding the items the workload operates on which hash to the
+// given shard.
+async fn storage(shard: usize, shards: usize) -> MemoryStorage {
+    let mut storage = MemoryStorage::default();
+    for index in 0..ITEMS {
+        let sku = sku(index);
+        if shard_index(shards, &sku) == shard {
+            storage.insert(sku, item(index)).await.unwrap();
+        }
+    }
+    storage
+}
+
+fn sku(index: usize) -> String {
+    format!("SKU-{:05}", index)
+}
+
+fn item(index: usize) -> Item {
+    Item {
+        identifier: Some(ItemIdentifier { sku: sku(index) }),
+        stock: Some(ItemStock {
+            price: 1.79,
+            quantity: 1_000,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn set_quantity(item: Option<Item>, quantity: u32) -> Item {
+    let mut item = item.unwrap();
+    item.stock.as_mut().unwrap().quantity = quantity;
+    item
+}
+
+// -----------------------------------------------------------------------------
+// Workload
+// -----------------------------------------------------------------------------
+
+// runs the mixed workload against a design across the given number of tasks,
+// each reading and writing items chosen at random.
+async fn run(design: &Design, tasks: usize) {
+    let handles: Vec<_> = (0..tasks)
+        .map(|task| {
+            let design = design.clone();
+            tokio::spawn(async move {
+                let mut rng = StdRng::seed_from_u64(task as u64);
+                for _ in 0..OPERATIONS_PER_TASK {
+                    let sku = sku(rng.gen_range(0..ITEMS));
+                    if rng.gen_bool(WRITE_RATIO) {
+                        design.write(sku, rng.gen_range(0..1_000)).await;
+                    } else {
+                        design.read(sku).await;
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap();
+    }
+}
+
+fn mixed_workload(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let designs = runtime.block_on(Design::all());
+
+    let mut group = c.benchmark_group("mixed_workload");
+    for tasks in CONCURRENCY {
+        group.throughput(Throughput::Elements((tasks * OPERATIONS_PER_TASK) as u64));
+        for (name, design) in &designs {
+            group.bench_with_input(BenchmarkId::new(name, tasks), &tasks, |b, &tasks| {
+                b.to_async(&runtime).iter(|| run(design, tasks))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, mixed_workload);
+criterion_main!(benches);
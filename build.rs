@@ -1,17 +1,125 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the messages which are read or written as JSON, along with those nested in
+// them.
+const JSON_MESSAGES: &[&str] = &[
+    ".store.AuditEntry",
+    ".store.Availability",
+    ".store.BatchGetResponse",
+    ".store.BatchRemoveResponse",
+    ".store.ConvertedPrice",
+    ".store.CountResponse",
+    ".store.InventoryChangeResponse",
+    ".store.InventoryStatsResponse",
+    ".store.InventoryUpdateResponse",
+    ".store.Item",
+    ".store.ItemEvent",
+    ".store.ItemIdentifier",
+    ".store.ItemImage",
+    ".store.ItemInformation",
+    ".store.ItemPhysical",
+    ".store.ItemStock",
+    ".store.PriceChangeRequest",
+    ".store.ServerInfo",
+];
+
+// the 64 bit integers of those messages, which are written as strings.
+const JSON_UINT64_FIELDS: &[&str] = &[
+    ".store.AuditEntry.timestamp",
+    ".store.CountResponse.count",
+    ".store.InventoryStatsResponse.items",
+    ".store.InventoryStatsResponse.units",
+    ".store.Item.last_updated",
+    ".store.Item.version",
+    ".store.ItemImage.size",
+    ".store.ServerInfo.built_at",
+    ".store.ServerInfo.uptime_ms",
+];
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let proto_file = "./proto/store.proto";
+    let proto_files = ["./proto/store.proto"];
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
 
-    tonic_build::configure()
+    let mut builder = tonic_build::configure();
+    // messages which are read or written as JSON (by the CLI, the REST
+    // gateway, the sidecar and the audit log) follow the proto3 JSON mapping:
+    // fields are named in lowerCamelCase, those which hold their default
+    // value are left out, and 64 bit integers are strings.
+    for message in JSON_MESSAGES {
+        builder = builder
+            .type_attribute(message, "#[derive(serde::Serialize, serde::Deserialize)]")
+            .type_attribute(
+                message,
+                r#"#[serde(default, deny_unknown_fields, rename_all = "camelCase")]"#,
+            )
+            .field_attribute(
+                message,
+                r#"#[serde(skip_serializing_if = "crate::json::is_default")]"#,
+            );
+    }
+    for field in JSON_UINT64_FIELDS {
+        builder = builder.field_attribute(field, r#"#[serde(with = "crate::json::uint64")]"#);
+    }
+    builder = builder
+        .field_attribute(
+            ".store.Item.expires_at",
+            r#"#[serde(with = "crate::json::optional_uint64")]"#,
+        )
+        // maps are written in key order, so that the output is stable
+        .field_attribute(
+            ".store.Item.attributes",
+            r#"#[serde(serialize_with = "crate::json::sorted")]"#,
+        );
+
+    builder
         .protoc_arg("--experimental_allow_proto3_optional") // for older systems
         .build_client(true)
         .build_server(true)
+        // batch operations hold whole items, which are far larger than the
+        // SKUs the other operations hold
+        .type_attribute(
+            "store.Operation.operation",
+            "#[allow(clippy::large_enum_variant)]",
+        )
+        // as do watch events, compared to heartbeats which hold nothing
+        .type_attribute(
+            "store.WatchEvent.event",
+            "#[allow(clippy::large_enum_variant)]",
+        )
         .file_descriptor_set_path(out_dir.join("store_descriptor.bin"))
         .out_dir("./src")
-        .compile(&[proto_file], &["proto"])?;
+        .compile(&proto_files, &["proto"])?;
+
+    // the build is identified by when it was built and, when it's built from
+    // a git checkout, the commit it was built from. The script is rerun
+    // whenever another commit is checked out.
+    let built_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    println!("cargo:rustc-env=STORE_BUILT_AT={}", built_at);
+    if let Some(sha) = git_sha() {
+        println!("cargo:rustc-env=STORE_GIT_SHA={}", sha);
+    }
+    for path in [".git/HEAD", ".git/refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
 
     Ok(())
 }
+
+// the commit checked out, if the build is of a git checkout and git is
+// installed.
+fn git_sha() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    let sha = String::from_utf8(output.stdout).ok()?;
+    match output.status.success() && !sha.trim().is_empty() {
+        true => Some(sha.trim().to_string()),
+        false => None,
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::store::{AuditEntry, Item};
+
+// -----------------------------------------------------------------------------
+// Audit Log
+// -----------------------------------------------------------------------------
+
+/// The number of recent audit entries kept for GetAuditLog by default.
+pub const DEFAULT_AUDIT_CAPACITY: usize = 1024;
+
+/// An append-only record of the changes made to items. The most recent
+/// entries are kept in memory for GetAuditLog, and every entry can also be
+/// appended to a file as a line of JSON, which is never rewritten.
+///
+/// Entries are recorded after the shard lock for the item is released, so
+/// changes to different items made at the same time may be recorded in a
+/// different order than their timestamps.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    capacity: usize,
+    recent: Arc<Mutex<VecDeque<AuditEntry>>>,
+    file: Option<Arc<Mutex<File>>>,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        AuditLog::new(DEFAULT_AUDIT_CAPACITY)
+    }
+}
+
+impl AuditLog {
+    /// Creates an audit log which keeps the given number of recent entries.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        AuditLog {
+            capacity,
+            recent: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            file: None,
+        }
+    }
+
+    /// Also appends every entry to the file at the path, creating it if it
+    /// doesn't exist. Entries already in the file are kept.
+    pub fn with_file(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.file = Some(Arc::new(Mutex::new(file)));
+        Ok(self)
+    }
+
+    /// Records a change to the item with the SKU, where before is unset for
+    /// additions and after is unset for removals. Failing to append the entry
+    /// to the file is logged, rather than failing the change which was
+    /// already made.
+    pub fn record(
+        &self,
+        timestamp: u64,
+        operation: &str,
+        sku: &str,
+        before: Option<Item>,
+        after: Option<Item>,
+    ) {
+        let entry = AuditEntry {
+            timestamp,
+            operation: operation.into(),
+            sku: sku.into(),
+            before,
+            after,
+        };
+
+        if let Some(file) = &self.file {
+            let mut line = serde_json::to_vec(&entry).expect("audit entries encode as JSON");
+            line.push(b'\n');
+            let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Err(err) = file.write_all(&line) {
+                tracing::error!("failed to append to the audit log: {}", err);
+            }
+        }
+
+        let mut recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        if recent.len() == self.capacity {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+
+    /// Retrieves up to the given number of the most recent entries, oldest
+    /// first, or all of those kept when the limit is 0.
+    pub fn recent(&self, limit: usize) -> Vec<AuditEntry> {
+        let recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        let skip = match limit {
+            0 => 0,
+            limit => recent.len().saturating_sub(limit),
+        };
+        recent.iter().skip(skip).cloned().collect()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::audit::AuditLog;
+    use crate::store::{Item, ItemIdentifier};
+
+    fn item(sku: &str) -> Item {
+        Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn audit_log() {
+        let log = AuditLog::new(2);
+        log.record(1, "Add", "FIRST", None, Some(item("FIRST")));
+        log.record(2, "Add", "SECOND", None, Some(item("SECOND")));
+        log.record(3, "Remove", "FIRST", Some(item("FIRST")), None);
+
+        // only the most recent entries are kept, oldest first
+        let skus: Vec<String> = log.recent(0).into_iter().map(|entry| entry.sku).collect();
+        assert_eq!(skus, vec!["SECOND", "FIRST"]);
+        let latest = log.recent(1);
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].operation, "Remove");
+        assert_eq!(latest[0].before, Some(item("FIRST")));
+        assert_eq!(latest[0].after, None);
+
+        // every entry is appended to the file, including those no longer kept
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let log = AuditLog::new(1).with_file(file.path()).unwrap();
+        log.record(1, "Add", "FIRST", None, Some(item("FIRST")));
+        log.record(2, "Remove", "FIRST", Some(item("FIRST")), None);
+        let lines = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(
+            lines,
+            concat!(
+                r#"{"timestamp":"1","operation":"Add","sku":"FIRST","#,
+                r#""after":{"identifier":{"sku":"FIRST"}}}"#,
+                "\n",
+                r#"{"timestamp":"2","operation":"Remove","sku":"FIRST","#,
+                r#""before":{"identifier":{"sku":"FIRST"}}}"#,
+                "\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::Status;
+use tower::{Layer, Service};
+
+use crate::error::InventoryError;
+use crate::routing::{is_read_only, INVENTORY_PREFIX};
+use crate::server::{constant_time_eq, AUTHORIZATION_HEADER};
+
+// -----------------------------------------------------------------------------
+// API Key Layer
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+struct Credentials {
+    api_key: String,
+    admin_token: Option<String>,
+    anonymous_reads: bool,
+}
+
+impl Credentials {
+    // reports whether a request for the method at the given path, carrying
+    // the given authorization metadata, may proceed.
+    fn allows(&self, path: &str, authorization: Option<&str>) -> bool {
+        // other services (e.g. reflection and health checks) stay open, so
+        // that probes and tooling work without credentials
+        if !path.starts_with(INVENTORY_PREFIX) {
+            return true;
+        }
+        if self.anonymous_reads && is_read_only(path) {
+            return true;
+        }
+
+        let token = match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+            Some(token) => token.as_bytes(),
+            None => return false,
+        };
+        let admin_token = self.admin_token.as_deref().unwrap_or_default();
+        constant_time_eq(token, self.api_key.as_bytes())
+            || (!admin_token.is_empty() && constant_time_eq(token, admin_token.as_bytes()))
+    }
+}
+
+/// A layer which requires Inventory requests to carry the API key as a bearer
+/// token in their authorization metadata (e.g. "Bearer <key>"), and rejects
+/// those which don't as unauthenticated. This is a layer rather than a tonic
+/// interceptor, as interceptors can't tell which method is being called.
+#[derive(Debug, Clone)]
+pub struct ApiKeyLayer {
+    credentials: Arc<Credentials>,
+}
+
+impl ApiKeyLayer {
+    /// Creates a layer which requires the given API key.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        ApiKeyLayer {
+            credentials: Arc::new(Credentials {
+                api_key: api_key.into(),
+                admin_token: None,
+                anonymous_reads: false,
+            }),
+        }
+    }
+
+    /// Sets the admin token, which is accepted in place of the API key so
+    /// that admin requests only need to carry the admin token.
+    pub fn with_admin_token(mut self, admin_token: Option<String>) -> Self {
+        Arc::make_mut(&mut self.credentials).admin_token = admin_token;
+        self
+    }
+
+    /// Sets whether read-only methods (e.g. Get and Watch) are exempt, so that
+    /// only changes to the inventory require the API key.
+    pub fn with_anonymous_reads(mut self, anonymous_reads: bool) -> Self {
+        Arc::make_mut(&mut self.credentials).anonymous_reads = anonymous_reads;
+        self
+    }
+}
+
+impl<S> Layer<S> for ApiKeyLayer {
+    type Service = ApiKey<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ApiKey {
+            inner,
+            credentials: self.credentials.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiKey<S> {
+    inner: S,
+    credentials: Arc<Credentials>,
+}
+
+impl<S, B> Service<http::Request<B>> for ApiKey<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let authorization = request
+            .headers()
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|value| value.to_str().ok());
+        if !self.credentials.allows(request.uri().path(), authorization) {
+            let response = Status::from(InventoryError::BadApiKey).to_http();
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::ApiKeyLayer;
+
+    #[test]
+    fn credentials() {
+        let layer = ApiKeyLayer::new("key").with_admin_token(Some("admin".into()));
+        let credentials = &layer.credentials;
+
+        // Inventory methods require the API key, or the admin token
+        let add = "/store.Inventory/Add";
+        assert!(credentials.allows(add, Some("Bearer key")));
+        assert!(credentials.allows(add, Some("Bearer admin")));
+        assert!(!credentials.allows(add, Some("Bearer other")));
+        assert!(!credentials.allows(add, Some("key")));
+        assert!(!credentials.allows(add, None));
+
+        // reads require the API key unless anonymous reads are allowed
+        let get = "/store.Inventory/Get";
+        assert!(!credentials.allows(get, None));
+        let layer = layer.with_anonymous_reads(true);
+        assert!(layer.credentials.allows(get, None));
+        assert!(!layer.credentials.allows(add, None));
+
+        // other services never require it
+        let health = "/grpc.health.v1.Health/Check";
+        assert!(layer.credentials.allows(health, None));
+
+        // an empty admin token doesn't stand in for the API key
+        let layer = ApiKeyLayer::new("key").with_admin_token(Some(String::new()));
+        assert!(!layer.credentials.allows(add, Some("Bearer ")));
+    }
+}
@@ -1,11 +1,45 @@
-pub mod store;
-
 use clap::Parser;
+use futures::future::BoxFuture;
 use futures::StreamExt;
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type as FieldType};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+};
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::{TcpStream, UnixStream};
+use tonic::body::BoxBody;
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::InterceptedService;
+use tonic::metadata::AsciiMetadataValue;
+use tonic::service::Interceptor;
+use tonic::transport::{Body, Endpoint};
+use tonic::Status;
+use tonic_reflection::proto::server_reflection_client::ServerReflectionClient;
+use tonic_reflection::proto::server_reflection_request::MessageRequest;
+use tonic_reflection::proto::server_reflection_response::MessageResponse;
+use tonic_reflection::proto::ServerReflectionRequest;
+use tower::Service;
 
+use demo::pool::{Backoff, ClientPool, PooledChannel};
+use demo::{json, store};
 use store::inventory_client::InventoryClient;
+use store::operation::Operation as BatchOperation;
+use store::sample_request::Weighting;
+use store::watch_event::Event as WatchedEvent;
 use store::{
-    Item, ItemIdentifier, ItemInformation, ItemStock, PriceChangeRequest, QuantityChangeRequest,
+    AccessStatsRequest, ApplyBatchRequest, AttributeFilter, AuditLogRequest, BatchGetRequest,
+    BatchRemoveRequest, ConsistencyRequest, CountRequest, GetRequest, InformationChangeRequest,
+    InventoryStatsRequest, Item, ItemIdentifier, ItemInformation, ItemPhysical, ItemStock,
+    LatencySummaryRequest, ListRequest, LowStockRequest, MetricsRequest, Operation,
+    PriceBucketsRequest, PriceChangeRequest, QuantityChangeRequest, ReadOnlyRequest, RemoveRequest,
+    RenameSkuRequest, ReorderThresholdRequest, ReservationRequest, SampleRequest, SearchRequest,
+    ServerInfoRequest, SetQuantityRequest, SwapStockRequest, TagFilter, TailLogsRequest,
+    WatchAllRequest, WatchEvent, WatchRequest, WeightRangeRequest,
 };
 
 // -----------------------------------------------------------------------------
@@ -14,18 +48,385 @@ use store::{
 
 #[derive(Debug, Parser)]
 struct Options {
+    /// the URL of the server to connect to, using the http or https scheme,
+    /// or unix:// followed by the path of the Unix domain socket it's served
+    /// on, e.g. unix:///run/store.sock
+    #[clap(
+        default_value = "http://127.0.0.1:9001",
+        global = true,
+        long,
+        value_parser = parse_endpoint
+    )]
+    endpoint: Address,
+    /// the API key to present to servers which require one
+    #[clap(global = true, long)]
+    api_key: Option<String>,
+    /// how to print responses: text to read, or json for scripts, which
+    /// prints each response as a JSON object on a line of its own
+    #[clap(default_value = "text", global = true, long, value_enum)]
+    format: Format,
+    /// how many seconds to wait for the server to respond to each request
+    /// before giving up, or 0 to wait indefinitely; watches are only bounded
+    /// until the server starts streaming
+    #[clap(default_value = "30", global = true, long)]
+    timeout_secs: u64,
+    /// how many times to retry connecting to the server when it can't be
+    /// reached, e.g. while it's still starting
+    #[clap(default_value = "0", global = true, long)]
+    connect_retries: u32,
+    /// how long to wait before the first retry in milliseconds, the wait
+    /// doubles with each retry up to 5 seconds, and each wait is randomly
+    /// shortened by up to half
+    #[clap(default_value = "100", global = true, long)]
+    connect_backoff_ms: u64,
+    /// the id to identify requests by in the server's logs, the server
+    /// generates one when it isn't given. The id the server used is printed
+    /// to stderr once the command is done.
+    #[clap(global = true, long)]
+    request_id: Option<String>,
+    /// print the request a command which changes the inventory (add, remove,
+    /// update-* and set-*) would send, and what it would do, without sending
+    /// it. Only add's dry runs contact the server, to validate the item
+    /// without adding it, the others can't tell whether the request would
+    /// succeed.
+    #[clap(global = true, long)]
+    dry_run: bool,
+    /// compress requests, and have the server compress its responses, which
+    /// helps with large responses (e.g. list) over slow links. gzip is the
+    /// only compression the server supports.
+    #[clap(default_value = "none", global = true, long, value_enum)]
+    compress: Compression,
     #[clap(subcommand)]
     command: Command,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Compression {
+    Gzip,
+    None,
+}
+
+// parses the URL of the server, which must be http or https, or unix for a
+// Unix domain socket.
+fn parse_endpoint(endpoint: &str) -> Result<Address, String> {
+    if let Some(path) = endpoint.strip_prefix("unix://") {
+        if path.is_empty() {
+            return Err("expected the path of a socket after unix://".into());
+        }
+        return Ok(Address {
+            endpoint: Endpoint::from_static(SOCKET_AUTHORITY),
+            socket: Some(path.into()),
+        });
+    }
+
+    let parsed = Endpoint::from_shared(endpoint.to_string()).map_err(|err| err.to_string())?;
+    match parsed.uri().scheme_str() {
+        Some("http" | "https") if parsed.uri().host().is_some() => Ok(Address {
+            endpoint: parsed,
+            socket: None,
+        }),
+        _ => Err(format!(
+            "expected an http://, https:// or unix:// URL, got {}",
+            endpoint
+        )),
+    }
+}
+
+// the URL requests sent over Unix domain sockets are addressed to, which
+// only names the server in the requests' authority.
+const SOCKET_AUTHORITY: &str = "http://localhost";
+
+// where the server is: an endpoint, along with the Unix domain socket to
+// reach it through rather than TCP when it's served on one.
+#[derive(Debug, Clone)]
+struct Address {
+    endpoint: Endpoint,
+    socket: Option<PathBuf>,
+}
+
+impl Address {
+    // a pool which connects to the address, retrying with the backoff.
+    fn pool(&self, backoff: Backoff) -> ClientPool {
+        let pool = ClientPool::with_endpoint(self.endpoint.clone()).with_backoff(backoff);
+        match &self.socket {
+            Some(socket) => pool.with_uds(socket),
+            None => pool,
+        }
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.socket {
+            Some(socket) => write!(f, "unix://{}", socket.display()),
+            None => write!(f, "{}", self.endpoint.uri()),
+        }
+    }
+}
+
+// the server to connect to, along with the credentials to present to it and
+// how to print its responses.
+struct Server {
+    endpoint: Address,
+    api_key: ApiKey,
+    request_id: RequestId,
+    format: Format,
+    pool: ClientPool,
+    dry_run: bool,
+    compress: Compression,
+}
+
+// the longest wait between attempts to connect.
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(5);
+
+// how failed connections to the server are retried, as set by the options.
+fn connect_backoff(opts: &Options) -> Backoff {
+    Backoff {
+        attempts: opts.connect_retries.saturating_add(1),
+        min: Duration::from_millis(opts.connect_backoff_ms),
+        max: MAX_CONNECT_BACKOFF,
+    }
+}
+
+// an inventory client which presents the API key, if there is one, and
+// identifies its requests.
+type Client = InventoryClient<InterceptedService<Identified, ApiKey>>;
+
+// attaches the API key to requests as a bearer token, unless they already
+// carry other credentials (e.g. the admin token).
+#[derive(Debug, Clone)]
+struct ApiKey(Option<AsciiMetadataValue>);
+
+impl Interceptor for ApiKey {
+    fn call(&mut self, mut request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
+        if let Some(authorization) = &self.0 {
+            let metadata = request.metadata_mut();
+            if !metadata.contains_key("authorization") {
+                metadata.insert("authorization", authorization.clone());
+            }
+        }
+        Ok(request)
+    }
+}
+
+// the metadata header requests are identified by in the server's logs.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// the id to send with requests, if one was given, and the id the server
+// echoed in its last response, which it generates for requests without one.
+#[derive(Debug, Clone, Default)]
+struct RequestId {
+    sent: Option<http::HeaderValue>,
+    echoed: Arc<Mutex<Option<String>>>,
+}
+
+impl RequestId {
+    // the id the server echoed in its last response, if it did.
+    fn echoed(&self) -> Option<String> {
+        self.echoed.lock().unwrap().clone()
+    }
+}
+
+// a channel which sends the request id with each request, and records the
+// one echoed in each response.
+#[derive(Debug, Clone)]
+struct Identified {
+    channel: PooledChannel,
+    request_id: RequestId,
+}
+
+impl Service<http::Request<BoxBody>> for Identified {
+    type Response = http::Response<Body>;
+    type Error = tonic::transport::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.channel.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<BoxBody>) -> Self::Future {
+        if let Some(request_id) = &self.request_id.sent {
+            request
+                .headers_mut()
+                .insert(REQUEST_ID_HEADER, request_id.clone());
+        }
+        let echoed = self.request_id.echoed.clone();
+        let response = self.channel.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            let request_id = response.headers().get(REQUEST_ID_HEADER);
+            if let Some(request_id) = request_id.and_then(|id| id.to_str().ok()) {
+                *echoed.lock().unwrap() = Some(request_id.into());
+            }
+            Ok(response)
+        })
+    }
+}
+
+// connects to the server, describing why the connection failed if it did
+// rather than only reporting a transport error.
+async fn connect(server: &Server) -> Result<Client, Box<dyn std::error::Error>> {
+    let channel = pooled_channel(&server.endpoint, &server.pool).await?;
+    let channel = Identified {
+        channel,
+        request_id: server.request_id.clone(),
+    };
+    let client = InventoryClient::with_interceptor(channel, server.api_key.clone());
+    Ok(match server.compress {
+        Compression::Gzip => client
+            .send_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Gzip),
+        Compression::None => client,
+    })
+}
+
+// retrieves the pool's connection to the address, which connects again once
+// a call failed to reach the server, retrying failed attempts with the pool's
+// backoff until they run out.
+async fn pooled_channel(
+    address: &Address,
+    pool: &ClientPool,
+) -> Result<PooledChannel, Box<dyn std::error::Error>> {
+    pool.channel().await.map_err(|err| {
+        let attempts = match pool.backoff().attempts {
+            0 | 1 => String::new(),
+            attempts => format!(" after {} attempts", attempts),
+        };
+        format!(
+            "couldn't connect to the server at {}{}: {}",
+            address,
+            attempts,
+            error_chain(&err)
+        )
+        .into()
+    })
+}
+
+// describes an error along with the errors which caused it, skipping causes
+// which repeat the description of the error they caused.
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut description = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let cause_description = cause.to_string();
+        if !description.ends_with(&cause_description) {
+            description.push_str(": ");
+            description.push_str(&cause_description);
+        }
+        source = cause.source();
+    }
+    description
+}
+
+// the note ending the dry runs of requests the server didn't check.
+const UNCHECKED_DRY_RUN: &str = "the server wasn't contacted, so the request may still fail.";
+
+// prints the request a command would send in place of sending it, along with
+// what it would do, for dry runs.
+fn print_dry_run(method: &str, request: &impl std::fmt::Debug, effect: &str) {
+    println!("dry run: {} {:?}", method, request);
+    println!("would {}.", effect);
+}
+
 #[derive(Debug, Parser)]
 enum Command {
     Add(AddOptions),
+    Validate(ItemOptions),
+    BatchAdd,
     Remove(RemoveOptions),
+    BatchRemove(BatchRemoveOptions),
     Get(GetOptions),
+    BatchGet(BatchGetOptions),
+    List(ListOptions),
+    Dump,
+    Restore(RestoreOptions),
     UpdateQuantity(UpdateQuantityOptions),
+    SetQuantity(SetQuantityOptions),
+    SetReorderThreshold(SetReorderThresholdOptions),
     UpdatePrice(UpdatePriceOptions),
-    Watch(GetOptions),
+    UpdateInformation(UpdateInformationOptions),
+    ListByAttribute(ListByAttributeOptions),
+    ListByTag(ListByTagOptions),
+    ListByWeight(ListByWeightOptions),
+    LowStock,
+    Search(SearchOptions),
+    Watch(WatchOptions),
+    VerifyWatch(VerifyWatchOptions),
+    SwapStock(SwapStockOptions),
+    Rename(RenameOptions),
+    Reserve(ReservationOptions),
+    Release(ReservationOptions),
+    Sample(SampleOptions),
+    Metrics,
+    Latency,
+    Stats,
+    Count,
+    ServerInfo,
+    TopAccessed(TopAccessedOptions),
+    PriceHistogram(PriceHistogramOptions),
+    SetReadOnly(SetReadOnlyOptions),
+    TailLogs(TailLogsOptions),
+    Tail(TailOptions),
+    AuditLog(AuditLogOptions),
+    CheckConsistency(CheckConsistencyOptions),
+    Describe,
+    Doctor,
+}
+
+impl Command {
+    // reports whether the command can print its responses as JSON.
+    fn supports_json(&self) -> bool {
+        use Command::*;
+        matches!(
+            self,
+            Add(_)
+                | Validate(_)
+                | Remove(_)
+                | BatchRemove(_)
+                | Get(_)
+                | BatchGet(_)
+                | List(_)
+                | UpdateQuantity(_)
+                | SetQuantity(_)
+                | SetReorderThreshold(_)
+                | UpdatePrice(_)
+                | UpdateInformation(_)
+                | ListByAttribute(_)
+                | ListByTag(_)
+                | ListByWeight(_)
+                | LowStock
+                | Search(_)
+                | Watch(_)
+                | Reserve(_)
+                | Release(_)
+                | Stats
+                | Count
+                | ServerInfo
+                | AuditLog(_)
+        )
+    }
+
+    // reports whether the command can be run as a dry run, which is the case
+    // for the commands which change a single item.
+    fn supports_dry_run(&self) -> bool {
+        use Command::*;
+        matches!(
+            self,
+            Add(_)
+                | Remove(_)
+                | UpdateQuantity(_)
+                | SetQuantity(_)
+                | SetReorderThreshold(_)
+                | UpdatePrice(_)
+                | UpdateInformation(_)
+        )
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -34,43 +435,215 @@ enum Command {
 
 #[derive(Debug, Parser)]
 struct AddOptions {
+    #[clap(flatten)]
+    item: ItemOptions,
+    /// replace the item if one with the same SKU already exists
     #[clap(long)]
-    sku: String,
+    upsert: bool,
+}
+
+// the flags describing an item to add, or to validate.
+#[derive(Debug, clap::Args)]
+struct ItemOptions {
     #[clap(long)]
+    sku: String,
+    #[clap(allow_hyphen_values = true, long)]
     price: f32,
     #[clap(default_value = "0", long)]
     quantity: u32,
+    /// list the item as low on stock once its quantity falls to this
+    #[clap(long)]
+    reorder_threshold: Option<u32>,
+    /// the ISO 4217 code of the currency the price is in, USD by default
+    #[clap(long)]
+    currency: Option<String>,
     #[clap(long)]
     name: Option<String>,
     #[clap(long)]
     description: Option<String>,
+    /// an attribute of the item as key=value, may be repeated
+    #[clap(long = "attr", value_parser = parse_attribute)]
+    attributes: Vec<(String, String)>,
+    /// a tag grouping the item into a category, may be repeated
+    #[clap(long = "tag")]
+    tags: Vec<String>,
+    /// remove the item from the inventory once this many seconds have passed
+    #[clap(long)]
+    ttl_secs: Option<u64>,
+    /// the weight of a single unit of the item in grams, for shipping
+    #[clap(allow_hyphen_values = true, long)]
+    weight_grams: Option<f32>,
+    /// the length of a single unit of the item in millimeters
+    #[clap(allow_hyphen_values = true, long)]
+    length_mm: Option<f32>,
+    /// the width of a single unit of the item in millimeters
+    #[clap(allow_hyphen_values = true, long)]
+    width_mm: Option<f32>,
+    /// the height of a single unit of the item in millimeters
+    #[clap(allow_hyphen_values = true, long)]
+    height_mm: Option<f32>,
 }
 
-async fn add(opts: AddOptions) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = InventoryClient::connect("http://127.0.0.1:9001").await?;
+// the expiry time (in unix epoch milliseconds) of an item which is to live for
+// the given number of seconds from now.
+fn expiry_after(ttl_secs: u64) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    (now + Duration::from_secs(ttl_secs)).as_millis() as u64
+}
 
-    let id = ItemIdentifier { sku: opts.sku };
+impl ItemOptions {
+    // the item the flags describe.
+    fn into_item(self) -> Item {
+        let physical = self.physical();
+        Item {
+            identifier: Some(ItemIdentifier { sku: self.sku }),
+            stock: Some(ItemStock {
+                price: self.price,
+                quantity: self.quantity,
+                reorder_threshold: self.reorder_threshold,
+                currency: self.currency.unwrap_or_default(),
+            }),
+            information: Some(ItemInformation {
+                name: self.name,
+                description: self.description,
+            }),
+            attributes: self.attributes.into_iter().collect(),
+            expires_at: self.ttl_secs.map(expiry_after),
+            tags: self.tags,
+            physical,
+            ..Default::default()
+        }
+    }
 
-    let stock = ItemStock {
-        price: opts.price,
-        quantity: opts.quantity,
-    };
+    // the physical attributes the flags describe, which are left unset when
+    // none of them were given. Those which weren't given are zero.
+    fn physical(&self) -> Option<ItemPhysical> {
+        let measures = [
+            self.weight_grams,
+            self.length_mm,
+            self.width_mm,
+            self.height_mm,
+        ];
+        if measures.iter().all(Option::is_none) {
+            return None;
+        }
+        Some(ItemPhysical {
+            weight_grams: self.weight_grams.unwrap_or_default(),
+            length_mm: self.length_mm.unwrap_or_default(),
+            width_mm: self.width_mm.unwrap_or_default(),
+            height_mm: self.height_mm.unwrap_or_default(),
+        })
+    }
+}
 
-    let info = ItemInformation {
-        name: opts.name,
-        description: opts.description,
-    };
+async fn add(server: &Server, opts: AddOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let item = opts.item.into_item();
 
-    let item = Item {
-        identifier: Some(id),
-        stock: Some(stock),
-        information: Some(info),
-    };
+    // dry runs have the server validate the item, which is as much as can be
+    // checked without adding it
+    if server.dry_run {
+        let sku = &item.identifier.as_ref().unwrap().sku;
+        match opts.upsert {
+            true => print_dry_run(
+                "Upsert",
+                &item,
+                &format!("add item {}, or replace it if it's in the inventory", sku),
+            ),
+            false => print_dry_run("Add", &item, &format!("add item {}", sku)),
+        }
+        let mut client = connect(server).await?;
+        match client.validate(item).await {
+            Ok(_) => println!("the server validated the item, it wasn't added."),
+            // servers from before items could be validated can't check them
+            Err(status) if status.code() == tonic::Code::Unimplemented => {
+                println!("the server can't validate items, so the request may still fail.")
+            }
+            Err(status) => return Err(status.into()),
+        }
+        return Ok(());
+    }
 
+    let mut client = connect(server).await?;
     let request = tonic::Request::new(item);
-    let response = client.add(request).await?;
-    assert_eq!(response.into_inner().status, "success");
-    println!("success: item was added to the inventory.");
+    if opts.upsert {
+        let response = client.upsert(request).await?.into_inner();
+        if server.format == Format::Json {
+            println!("{}", serde_json::to_string(&response)?);
+            return Ok(());
+        }
+        let action = if response.replaced {
+            "replaced"
+        } else {
+            "added"
+        };
+        println!(
+            "success: item was {} in the inventory: {:?}",
+            action, response.item
+        );
+        return Ok(());
+    }
+    let response = client.add(request).await?.into_inner();
+    assert_eq!(response.status, "success");
+    if server.format == Format::Json {
+        println!("{}", serde_json::to_string(&response)?);
+        return Ok(());
+    }
+    match response.item {
+        Some(item) => println!("success: item was added to the inventory: {:?}", item),
+        // servers from before items were returned only report the status
+        None => println!("success: item was added to the inventory."),
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Validate Command
+// -----------------------------------------------------------------------------
+
+// has the server check whether an item would be accepted by add, without
+// adding it.
+async fn validate(server: &Server, opts: ItemOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(opts.into_item());
+    let response = client.validate(request).await?.into_inner();
+    assert!(response.status.starts_with("success"));
+    match server.format {
+        Format::Text => match response.item {
+            Some(item) => println!("success: item is valid, it would be added as: {:?}", item),
+            None => println!("{}", response.status),
+        },
+        Format::Json => println!("{}", serde_json::to_string(&response)?),
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Batch Add Command
+// -----------------------------------------------------------------------------
+
+// adds the items read from stdin, one JSON encoded item per line, streaming
+// them to the server in a single request. Every line is decoded before any
+// are sent, so that a malformed line adds nothing.
+async fn batch_add(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut items = Vec::new();
+    for (index, line) in std::io::stdin().lock().lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let item = json::parse_item(&line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+        items.push(item);
+    }
+
+    let mut client = connect(server).await?;
+    let request = tonic::Request::new(futures::stream::iter(items));
+    let response = client.batch_add(request).await?;
+    println!("{}", response.into_inner().status);
 
     Ok(())
 }
@@ -83,16 +656,76 @@ async fn add(opts: AddOptions) -> Result<(), Box<dyn std::error::Error>> {
 struct RemoveOptions {
     #[clap(long)]
     sku: String,
+    /// fail if the item isn't in the inventory, rather than reporting that
+    /// it didn't exist
+    #[clap(long)]
+    strict: bool,
+}
+
+async fn remove(server: &Server, opts: RemoveOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let request = RemoveRequest {
+        sku: opts.sku,
+        strict: opts.strict,
+    };
+    if server.dry_run {
+        let effect = match request.strict {
+            true => format!("remove item {}, failing if it isn't there", request.sku),
+            false => format!("remove item {}, if it's there", request.sku),
+        };
+        print_dry_run("Remove", &request, &effect);
+        println!("{}", UNCHECKED_DRY_RUN);
+        return Ok(());
+    }
+
+    let mut client = connect(server).await?;
+    let request = tonic::Request::new(request);
+    let response = client.remove(request).await?.into_inner();
+    match (server.format, response.existed) {
+        (Format::Text, true) => println!("success: item was removed."),
+        (Format::Text, false) => println!("success: item didn't exist."),
+        (Format::Json, _) => println!("{}", serde_json::to_string(&response)?),
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Batch Remove Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct BatchRemoveOptions {
+    /// a file listing the SKUs to remove one per line, SKUs are read from
+    /// stdin when it isn't given
+    #[clap(long)]
+    file: Option<std::path::PathBuf>,
 }
 
-async fn remove(opts: RemoveOptions) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = InventoryClient::connect("http://127.0.0.1:9001").await?;
+async fn batch_remove(
+    server: &Server,
+    opts: BatchRemoveOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let text = match opts.file {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => std::io::read_to_string(std::io::stdin())?,
+    };
+    let skus = text
+        .lines()
+        .map(str::trim)
+        .filter(|sku| !sku.is_empty())
+        .map(String::from)
+        .collect();
 
-    let request = tonic::Request::new(ItemIdentifier { sku: opts.sku });
-    let response = client.remove(request).await?;
-    let msg = response.into_inner().status;
-    assert!(msg.starts_with("success"));
-    println!("{}", msg);
+    let mut client = connect(server).await?;
+    let request = tonic::Request::new(BatchRemoveRequest { skus });
+    let response = client.batch_remove(request).await?.into_inner();
+    match server.format {
+        Format::Text => println!(
+            "success: {} removed, {} didn't exist",
+            response.removed, response.missing
+        ),
+        Format::Json => println!("{}", serde_json::to_string(&response)?),
+    }
 
     Ok(())
 }
@@ -105,14 +738,235 @@ async fn remove(opts: RemoveOptions) -> Result<(), Box<dyn std::error::Error>> {
 struct GetOptions {
     #[clap(long)]
     sku: String,
+    #[clap(long)]
+    include_reservations: bool,
 }
 
-async fn get(opts: GetOptions) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = InventoryClient::connect("http://127.0.0.1:9001").await?;
+async fn get(server: &Server, opts: GetOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
 
-    let request = tonic::Request::new(ItemIdentifier { sku: opts.sku });
+    let request = tonic::Request::new(GetRequest {
+        sku: opts.sku,
+        include_reservations: opts.include_reservations,
+    });
     let item = client.get(request).await?.into_inner();
+    if server.format == Format::Json {
+        println!("{}", serde_json::to_string(&item)?);
+        return Ok(());
+    }
     println!("found item: {:?}", item);
+    if item.stock.is_none() {
+        println!("the item has no stock.");
+    }
+    if let Some(physical) = &item.physical {
+        println!("shipping: {}", describe_physical(physical));
+    }
+    if let Some(availability) = item.availability {
+        println!("available: {}", availability.available);
+    }
+
+    Ok(())
+}
+
+// describes the weight and dimensions of a unit of an item.
+fn describe_physical(physical: &ItemPhysical) -> String {
+    format!(
+        "{} g, {} x {} x {} mm",
+        physical.weight_grams, physical.length_mm, physical.width_mm, physical.height_mm
+    )
+}
+
+// -----------------------------------------------------------------------------
+// Batch Get Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct BatchGetOptions {
+    /// the SKU of an item to retrieve, may be repeated
+    #[clap(long = "sku", required = true)]
+    skus: Vec<String>,
+}
+
+async fn batch_get(
+    server: &Server,
+    opts: BatchGetOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(BatchGetRequest { skus: opts.skus });
+    let response = client.batch_get(request).await?.into_inner();
+    if server.format == Format::Json {
+        println!("{}", serde_json::to_string(&response)?);
+        return Ok(());
+    }
+    for item in &response.items {
+        println!("found item: {:?}", item);
+    }
+    for sku in &response.missing {
+        println!("missing item: {}", sku);
+    }
+    println!(
+        "success: found {} items, {} were missing.",
+        response.items.len(),
+        response.missing.len()
+    );
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// List Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct ListOptions {
+    /// the number of items to retrieve from the server at a time
+    #[clap(default_value = "100", long)]
+    page_size: u32,
+}
+
+async fn list(server: &Server, opts: ListOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    // follow the pages until the whole inventory was listed
+    let mut page_token = String::new();
+    let mut items = 0;
+    loop {
+        let request = tonic::Request::new(ListRequest {
+            page_size: opts.page_size,
+            page_token,
+        });
+        let page = client.list(request).await?.into_inner();
+        for item in page.items {
+            match server.format {
+                Format::Text => println!("{:?}", item),
+                Format::Json => println!("{}", serde_json::to_string(&item)?),
+            }
+            items += 1;
+        }
+
+        if page.next_page_token.is_empty() {
+            break;
+        }
+        page_token = page.next_page_token;
+    }
+    if server.format == Format::Text {
+        println!("success: listed {} items.", items);
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Dump Command
+// -----------------------------------------------------------------------------
+
+// writes every item in the inventory to stdout as JSON, one item per line, the
+// form restore and batch-add read. Images and reservations aren't included.
+async fn dump(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let mut page_token = String::new();
+    let mut items = 0;
+    loop {
+        // pages of the server's default size
+        let request = tonic::Request::new(ListRequest {
+            page_size: 0,
+            page_token,
+        });
+        let page = client.list(request).await?.into_inner();
+        for item in page.items {
+            println!("{}", serde_json::to_string(&item)?);
+            items += 1;
+        }
+
+        if page.next_page_token.is_empty() {
+            break;
+        }
+        page_token = page.next_page_token;
+    }
+    eprintln!("success: dumped {} items.", items);
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Restore Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct RestoreOptions {
+    /// a dump to restore, which is read from stdin when it isn't given
+    #[clap(long)]
+    file: Option<std::path::PathBuf>,
+    /// leave items which are already in the inventory as they are
+    #[clap(conflicts_with = "overwrite", long)]
+    skip_existing: bool,
+    /// replace items which are already in the inventory with the dumped ones
+    #[clap(long)]
+    overwrite: bool,
+}
+
+// adds the items of a dump to the inventory one at a time. Items which are
+// already in the inventory fail the restore, unless they're to be skipped or
+// overwritten. The whole dump is read first, so that a corrupt dump changes
+// nothing.
+async fn restore(server: &Server, opts: RestoreOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let text = match opts.file {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => std::io::read_to_string(std::io::stdin())?,
+    };
+    let mut items = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let item = json::parse_item(line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+        items.push(item);
+    }
+
+    let mut client = connect(server).await?;
+    let (mut added, mut overwritten, mut skipped) = (0, 0, 0);
+    for item in items {
+        let sku = item
+            .identifier
+            .as_ref()
+            .map(|id| id.sku.clone())
+            .unwrap_or_default();
+        let status = match client.add(item.clone()).await {
+            Ok(_) => {
+                added += 1;
+                continue;
+            }
+            Err(status) => status,
+        };
+        if status.code() != tonic::Code::AlreadyExists || !(opts.skip_existing || opts.overwrite) {
+            return Err(format!("failed to restore item {}: {}", sku, status.message()).into());
+        }
+
+        if opts.skip_existing {
+            skipped += 1;
+            continue;
+        }
+
+        // the existing item is replaced in a single batch, so that it's never
+        // missing from the inventory
+        let operations = vec![
+            Operation {
+                operation: Some(BatchOperation::Remove(ItemIdentifier { sku })),
+            },
+            Operation {
+                operation: Some(BatchOperation::Add(item)),
+            },
+        ];
+        client.apply_batch(ApplyBatchRequest { operations }).await?;
+        overwritten += 1;
+    }
+
+    println!(
+        "success: added {} items, overwrote {} and skipped {} existing items.",
+        added, overwritten, skipped
+    );
 
     Ok(())
 }
@@ -129,104 +983,2299 @@ struct UpdateQuantityOptions {
     change: i32,
 }
 
-async fn update_quantity(opts: UpdateQuantityOptions) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = InventoryClient::connect("http://127.0.0.1:9001").await?;
-
-    let request = tonic::Request::new(QuantityChangeRequest {
+async fn update_quantity(
+    server: &Server,
+    opts: UpdateQuantityOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = QuantityChangeRequest {
         sku: opts.sku,
         change: opts.change,
-    });
+    };
+    if server.dry_run {
+        let effect = format!(
+            "change the quantity of item {} by {}",
+            request.sku, request.change
+        );
+        print_dry_run("UpdateQuantity", &request, &effect);
+        println!("{}", UNCHECKED_DRY_RUN);
+        return Ok(());
+    }
+
+    let mut client = connect(server).await?;
+    let request = tonic::Request::new(request);
 
     let message = client.update_quantity(request).await?.into_inner();
     assert_eq!(message.status, "success");
-    println!(
-        "success: quantity was updated. Quantity: {} Price: {}",
-        message.quantity, message.price
-    );
+    match server.format {
+        Format::Text => println!(
+            "success: quantity was updated. Quantity: {} Price: {}",
+            message.quantity, message.price
+        ),
+        Format::Json => println!("{}", serde_json::to_string(&message)?),
+    }
 
     Ok(())
 }
 
 // -----------------------------------------------------------------------------
-// UpdatePrice Command
+// SetQuantity Command
 // -----------------------------------------------------------------------------
 
 #[derive(Debug, Parser)]
-struct UpdatePriceOptions {
+struct SetQuantityOptions {
     #[clap(long)]
     sku: String,
     #[clap(long)]
-    price: f32,
+    quantity: u32,
 }
 
-async fn update_price(opts: UpdatePriceOptions) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = InventoryClient::connect("http://127.0.0.1:9001").await?;
-
-    let request = tonic::Request::new(PriceChangeRequest {
+async fn set_quantity(
+    server: &Server,
+    opts: SetQuantityOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = SetQuantityRequest {
         sku: opts.sku,
-        price: opts.price,
-    });
+        quantity: opts.quantity,
+    };
+    if server.dry_run {
+        let effect = format!(
+            "set the quantity of item {} to {}",
+            request.sku, request.quantity
+        );
+        print_dry_run("SetQuantity", &request, &effect);
+        println!("{}", UNCHECKED_DRY_RUN);
+        return Ok(());
+    }
 
-    let message = client.update_price(request).await?.into_inner();
+    let mut client = connect(server).await?;
+    let request = tonic::Request::new(request);
+
+    let message = client.set_quantity(request).await?.into_inner();
     assert_eq!(message.status, "success");
-    println!(
-        "success: price was updated. Quantity: {} Price: {}",
-        message.quantity, message.price
-    );
+    match server.format {
+        Format::Text => println!(
+            "success: quantity was set. Quantity: {} Price: {}",
+            message.quantity, message.price
+        ),
+        Format::Json => println!("{}", serde_json::to_string(&message)?),
+    }
 
     Ok(())
 }
 
 // -----------------------------------------------------------------------------
-// Watch Command
+// SetReorderThreshold Command
 // -----------------------------------------------------------------------------
 
-async fn watch(opts: GetOptions) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = InventoryClient::connect("http://127.0.0.1:9001").await?;
-
-    let mut stream = client
-        .watch(ItemIdentifier {
-            sku: opts.sku.clone(),
-        })
-        .await?
-        .into_inner();
+#[derive(Debug, Parser)]
+struct SetReorderThresholdOptions {
+    #[clap(long)]
+    sku: String,
+    /// the quantity at which the item is low on stock, the threshold is
+    /// cleared when it isn't given
+    #[clap(long)]
+    threshold: Option<u32>,
+}
 
-    println!("streaming changes to item {}", opts.sku);
-    while let Some(item) = stream.next().await {
-        match item {
-            Ok(item) => println!("item was updated: {:?}", item),
-            Err(err) => {
-                if err.code() == tonic::Code::NotFound {
-                    println!("watched item has been removed from the inventory.");
-                    break;
-                } else {
-                    return Err(err.into());
-                }
-            }
+async fn set_reorder_threshold(
+    server: &Server,
+    opts: SetReorderThresholdOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = ReorderThresholdRequest {
+        sku: opts.sku,
+        threshold: opts.threshold,
+    };
+    if server.dry_run {
+        let effect = match request.threshold {
+            Some(threshold) => format!(
+                "set the reorder threshold of item {} to {}",
+                request.sku, threshold
+            ),
+            None => format!("clear the reorder threshold of item {}", request.sku),
         };
+        print_dry_run("SetReorderThreshold", &request, &effect);
+        println!("{}", UNCHECKED_DRY_RUN);
+        return Ok(());
+    }
+
+    let mut client = connect(server).await?;
+    let request = tonic::Request::new(request);
+
+    let message = client.set_reorder_threshold(request).await?.into_inner();
+    assert_eq!(message.status, "success");
+    match server.format {
+        Format::Text => println!(
+            "success: reorder threshold was set. Quantity: {} Price: {}",
+            message.quantity, message.price
+        ),
+        Format::Json => println!("{}", serde_json::to_string(&message)?),
     }
-    println!("stream closed");
 
     Ok(())
 }
 
 // -----------------------------------------------------------------------------
-// Main
+// UpdatePrice Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct UpdatePriceOptions {
+    #[clap(long)]
+    sku: String,
+    #[clap(allow_hyphen_values = true, long)]
+    price: f32,
+    /// only update the price if the item is still at this price
+    #[clap(allow_hyphen_values = true, long)]
+    expected_price: Option<f32>,
+    /// the currency the price is in, the update is rejected if the item is
+    /// priced in another
+    #[clap(long)]
+    currency: Option<String>,
+}
+
+async fn update_price(
+    server: &Server,
+    opts: UpdatePriceOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = PriceChangeRequest {
+        sku: opts.sku,
+        price: opts.price,
+        expected_price: opts.expected_price,
+        currency: opts.currency,
+    };
+    if server.dry_run {
+        let mut effect = format!("set the price of item {} to {}", request.sku, request.price);
+        if let Some(currency) = &request.currency {
+            effect.push_str(&format!(" {}", currency));
+        }
+        if let Some(expected_price) = request.expected_price {
+            effect.push_str(&format!(" if it's still {}", expected_price));
+        }
+        print_dry_run("UpdatePrice", &request, &effect);
+        println!("{}", UNCHECKED_DRY_RUN);
+        return Ok(());
+    }
+
+    let mut client = connect(server).await?;
+    let request = tonic::Request::new(request);
+
+    let message = client.update_price(request).await?.into_inner();
+    assert!(message.status.starts_with("success"));
+    let outcome = match message.status.as_str() {
+        "success" => "updated",
+        _ => "already set",
+    };
+    match server.format {
+        Format::Text => println!(
+            "success: price was {}. Quantity: {} Price: {}",
+            outcome, message.quantity, message.price
+        ),
+        Format::Json => println!("{}", serde_json::to_string(&message)?),
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// UpdateInformation Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct UpdateInformationOptions {
+    #[clap(long)]
+    sku: String,
+    #[clap(long)]
+    name: Option<String>,
+    #[clap(long)]
+    description: Option<String>,
+    /// an attribute to set as key=value, or to remove as key=, may be repeated
+    #[clap(long = "attr", value_parser = parse_attribute)]
+    attributes: Vec<(String, String)>,
+}
+
+async fn update_information(
+    server: &Server,
+    opts: UpdateInformationOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = InformationChangeRequest {
+        sku: opts.sku,
+        information: Some(ItemInformation {
+            name: opts.name,
+            description: opts.description,
+        }),
+        attributes: opts.attributes.into_iter().collect(),
+    };
+    if server.dry_run {
+        let effect = format!("update the information of item {}", request.sku);
+        print_dry_run("UpdateInformation", &request, &effect);
+        println!("{}", UNCHECKED_DRY_RUN);
+        return Ok(());
+    }
+
+    let mut client = connect(server).await?;
+    let request = tonic::Request::new(request);
+
+    let response = client.update_information(request).await?.into_inner();
+    assert_eq!(response.status, "success");
+    match server.format {
+        Format::Text => println!("success: item information was updated."),
+        Format::Json => println!("{}", serde_json::to_string(&response)?),
+    }
+
+    Ok(())
+}
+
+// parses an attribute given as key=value.
+fn parse_attribute(attribute: &str) -> Result<(String, String), String> {
+    match attribute.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.into(), value.into())),
+        _ => Err(format!("expected key=value, got {}", attribute)),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// ListByAttribute Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct ListByAttributeOptions {
+    #[clap(long)]
+    key: String,
+    #[clap(long)]
+    value: String,
+}
+
+async fn list_by_attribute(
+    server: &Server,
+    opts: ListByAttributeOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(AttributeFilter {
+        key: opts.key,
+        value: opts.value,
+    });
+
+    let items = client.list_by_attribute(request).await?.into_inner().items;
+    print_items(server, &items, "no items have that attribute.")?;
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// ListByTag Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct ListByTagOptions {
+    #[clap(long)]
+    tag: String,
+}
+
+async fn list_by_tag(
+    server: &Server,
+    opts: ListByTagOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(TagFilter { tag: opts.tag });
+    let items = client.list_by_tag(request).await?.into_inner().items;
+    print_items(server, &items, "no items have that tag.")?;
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// ListByWeight Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct ListByWeightOptions {
+    /// the lightest weight in grams of the items to list
+    #[clap(allow_hyphen_values = true, default_value = "0", long)]
+    min_grams: f32,
+    /// the heaviest weight in grams of the items to list, unbounded if unset
+    #[clap(allow_hyphen_values = true, long)]
+    max_grams: Option<f32>,
+}
+
+async fn list_by_weight(
+    server: &Server,
+    opts: ListByWeightOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(WeightRangeRequest {
+        min_grams: opts.min_grams,
+        max_grams: opts.max_grams,
+    });
+    let items = client
+        .list_by_weight_range(request)
+        .await?
+        .into_inner()
+        .items;
+    print_items(server, &items, "no items weigh within that range.")?;
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// LowStock Command
+// -----------------------------------------------------------------------------
+
+async fn low_stock(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(LowStockRequest {});
+    let items = client.list_low_stock(request).await?.into_inner().items;
+    if server.format == Format::Text && items.is_empty() {
+        println!("no items are low on stock.");
+    }
+    for item in &items {
+        let stock = item.stock.clone().unwrap_or_default();
+        match server.format {
+            Format::Text => println!(
+                "{}: quantity {} (reorder at {})",
+                item.identifier.as_ref().map_or("", |id| id.sku.as_str()),
+                stock.quantity,
+                stock.reorder_threshold.unwrap_or_default()
+            ),
+            Format::Json => println!("{}", serde_json::to_string(&item)?),
+        }
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Search Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct SearchOptions {
+    /// the text to find in item names and descriptions, ignoring case
+    #[clap(long)]
+    query: String,
+}
+
+async fn search(server: &Server, opts: SearchOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(SearchRequest { query: opts.query });
+    let items = client.search(request).await?.into_inner().items;
+    print_items(server, &items, "no items match the query.")?;
+
+    Ok(())
+}
+
+// prints the items found by a query, as text with the given message when none
+// were found, or as JSON with one item per line.
+fn print_items(server: &Server, items: &[Item], none_found: &str) -> serde_json::Result<()> {
+    if server.format == Format::Text && items.is_empty() {
+        println!("{}", none_found);
+    }
+    for item in items {
+        match server.format {
+            Format::Text => println!("found item: {:?}", item),
+            Format::Json => println!("{}", serde_json::to_string(item)?),
+        }
+    }
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Watch Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct WatchOptions {
+    #[clap(long, required_unless_present = "all", conflicts_with = "all")]
+    sku: Option<String>,
+    /// watch every item in the inventory rather than a single one
+    #[clap(long)]
+    all: bool,
+    /// identifies the stream in the server's logs, reuse the id reported by
+    /// a previous watch to correlate a reconnect with it
+    #[clap(long)]
+    stream_id: Option<String>,
+    /// print the item as it is now before its changes
+    #[clap(long, conflicts_with = "all")]
+    initial: bool,
+    /// the version of the item last printed by a previous watch, the item is
+    /// printed as it is now first if it's been changed since
+    #[clap(long, conflicts_with_all = ["all", "initial"])]
+    from_version: Option<u64>,
+}
+
+async fn watch(server: &Server, opts: WatchOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let sku = match opts.sku {
+        Some(sku) => sku,
+        None => return watch_all(server, &mut client, opts.stream_id).await,
+    };
+    let mut request = tonic::Request::new(WatchRequest {
+        sku: sku.clone(),
+        from_version: opts.from_version,
+    });
+    if let Some(stream_id) = opts.stream_id {
+        request
+            .metadata_mut()
+            .insert("x-stream-id", stream_id.parse()?);
+    }
+    let response = match opts.initial {
+        true => {
+            let request = request.map(|watch| ItemIdentifier { sku: watch.sku });
+            client.get_and_watch(request).await?
+        }
+        false => client.watch(request).await?,
+    };
+    let stream_id = response_stream_id(&response);
+    let mut stream = watched_items(response.into_inner());
+
+    note(
+        server,
+        &format!(
+            "streaming changes to item {} (stream id {})",
+            sku, stream_id
+        ),
+    );
+    while let Some(item) = stream.next().await {
+        match item {
+            Ok(item) => match server.format {
+                Format::Text => println!("item was updated: {:?}", item),
+                Format::Json => println!("{}", serde_json::to_string(&item)?),
+            },
+            Err(err) => {
+                if err.code() == tonic::Code::NotFound {
+                    note(server, "watched item has been removed from the inventory.");
+                    break;
+                } else {
+                    return Err(err.into());
+                }
+            }
+        };
+    }
+    note(server, "stream closed");
+
+    Ok(())
+}
+
+async fn watch_all(
+    server: &Server,
+    client: &mut Client,
+    stream_id: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut request = tonic::Request::new(WatchAllRequest {});
+    if let Some(stream_id) = stream_id {
+        request
+            .metadata_mut()
+            .insert("x-stream-id", stream_id.parse()?);
+    }
+    let response = client.watch_all(request).await?;
+    let stream_id = response_stream_id(&response);
+    let mut stream = response.into_inner();
+
+    let started = format!("streaming changes to all items (stream id {})", stream_id);
+    note(server, &started);
+    while let Some(event) = stream.next().await {
+        let event = event?;
+        match (server.format, event.item.as_ref()) {
+            (Format::Json, _) => println!("{}", serde_json::to_string(&event)?),
+            (Format::Text, Some(item)) if event.removed => {
+                println!("item was removed: {:?}", item)
+            }
+            (Format::Text, Some(item)) => println!("item was updated: {:?}", item),
+            (Format::Text, None) => continue,
+        }
+    }
+    note(server, "stream closed");
+
+    Ok(())
+}
+
+// the items sent by a watch, skipping the heartbeats which only keep the
+// stream alive.
+fn watched_items(
+    stream: tonic::Streaming<WatchEvent>,
+) -> impl futures::Stream<Item = Result<Item, Status>> + Unpin {
+    stream.filter_map(|event| {
+        let item = match event {
+            Ok(WatchEvent {
+                event: Some(WatchedEvent::Item(item)),
+            }) => Some(Ok(item)),
+            Ok(_) => None,
+            Err(status) => Some(Err(status)),
+        };
+        futures::future::ready(item)
+    })
+}
+
+// prints a note about the progress of a command, which goes to stderr when
+// printing JSON so that stdout only holds the responses.
+fn note(server: &Server, note: &str) {
+    match server.format {
+        Format::Text => println!("{}", note),
+        Format::Json => eprintln!("{}", note),
+    }
+}
+
+// the id the server reported for a watch stream.
+fn response_stream_id<T>(response: &tonic::Response<T>) -> String {
+    response
+        .metadata()
+        .get("x-stream-id")
+        .and_then(|stream_id| stream_id.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+// -----------------------------------------------------------------------------
+// VerifyWatch Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct VerifyWatchOptions {
+    #[clap(long)]
+    sku: String,
+    /// how often to poll the item, in milliseconds
+    #[clap(default_value = "250", long)]
+    poll_interval_ms: u64,
+    /// how long to verify the watch for, in seconds
+    #[clap(default_value = "30", long)]
+    duration_secs: u64,
+    /// how long to wait for the watch to catch up after verifying, in seconds
+    #[clap(default_value = "3", long)]
+    grace_secs: u64,
+}
+
+async fn verify_watch(
+    server: &Server,
+    opts: VerifyWatchOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let watch = WatchRequest {
+        sku: opts.sku.clone(),
+        from_version: None,
+    };
+    let get = GetRequest {
+        sku: opts.sku.clone(),
+        ..Default::default()
+    };
+    let mut stream = watched_items(client.watch(watch).await?.into_inner());
+
+    // versions of the item received from the watch, and the distinct versions
+    // observed by polling. The first polled version is the baseline the watch
+    // started from, which the watch never sends.
+    let mut watched = Vec::new();
+    let mut polled: Vec<Item> = Vec::new();
+    let mut removed = false;
+
+    println!(
+        "verifying watch of item {} for {}s",
+        opts.sku, opts.duration_secs
+    );
+    let deadline = tokio::time::sleep(Duration::from_secs(opts.duration_secs));
+    tokio::pin!(deadline);
+    let mut interval = tokio::time::interval(Duration::from_millis(opts.poll_interval_ms.max(1)));
+    while !removed {
+        tokio::select! {
+            _ = &mut deadline => break,
+            _ = interval.tick() => match client.get(get.clone()).await {
+                Ok(item) => {
+                    let item = item.into_inner();
+                    if polled.last() != Some(&item) {
+                        polled.push(item);
+                    }
+                }
+                Err(status) if status.code() == tonic::Code::NotFound => removed = true,
+                Err(status) => return Err(status.into()),
+            },
+            update = stream.next() => match update {
+                Some(Ok(item)) => watched.push(item),
+                Some(Err(status)) if status.code() == tonic::Code::NotFound => removed = true,
+                Some(Err(status)) => return Err(status.into()),
+                None => return Err("watch stream closed unexpectedly".into()),
+            },
+        }
+    }
+
+    // the watch is allowed to lag behind polling, so give it a chance to
+    // deliver the last changes the poller observed
+    if !removed {
+        let grace = Duration::from_secs(opts.grace_secs);
+        while let Ok(Some(Ok(item))) = tokio::time::timeout(grace, stream.next()).await {
+            watched.push(item);
+        }
+    }
+
+    // every change observed by polling must have been delivered by the watch,
+    // and the watch must not deliver the same version twice in a row
+    let missed: Vec<&Item> = polled
+        .iter()
+        .skip(1)
+        .filter(|item| !watched.contains(item))
+        .collect();
+    let duplicated = watched.windows(2).filter(|pair| pair[0] == pair[1]).count();
+
+    println!(
+        "polled {} change(s), watched {} update(s)",
+        polled.len().saturating_sub(1),
+        watched.len()
+    );
+    for item in &missed {
+        println!("MISSED update: {:?}", item);
+    }
+    if duplicated > 0 {
+        println!("DUPLICATED updates: {}", duplicated);
+    }
+
+    if !missed.is_empty() || duplicated > 0 {
+        println!("drift detected between the watch and the inventory");
+        std::process::exit(1);
+    }
+    println!("success: the watch reflected every observed change.");
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// SwapStock Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct SwapStockOptions {
+    #[clap(long)]
+    first_sku: String,
+    #[clap(long)]
+    second_sku: String,
+}
+
+async fn swap_stock(
+    server: &Server,
+    opts: SwapStockOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(SwapStockRequest {
+        first_sku: opts.first_sku,
+        second_sku: opts.second_sku,
+    });
+    let response = client.swap_stock(request).await?;
+    assert_eq!(response.into_inner().status, "success");
+    println!("success: stock was swapped between the items.");
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Rename Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct RenameOptions {
+    #[clap(long)]
+    from: String,
+    #[clap(long)]
+    to: String,
+}
+
+async fn rename(server: &Server, opts: RenameOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(RenameSkuRequest {
+        old_sku: opts.from,
+        new_sku: opts.to,
+    });
+    let response = client.rename_sku(request).await?;
+    assert_eq!(response.into_inner().status, "success");
+    println!("success: item was renamed.");
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Reserve & Release Commands
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct ReservationOptions {
+    #[clap(long)]
+    sku: String,
+    #[clap(long)]
+    quantity: u32,
+}
+
+async fn reserve(
+    server: &Server,
+    opts: ReservationOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(ReservationRequest {
+        sku: opts.sku,
+        quantity: opts.quantity,
+    });
+    let availability = client.reserve(request).await?.into_inner();
+    match server.format {
+        Format::Text => println!("success: stock was reserved: {:?}", availability),
+        Format::Json => println!("{}", serde_json::to_string(&availability)?),
+    }
+
+    Ok(())
+}
+
+async fn release(
+    server: &Server,
+    opts: ReservationOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(ReservationRequest {
+        sku: opts.sku,
+        quantity: opts.quantity,
+    });
+    let availability = client.release(request).await?.into_inner();
+    match server.format {
+        Format::Text => println!("success: stock was released: {:?}", availability),
+        Format::Json => println!("{}", serde_json::to_string(&availability)?),
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Sample Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+enum SampleWeighting {
+    Uniform,
+    Quantity,
+    Value,
+}
+
+#[derive(Debug, Parser)]
+struct SampleOptions {
+    #[clap(long)]
+    count: u32,
+    #[clap(default_value = "uniform", long, value_enum)]
+    weighting: SampleWeighting,
+    #[clap(long)]
+    seed: Option<u64>,
+}
+
+async fn sample(server: &Server, opts: SampleOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let weighting = match opts.weighting {
+        SampleWeighting::Uniform => Weighting::Uniform,
+        SampleWeighting::Quantity => Weighting::Quantity,
+        SampleWeighting::Value => Weighting::Value,
+    };
+    let request = tonic::Request::new(SampleRequest {
+        count: opts.count,
+        weighting: weighting.into(),
+        seed: opts.seed,
+    });
+
+    let items = client.sample(request).await?.into_inner().items;
+    println!("sampled {} item(s):", items.len());
+    for item in items {
+        println!("{:?}", item);
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Metrics Command
+// -----------------------------------------------------------------------------
+
+async fn metrics(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let metrics = client
+        .get_metrics(tonic::Request::new(MetricsRequest {}))
+        .await?
+        .into_inner();
+
+    println!("items: {}", metrics.items);
+    println!("active watchers: {}", metrics.active_watchers);
+    for watcher in &metrics.watchers {
+        println!("  stream {} watching {}", watcher.stream_id, watcher.sku);
+    }
+    println!(
+        "{:<34} {:>10} {:>10} {:>12} {:>12}",
+        "method", "requests", "errors", "mean (us)", "max (us)"
+    );
+    for method in metrics.methods {
+        println!(
+            "{:<34} {:>10} {:>10} {:>12} {:>12}",
+            method.method,
+            method.requests,
+            method.errors,
+            method.mean_latency_micros,
+            method.max_latency_micros
+        );
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Latency Command
+// -----------------------------------------------------------------------------
+
+async fn latency(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let summary = client
+        .get_latency_summary(tonic::Request::new(LatencySummaryRequest {}))
+        .await?
+        .into_inner();
+
+    println!("latency over the last {}s:", summary.window_seconds);
+    println!(
+        "{:<34} {:>10} {:>12} {:>12} {:>12}",
+        "method", "requests", "p50 (us)", "p90 (us)", "p99 (us)"
+    );
+    for method in summary.methods {
+        println!(
+            "{:<34} {:>10} {:>12} {:>12} {:>12}",
+            method.method, method.requests, method.p50_micros, method.p90_micros, method.p99_micros
+        );
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Stats Command
+// -----------------------------------------------------------------------------
+
+async fn stats(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let stats = client
+        .inventory_stats(tonic::Request::new(InventoryStatsRequest {}))
+        .await?
+        .into_inner();
+
+    if server.format == Format::Json {
+        println!("{}", serde_json::to_string(&stats)?);
+        return Ok(());
+    }
+    println!("items:       {}", stats.items);
+    println!("units:       {}", stats.units);
+    println!("total value: {:.2} {}", stats.total_value, stats.currency);
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Count Command
+// -----------------------------------------------------------------------------
+
+async fn count(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let response = client
+        .count(tonic::Request::new(CountRequest {}))
+        .await?
+        .into_inner();
+    match server.format {
+        Format::Text => println!("items: {}", response.count),
+        Format::Json => println!("{}", serde_json::to_string(&response)?),
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// ServerInfo Command
+// -----------------------------------------------------------------------------
+
+// reports which build of the server is running, e.g. to tell which build is
+// serving behind a load balancer, and how long it has been up.
+async fn server_info(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let info = client
+        .get_server_info(tonic::Request::new(ServerInfoRequest {}))
+        .await?
+        .into_inner();
+    if server.format == Format::Json {
+        println!("{}", serde_json::to_string(&info)?);
+        return Ok(());
+    }
+    let git_sha = match info.git_sha.as_str() {
+        "" => "unknown",
+        git_sha => git_sha,
+    };
+    println!("version: {}", info.version);
+    println!("commit: {}", git_sha);
+    println!("built at: {}", info.built_at);
+    println!("uptime: {:?}", Duration::from_millis(info.uptime_ms));
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// TopAccessed Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct TopAccessedOptions {
+    /// the number of most fetched items to list
+    #[clap(default_value = "10", long)]
+    limit: u32,
+}
+
+async fn top_accessed(
+    server: &Server,
+    opts: TopAccessedOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(AccessStatsRequest { limit: opts.limit });
+    let items = client.get_access_stats(request).await?.into_inner().items;
+    if items.is_empty() {
+        println!("no items have been fetched yet");
+    }
+    for (rank, item) in items.iter().enumerate() {
+        println!("{:>3}. {} ({} fetches)", rank + 1, item.sku, item.accesses);
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// PriceHistogram Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct PriceHistogramOptions {
+    /// the prices between buckets, in ascending order (e.g. 1,5,20). Items
+    /// priced exactly on a boundary are counted in the bucket above it.
+    #[clap(default_value = "1,5,20", long, value_delimiter = ',')]
+    boundaries: Vec<f32>,
+}
+
+async fn price_histogram(
+    server: &Server,
+    opts: PriceHistogramOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let request = tonic::Request::new(PriceBucketsRequest {
+        boundaries: opts.boundaries,
+    });
+    let buckets = client
+        .group_by_price_bucket(request)
+        .await?
+        .into_inner()
+        .buckets;
+
+    // bars are scaled so that the fullest bucket fills the width
+    const WIDTH: u64 = 40;
+    let fullest = buckets.iter().map(|bucket| bucket.count).max().unwrap_or(0);
+    for bucket in buckets {
+        let range = match (bucket.min, bucket.max) {
+            (Some(min), Some(max)) => format!("{:.2} - {:.2}", min, max),
+            (None, Some(max)) => format!("under {:.2}", max),
+            (Some(min), None) => format!("{:.2} and up", min),
+            (None, None) => "any price".into(),
+        };
+        let bar = match fullest {
+            0 => 0,
+            fullest => (bucket.count * WIDTH).div_ceil(fullest),
+        };
+        println!(
+            "{:>18} | {:<40} {} item(s), total value {:.2}",
+            range,
+            "#".repeat(bar as usize),
+            bucket.count,
+            bucket.total_value
+        );
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// SetReadOnly Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct SetReadOnlyOptions {
+    /// leave read-only maintenance mode rather than entering it
+    #[clap(long)]
+    off: bool,
+    #[clap(long)]
+    admin_token: String,
+}
+
+async fn set_read_only(
+    server: &Server,
+    opts: SetReadOnlyOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let mut request = tonic::Request::new(ReadOnlyRequest {
+        read_only: !opts.off,
+    });
+    let authorization = format!("Bearer {}", opts.admin_token).parse()?;
+    request
+        .metadata_mut()
+        .insert("authorization", authorization);
+
+    let response = client.set_read_only(request).await?;
+    assert_eq!(response.into_inner().status, "success");
+    match opts.off {
+        true => println!("success: the inventory is writable again."),
+        false => println!("success: the inventory is now read-only."),
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// TailLogs Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct TailLogsOptions {
+    /// the number of recent log lines to show before following live ones
+    #[clap(default_value = "100", long)]
+    backlog: u32,
+    #[clap(long)]
+    admin_token: String,
+}
+
+async fn tail_logs(
+    server: &Server,
+    opts: TailLogsOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let mut request = tonic::Request::new(TailLogsRequest {
+        backlog: opts.backlog,
+    });
+    let authorization = format!("Bearer {}", opts.admin_token).parse()?;
+    request
+        .metadata_mut()
+        .insert("authorization", authorization);
+
+    let mut stream = client.tail_logs(request).await?.into_inner();
+    while let Some(line) = stream.message().await? {
+        if line.dropped > 0 {
+            println!("... {} log lines dropped", line.dropped);
+        }
+        println!(
+            "{} {} {}: {}",
+            line.timestamp, line.level, line.target, line.message
+        );
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Tail Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct TailOptions {
+    /// the file to append each change to all items to, as a line of JSON
+    #[clap(long)]
+    output: std::path::PathBuf,
+    /// empty the file before writing to it, rather than appending to it
+    #[clap(long)]
+    truncate: bool,
+    /// once the file would grow past this many bytes, move it aside to
+    /// <output>.1 (replacing any file already there) and start a new one
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    max_bytes: Option<u64>,
+}
+
+// a file changes are appended to, which is rotated once it grows too large.
+struct ChangeLog {
+    path: std::path::PathBuf,
+    file: std::io::BufWriter<std::fs::File>,
+    written: u64,
+    max_bytes: Option<u64>,
+}
+
+impl ChangeLog {
+    fn open(
+        path: std::path::PathBuf,
+        truncate: bool,
+        max_bytes: Option<u64>,
+    ) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(!truncate)
+            .truncate(truncate)
+            .open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(ChangeLog {
+            path,
+            file: std::io::BufWriter::new(file),
+            written,
+            max_bytes,
+        })
+    }
+
+    // appends a line to the file, flushing it so that it's written even if
+    // the command is killed.
+    fn append(&mut self, line: &str) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let len = line.len() as u64 + 1;
+        if let Some(max_bytes) = self.max_bytes {
+            if self.written > 0 && self.written + len > max_bytes {
+                self.rotate()?;
+            }
+        }
+        writeln!(self.file, "{}", line)?;
+        self.file.flush()?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.close()?;
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(".1");
+        std::fs::rename(&self.path, rotated)?;
+        *self = ChangeLog::open(self.path.clone(), true, self.max_bytes)?;
+        Ok(())
+    }
+
+    fn close(&mut self) -> std::io::Result<()> {
+        use std::io::Write;
+
+        self.file.flush()?;
+        self.file.get_ref().sync_all()
+    }
+}
+
+// reports whether a watch ended with an error which reconnecting may get past,
+// such as the connection dropping or the server restarting.
+fn is_transient(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        tonic::Code::Unavailable | tonic::Code::Unknown | tonic::Code::Cancelled
+    )
+}
+
+// appends changes to all items to a file until interrupted, reconnecting with
+// backoff whenever the stream is lost. Changes made while disconnected, or
+// which the stream fell too far behind to deliver, aren't in the file.
+async fn tail(server: &Server, opts: TailOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut log = ChangeLog::open(opts.output.clone(), opts.truncate, opts.max_bytes)?;
+    let mut client = connect(server).await?;
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+
+    let mut attempt = 0;
+    loop {
+        let request = tonic::Request::new(WatchAllRequest {});
+        let ended = match client.watch_all(request).await {
+            Ok(response) => {
+                let stream_id = response_stream_id(&response);
+                let mut stream = response.into_inner();
+                note(
+                    server,
+                    &format!(
+                        "appending changes to all items to {} (stream id {})",
+                        opts.output.display(),
+                        stream_id
+                    ),
+                );
+                loop {
+                    let event = tokio::select! {
+                        _ = &mut interrupted => {
+                            log.close()?;
+                            note(server, "interrupted, the file was closed");
+                            return Ok(());
+                        }
+                        event = stream.next() => event,
+                    };
+                    match event {
+                        Some(Ok(event)) if event.item.is_some() => {
+                            log.append(&serde_json::to_string(&event)?)?;
+                            attempt = 0;
+                        }
+                        // heartbeats only keep the stream alive
+                        Some(Ok(_)) => {}
+                        Some(Err(status)) => break Some(status),
+                        None => break None,
+                    }
+                }
+            }
+            Err(status) => Some(status),
+        };
+
+        match ended {
+            Some(status) if status.code() == tonic::Code::DataLoss => {
+                note(server, "the stream fell behind and changes were missed")
+            }
+            Some(status) if !is_transient(&status) => {
+                log.close()?;
+                return Err(status.into());
+            }
+            Some(status) => note(server, &format!("stream lost: {}", status.message())),
+            None => note(server, "stream closed by the server"),
+        }
+
+        // the pool connects again when the stream was lost along with the
+        // connection, otherwise only the watch is retried
+        let delay = server.pool.backoff().delay(attempt);
+        note(server, &format!("reconnecting in {}ms", delay.as_millis()));
+        tokio::select! {
+            _ = &mut interrupted => {
+                log.close()?;
+                note(server, "interrupted, the file was closed");
+                return Ok(());
+            }
+            _ = tokio::time::sleep(delay) => {}
+        }
+        attempt = attempt.saturating_add(1);
+        // the old client is kept when connecting fails, so that its watch
+        // fails in turn and the next attempt comes after a longer wait
+        match connect(server).await {
+            Ok(connected) => client = connected,
+            Err(err) => note(server, &err.to_string()),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// AuditLog Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct AuditLogOptions {
+    /// the number of recent changes to show, all of those kept when 0
+    #[clap(default_value = "100", long)]
+    limit: u32,
+    #[clap(long)]
+    admin_token: String,
+}
+
+async fn audit_log(
+    server: &Server,
+    opts: AuditLogOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let mut request = tonic::Request::new(AuditLogRequest { limit: opts.limit });
+    let authorization = format!("Bearer {}", opts.admin_token).parse()?;
+    request
+        .metadata_mut()
+        .insert("authorization", authorization);
+
+    let entries = client.get_audit_log(request).await?.into_inner().entries;
+    for entry in &entries {
+        match server.format {
+            Format::Text => println!(
+                "{} {} {}: {} -> {}",
+                entry.timestamp,
+                entry.operation,
+                entry.sku,
+                describe_stock(entry.before.as_ref()),
+                describe_stock(entry.after.as_ref())
+            ),
+            Format::Json => println!("{}", serde_json::to_string(&entry)?),
+        }
+    }
+
+    Ok(())
+}
+
+// describes the stock of an audited item, or its absence.
+fn describe_stock(item: Option<&Item>) -> String {
+    match item.map(|item| item.stock.as_ref()) {
+        Some(Some(stock)) => format!("{} at ${:.2}", stock.quantity, stock.price),
+        Some(None) => "(no stock)".into(),
+        None => "(none)".into(),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// CheckConsistency Command
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+struct CheckConsistencyOptions {
+    /// repair the issues found rather than only reporting them
+    #[clap(long)]
+    repair: bool,
+    #[clap(long)]
+    admin_token: String,
+}
+
+async fn check_consistency(
+    server: &Server,
+    opts: CheckConsistencyOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server).await?;
+
+    let mut request = tonic::Request::new(ConsistencyRequest {
+        repair: opts.repair,
+    });
+    let authorization = format!("Bearer {}", opts.admin_token).parse()?;
+    request
+        .metadata_mut()
+        .insert("authorization", authorization);
+
+    let report = client.check_consistency(request).await?.into_inner();
+    if report.issues.is_empty() {
+        println!("success: no consistency issues found.");
+    }
+    for issue in report.issues {
+        let repaired = if issue.repaired { " (repaired)" } else { "" };
+        println!("{}: {}{}", issue.sku, issue.description, repaired);
+    }
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Describe Command
+// -----------------------------------------------------------------------------
+
+// the fully qualified name of the service described by the describe command.
+const INVENTORY_SERVICE: &str = "store.Inventory";
+
+async fn describe(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let channel = pooled_channel(&server.endpoint, &server.pool).await?;
+    let mut client = ServerReflectionClient::new(channel);
+
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(MessageRequest::FileContainingSymbol(
+            INVENTORY_SERVICE.into(),
+        )),
+    };
+    let response = match client
+        .server_reflection_info(tokio_stream::iter(vec![request]))
+        .await
+    {
+        Ok(response) => response.into_inner().next().await,
+        Err(status) => Some(Err(status)),
+    };
+
+    let files = match response {
+        Some(Ok(response)) => match response.message_response {
+            Some(MessageResponse::FileDescriptorResponse(files)) => files.file_descriptor_proto,
+            Some(MessageResponse::ErrorResponse(err)) => return Err(err.error_message.into()),
+            _ => return Err("the server sent an unexpected reflection response".into()),
+        },
+        Some(Err(status)) if status.code() == tonic::Code::Unimplemented => {
+            return Err(
+                "the server has reflection disabled, so it can't be described. \
+                The API is defined in proto/store.proto"
+                    .into(),
+            )
+        }
+        Some(Err(status)) => return Err(status.into()),
+        None => return Err("reflection stream closed without a response".into()),
+    };
+
+    let files = files
+        .iter()
+        .map(|file| FileDescriptorProto::decode(file.as_slice()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match describe_service(&files, INVENTORY_SERVICE) {
+        Some(description) => print!("{}", description),
+        None => return Err(format!("the server doesn't describe {}", INVENTORY_SERVICE).into()),
+    }
+
+    Ok(())
+}
+
+// describes a service in the files which define it, listing its methods and
+// the message and enum types of its package, in a form resembling the proto.
+fn describe_service(files: &[FileDescriptorProto], service: &str) -> Option<String> {
+    let (file, definition) = files.iter().find_map(|file| {
+        file.service
+            .iter()
+            .find(|definition| qualify(file.package(), definition.name()) == service)
+            .map(|definition| (file, definition))
+    })?;
+
+    let mut text = format!("service {} {{\n", service);
+    for method in &definition.method {
+        let stream = |streaming: bool| if streaming { "stream " } else { "" };
+        text += &format!(
+            "  rpc {}({}{}) returns ({}{});\n",
+            method.name(),
+            stream(method.client_streaming()),
+            method.input_type().trim_start_matches('.'),
+            stream(method.server_streaming()),
+            method.output_type().trim_start_matches('.'),
+        );
+    }
+    text += "}\n";
+
+    for message in &file.message_type {
+        describe_message(&mut text, file.package(), message);
+    }
+    for definition in &file.enum_type {
+        describe_enum(&mut text, file.package(), definition);
+    }
+
+    Some(text)
+}
+
+// describes a message along with the messages and enums nested in it. Map
+// entries are described as the maps they make up, rather than as messages.
+fn describe_message(text: &mut String, scope: &str, message: &DescriptorProto) {
+    let name = qualify(scope, message.name());
+    *text += &format!("\nmessage {} {{\n", name);
+    for field in &message.field {
+        let map_entry = message.nested_type.iter().find(|nested| {
+            is_map_entry(nested) && qualify(&name, nested.name()) == type_name(field)
+        });
+        let declaration = match map_entry.map(|entry| entry.field.as_slice()) {
+            Some([key, value]) => format!("map<{}, {}>", type_name(key), type_name(value)),
+            _ if field.label() == Label::Repeated => format!("repeated {}", type_name(field)),
+            _ if field.proto3_optional() => format!("optional {}", type_name(field)),
+            _ => type_name(field).to_string(),
+        };
+        *text += &format!("  {} {} = {};\n", declaration, field.name(), field.number());
+    }
+    *text += "}\n";
+
+    for nested in message
+        .nested_type
+        .iter()
+        .filter(|nested| !is_map_entry(nested))
+    {
+        describe_message(text, &name, nested);
+    }
+    for definition in &message.enum_type {
+        describe_enum(text, &name, definition);
+    }
+}
+
+// reports whether a nested message is the entry type generated for a map.
+fn is_map_entry(message: &DescriptorProto) -> bool {
+    message
+        .options
+        .as_ref()
+        .is_some_and(|options| options.map_entry())
+}
+
+// the type of a field as it's written in a proto file, message and enum types
+// are fully qualified.
+fn type_name(field: &FieldDescriptorProto) -> &str {
+    match field.r#type() {
+        FieldType::Message | FieldType::Enum => field.type_name().trim_start_matches('.'),
+        other => scalar_type(other),
+    }
+}
+
+fn describe_enum(text: &mut String, scope: &str, definition: &EnumDescriptorProto) {
+    *text += &format!("\nenum {} {{\n", qualify(scope, definition.name()));
+    for value in &definition.value {
+        *text += &format!("  {} = {};\n", value.name(), value.number());
+    }
+    *text += "}\n";
+}
+
+// the name of a scalar field type as it's written in a proto file.
+fn scalar_type(field_type: FieldType) -> &'static str {
+    match field_type {
+        FieldType::Double => "double",
+        FieldType::Float => "float",
+        FieldType::Int64 => "int64",
+        FieldType::Uint64 => "uint64",
+        FieldType::Int32 => "int32",
+        FieldType::Fixed64 => "fixed64",
+        FieldType::Fixed32 => "fixed32",
+        FieldType::Bool => "bool",
+        FieldType::String => "string",
+        FieldType::Group => "group",
+        FieldType::Message => "message",
+        FieldType::Bytes => "bytes",
+        FieldType::Uint32 => "uint32",
+        FieldType::Enum => "enum",
+        FieldType::Sfixed32 => "sfixed32",
+        FieldType::Sfixed64 => "sfixed64",
+        FieldType::Sint32 => "sint32",
+        FieldType::Sint64 => "sint64",
+    }
+}
+
+// the fully qualified name of a definition within a package or message.
+fn qualify(scope: &str, name: &str) -> String {
+    match scope {
+        "" => name.to_string(),
+        scope => format!("{}.{}", scope, name),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Doctor Command
+// -----------------------------------------------------------------------------
+
+async fn doctor(server: &Server) -> Result<(), Box<dyn std::error::Error>> {
+    let mut healthy = true;
+
+    healthy &= match &server.endpoint.socket {
+        Some(socket) => {
+            let reachable = UnixStream::connect(socket)
+                .await
+                .map(|_| ())
+                .map_err(|err| err.to_string());
+            report(
+                "socket reachability",
+                reachable,
+                &format!("is the server running and serving on {}?", socket.display()),
+            )
+        }
+        None => {
+            let uri = server.endpoint.endpoint.uri();
+            let host = uri.host().unwrap_or_default();
+            let port = match (uri.port_u16(), uri.scheme_str()) {
+                (Some(port), _) => port,
+                (None, Some("https")) => 443,
+                (None, _) => 80,
+            };
+            let reachable = TcpStream::connect((host, port))
+                .await
+                .map(|_| ())
+                .map_err(|err| err.to_string());
+            report(
+                "tcp reachability",
+                reachable,
+                &format!("is the server running and listening on {}:{}?", host, port),
+            )
+        }
+    };
+
+    healthy &= report(
+        "reflection availability",
+        check_reflection(&server.pool).await,
+        "the server may have reflection disabled, which is only needed by tools like grpcurl",
+    );
+
+    healthy &= report(
+        "inventory round trip",
+        check_round_trip(server).await,
+        "the server is reachable but isn't serving store.Inventory correctly",
+    );
+
+    if !healthy {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+// prints the outcome of a doctor check, along with a hint on how to fix it if
+// the check failed, and returns whether the check passed.
+fn report(check: &str, result: Result<(), String>, hint: &str) -> bool {
+    match result {
+        Ok(()) => {
+            println!("PASS {}", check);
+            true
+        }
+        Err(err) => {
+            println!("FAIL {}: {}", check, err);
+            println!("     hint: {}", hint);
+            false
+        }
+    }
+}
+
+// verifies the server offers reflection, and that store.Inventory is listed.
+async fn check_reflection(pool: &ClientPool) -> Result<(), String> {
+    let channel = pool.channel().await.map_err(|err| error_chain(&err))?;
+    let mut client = ServerReflectionClient::new(channel);
+
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(MessageRequest::ListServices(String::new())),
+    };
+    let mut stream = client
+        .server_reflection_info(tokio_stream::iter(vec![request]))
+        .await
+        .map_err(|err| err.message().to_string())?
+        .into_inner();
+
+    match stream.next().await {
+        Some(Ok(response)) => match response.message_response {
+            Some(MessageResponse::ListServicesResponse(list))
+                if list.service.iter().any(|svc| svc.name == "store.Inventory") =>
+            {
+                Ok(())
+            }
+            _ => Err("store.Inventory is not listed by reflection".into()),
+        },
+        Some(Err(err)) => Err(err.message().to_string()),
+        None => Err("reflection stream closed without a response".into()),
+    }
+}
+
+// makes a request to the Inventory service, any response for the probe SKU
+// (including not found) proves the service is working.
+async fn check_round_trip(server: &Server) -> Result<(), String> {
+    let mut client = connect(server).await.map_err(|err| err.to_string())?;
+
+    let request = tonic::Request::new(GetRequest {
+        sku: "doctor-probe".into(),
+        ..Default::default()
+    });
+    match client.get(request).await {
+        Ok(_) => Ok(()),
+        Err(status) if status.code() == tonic::Code::NotFound => Ok(()),
+        Err(status) => Err(status.message().to_string()),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Main
 // -----------------------------------------------------------------------------
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
+    // errors are printed as their description, rather than their debug
+    // representation, so that they're readable
     let opts = Options::parse();
+    let timeout_secs = opts.timeout_secs;
+    if let Err(err) = run(opts).await {
+        if timed_out(err.as_ref()) {
+            eprintln!("error: request timed out after {}s", timeout_secs);
+            std::process::exit(1);
+        }
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+// the message of the status tonic cancels requests with when they outlive the
+// endpoint's timeout, as the TimeoutExpired error can't be matched once it's
+// been converted into a status.
+const TIMEOUT_EXPIRED: &str = "Timeout expired";
+
+// reports whether a request failed because it outlived the timeout.
+fn timed_out(err: &(dyn std::error::Error + 'static)) -> bool {
+    match err.downcast_ref::<Status>() {
+        Some(status) => {
+            status.code() == tonic::Code::Cancelled && status.message() == TIMEOUT_EXPIRED
+        }
+        None => err.is::<tonic::transport::TimeoutExpired>(),
+    }
+}
+
+async fn run(opts: Options) -> Result<(), Box<dyn std::error::Error>> {
+    let backoff = connect_backoff(&opts);
+    let api_key = match opts.api_key {
+        Some(api_key) => Some(format!("Bearer {}", api_key).parse()?),
+        None => None,
+    };
+    if opts.format == Format::Json && !opts.command.supports_json() {
+        return Err("this command can't print its responses as JSON".into());
+    }
+    if opts.dry_run && !opts.command.supports_dry_run() {
+        return Err("this command can't be run as a dry run".into());
+    }
+    if opts.dry_run && opts.format == Format::Json {
+        return Err("dry runs can only be printed as text".into());
+    }
+    let mut endpoint = opts.endpoint;
+    if opts.timeout_secs > 0 {
+        let timeout = Duration::from_secs(opts.timeout_secs);
+        endpoint.endpoint = endpoint.endpoint.timeout(timeout).connect_timeout(timeout);
+    }
+    let request_id = RequestId {
+        sent: match opts.request_id {
+            Some(request_id) => Some(request_id.parse()?),
+            None => None,
+        },
+        ..Default::default()
+    };
+    let server = Server {
+        pool: endpoint.pool(backoff),
+        endpoint,
+        api_key: ApiKey(api_key),
+        request_id: request_id.clone(),
+        format: opts.format,
+        dry_run: opts.dry_run,
+        compress: opts.compress,
+    };
 
     use Command::*;
-    match opts.command {
-        Add(opts) => add(opts).await?,
-        Remove(opts) => remove(opts).await?,
-        Get(opts) => get(opts).await?,
-        UpdateQuantity(opts) => update_quantity(opts).await?,
-        UpdatePrice(opts) => update_price(opts).await?,
-        Watch(opts) => watch(opts).await?,
+    let result = match opts.command {
+        Add(opts) => add(&server, opts).await,
+        Validate(opts) => validate(&server, opts).await,
+        BatchAdd => batch_add(&server).await,
+        Remove(opts) => remove(&server, opts).await,
+        BatchRemove(opts) => batch_remove(&server, opts).await,
+        Get(opts) => get(&server, opts).await,
+        BatchGet(opts) => batch_get(&server, opts).await,
+        List(opts) => list(&server, opts).await,
+        Dump => dump(&server).await,
+        Restore(opts) => restore(&server, opts).await,
+        UpdateQuantity(opts) => update_quantity(&server, opts).await,
+        SetQuantity(opts) => set_quantity(&server, opts).await,
+        SetReorderThreshold(opts) => set_reorder_threshold(&server, opts).await,
+        UpdatePrice(opts) => update_price(&server, opts).await,
+        UpdateInformation(opts) => update_information(&server, opts).await,
+        ListByAttribute(opts) => list_by_attribute(&server, opts).await,
+        ListByTag(opts) => list_by_tag(&server, opts).await,
+        ListByWeight(opts) => list_by_weight(&server, opts).await,
+        LowStock => low_stock(&server).await,
+        Search(opts) => search(&server, opts).await,
+        Watch(opts) => watch(&server, opts).await,
+        VerifyWatch(opts) => verify_watch(&server, opts).await,
+        SwapStock(opts) => swap_stock(&server, opts).await,
+        Rename(opts) => rename(&server, opts).await,
+        Reserve(opts) => reserve(&server, opts).await,
+        Release(opts) => release(&server, opts).await,
+        Sample(opts) => sample(&server, opts).await,
+        Metrics => metrics(&server).await,
+        Latency => latency(&server).await,
+        Stats => stats(&server).await,
+        Count => count(&server).await,
+        ServerInfo => server_info(&server).await,
+        TopAccessed(opts) => top_accessed(&server, opts).await,
+        PriceHistogram(opts) => price_histogram(&server, opts).await,
+        SetReadOnly(opts) => set_read_only(&server, opts).await,
+        TailLogs(opts) => tail_logs(&server, opts).await,
+        Tail(opts) => tail(&server, opts).await,
+        AuditLog(opts) => audit_log(&server, opts).await,
+        CheckConsistency(opts) => check_consistency(&server, opts).await,
+        Describe => describe(&server).await,
+        Doctor => doctor(&server).await,
     };
 
-    Ok(())
+    // the id is reported whether the command succeeded or not, so that
+    // failures can be found in the server's logs too
+    if let Some(request_id) = request_id.echoed() {
+        eprintln!("request id: {}", request_id);
+    }
+    result
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use prost::Message;
+    use prost_types::FileDescriptorSet;
+    use std::time::Duration;
+    use tonic::service::Interceptor;
+    use tonic::Status;
+
+    use crate::store::{Item, ItemIdentifier, ItemPhysical, ItemStock};
+    use crate::{
+        connect_backoff, describe_physical, describe_service, describe_stock, parse_endpoint,
+        pooled_channel, run, timed_out, ApiKey, ChangeLog, Command, Compression, Format, Options,
+        RequestId, INVENTORY_SERVICE, MAX_CONNECT_BACKOFF, TIMEOUT_EXPIRED,
+    };
+    use demo::pool::Backoff;
+
+    #[test]
+    fn command_definitions() {
+        Options::command().debug_assert();
+    }
+
+    #[test]
+    fn negative_numbers() {
+        // flags which accept negative numbers must not mistake them for flags
+        let opts = Options::try_parse_from(["cli", "add", "--sku", "A", "--price", "-5"]).unwrap();
+        assert!(matches!(opts.command, Command::Add(opts) if opts.item.price == -5.0));
+
+        let opts = Options::try_parse_from(["cli", "update-price", "--sku", "A", "--price", "-5"])
+            .unwrap();
+        assert!(matches!(opts.command, Command::UpdatePrice(opts) if opts.price == -5.0));
+
+        let opts =
+            Options::try_parse_from(["cli", "update-quantity", "--sku", "A", "--change", "-10"])
+                .unwrap();
+        assert!(matches!(opts.command, Command::UpdateQuantity(opts) if opts.change == -10));
+
+        // as do the weight and dimensions, which the server rejects
+        let args = [
+            "cli",
+            "add",
+            "--sku",
+            "A",
+            "--price",
+            "1",
+            "--weight-grams",
+            "-5",
+        ];
+        let opts = Options::try_parse_from(args).unwrap();
+        assert!(matches!(opts.command, Command::Add(opts) if opts.item.weight_grams == Some(-5.0)));
+
+        // unsigned flags still reject negative numbers
+        let args = ["cli", "sample", "--count", "-5"];
+        assert!(Options::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn endpoints() {
+        // the endpoint defaults to the local server, and can be given before
+        // or after the command
+        let opts = Options::try_parse_from(["cli", "metrics"]).unwrap();
+        assert_eq!(opts.endpoint.to_string(), "http://127.0.0.1:9001/");
+        for args in [
+            ["cli", "--endpoint", "https://store.example.com", "metrics"],
+            ["cli", "metrics", "--endpoint", "https://store.example.com"],
+        ] {
+            let opts = Options::try_parse_from(args).unwrap();
+            assert_eq!(opts.endpoint.to_string(), "https://store.example.com/");
+            assert!(opts.endpoint.socket.is_none());
+        }
+
+        // servers on Unix domain sockets are reached through the socket
+        let args = ["cli", "--endpoint", "unix:///run/store.sock", "metrics"];
+        let opts = Options::try_parse_from(args).unwrap();
+        assert_eq!(opts.endpoint.socket, Some("/run/store.sock".into()));
+        assert_eq!(opts.endpoint.to_string(), "unix:///run/store.sock");
+
+        // only http and https URLs with a host, and unix URLs with a path,
+        // are accepted
+        for endpoint in [
+            "ftp://store.example.com",
+            "store.example.com:9001",
+            "http://",
+            "unix://",
+        ] {
+            let args = ["cli", "--endpoint", endpoint, "metrics"];
+            assert!(Options::try_parse_from(args).is_err(), "{}", endpoint);
+        }
+    }
+
+    #[test]
+    fn watch_targets() {
+        // watches are either of a single item or of all of them
+        let opts = Options::try_parse_from(["cli", "watch", "--all"]).unwrap();
+        assert!(matches!(opts.command, Command::Watch(opts) if opts.all && opts.sku.is_none()));
+        let opts = Options::try_parse_from(["cli", "watch", "--sku", "A"]).unwrap();
+        assert!(matches!(opts.command, Command::Watch(opts) if !opts.all && !opts.initial));
+        let opts = Options::try_parse_from(["cli", "watch", "--sku", "A", "--initial"]).unwrap();
+        assert!(matches!(opts.command, Command::Watch(opts) if opts.initial));
+        let args = ["cli", "watch", "--sku", "A", "--from-version", "7"];
+        let opts = Options::try_parse_from(args).unwrap();
+        assert!(matches!(opts.command, Command::Watch(opts) if opts.from_version == Some(7)));
+
+        // only watches of a single item can start with the item, or resume
+        // from a version of it
+        for args in [
+            &["cli", "watch"][..],
+            &["cli", "watch", "--all", "--sku", "A"],
+            &["cli", "watch", "--all", "--initial"],
+            &["cli", "watch", "--all", "--from-version", "7"],
+            &[
+                "cli",
+                "watch",
+                "--sku",
+                "A",
+                "--initial",
+                "--from-version",
+                "7",
+            ],
+        ] {
+            assert!(Options::try_parse_from(args).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn formats() {
+        // responses are printed as text unless JSON is requested, which can
+        // be given before or after the command
+        let opts = Options::try_parse_from(["cli", "count"]).unwrap();
+        assert_eq!(opts.format, Format::Text);
+        for args in [
+            ["cli", "--format", "json", "count"],
+            ["cli", "count", "--format", "json"],
+        ] {
+            let opts = Options::try_parse_from(args).unwrap();
+            assert_eq!(opts.format, Format::Json);
+            assert!(opts.command.supports_json());
+        }
+        assert!(Options::try_parse_from(["cli", "--format", "xml", "count"]).is_err());
+
+        // commands without a JSON form say so rather than printing text
+        let opts = Options::try_parse_from(["cli", "--format", "json", "doctor"]).unwrap();
+        assert!(!opts.command.supports_json());
+    }
+
+    #[test]
+    fn compression() {
+        // requests are only compressed when asked, and only with gzip
+        let opts = Options::try_parse_from(["cli", "list"]).unwrap();
+        assert_eq!(opts.compress, Compression::None);
+        let opts = Options::try_parse_from(["cli", "list", "--compress", "gzip"]).unwrap();
+        assert_eq!(opts.compress, Compression::Gzip);
+        assert!(Options::try_parse_from(["cli", "--compress", "zstd", "list"]).is_err());
+    }
+
+    #[test]
+    fn timeouts() {
+        // requests give up after 30 seconds unless told otherwise
+        let opts = Options::try_parse_from(["cli", "count"]).unwrap();
+        assert_eq!(opts.timeout_secs, 30);
+        let opts = Options::try_parse_from(["cli", "count", "--timeout-secs", "5"]).unwrap();
+        assert_eq!(opts.timeout_secs, 5);
+        assert!(Options::try_parse_from(["cli", "--timeout-secs", "-1", "count"]).is_err());
+
+        // only the status tonic cancels expired requests with is a timeout
+        let expired = Status::cancelled(TIMEOUT_EXPIRED);
+        assert!(timed_out(&expired));
+        assert!(!timed_out(&Status::cancelled("cancelled by the server")));
+        assert!(!timed_out(&Status::deadline_exceeded(TIMEOUT_EXPIRED)));
+    }
+
+    #[test]
+    fn service_descriptions() {
+        let descriptors = tonic::include_file_descriptor_set!("store_descriptor");
+        let files = FileDescriptorSet::decode(&descriptors[..]).unwrap().file;
+        let description = describe_service(&files, INVENTORY_SERVICE).unwrap();
+
+        for line in [
+            "service store.Inventory {",
+            "  rpc Add(store.Item) returns (store.InventoryChangeResponse);",
+            "  rpc BatchAdd(stream store.Item) returns (store.InventoryChangeResponse);",
+            "  rpc Watch(store.WatchRequest) returns (stream store.WatchEvent);",
+            "message store.Item {",
+            "  optional store.ItemInformation information = 3;",
+            "  map<string, string> attributes = 8;",
+            "  repeated string skus = 1;",
+            "enum store.GetManyResult.Outcome {",
+            "  NOT_FOUND = 1;",
+        ] {
+            assert!(description.lines().any(|l| l == line), "{}", line);
+        }
+        assert!(!description.contains("AttributesEntry"));
+
+        assert!(describe_service(&files, "store.Missing").is_none());
+    }
+
+    #[test]
+    fn connect_retries() {
+        // connections aren't retried unless asked to be
+        let opts = Options::try_parse_from(["cli", "count"]).unwrap();
+        assert_eq!((opts.connect_retries, opts.connect_backoff_ms), (0, 100));
+        let args = [
+            "cli",
+            "count",
+            "--connect-retries",
+            "3",
+            "--connect-backoff-ms",
+            "50",
+        ];
+        let opts = Options::try_parse_from(args).unwrap();
+        assert_eq!((opts.connect_retries, opts.connect_backoff_ms), (3, 50));
+
+        // the first attempt is followed by the retries, whose backoff
+        // doubles up to a limit
+        let backoff = connect_backoff(&opts);
+        let expected = Backoff {
+            attempts: 4,
+            min: Duration::from_millis(50),
+            max: MAX_CONNECT_BACKOFF,
+        };
+        assert_eq!(backoff, expected);
+    }
+
+    #[tokio::test]
+    async fn connect_retries_run_out() {
+        // nothing listens on the port once the listener is dropped
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let address = parse_endpoint(&format!("http://{}", addr)).unwrap();
+        let backoff = Backoff {
+            attempts: 3,
+            min: Duration::from_millis(1),
+            max: Duration::from_millis(1),
+        };
+        let err = pooled_channel(&address, &address.pool(backoff))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("after 3 attempts"), "{}", err);
+
+        // as do connections to sockets which don't exist
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("store.sock");
+        let address = parse_endpoint(&format!("unix://{}", socket.display())).unwrap();
+        let err = pooled_channel(&address, &address.pool(backoff))
+            .await
+            .unwrap_err();
+        let expected = format!("at unix://{} after 3 attempts", socket.display());
+        assert!(err.to_string().contains(&expected), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn unix_domain_sockets() {
+        // a socket accepting connections in place of a server, whose
+        // connections are only held open
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("store.sock");
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        let accepted =
+            tokio::spawn(async move { listener.accept().await.map(|(stream, _)| stream) });
+
+        let address = parse_endpoint(&format!("unix://{}", socket.display())).unwrap();
+        let _channel = address.pool(Backoff::default()).channel().await.unwrap();
+        assert!(accepted.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn dry_runs() {
+        // nothing listens on the port, so any attempt to connect would fail
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        // the flag can be given before or after the command, and dry runs of
+        // commands other than add don't contact the server
+        for args in [
+            vec!["cli", "--dry-run", "remove", "--sku", "A"],
+            vec!["cli", "remove", "--sku", "A", "--strict", "--dry-run"],
+            vec!["cli", "update-quantity", "--sku", "A", "--change", "-3"],
+            vec!["cli", "set-quantity", "--sku", "A", "--quantity", "3"],
+            vec!["cli", "set-reorder-threshold", "--sku", "A"],
+            vec![
+                "cli",
+                "update-price",
+                "--sku",
+                "A",
+                "--price",
+                "2",
+                "--currency",
+                "EUR",
+            ],
+            vec!["cli", "update-information", "--sku", "A", "--name", "apple"],
+        ] {
+            let mut args = args;
+            if !args.contains(&"--dry-run") {
+                args.push("--dry-run");
+            }
+            args.extend(["--endpoint", &endpoint]);
+            let opts = Options::try_parse_from(&args).unwrap();
+            assert!(opts.dry_run && opts.command.supports_dry_run());
+            run(opts).await.unwrap();
+        }
+
+        // while dry runs of add have the server validate the item
+        for args in [
+            vec!["cli", "--dry-run", "add", "--sku", "A", "--price", "1.79"],
+            vec![
+                "cli",
+                "--dry-run",
+                "add",
+                "--sku",
+                "A",
+                "--price",
+                "1",
+                "--upsert",
+            ],
+        ] {
+            let mut args = args;
+            args.extend(["--endpoint", &endpoint]);
+            let opts = Options::try_parse_from(&args).unwrap();
+            let err = run(opts).await.unwrap_err();
+            assert!(err.to_string().contains("couldn't connect"), "{}", err);
+        }
+
+        // commands which don't change a single item can't be run dry, and dry
+        // runs have no JSON form
+        for args in [
+            vec!["cli", "--dry-run", "count"],
+            vec!["cli", "--dry-run", "batch-remove"],
+            vec![
+                "cli",
+                "--dry-run",
+                "--format",
+                "json",
+                "remove",
+                "--sku",
+                "A",
+            ],
+        ] {
+            let mut args = args;
+            args.extend(["--endpoint", &endpoint]);
+            let opts = Options::try_parse_from(&args).unwrap();
+            let err = run(opts).await.unwrap_err();
+            assert!(err.to_string().contains("dry run"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn batch_get_options() {
+        let opts =
+            Options::try_parse_from(["cli", "batch-get", "--sku", "A", "--sku", "B"]).unwrap();
+        match opts.command {
+            Command::BatchGet(opts) => assert_eq!(opts.skus, ["A", "B"]),
+            command => panic!("unexpected command: {:?}", command),
+        }
+
+        // at least one SKU must be given
+        assert!(Options::try_parse_from(["cli", "batch-get"]).is_err());
+    }
+
+    #[test]
+    fn restore_options() {
+        let opts = Options::try_parse_from(["cli", "restore", "--overwrite"]).unwrap();
+        match opts.command {
+            Command::Restore(opts) => assert!(opts.overwrite && !opts.skip_existing),
+            command => panic!("unexpected command: {:?}", command),
+        }
+
+        // existing items can be skipped or overwritten, but not both
+        let args = ["cli", "restore", "--overwrite", "--skip-existing"];
+        assert!(Options::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn request_ids() {
+        // the id can be given before or after the command, like other options
+        for args in [
+            ["cli", "--request-id", "request-1", "count"],
+            ["cli", "count", "--request-id", "request-1"],
+        ] {
+            let opts = Options::try_parse_from(args).unwrap();
+            assert_eq!(opts.request_id.as_deref(), Some("request-1"));
+        }
+        let opts = Options::try_parse_from(["cli", "count"]).unwrap();
+        assert_eq!(opts.request_id, None);
+
+        // nothing is reported until the server echoes an id
+        let request_id = RequestId::default();
+        assert_eq!(request_id.echoed(), None);
+        *request_id.clone().echoed.lock().unwrap() = Some("generated".into());
+        assert_eq!(request_id.echoed().as_deref(), Some("generated"));
+    }
+
+    #[test]
+    fn api_keys() {
+        let mut api_key = ApiKey(Some("Bearer key".parse().unwrap()));
+        let authorization = |request: &tonic::Request<()>| {
+            let value = request.metadata().get("authorization");
+            value.map(|value| value.to_str().unwrap().to_string())
+        };
+
+        // the API key is attached to requests without other credentials
+        let request = api_key.call(tonic::Request::new(())).unwrap();
+        assert_eq!(authorization(&request).as_deref(), Some("Bearer key"));
+
+        // credentials a request already carries (e.g. the admin token) are kept
+        let mut request = tonic::Request::new(());
+        let admin = "Bearer admin".parse().unwrap();
+        request.metadata_mut().insert("authorization", admin);
+        let request = api_key.call(request).unwrap();
+        assert_eq!(authorization(&request).as_deref(), Some("Bearer admin"));
+
+        // nothing is attached without an API key
+        let request = ApiKey(None).call(tonic::Request::new(())).unwrap();
+        assert_eq!(authorization(&request), None);
+    }
+
+    #[test]
+    fn change_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("changes.log");
+        let read = |path: &std::path::Path| std::fs::read_to_string(path).unwrap();
+
+        // lines are appended to what's already in the file, unless truncated
+        std::fs::write(&path, "old\n").unwrap();
+        let mut log = ChangeLog::open(path.clone(), false, None).unwrap();
+        log.append("first").unwrap();
+        log.close().unwrap();
+        assert_eq!(read(&path), "old\nfirst\n");
+        let mut log = ChangeLog::open(path.clone(), true, None).unwrap();
+        log.append("second").unwrap();
+        assert_eq!(read(&path), "second\n");
+
+        // files which would grow too large are moved aside, with lines never
+        // split between them
+        let mut log = ChangeLog::open(path.clone(), true, Some(10)).unwrap();
+        for line in ["one", "two", "three"] {
+            log.append(line).unwrap();
+        }
+        log.close().unwrap();
+        assert_eq!(read(&dir.path().join("changes.log.1")), "one\ntwo\n");
+        assert_eq!(read(&path), "three\n");
+
+        // the size limit must leave room for something
+        let args = ["cli", "tail", "--output", "changes.log", "--max-bytes", "0"];
+        assert!(Options::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn stockless_items() {
+        // items without stock are described as such rather than as empty
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "NOSTOCK".into(),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(describe_stock(Some(&item)), "(no stock)");
+        assert_eq!(describe_stock(None), "(none)");
+        assert_eq!(
+            serde_json::to_string(&item).unwrap(),
+            r#"{"identifier":{"sku":"NOSTOCK"}}"#
+        );
+
+        let item = Item {
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..item
+        };
+        assert_eq!(describe_stock(Some(&item)), "42 at $1.79");
+    }
+
+    #[test]
+    fn physical() {
+        // items are only given physical attributes when one of them is set
+        let item = |args: &[&str]| {
+            let base = ["cli", "add", "--sku", "A", "--price", "1.79"];
+            let args = base.iter().chain(args);
+            match Options::try_parse_from(args).unwrap().command {
+                Command::Add(opts) => opts.item.into_item(),
+                command => panic!("unexpected command {:?}", command),
+            }
+        };
+        assert_eq!(item(&[]).physical, None);
+        let physical = item(&["--weight-grams", "180", "--height-mm", "75.5"])
+            .physical
+            .unwrap();
+        assert_eq!(
+            physical,
+            ItemPhysical {
+                weight_grams: 180.0,
+                height_mm: 75.5,
+                ..Default::default()
+            }
+        );
+        assert_eq!(describe_physical(&physical), "180 g, 0 x 0 x 75.5 mm");
+
+        let opts =
+            Options::try_parse_from(["cli", "list-by-weight", "--max-grams", "500"]).unwrap();
+        assert!(matches!(
+            opts.command,
+            Command::ListByWeight(opts) if opts.min_grams == 0.0 && opts.max_grams == Some(500.0)
+        ));
+    }
 }
@@ -0,0 +1,217 @@
+use futures::{future, Stream, StreamExt};
+use tonic::body::BoxBody;
+use tonic::client::GrpcService;
+use tonic::codegen::{Body, Bytes, StdError};
+use tonic::transport::{Channel, Endpoint, Error};
+use tonic::{Code, Status};
+
+use crate::store::inventory_client::InventoryClient;
+use crate::store::watch_event::Event;
+use crate::store::{
+    GetRequest, Item, ItemIdentifier, ItemStock, PriceChangeRequest, QuantityChangeRequest,
+    RemoveRequest, WatchEvent, WatchRequest,
+};
+
+// -----------------------------------------------------------------------------
+// Inventory API
+// -----------------------------------------------------------------------------
+
+/// A client for the inventory server, which wraps the generated
+/// InventoryClient with methods taking and returning plain values. Items
+/// which aren't in the inventory are reported as None rather than as
+/// NOT_FOUND errors, other errors are returned as the server sent them.
+///
+/// ```no_run
+/// use demo::client::InventoryApi;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut api = InventoryApi::connect("http://127.0.0.1:9001").await?;
+/// api.add_item("APPLE", 1.79, 42).await?;
+/// if let Some(item) = api.get_item("APPLE").await? {
+///     println!("found {:?}", item);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InventoryApi<T = Channel> {
+    client: InventoryClient<T>,
+}
+
+impl InventoryApi {
+    /// Connects to the inventory server at the given address, e.g.
+    /// "http://127.0.0.1:9001".
+    pub async fn connect<D>(dst: D) -> Result<Self, Error>
+    where
+        D: TryInto<Endpoint>,
+        D::Error: Into<StdError>,
+    {
+        Ok(InventoryApi::new(Endpoint::new(dst)?.connect().await?))
+    }
+}
+
+impl<T> InventoryApi<T>
+where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    /// Creates a client which sends its requests over an existing channel,
+    /// e.g. one configured with timeouts or handed out by a
+    /// [`ClientPool`](crate::pool::ClientPool).
+    pub fn new(channel: T) -> Self {
+        InventoryApi {
+            client: InventoryClient::new(channel),
+        }
+    }
+
+    /// Adds an item with the given stock to the inventory, returning the item
+    /// as it was stored (e.g. with its price rounded to the cent).
+    ///
+    /// ```no_run
+    /// # use demo::client::InventoryApi;
+    /// # async fn example(mut api: InventoryApi) -> Result<(), tonic::Status> {
+    /// let item = api.add_item("APPLE", 1.789, 42).await?;
+    /// assert_eq!(item.stock.unwrap().price, 1.79);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn add_item(
+        &mut self,
+        sku: impl Into<String>,
+        price: f32,
+        quantity: u32,
+    ) -> Result<Item, Status> {
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let response = self.client.add(item.clone()).await?.into_inner();
+        // servers from before items were returned only report the status
+        Ok(response.item.unwrap_or(item))
+    }
+
+    /// Retrieves an item, which is None if it isn't in the inventory.
+    ///
+    /// ```no_run
+    /// # use demo::client::InventoryApi;
+    /// # async fn example(mut api: InventoryApi) -> Result<(), tonic::Status> {
+    /// assert_eq!(api.get_item("DOESNTEXIST").await?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_item(&mut self, sku: impl Into<String>) -> Result<Option<Item>, Status> {
+        let request = GetRequest {
+            sku: sku.into(),
+            ..Default::default()
+        };
+        let response = not_found_as_none(self.client.get(request).await)?;
+        Ok(response.map(|response| response.into_inner()))
+    }
+
+    /// Removes an item, returning whether it was in the inventory.
+    pub async fn remove_item(&mut self, sku: impl Into<String>) -> Result<bool, Status> {
+        let request = RemoveRequest {
+            sku: sku.into(),
+            strict: true,
+        };
+        let removed = not_found_as_none(self.client.remove(request).await)?;
+        Ok(removed.is_some())
+    }
+
+    /// Changes the quantity of an item by the given amount, returning the
+    /// resulting quantity, or None if the item isn't in the inventory.
+    pub async fn update_quantity(
+        &mut self,
+        sku: impl Into<String>,
+        change: i32,
+    ) -> Result<Option<u32>, Status> {
+        let request = QuantityChangeRequest {
+            sku: sku.into(),
+            change,
+        };
+        let response = not_found_as_none(self.client.update_quantity(request).await)?;
+        Ok(response.map(|response| response.into_inner().quantity))
+    }
+
+    /// Changes the price of an item, returning the resulting price, or None if
+    /// the item isn't in the inventory.
+    pub async fn update_price(
+        &mut self,
+        sku: impl Into<String>,
+        price: f32,
+    ) -> Result<Option<f32>, Status> {
+        let request = PriceChangeRequest {
+            sku: sku.into(),
+            price,
+            ..Default::default()
+        };
+        let response = not_found_as_none(self.client.update_price(request).await)?;
+        Ok(response.map(|response| response.into_inner().price))
+    }
+
+    /// Streams an item each time it changes, which is None if the item isn't
+    /// in the inventory. The stream ends once the item is removed, and the
+    /// heartbeats the server may send are skipped.
+    ///
+    /// ```no_run
+    /// # use demo::client::InventoryApi;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn example(mut api: InventoryApi) -> Result<(), tonic::Status> {
+    /// if let Some(mut changes) = api.watch_item("APPLE").await? {
+    ///     while let Some(item) = changes.next().await {
+    ///         println!("changed: {:?}", item?);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn watch_item(
+        &mut self,
+        sku: impl Into<String>,
+    ) -> Result<Option<impl Stream<Item = Result<Item, Status>> + Unpin>, Status> {
+        let request = WatchRequest {
+            sku: sku.into(),
+            from_version: None,
+        };
+        let stream = match not_found_as_none(self.client.watch(request).await)? {
+            Some(response) => response.into_inner(),
+            None => return Ok(None),
+        };
+
+        let changes = stream
+            .take_while(|event| future::ready(!is_not_found(event)))
+            .filter_map(|event| {
+                future::ready(match event {
+                    Ok(WatchEvent {
+                        event: Some(Event::Item(item)),
+                    }) => Some(Ok(item)),
+                    Ok(_) => None,
+                    Err(status) => Some(Err(status)),
+                })
+            });
+        Ok(Some(changes))
+    }
+}
+
+// maps a NOT_FOUND error to None, leaving other errors as they are.
+#[allow(clippy::result_large_err)]
+fn not_found_as_none<T>(result: Result<T, Status>) -> Result<Option<T>, Status> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(status) if status.code() == Code::NotFound => Ok(None),
+        Err(status) => Err(status),
+    }
+}
+
+// reports whether a watch ended because its item was removed.
+fn is_not_found<T>(event: &Result<T, Status>) -> bool {
+    matches!(event, Err(status) if status.code() == Code::NotFound)
+}
@@ -0,0 +1,393 @@
+use bytes::{Buf, Bytes, BytesMut};
+use flate2::read::GzDecoder;
+use http::HeaderMap;
+use http_body::Body;
+use std::io::Read;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tonic::server::NamedService;
+use tonic::Status;
+use tower::Service;
+
+use crate::error::InventoryError;
+
+// -----------------------------------------------------------------------------
+// Decompression Guard
+// -----------------------------------------------------------------------------
+
+/// The largest size of a message accepted by default, in bytes.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+// the metadata header which names the encoding request messages are
+// compressed with.
+const ENCODING_HEADER: &str = "grpc-encoding";
+
+// the length of the prefix of each message in a gRPC request body, which is a
+// flag for whether the message is compressed followed by its length.
+const MESSAGE_PREFIX_LEN: usize = 5;
+
+/// Wraps a gRPC service to reject request messages which are larger than a
+/// maximum size once decompressed, with `invalid_argument`. tonic 0.8 inflates
+/// gzip messages without any limit, so compressed messages are measured
+/// before they're handed to the service: they're inflated into nothing, and
+/// given up on as soon as they pass the maximum.
+#[derive(Debug, Clone)]
+pub struct DecompressionGuard<S> {
+    inner: S,
+    max_message_size: usize,
+}
+
+impl<S> DecompressionGuard<S> {
+    pub fn new(inner: S, max_message_size: usize) -> Self {
+        DecompressionGuard {
+            inner,
+            max_message_size,
+        }
+    }
+}
+
+impl<S: NamedService> NamedService for DecompressionGuard<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> Service<http::Request<B>> for DecompressionGuard<S>
+where
+    S: Service<http::Request<GuardedBody<B>>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let gzip = match request.headers().get(ENCODING_HEADER) {
+            Some(encoding) => encoding == "gzip",
+            None => false,
+        };
+        let max_message_size = self.max_message_size;
+        self.inner.call(request.map(|body| GuardedBody {
+            inner: body,
+            gzip,
+            max_message_size,
+            pending: BytesMut::new(),
+            failed: false,
+        }))
+    }
+}
+
+/// A request body which only releases messages once they're known to be
+/// within the maximum size, see DecompressionGuard.
+#[derive(Debug)]
+pub struct GuardedBody<B> {
+    inner: B,
+    // whether compressed messages are gzip encoded, compressed messages in any
+    // other encoding can't be measured so they're rejected.
+    gzip: bool,
+    max_message_size: usize,
+    // data received which doesn't make up a whole message yet.
+    pending: BytesMut,
+    failed: bool,
+}
+
+impl<B> GuardedBody<B> {
+    // takes the next message from the pending data if it has all arrived,
+    // failing if it's too large.
+    fn next_message(&mut self) -> Result<Option<Bytes>, InventoryError> {
+        if self.pending.len() < MESSAGE_PREFIX_LEN {
+            return Ok(None);
+        }
+
+        // compressed messages are never expected to be larger than they are
+        // once decompressed, so both are held to the maximum.
+        let compressed = self.pending[0] == 1;
+        let mut len = [0; 4];
+        len.copy_from_slice(&self.pending[1..MESSAGE_PREFIX_LEN]);
+        let len = u32::from_be_bytes(len) as usize;
+        if len > self.max_message_size {
+            return Err(InventoryError::BigMessage);
+        }
+        if self.pending.len() < MESSAGE_PREFIX_LEN + len {
+            return Ok(None);
+        }
+
+        if compressed {
+            if !self.gzip {
+                return Err(InventoryError::BadCompression);
+            }
+            let message = &self.pending[MESSAGE_PREFIX_LEN..MESSAGE_PREFIX_LEN + len];
+            gunzipped_size(message, self.max_message_size)?;
+        }
+
+        Ok(Some(
+            self.pending.split_to(MESSAGE_PREFIX_LEN + len).freeze(),
+        ))
+    }
+}
+
+// measures the size of a gzip compressed message once decompressed, failing
+// as soon as it passes the limit.
+fn gunzipped_size(gzip: &[u8], limit: usize) -> Result<usize, InventoryError> {
+    let mut decoder = GzDecoder::new(gzip).take(limit as u64 + 1);
+    let size = std::io::copy(&mut decoder, &mut std::io::sink())
+        .map_err(|_| InventoryError::BadCompression)? as usize;
+    if size > limit {
+        return Err(InventoryError::BigMessage);
+    }
+    Ok(size)
+}
+
+impl<B> Body for GuardedBody<B>
+where
+    B: Body + Unpin,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.get_mut();
+        if this.failed {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match this.next_message() {
+                Ok(Some(message)) => return Poll::Ready(Some(Ok(message))),
+                Ok(None) => {}
+                Err(err) => {
+                    this.failed = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+            }
+
+            match ready!(Pin::new(&mut this.inner).poll_data(cx)) {
+                Some(Ok(mut data)) => {
+                    while data.has_remaining() {
+                        let chunk = data.chunk();
+                        let len = chunk.len();
+                        this.pending.extend_from_slice(chunk);
+                        data.advance(len);
+                    }
+                }
+                Some(Err(err)) => return Poll::Ready(Some(Err(Status::from_error(err.into())))),
+                // a partial message is passed on as is for the service to
+                // report as truncated.
+                None if this.pending.is_empty() => return Poll::Ready(None),
+                None => return Poll::Ready(Some(Ok(this.pending.split().freeze()))),
+            }
+        }
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        Pin::new(&mut self.get_mut().inner)
+            .poll_trailers(cx)
+            .map_err(|err| Status::from_error(err.into()))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.pending.is_empty() && self.inner.is_end_stream()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use http_body::Body as _;
+    use std::io::Write;
+    use std::println as info;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::codec::CompressionEncoding;
+    use tonic::transport::{Body, Server};
+    use tonic::{Request, Status};
+    use tower::{service_fn, ServiceExt};
+
+    use crate::compression::{
+        gunzipped_size, DecompressionGuard, GuardedBody, DEFAULT_MAX_MESSAGE_SIZE,
+    };
+    use crate::error::InventoryError;
+    use crate::server::StoreInventory;
+    use crate::store::inventory_client::InventoryClient;
+    use crate::store::inventory_server::InventoryServer;
+    use crate::store::{Item, ItemIdentifier, ItemInformation, ItemStock, ListRequest};
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    // frames a message as it's sent in a gRPC request body.
+    fn frame(compressed: bool, message: &[u8]) -> Vec<u8> {
+        let mut frame = vec![compressed as u8];
+        frame.extend((message.len() as u32).to_be_bytes());
+        frame.extend(message);
+        frame
+    }
+
+    // sends a request body through the guard, returning the data the service
+    // received or the status the guard failed the body with.
+    async fn guard(
+        body: Vec<u8>,
+        encoding: Option<&str>,
+        max_message_size: usize,
+    ) -> Result<Vec<u8>, Status> {
+        let service = service_fn(|request: http::Request<GuardedBody<Body>>| async move {
+            let mut body = request.into_body();
+            let mut received = Vec::new();
+            while let Some(data) = body.data().await {
+                received.extend(data?);
+            }
+            Ok::<_, Status>(received)
+        });
+
+        let mut request = http::Request::post("/store.Inventory/Add");
+        if let Some(encoding) = encoding {
+            request = request.header("grpc-encoding", encoding);
+        }
+        let request = request.body(Body::from(body)).unwrap();
+        DecompressionGuard::new(service, max_message_size)
+            .oneshot(request)
+            .await
+    }
+
+    #[test]
+    fn gunzipped_sizes() {
+        let compressed = gzip(&[b'a'; 10_000]);
+        assert_eq!(gunzipped_size(&compressed, 10_000), Ok(10_000));
+        assert_eq!(
+            gunzipped_size(&compressed, 9_999),
+            Err(InventoryError::BigMessage)
+        );
+        assert_eq!(
+            gunzipped_size(&compressed[..20], 10_000),
+            Err(InventoryError::BadCompression)
+        );
+        assert_eq!(
+            gunzipped_size(b"not gzip", 10_000),
+            Err(InventoryError::BadCompression)
+        );
+    }
+
+    #[tokio::test]
+    async fn decompression_bombs() -> Result<(), Error> {
+        let max_message_size = 64 * 1024;
+
+        info!("verifying messages within the maximum are passed on as is");
+        let messages = [frame(false, b"message"), frame(true, &gzip(&[b'a'; 1000]))];
+        for message in messages {
+            let received = guard(message.clone(), Some("gzip"), max_message_size).await?;
+            assert_eq!(received, message);
+        }
+
+        info!("verifying a small message which expands enormously is rejected");
+        let bomb = gzip(&vec![b'a'; 8 * 1024 * 1024]);
+        assert!(bomb.len() < max_message_size);
+        let status = guard(frame(true, &bomb), Some("gzip"), max_message_size)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BigMessage)
+        );
+
+        info!("verifying large and unmeasurable messages are rejected");
+        let large = frame(false, &vec![0; max_message_size + 1]);
+        let status = guard(large, None, max_message_size).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BigMessage)
+        );
+        let status = guard(frame(true, &bomb), Some("zstd"), max_message_size)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadCompression)
+        );
+        let status = guard(frame(true, b"not gzip"), Some("gzip"), max_message_size)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadCompression)
+        );
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn gzip_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        // a server set up as the server binary sets it up, with compression
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let inventory = InventoryServer::new(StoreInventory::default())
+            .accept_compressed(CompressionEncoding::Gzip)
+            .send_compressed(CompressionEncoding::Gzip);
+        tokio::spawn(
+            Server::builder()
+                .add_service(DecompressionGuard::new(inventory, DEFAULT_MAX_MESSAGE_SIZE))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        let mut client = InventoryClient::connect(format!("http://{}", addr))
+            .await?
+            .send_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Gzip);
+
+        info!("adding items with long descriptions in compressed requests");
+        let description = "a long and very repetitive description ".repeat(100);
+        for i in 0..500 {
+            let item = Item {
+                identifier: Some(ItemIdentifier {
+                    sku: format!("SKU{:03}", i),
+                }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: i,
+                    ..Default::default()
+                }),
+                information: Some(ItemInformation {
+                    name: Some(format!("item {}", i)),
+                    description: Some(description.clone()),
+                }),
+                ..Default::default()
+            };
+            client.add(Request::new(item)).await?;
+        }
+
+        info!("listing them all in a single compressed response");
+        let request = Request::new(ListRequest {
+            page_size: 1000,
+            ..Default::default()
+        });
+        let response = client.list(request).await?;
+        let encoding = response.metadata().get("grpc-encoding");
+        assert_eq!(encoding.and_then(|value| value.to_str().ok()), Some("gzip"));
+        let items = response.into_inner().items;
+        assert_eq!(items.len(), 500);
+        for (i, item) in items.iter().enumerate() {
+            assert_eq!(
+                item.identifier.as_ref().unwrap().sku,
+                format!("SKU{:03}", i)
+            );
+            let information = item.information.as_ref().unwrap();
+            assert_eq!(information.description.as_ref(), Some(&description));
+        }
+
+        Ok(())
+    }
+}
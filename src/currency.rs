@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::{fs, io};
+
+// -----------------------------------------------------------------------------
+// Currency Conversion
+// -----------------------------------------------------------------------------
+
+/// The currency items are priced in unless they say otherwise, which exchange
+/// rates are relative to.
+pub const BASE_CURRENCY: &str = "USD";
+
+/// The ISO 4217 codes of the currencies items can be priced in.
+pub const KNOWN_CURRENCIES: &[&str] = &[
+    "AUD", "BRL", "CAD", "CHF", "CNY", "CZK", "DKK", "EUR", "GBP", "HKD", "HUF", "IDR", "ILS",
+    "INR", "JPY", "KRW", "MXN", "MYR", "NOK", "NZD", "PHP", "PLN", "SEK", "SGD", "THB", "TRY",
+    "USD", "ZAR",
+];
+
+/// Reports whether the given currency code (in any case) is one items can be
+/// priced in.
+pub fn is_known_currency(currency: &str) -> bool {
+    KNOWN_CURRENCIES.contains(&currency.to_uppercase().as_str())
+}
+
+/// Exchange rates from the base currency into other currencies, used to
+/// convert prices for display and to total values across currencies.
+/// Conversion never changes stored prices.
+#[derive(Debug, Clone, Default)]
+pub struct RateTable {
+    rates: HashMap<String, f32>,
+}
+
+impl RateTable {
+    /// Creates a rate table from pairs of currency codes and the amount of
+    /// that currency one unit of the base currency buys.
+    pub fn new<I, S>(rates: I) -> Self
+    where
+        I: IntoIterator<Item = (S, f32)>,
+        S: Into<String>,
+    {
+        RateTable {
+            rates: rates
+                .into_iter()
+                .map(|(currency, rate)| (currency.into().to_uppercase(), rate))
+                .collect(),
+        }
+    }
+
+    /// Loads a rate table from a file containing one currency code and rate
+    /// per line (e.g. "EUR 0.92"). Blank lines and lines starting with '#' are
+    /// ignored.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut rates = Vec::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, line.to_string());
+            let mut fields = line.split_whitespace();
+            let currency = fields.next().ok_or_else(invalid)?;
+            let rate = fields
+                .next()
+                .and_then(|rate| rate.parse::<f32>().ok())
+                .filter(|rate| *rate > 0.0)
+                .ok_or_else(invalid)?;
+            rates.push((currency.to_string(), rate));
+        }
+
+        Ok(RateTable::new(rates))
+    }
+
+    /// Converts a price in the base currency into the given currency, rounded
+    /// to the nearest hundredth (halves round away from zero). Returns None if
+    /// there's no rate for the currency.
+    pub fn convert(&self, price: f32, currency: &str) -> Option<f32> {
+        let currency = currency.to_uppercase();
+        if currency == BASE_CURRENCY {
+            return Some(price);
+        }
+
+        let rate = self.rates.get(&currency)?;
+        Some((price * rate * 100.0).round() / 100.0)
+    }
+
+    /// Converts a price in any currency into another through the base
+    /// currency, rounded like convert. Returns None if there's no rate for
+    /// either currency.
+    pub fn exchange(&self, price: f32, from: &str, to: &str) -> Option<f32> {
+        if from.eq_ignore_ascii_case(to) {
+            return Some(price);
+        }
+
+        let price = price / self.rate(from)? * self.rate(to)?;
+        Some((price * 100.0).round() / 100.0)
+    }
+
+    /// The amount of the given currency one unit of the base currency buys,
+    /// or None if there's no rate for the currency.
+    pub fn rate(&self, currency: &str) -> Option<f32> {
+        let currency = currency.to_uppercase();
+        if currency == BASE_CURRENCY {
+            return Some(1.0);
+        }
+
+        self.rates.get(&currency).copied()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::currency::{is_known_currency, RateTable};
+
+    #[test]
+    fn conversion() {
+        let rates = RateTable::new([("EUR", 0.5), ("jpy", 150.0)]);
+
+        assert_eq!(rates.convert(2.49, "USD"), Some(2.49));
+        assert_eq!(rates.convert(2.49, "EUR"), Some(1.25));
+        assert_eq!(rates.convert(2.49, "eur"), Some(1.25));
+        assert_eq!(rates.convert(1.79, "JPY"), Some(268.5));
+        assert_eq!(rates.convert(1.79, "GBP"), None);
+    }
+
+    #[test]
+    fn exchange() {
+        let rates = RateTable::new([("EUR", 0.5), ("JPY", 150.0)]);
+
+        assert_eq!(rates.exchange(2.49, "EUR", "eur"), Some(2.49));
+        assert_eq!(rates.exchange(2.49, "USD", "EUR"), Some(1.25));
+        assert_eq!(rates.exchange(1.25, "EUR", "USD"), Some(2.5));
+        assert_eq!(rates.exchange(1.0, "EUR", "JPY"), Some(300.0));
+        assert_eq!(rates.exchange(1.0, "GBP", "USD"), None);
+        assert_eq!(rates.exchange(1.0, "USD", "GBP"), None);
+    }
+
+    #[test]
+    fn known_currencies() {
+        assert!(is_known_currency("USD"));
+        assert!(is_known_currency("eur"));
+        assert!(!is_known_currency("XYZ"));
+        assert!(!is_known_currency(""));
+    }
+
+    #[test]
+    fn rates_from_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "# rates from USD\nEUR 0.5\n\nJPY 150").unwrap();
+
+        let rates = RateTable::from_file(file.path()).unwrap();
+        assert_eq!(rates.convert(2.49, "EUR"), Some(1.25));
+        assert_eq!(rates.convert(1.79, "JPY"), Some(268.5));
+
+        writeln!(file, "GBP lots").unwrap();
+        assert!(RateTable::from_file(file.path()).is_err());
+    }
+}
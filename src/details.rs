@@ -0,0 +1,161 @@
+use bytes::Bytes;
+use prost::Message;
+use prost_types::Any;
+use tonic::{Code, Status};
+
+use crate::error::InventoryError;
+
+// -----------------------------------------------------------------------------
+// Error Details
+// -----------------------------------------------------------------------------
+
+// the type URL BadRequest details are packed into google.protobuf.Any with.
+const BAD_REQUEST_TYPE_URL: &str = "type.googleapis.com/google.rpc.BadRequest";
+
+/// The google.rpc.Status message, which carries the details of a failed
+/// request in its grpc-status-details-bin metadata following the gRPC richer
+/// error model.
+#[derive(Clone, PartialEq, Message)]
+pub struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    pub code: i32,
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(message, repeated, tag = "3")]
+    pub details: Vec<Any>,
+}
+
+/// The google.rpc.BadRequest message, which describes the fields of a
+/// request that were invalid.
+#[derive(Clone, PartialEq, Message)]
+pub struct BadRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub field_violations: Vec<FieldViolation>,
+}
+
+/// A field of a request which was invalid, named by its path within the
+/// request (e.g. "identifier.sku") in the proto's field names.
+#[derive(Clone, PartialEq, Message)]
+pub struct FieldViolation {
+    #[prost(string, tag = "1")]
+    pub field: String,
+    #[prost(string, tag = "2")]
+    pub description: String,
+}
+
+/// Rejects a request with an invalid field for the error, along with a
+/// BadRequest naming the field in the status details, so that clients don't
+/// need to match on messages.
+pub fn bad_request(field: &str, error: InventoryError) -> Status {
+    let status = Status::from(error);
+    let violations = vec![FieldViolation {
+        field: field.into(),
+        description: error.message().into(),
+    }];
+    let details = encode_details(status.code(), status.message(), violations);
+    Status::with_details_and_metadata(
+        status.code(),
+        status.message(),
+        details,
+        status.metadata().clone(),
+    )
+}
+
+/// Retrieves the fields a request was rejected for from the BadRequest in its
+/// status details, which is empty if there is none.
+pub fn field_violations(status: &Status) -> Vec<FieldViolation> {
+    let details = match RpcStatus::decode(status.details()) {
+        Ok(details) => details.details,
+        Err(_) => return Vec::new(),
+    };
+    details
+        .iter()
+        .filter(|any| any.type_url == BAD_REQUEST_TYPE_URL)
+        .filter_map(|any| BadRequest::decode(any.value.as_slice()).ok())
+        .flat_map(|bad_request| bad_request.field_violations)
+        .collect()
+}
+
+/// Moves the fields named by a status into a parent field, for validations
+/// shared between requests which hold the fields at different paths (e.g.
+/// the SKU of an Item is at "identifier.sku"). Other statuses are unchanged.
+pub fn within(parent: &str, status: Status) -> Status {
+    let violations = field_violations(&status);
+    if violations.is_empty() {
+        return status;
+    }
+
+    let violations = violations
+        .into_iter()
+        .map(|violation| FieldViolation {
+            field: format!("{}.{}", parent, violation.field),
+            ..violation
+        })
+        .collect();
+    let details = encode_details(status.code(), status.message(), violations);
+    Status::with_details_and_metadata(
+        status.code(),
+        status.message(),
+        details,
+        status.metadata().clone(),
+    )
+}
+
+// encodes the details of a status holding a BadRequest with the violations.
+fn encode_details(code: Code, message: &str, violations: Vec<FieldViolation>) -> Bytes {
+    let bad_request = BadRequest {
+        field_violations: violations,
+    };
+    let details = RpcStatus {
+        code: code as i32,
+        message: message.into(),
+        details: vec![Any {
+            type_url: BAD_REQUEST_TYPE_URL.into(),
+            value: bad_request.encode_to_vec(),
+        }],
+    };
+    details.encode_to_vec().into()
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use tonic::{Code, Status};
+
+    use crate::details::{bad_request, field_violations, within};
+    use crate::error::InventoryError;
+
+    #[test]
+    fn field_violations_round_trip() {
+        let status = bad_request("sku", InventoryError::EmptySku);
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "provided SKU was empty");
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::EmptySku)
+        );
+        let violations = field_violations(&status);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].field, "sku");
+        assert_eq!(violations[0].description, "provided SKU was empty");
+
+        // the details survive being sent as metadata
+        let sent = Status::from_header_map(status.clone().to_http().headers()).unwrap();
+        assert_eq!(field_violations(&sent), violations);
+
+        let nested = within("identifier", status);
+        assert_eq!(field_violations(&nested)[0].field, "identifier.sku");
+        assert_eq!(
+            InventoryError::from_status(&nested),
+            Some(InventoryError::EmptySku)
+        );
+
+        // statuses without details have no violations, and aren't nested
+        let plain = Status::invalid_argument("invalid");
+        assert!(field_violations(&plain).is_empty());
+        assert!(field_violations(&within("identifier", plain)).is_empty());
+    }
+}
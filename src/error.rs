@@ -0,0 +1,348 @@
+use std::fmt;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::{Code, Status};
+
+// -----------------------------------------------------------------------------
+// Inventory Errors
+// -----------------------------------------------------------------------------
+
+/// The metadata header errors carry their reason in.
+pub const REASON_HEADER: &str = "x-error-code";
+
+/// The errors the inventory rejects requests with. Each error is sent with a
+/// fixed status code, message and reason, so clients can match on the error a
+/// status carries (see InventoryError::from_status) rather than on its
+/// message, which may have been translated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InventoryError {
+    AdminDisabled,
+    BadAdminToken,
+    BadApiKey,
+    BadAttribute,
+    BadBuckets,
+    BadCompression,
+    BadContentType,
+    BadCurrency,
+    BadMessage,
+    BadPageToken,
+    BadPhysical,
+    BadPrice,
+    BadStreamId,
+    BadTag,
+    BadWeightRange,
+    BigAttributes,
+    BigBatch,
+    BigDescription,
+    BigImage,
+    BigInitialQuantity,
+    BigMessage,
+    BigName,
+    BigQuantity,
+    BigSku,
+    BigTags,
+    CurrencyMismatch,
+    DuplicateItem,
+    EmptyImage,
+    EmptyQuantity,
+    EmptyQuery,
+    EmptySku,
+    InsufficientInventory,
+    MixedCurrencies,
+    NoId,
+    NoImage,
+    NoItem,
+    NoOperation,
+    NoStock,
+    NoStockToUpdate,
+    OverRelease,
+    OverReserve,
+    ReadOnly,
+    ReservedStock,
+    SameRename,
+    SameSku,
+    StalePrice,
+    StaleSync,
+    StorageFailure,
+    UnknownCurrency,
+    WatchLagged,
+}
+
+impl InventoryError {
+    /// Every error, for finding the error a status carries.
+    pub const ALL: &'static [InventoryError] = &[
+        InventoryError::AdminDisabled,
+        InventoryError::BadAdminToken,
+        InventoryError::BadApiKey,
+        InventoryError::BadAttribute,
+        InventoryError::BadBuckets,
+        InventoryError::BadCompression,
+        InventoryError::BadContentType,
+        InventoryError::BadCurrency,
+        InventoryError::BadMessage,
+        InventoryError::BadPageToken,
+        InventoryError::BadPhysical,
+        InventoryError::BadPrice,
+        InventoryError::BadStreamId,
+        InventoryError::BadTag,
+        InventoryError::BadWeightRange,
+        InventoryError::BigAttributes,
+        InventoryError::BigBatch,
+        InventoryError::BigDescription,
+        InventoryError::BigImage,
+        InventoryError::BigInitialQuantity,
+        InventoryError::BigMessage,
+        InventoryError::BigName,
+        InventoryError::BigQuantity,
+        InventoryError::BigSku,
+        InventoryError::BigTags,
+        InventoryError::CurrencyMismatch,
+        InventoryError::DuplicateItem,
+        InventoryError::EmptyImage,
+        InventoryError::EmptyQuantity,
+        InventoryError::EmptyQuery,
+        InventoryError::EmptySku,
+        InventoryError::InsufficientInventory,
+        InventoryError::MixedCurrencies,
+        InventoryError::NoId,
+        InventoryError::NoImage,
+        InventoryError::NoItem,
+        InventoryError::NoOperation,
+        InventoryError::NoStock,
+        InventoryError::NoStockToUpdate,
+        InventoryError::OverRelease,
+        InventoryError::OverReserve,
+        InventoryError::ReadOnly,
+        InventoryError::ReservedStock,
+        InventoryError::SameRename,
+        InventoryError::SameSku,
+        InventoryError::StalePrice,
+        InventoryError::StaleSync,
+        InventoryError::StorageFailure,
+        InventoryError::UnknownCurrency,
+        InventoryError::WatchLagged,
+    ];
+
+    /// The status code the error is sent with.
+    pub fn code(self) -> Code {
+        use InventoryError::*;
+        match self {
+            DuplicateItem => Code::AlreadyExists,
+            WatchLagged => Code::DataLoss,
+            CurrencyMismatch | MixedCurrencies | NoStockToUpdate | OverRelease | ReservedStock
+            | StalePrice | StaleSync => Code::FailedPrecondition,
+            StorageFailure => Code::Internal,
+            BadAttribute | BadBuckets | BadCompression | BadContentType | BadCurrency
+            | BadMessage | BadPageToken | BadPhysical | BadPrice | BadStreamId | BadTag
+            | BadWeightRange | BigAttributes | BigDescription | BigImage | BigInitialQuantity
+            | BigMessage | BigName | BigSku | BigTags | EmptyImage | EmptyQuantity | EmptyQuery
+            | EmptySku | NoId | NoOperation | NoStock | SameRename | SameSku | UnknownCurrency => {
+                Code::InvalidArgument
+            }
+            NoImage | NoItem => Code::NotFound,
+            BigQuantity => Code::OutOfRange,
+            AdminDisabled => Code::PermissionDenied,
+            BigBatch | InsufficientInventory | OverReserve => Code::ResourceExhausted,
+            BadAdminToken | BadApiKey => Code::Unauthenticated,
+            ReadOnly => Code::Unavailable,
+        }
+    }
+
+    /// The stable reason the error is sent with in the x-error-code metadata
+    /// (e.g. "NO_ITEM"), which unlike its message is never translated.
+    pub fn reason(self) -> &'static str {
+        use InventoryError::*;
+        match self {
+            AdminDisabled => "ADMIN_DISABLED",
+            BadAdminToken => "BAD_ADMIN_TOKEN",
+            BadApiKey => "BAD_API_KEY",
+            BadAttribute => "BAD_ATTRIBUTE",
+            BadBuckets => "BAD_BUCKETS",
+            BadCompression => "BAD_COMPRESSION",
+            BadContentType => "BAD_CONTENT_TYPE",
+            BadCurrency => "BAD_CURRENCY",
+            BadMessage => "BAD_MESSAGE",
+            BadPageToken => "BAD_PAGE_TOKEN",
+            BadPhysical => "BAD_PHYSICAL",
+            BadPrice => "BAD_PRICE",
+            BadStreamId => "BAD_STREAM_ID",
+            BadTag => "BAD_TAG",
+            BadWeightRange => "BAD_WEIGHT_RANGE",
+            BigAttributes => "BIG_ATTRIBUTES",
+            BigBatch => "BIG_BATCH",
+            BigDescription => "BIG_DESCRIPTION",
+            BigImage => "BIG_IMAGE",
+            BigInitialQuantity => "BIG_INITIAL_QUANTITY",
+            BigMessage => "BIG_MESSAGE",
+            BigName => "BIG_NAME",
+            BigQuantity => "BIG_QUANTITY",
+            BigSku => "BIG_SKU",
+            BigTags => "BIG_TAGS",
+            CurrencyMismatch => "CURRENCY_MISMATCH",
+            DuplicateItem => "DUPLICATE_ITEM",
+            EmptyImage => "EMPTY_IMAGE",
+            EmptyQuantity => "EMPTY_QUANTITY",
+            EmptyQuery => "EMPTY_QUERY",
+            EmptySku => "EMPTY_SKU",
+            InsufficientInventory => "INSUFFICIENT_INVENTORY",
+            MixedCurrencies => "MIXED_CURRENCIES",
+            NoId => "NO_ID",
+            NoImage => "NO_IMAGE",
+            NoItem => "NO_ITEM",
+            NoOperation => "NO_OPERATION",
+            NoStock => "NO_STOCK",
+            NoStockToUpdate => "NO_STOCK_TO_UPDATE",
+            OverRelease => "OVER_RELEASE",
+            OverReserve => "OVER_RESERVE",
+            ReadOnly => "READ_ONLY",
+            ReservedStock => "RESERVED_STOCK",
+            SameRename => "SAME_RENAME",
+            SameSku => "SAME_SKU",
+            StalePrice => "STALE_PRICE",
+            StaleSync => "STALE_SYNC",
+            StorageFailure => "STORAGE_FAILURE",
+            UnknownCurrency => "UNKNOWN_CURRENCY",
+            WatchLagged => "WATCH_LAGGED",
+        }
+    }
+
+    /// The English message the error is sent with, which the LocalizeLayer
+    /// translates for clients which ask for another language.
+    pub fn message(self) -> &'static str {
+        use InventoryError::*;
+        match self {
+            AdminDisabled => "admin operations are disabled on this server",
+            BadAdminToken => "missing or invalid admin credentials",
+            BadApiKey => "missing or invalid API key",
+            BadAttribute => "provided attribute key was empty",
+            BadBuckets => "price bucket boundaries must be finite and strictly ascending",
+            BadCompression => "compressed message was malformed or used an unsupported encoding",
+            BadContentType => "unsupported image content type provided",
+            BadCurrency => "provided currency isn't a known ISO 4217 code",
+            BadMessage => "request message was malformed",
+            BadPageToken => "provided page token was invalid",
+            BadPhysical => "provided weight or dimensions were negative",
+            BadPrice => "provided PRICE was invalid",
+            BadStreamId => "provided stream id was invalid",
+            BadTag => "provided tag was empty",
+            BadWeightRange => "weight range bounds must be non-negative and ascending",
+            BigAttributes => "attributes exceed the maximum number or size",
+            BigBatch => "batch exceeds the maximum size",
+            BigDescription => "description exceeds the maximum length",
+            BigImage => "image exceeds the maximum size",
+            BigInitialQuantity => "initial quantity exceeds the maximum quantity",
+            BigMessage => "message exceeds the maximum size once decompressed",
+            BigName => "name exceeds the maximum length",
+            BigQuantity => "quantity change exceeds the maximum quantity",
+            BigSku => "SKU exceeds the maximum length",
+            BigTags => "tags exceed the maximum number or size",
+            CurrencyMismatch => "the item is priced in another currency",
+            DuplicateItem => "item already exists in inventory",
+            EmptyImage => "no image data provided",
+            EmptyQuantity => "invalid quantity of 0 provided",
+            EmptyQuery => "provided search query was empty",
+            EmptySku => "provided SKU was empty",
+            InsufficientInventory => "not enough inventory for quantity change",
+            MixedCurrencies => "items are priced in currencies without exchange rates",
+            NoId => "no ID or SKU provided for item",
+            NoImage => "no image was found for the item",
+            NoItem => "the item requested was not found",
+            NoOperation => "no operation provided",
+            NoStock => "no stock provided for item",
+            NoStockToUpdate => "the item has no stock to update",
+            OverRelease => "more inventory was released than is reserved",
+            OverReserve => "not enough available inventory to reserve",
+            ReadOnly => "the inventory is in read-only maintenance mode",
+            ReservedStock => "quantity can't drop below the stock which is reserved",
+            SameRename => "an item can't be renamed to the SKU it already has",
+            SameSku => "an item's stock can't be swapped with itself",
+            StalePrice => "the item is no longer at the expected price",
+            StaleSync => "removals since then are no longer tracked, sync everything again",
+            StorageFailure => "the inventory's storage failed",
+            UnknownCurrency => "no exchange rate for the requested currency",
+            WatchLagged => "the watch fell behind and missed changes",
+        }
+    }
+
+    /// Finds the error a status was sent for by its code and the reason in
+    /// its metadata, which is none for other statuses. Messages are never
+    /// matched on, so errors are found in translated statuses too.
+    pub fn from_status(status: &Status) -> Option<InventoryError> {
+        let reason = status.metadata().get(REASON_HEADER)?.to_str().ok()?;
+        InventoryError::from_reason(reason).filter(|error| error.code() == status.code())
+    }
+
+    /// Finds the error with the given reason.
+    pub fn from_reason(reason: &str) -> Option<InventoryError> {
+        InventoryError::ALL
+            .iter()
+            .copied()
+            .find(|error| error.reason() == reason)
+    }
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+impl From<InventoryError> for Status {
+    fn from(error: InventoryError) -> Self {
+        let mut metadata = MetadataMap::new();
+        let reason = MetadataValue::from_static(error.reason());
+        metadata.insert(REASON_HEADER, reason);
+        Status::with_metadata(error.code(), error.message(), metadata)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use tonic::{Code, Status};
+
+    use crate::error::InventoryError;
+
+    #[test]
+    fn errors_round_trip() {
+        // every error can be told apart by its reason
+        let reasons: HashSet<&str> = InventoryError::ALL.iter().map(|e| e.reason()).collect();
+        assert_eq!(reasons.len(), InventoryError::ALL.len());
+
+        for error in InventoryError::ALL.iter().copied() {
+            let status = Status::from(error);
+            assert_eq!(status.code(), error.code());
+            assert_eq!(status.message(), error.to_string());
+            assert_eq!(InventoryError::from_status(&status), Some(error));
+
+            // the reason survives being sent as metadata
+            let sent = Status::from_header_map(status.to_http().headers()).unwrap();
+            assert_eq!(InventoryError::from_status(&sent), Some(error));
+        }
+
+        // errors are found by their reason whatever the message says
+        let mut status = Status::from(InventoryError::NoItem);
+        let metadata = std::mem::take(status.metadata_mut());
+        let status = Status::with_metadata(Code::NotFound, "no se encontró", metadata);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+
+        // statuses which weren't sent for an error don't carry one
+        let status = Status::not_found("unknown service");
+        assert_eq!(InventoryError::from_status(&status), None);
+        let status = Status::not_found(InventoryError::NoItem.message());
+        assert_eq!(InventoryError::from_status(&status), None);
+        let mut status = Status::from(InventoryError::NoItem);
+        let metadata = std::mem::take(status.metadata_mut());
+        let status = Status::with_metadata(Code::Internal, "", metadata);
+        assert_eq!(InventoryError::from_status(&status), None);
+        assert_eq!(InventoryError::NoItem.code(), Code::NotFound);
+    }
+}
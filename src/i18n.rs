@@ -0,0 +1,373 @@
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::Status;
+use tower::{Layer, Service};
+
+use crate::error::InventoryError;
+
+// -----------------------------------------------------------------------------
+// Message Translations
+// -----------------------------------------------------------------------------
+
+// the metadata header clients use to request a language for error messages.
+const LANGUAGE_HEADER: &str = "accept-language";
+
+// translated error messages, keyed by language and the error. English is the
+// default language and has no entries here, as it's what the server emits
+// natively.
+const MESSAGES: &[(&str, InventoryError, &str)] = &[
+    (
+        "es",
+        InventoryError::AdminDisabled,
+        "las operaciones de administración están deshabilitadas en este servidor",
+    ),
+    (
+        "es",
+        InventoryError::BadAdminToken,
+        "faltan las credenciales de administración o no son válidas",
+    ),
+    (
+        "es",
+        InventoryError::BadApiKey,
+        "clave de API ausente o no válida",
+    ),
+    (
+        "es",
+        InventoryError::BadAttribute,
+        "la clave de atributo proporcionada estaba vacía",
+    ),
+    (
+        "es",
+        InventoryError::BadBuckets,
+        "los límites de los rangos de precios deben ser finitos y estrictamente ascendentes",
+    ),
+    (
+        "es",
+        InventoryError::BadCompression,
+        "el mensaje comprimido no es válido o usa una codificación no compatible",
+    ),
+    (
+        "es",
+        InventoryError::BadContentType,
+        "el tipo de contenido de la imagen no es compatible",
+    ),
+    (
+        "es",
+        InventoryError::BadCurrency,
+        "la moneda proporcionada no es un código ISO 4217 conocido",
+    ),
+    (
+        "es",
+        InventoryError::BadMessage,
+        "el mensaje de la solicitud no es válido",
+    ),
+    (
+        "es",
+        InventoryError::BadPageToken,
+        "el token de página proporcionado no es válido",
+    ),
+    (
+        "es",
+        InventoryError::BadPhysical,
+        "el peso o las dimensiones proporcionados eran negativos",
+    ),
+    (
+        "es",
+        InventoryError::BadPrice,
+        "el PRECIO proporcionado no es válido",
+    ),
+    (
+        "es",
+        InventoryError::BadStreamId,
+        "el identificador de flujo proporcionado no es válido",
+    ),
+    (
+        "es",
+        InventoryError::BadTag,
+        "la etiqueta proporcionada estaba vacía",
+    ),
+    (
+        "es",
+        InventoryError::BadWeightRange,
+        "los límites del rango de peso deben ser no negativos y ascendentes",
+    ),
+    (
+        "es",
+        InventoryError::BigAttributes,
+        "los atributos superan el número o el tamaño máximo",
+    ),
+    (
+        "es",
+        InventoryError::BigBatch,
+        "el lote supera el tamaño máximo",
+    ),
+    (
+        "es",
+        InventoryError::BigDescription,
+        "la descripción supera la longitud máxima",
+    ),
+    (
+        "es",
+        InventoryError::BigImage,
+        "la imagen supera el tamaño máximo",
+    ),
+    (
+        "es",
+        InventoryError::BigInitialQuantity,
+        "la cantidad inicial excede la cantidad máxima",
+    ),
+    (
+        "es",
+        InventoryError::BigMessage,
+        "el mensaje supera el tamaño máximo una vez descomprimido",
+    ),
+    (
+        "es",
+        InventoryError::BigName,
+        "el nombre supera la longitud máxima",
+    ),
+    (
+        "es",
+        InventoryError::BigQuantity,
+        "el cambio de cantidad supera la cantidad máxima",
+    ),
+    (
+        "es",
+        InventoryError::BigSku,
+        "el SKU supera la longitud máxima",
+    ),
+    (
+        "es",
+        InventoryError::BigTags,
+        "las etiquetas superan el número o el tamaño máximo",
+    ),
+    (
+        "es",
+        InventoryError::CurrencyMismatch,
+        "el artículo tiene un precio en otra moneda",
+    ),
+    (
+        "es",
+        InventoryError::DuplicateItem,
+        "el artículo ya existe en el inventario",
+    ),
+    (
+        "es",
+        InventoryError::EmptyImage,
+        "no se proporcionaron datos de imagen",
+    ),
+    (
+        "es",
+        InventoryError::EmptyQuantity,
+        "se proporcionó una cantidad no válida de 0",
+    ),
+    (
+        "es",
+        InventoryError::EmptyQuery,
+        "la consulta de búsqueda proporcionada estaba vacía",
+    ),
+    (
+        "es",
+        InventoryError::EmptySku,
+        "el SKU proporcionado estaba vacío",
+    ),
+    (
+        "es",
+        InventoryError::MixedCurrencies,
+        "los artículos tienen precios en monedas sin tipo de cambio",
+    ),
+    (
+        "es",
+        InventoryError::NoId,
+        "no se proporcionó un ID o SKU para el artículo",
+    ),
+    (
+        "es",
+        InventoryError::NoImage,
+        "no se encontró ninguna imagen para el artículo",
+    ),
+    (
+        "es",
+        InventoryError::NoItem,
+        "no se encontró el artículo solicitado",
+    ),
+    (
+        "es",
+        InventoryError::NoOperation,
+        "no se proporcionó ninguna operación",
+    ),
+    (
+        "es",
+        InventoryError::NoStock,
+        "no se proporcionaron existencias para el artículo",
+    ),
+    (
+        "es",
+        InventoryError::NoStockToUpdate,
+        "el artículo no tiene existencias que actualizar",
+    ),
+    (
+        "es",
+        InventoryError::OverRelease,
+        "se liberó más inventario del que estaba reservado",
+    ),
+    (
+        "es",
+        InventoryError::OverReserve,
+        "no hay suficiente inventario disponible para reservar",
+    ),
+    (
+        "es",
+        InventoryError::ReadOnly,
+        "el inventario está en modo de mantenimiento de solo lectura",
+    ),
+    (
+        "es",
+        InventoryError::ReservedStock,
+        "la cantidad no puede quedar por debajo de las existencias reservadas",
+    ),
+    (
+        "es",
+        InventoryError::SameRename,
+        "no se puede renombrar un artículo al SKU que ya tiene",
+    ),
+    (
+        "es",
+        InventoryError::SameSku,
+        "no se pueden intercambiar las existencias de un artículo consigo mismo",
+    ),
+    (
+        "es",
+        InventoryError::StalePrice,
+        "el artículo ya no tiene el precio esperado",
+    ),
+    (
+        "es",
+        InventoryError::StaleSync,
+        "las eliminaciones desde entonces ya no se registran, sincronice todo de nuevo",
+    ),
+    (
+        "es",
+        InventoryError::StorageFailure,
+        "el almacenamiento del inventario falló",
+    ),
+    (
+        "es",
+        InventoryError::UnknownCurrency,
+        "no hay tipo de cambio para la moneda solicitada",
+    ),
+    (
+        "es",
+        InventoryError::InsufficientInventory,
+        "no hay suficiente inventario para el cambio de cantidad",
+    ),
+    (
+        "es",
+        InventoryError::WatchLagged,
+        "la observación se quedó atrás y perdió cambios",
+    ),
+];
+
+/// Translates the message of an error into the given language, if a
+/// translation exists for it.
+pub fn translate(language: &str, error: InventoryError) -> Option<&'static str> {
+    MESSAGES
+        .iter()
+        .find(|(lang, id, _)| *lang == language && *id == error)
+        .map(|(_, _, translated)| *translated)
+}
+
+// picks the first language from an accept-language header that we have
+// translations for. Quality values are ignored, languages are considered in
+// the order the client listed them.
+fn negotiate(accept_language: &str) -> Option<&'static str> {
+    accept_language
+        .split(',')
+        .filter_map(|tag| tag.split(';').next())
+        .filter_map(|tag| tag.trim().split('-').next())
+        .find_map(|primary| {
+            MESSAGES
+                .iter()
+                .map(|(lang, _, _)| *lang)
+                .find(|lang| lang.eq_ignore_ascii_case(primary))
+        })
+}
+
+// -----------------------------------------------------------------------------
+// Localization Layer
+// -----------------------------------------------------------------------------
+
+/// A layer which translates the message of error statuses returned by the
+/// inner service into the language requested by the client. Status codes are
+/// never changed, only the human readable message.
+#[derive(Debug, Clone, Default)]
+pub struct LocalizeLayer;
+
+impl<S> Layer<S> for LocalizeLayer {
+    type Service = Localize<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Localize { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Localize<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for Localize<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let language = request
+            .headers()
+            .get(LANGUAGE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(negotiate);
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response.await?;
+
+            // errors returned by handlers are sent as trailers-only responses,
+            // so the status is found in the headers. Errors which occur mid
+            // stream are sent in the trailers, and are left untranslated.
+            let language = match language {
+                Some(language) => language,
+                None => return Ok(response),
+            };
+            let status = match Status::from_header_map(response.headers()) {
+                Some(status) => status,
+                None => return Ok(response),
+            };
+            // the error is found by the reason it carries, which is kept
+            // along with the rest of the metadata
+            let error = match InventoryError::from_status(&status) {
+                Some(error) => error,
+                None => return Ok(response),
+            };
+            match translate(language, error) {
+                Some(message) => Ok(Status::with_details_and_metadata(
+                    status.code(),
+                    message,
+                    status.details().to_vec().into(),
+                    status.metadata().clone(),
+                )
+                .to_http()),
+                None => Ok(response),
+            }
+        })
+    }
+}
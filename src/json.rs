@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::store::{Item, PriceChangeRequest};
+
+// -----------------------------------------------------------------------------
+// Item Decoding
+// -----------------------------------------------------------------------------
+
+/// Decodes an Item from JSON following the proto3 JSON mapping, the form the
+/// sidecar encodes items in. Fields may be left out or null. The fields the
+/// server manages (lastUpdated, version and image) are accepted so that
+/// encoded items can be decoded again, but are ignored, along with those it
+/// computes for display (converted and availability).
+pub fn parse_item(text: &str) -> Result<Item, String> {
+    let mut item: Item = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    item.last_updated = 0;
+    item.version = 0;
+    item.image = None;
+    item.converted = None;
+    item.availability = None;
+    Ok(item)
+}
+
+/// Decodes a PriceChangeRequest from JSON following the proto3 JSON mapping,
+/// like [`parse_item`]. The SKU may be left out when the caller knows it from
+/// elsewhere (e.g. the REST gateway takes it from the path).
+pub fn parse_price_change(text: &str) -> Result<PriceChangeRequest, String> {
+    serde_json::from_str(text).map_err(|err| err.to_string())
+}
+
+// -----------------------------------------------------------------------------
+// Field Encodings
+// -----------------------------------------------------------------------------
+
+// the fields of messages encoded as JSON are set up by the build script, see
+// JSON_MESSAGES in build.rs.
+
+/// Whether a field holds its default value, which is left out when encoding.
+/// Optional fields are only left out when they're unset.
+pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Encodes 64 bit integers as strings, so that they aren't rounded to the
+/// precision of a number, and decodes them from strings or numbers.
+pub mod uint64 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        match super::Uint64::deserialize(deserializer)? {
+            super::Uint64::Number(value) => Ok(value),
+            super::Uint64::String(value) => value.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Encodes optional 64 bit integers as [`uint64`] does.
+pub mod optional_uint64 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => super::uint64::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        match Option::<super::Uint64>::deserialize(deserializer)? {
+            Some(super::Uint64::Number(value)) => Ok(Some(value)),
+            Some(super::Uint64::String(value)) => {
+                value.parse().map(Some).map_err(serde::de::Error::custom)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+// a 64 bit integer as it's written in JSON.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Uint64 {
+    Number(u64),
+    String(String),
+}
+
+/// Encodes a map with its entries in key order, so that the output is
+/// stable.
+pub fn sorted<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{parse_item, parse_price_change};
+    use crate::store::{
+        Availability, BatchGetResponse, InventoryChangeResponse, InventoryStatsResponse, Item,
+        ItemEvent, ItemIdentifier, ItemInformation, ItemPhysical, ItemStock, ServerInfo,
+    };
+
+    fn item(sku: &str, quantity: u32) -> Item {
+        Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn json(message: &impl serde::Serialize) -> String {
+        serde_json::to_string(message).unwrap()
+    }
+
+    #[test]
+    fn items() {
+        let item = parse_item(
+            r#"{"identifier": {"sku": "A"}, "stock": {"price": 1.5, "quantity": 3,
+                "reorderThreshold": 0}, "information": {"name": "apple", "description": null},
+                "attributes": {"color": "red"}, "lastUpdated": "1700000000",
+                "expiresAt": "1700000060000", "tags": ["produce", "organic"],
+                "physical": {"weightGrams": 180, "lengthMm": 8.5}}"#,
+        )
+        .unwrap();
+        assert_eq!(item.identifier.unwrap().sku, "A");
+        let stock = item.stock.unwrap();
+        assert_eq!((stock.price, stock.quantity), (1.5, 3));
+        assert_eq!(stock.reorder_threshold, Some(0));
+        let information = item.information.unwrap();
+        assert_eq!(information.name.as_deref(), Some("apple"));
+        assert_eq!(information.description, None);
+        assert_eq!(item.attributes["color"], "red");
+        assert_eq!(item.last_updated, 0);
+        assert_eq!(item.expires_at, Some(1_700_000_060_000));
+        assert_eq!(item.tags, ["produce", "organic"]);
+        let physical = item.physical.unwrap();
+        assert_eq!((physical.weight_grams, physical.length_mm), (180.0, 8.5));
+        assert_eq!((physical.width_mm, physical.height_mm), (0.0, 0.0));
+
+        // fields the server validates (e.g. a missing SKU) are left to it
+        let item = parse_item("{}").unwrap();
+        assert_eq!(item.identifier, None);
+        let item = parse_item(r#"{"expiresAt": 1700000060000}"#).unwrap();
+        assert_eq!(item.expires_at, Some(1_700_000_060_000));
+
+        assert!(parse_item(r#"{"sku": "A"}"#).is_err());
+        assert!(parse_item(r#"{"stock": {"quantity": -1}}"#).is_err());
+        assert!(parse_item(r#"{"stock": {"quantity": 1.5}}"#).is_err());
+        assert!(parse_item(r#"{"stock": {"price": "cheap"}}"#).is_err());
+        assert!(parse_item(r#"{"stock": {"reorderThreshold": -1}}"#).is_err());
+        assert!(parse_item(r#"{"expiresAt": "soon"}"#).is_err());
+        assert!(parse_item(r#"{"tags": "produce"}"#).is_err());
+        assert!(parse_item(r#"{"tags": [1]}"#).is_err());
+        assert!(parse_item(r#"{"physical": {"weight": 180}}"#).is_err());
+        assert!(parse_item(r#"{"expiresAt": -1}"#).is_err());
+        assert!(parse_item(&"[".repeat(1000)).is_err());
+    }
+
+    #[test]
+    fn price_changes() {
+        let request = parse_price_change(r#"{"price": 2.49, "expectedPrice": 1.79}"#).unwrap();
+        assert_eq!(request.sku, "");
+        assert_eq!((request.price, request.expected_price), (2.49, Some(1.79)));
+        let request = parse_price_change(r#"{"sku": "A", "expectedPrice": null}"#).unwrap();
+        assert_eq!(request.sku, "A");
+        assert_eq!(request.expected_price, None);
+
+        assert!(parse_price_change(r#"{"price": "cheap"}"#).is_err());
+        assert!(parse_price_change(r#"{"quantity": 1}"#).is_err());
+        assert!(parse_price_change("2.49").is_err());
+    }
+
+    #[test]
+    fn items_encoding() {
+        let mut item = item("A \"quoted\" SKU", 42);
+        item.information = Some(ItemInformation {
+            name: Some("tab\tand\u{1}control".into()),
+            description: None,
+        });
+        item.last_updated = 1_000_000;
+        item.version = 3;
+        item.expires_at = Some(2_000_000);
+        item.stock.as_mut().unwrap().reorder_threshold = Some(5);
+        item.stock.as_mut().unwrap().currency = "EUR".into();
+        item.tags = vec!["produce".into(), "on \"sale\"".into()];
+        item.physical = Some(ItemPhysical {
+            weight_grams: 180.0,
+            length_mm: 8.5,
+            ..Default::default()
+        });
+        item.attributes = [("weight", "2kg"), ("brand", "Acme")]
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        assert_eq!(
+            json(&item),
+            concat!(
+                r#"{"identifier":{"sku":"A \"quoted\" SKU"},"#,
+                r#""stock":{"price":1.79,"quantity":42,"reorderThreshold":5,"currency":"EUR"},"#,
+                r#""information":{"name":"tab\tand\u0001control"},"#,
+                r#""lastUpdated":"1000000","attributes":{"brand":"Acme","weight":"2kg"},"#,
+                r#""expiresAt":"2000000","tags":["produce","on \"sale\""],"version":"3","#,
+                r#""physical":{"weightGrams":180.0,"lengthMm":8.5}}"#,
+            )
+        );
+        assert_eq!(json(&Item::default()), "{}");
+
+        // encoded items decode to the same item, apart from the fields which
+        // the server manages
+        item.availability = Some(Availability {
+            total: 42,
+            reserved: 2,
+            available: 40,
+        });
+        let decoded = parse_item(&json(&item)).unwrap();
+        assert_eq!(decoded.attributes, item.attributes);
+        assert_eq!(decoded.information, item.information);
+        assert_eq!((decoded.last_updated, decoded.version), (0, 0));
+        assert_eq!(decoded.availability, None);
+        assert_eq!(decoded.expires_at, item.expires_at);
+        assert_eq!(decoded.stock, item.stock);
+        assert_eq!(decoded.tags, item.tags);
+        assert_eq!(decoded.physical, item.physical);
+    }
+
+    #[test]
+    fn responses_encoding() {
+        let response = InventoryChangeResponse {
+            status: "success: item was replaced".into(),
+            item: Some(item("SKU", 0)),
+            replaced: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            json(&response),
+            concat!(
+                r#"{"status":"success: item was replaced","#,
+                r#""item":{"identifier":{"sku":"SKU"},"stock":{"price":1.79}},"#,
+                r#""replaced":true}"#,
+            )
+        );
+        let response = InventoryChangeResponse {
+            status: "success: item was removed".into(),
+            existed: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            json(&response),
+            r#"{"status":"success: item was removed","existed":true}"#
+        );
+
+        let event = ItemEvent {
+            item: Some(Item {
+                identifier: Some(ItemIdentifier { sku: "SKU".into() }),
+                ..Default::default()
+            }),
+            removed: true,
+        };
+        assert_eq!(
+            json(&event),
+            r#"{"item":{"identifier":{"sku":"SKU"}},"removed":true}"#
+        );
+
+        // 64 bit integers are strings, doubles keep their precision
+        let stats = InventoryStatsResponse {
+            items: 3,
+            units: 0,
+            total_value: 0.1 + 0.2,
+            currency: "USD".into(),
+        };
+        assert_eq!(
+            json(&stats),
+            r#"{"items":"3","totalValue":0.30000000000000004,"currency":"USD"}"#
+        );
+
+        let batch = BatchGetResponse {
+            items: vec![item("SKU", 3)],
+            missing: vec!["GONE".into(), "\"QUOTED\"".into()],
+        };
+        assert_eq!(
+            json(&batch),
+            concat!(
+                r#"{"items":[{"identifier":{"sku":"SKU"},"stock":{"price":1.79,"quantity":3}}],"#,
+                r#""missing":["GONE","\"QUOTED\""]}"#,
+            )
+        );
+        assert_eq!(json(&BatchGetResponse::default()), "{}");
+
+        // builds without a commit leave it out
+        let info = ServerInfo {
+            version: "0.1.0".into(),
+            built_at: 1_000_000,
+            git_sha: String::new(),
+            uptime_ms: 1500,
+        };
+        assert_eq!(
+            json(&info),
+            r#"{"version":"0.1.0","builtAt":"1000000","uptimeMs":"1500"}"#
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! The inventory server, and a client library for it. [`client::InventoryApi`]
+//! wraps the generated gRPC client for applications which would rather not
+//! deal with tonic requests and responses, and the messages it uses are the
+//! generated ones in [`store`]. Applications which make many calls can share
+//! one connection between their clients with a [`pool::ClientPool`].
+//!
+//! The server binary serves [`server::StoreInventory`], along with the layers
+//! and gateways around it which the rest of the modules hold.
+
+pub mod audit;
+pub mod auth;
+pub mod client;
+pub mod compression;
+pub mod currency;
+pub mod details;
+pub mod error;
+pub mod i18n;
+pub mod json;
+pub mod logging;
+pub mod malformed;
+pub mod metrics;
+pub mod pagination;
+pub mod pool;
+pub mod pricing;
+pub mod prometheus;
+pub mod rest;
+pub mod routing;
+pub mod server;
+pub mod sidecar;
+pub mod storage;
+pub mod store;
+pub mod version;
+
+/// The encoded descriptors of the messages and services in store.proto, for
+/// serving reflection.
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("store_descriptor");
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use crate::store::get_many_result::Outcome;
+    use crate::store::{
+        GetManyResult, GetRequest, Item, ItemIdentifier, ItemImage, ItemInformation, ItemStock,
+    };
+
+    // an Item as it was before any fields were added to it, as an old client
+    // or server would know it.
+    #[derive(Clone, PartialEq, Message)]
+    struct LegacyItem {
+        #[prost(message, optional, tag = "1")]
+        identifier: Option<ItemIdentifier>,
+        #[prost(message, optional, tag = "2")]
+        stock: Option<ItemStock>,
+        #[prost(message, optional, tag = "3")]
+        information: Option<ItemInformation>,
+    }
+
+    // an Item as a future version of the schema might have it, with a field
+    // the current schema doesn't know.
+    #[derive(Clone, PartialEq, Message)]
+    struct FutureItem {
+        #[prost(message, optional, tag = "1")]
+        identifier: Option<ItemIdentifier>,
+        #[prost(message, optional, tag = "2")]
+        stock: Option<ItemStock>,
+        #[prost(string, tag = "100")]
+        future: String,
+    }
+
+    fn current_item() -> Item {
+        Item {
+            identifier: Some(ItemIdentifier { sku: "SKU".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            information: Some(ItemInformation {
+                name: Some("name".into()),
+                description: None,
+            }),
+            last_updated: 1_000_000,
+            image: Some(ItemImage {
+                content_type: "image/png".into(),
+                size: 1024,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn old_readers_ignore_new_fields() {
+        let item = current_item();
+        let legacy = LegacyItem::decode(item.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(legacy.identifier, item.identifier);
+        assert_eq!(legacy.stock, item.stock);
+        assert_eq!(legacy.information, item.information);
+    }
+
+    #[test]
+    fn new_readers_default_missing_fields() {
+        let legacy = LegacyItem {
+            identifier: Some(ItemIdentifier { sku: "SKU".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            information: None,
+        };
+        let item = Item::decode(legacy.encode_to_vec().as_slice()).unwrap();
+        let expected = Item {
+            identifier: legacy.identifier,
+            stock: legacy.stock,
+            ..Default::default()
+        };
+        assert_eq!(item, expected);
+    }
+
+    #[test]
+    fn unknown_fields_are_dropped() {
+        let future = FutureItem {
+            identifier: Some(ItemIdentifier { sku: "SKU".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            future: "from the future".into(),
+        };
+
+        // fields the schema doesn't know are skipped when decoding, and the
+        // known fields are intact
+        let item = Item::decode(future.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(item.identifier, future.identifier);
+        assert_eq!(item.stock, future.stock);
+
+        // prost doesn't retain unknown fields, so a message which passes
+        // through this server loses them. Fields must never be relied upon to
+        // survive a round trip through an older server.
+        let round_tripped = FutureItem::decode(item.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(round_tripped.future, "");
+        assert_eq!(round_tripped.stock, future.stock);
+    }
+
+    #[test]
+    fn unknown_enum_values_are_kept() {
+        // an outcome added in a future version of the schema
+        let result = GetManyResult {
+            sku: "SKU".into(),
+            outcome: 42,
+            item: None,
+        };
+
+        // unknown enum values survive decoding as their number, while the
+        // accessor falls back to the default value
+        let decoded = GetManyResult::decode(result.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded.outcome, 42);
+        assert_eq!(decoded.outcome(), Outcome::Found);
+    }
+
+    #[test]
+    fn get_request_is_compatible_with_item_identifier() {
+        let id = ItemIdentifier { sku: "SKU".into() };
+        let get = GetRequest::decode(id.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(get.sku, "SKU");
+        assert!(!get.include_reservations);
+
+        let get = GetRequest {
+            sku: "SKU".into(),
+            include_reservations: true,
+        };
+        let id = ItemIdentifier::decode(get.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(id.sku, "SKU");
+    }
+}
@@ -0,0 +1,528 @@
+use futures::future::BoxFuture;
+use futures::{stream, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tonic::Code;
+use tower::{Layer, Service};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Instrument, Subscriber};
+use tracing_subscriber::layer::Context as LayerContext;
+use tracing_subscriber::registry::LookupSpan;
+use uuid::Uuid;
+
+use crate::routing::INVENTORY_PREFIX;
+use crate::store::LogLine;
+
+// -----------------------------------------------------------------------------
+// Log Broadcasting
+// -----------------------------------------------------------------------------
+
+/// The number of recent log lines kept for new tails by default, which is
+/// also how far behind a tail can fall before it starts missing lines.
+pub const DEFAULT_LOG_CAPACITY: usize = 1024;
+
+/// Recent and live log lines of the server, shared between the LogLayer
+/// which records them and the TailLogs handler which streams them to clients.
+/// Both the recent lines and the lines waiting for each tail are bounded, and
+/// recording a line never waits for a tail.
+#[derive(Debug, Clone)]
+pub struct LogBroadcaster {
+    capacity: usize,
+    recent: Arc<Mutex<VecDeque<LogLine>>>,
+    sender: broadcast::Sender<LogLine>,
+}
+
+impl Default for LogBroadcaster {
+    fn default() -> Self {
+        LogBroadcaster::new(DEFAULT_LOG_CAPACITY)
+    }
+}
+
+impl LogBroadcaster {
+    /// Creates a broadcaster which keeps the given number of recent lines.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        LogBroadcaster {
+            capacity,
+            recent: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            sender: broadcast::channel(capacity).0,
+        }
+    }
+
+    /// Records a line, dropping the oldest recent line if there's no room.
+    pub fn publish(&self, line: LogLine) {
+        // the lock is held while sending, so that a new tail sees every line
+        // exactly once across the recent lines and its receiver.
+        let mut recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        if recent.len() == self.capacity {
+            recent.pop_front();
+        }
+        recent.push_back(line.clone());
+
+        // sending only fails when nobody is tailing
+        let _ = self.sender.send(line);
+    }
+
+    /// Streams up to the given number of recent lines followed by live ones.
+    /// Tails which fall behind skip the oldest lines they haven't received,
+    /// and the next line they do receive reports how many were dropped.
+    pub fn tail(&self, backlog: usize) -> impl Stream<Item = LogLine> + Send + 'static {
+        let (recent, receiver) = {
+            let recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+            let skip = recent.len().saturating_sub(backlog);
+            let lines: Vec<LogLine> = recent.iter().skip(skip).cloned().collect();
+            (lines, self.sender.subscribe())
+        };
+
+        let live = stream::unfold(receiver, |mut receiver| async move {
+            let mut dropped = 0;
+            loop {
+                match receiver.recv().await {
+                    Ok(mut line) => {
+                        line.dropped = dropped;
+                        return Some((line, receiver));
+                    }
+                    Err(RecvError::Lagged(skipped)) => dropped += skipped,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        stream::iter(recent).chain(live)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Log Layer
+// -----------------------------------------------------------------------------
+
+// the fields of a span formatted as they're appended to the events within it,
+// which are kept in the span's extensions.
+struct SpanFields(String);
+
+/// A tracing layer which publishes events to a LogBroadcaster, for TailLogs
+/// to stream. The fields of the spans an event is within (e.g. the method and
+/// SKU of a request) are appended to it. Which events are published is left
+/// to the filters of the subscriber the layer is part of.
+#[derive(Debug, Clone)]
+pub struct LogLayer {
+    logs: LogBroadcaster,
+}
+
+impl LogLayer {
+    /// Creates a layer which publishes events to the broadcaster.
+    pub fn new(logs: LogBroadcaster) -> Self {
+        LogLayer { logs }
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for LogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, ctx: LayerContext<'_, S>) {
+        let mut fields = MessageVisitor::default();
+        attributes.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields.0));
+        }
+    }
+
+    // fields recorded after the span was created (e.g. a SKU which is only
+    // known once the request was decoded) are appended to its fields.
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: LayerContext<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+                let mut visitor = MessageVisitor(std::mem::take(fields));
+                values.record(&mut visitor);
+                *fields = visitor.0;
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: LayerContext<'_, S>) {
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+
+        // the fields of the spans the event is within follow its own,
+        // outermost first
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    message.0.push_str(fields);
+                }
+            }
+        }
+
+        let metadata = event.metadata();
+        self.logs.publish(LogLine {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            level: metadata.level().to_string(),
+            target: metadata.target().into(),
+            message: message.0,
+            dropped: 0,
+        });
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Request Logging Layer
+// -----------------------------------------------------------------------------
+
+/// The metadata header requests are identified by, so that they can be traced
+/// across services.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// the longest request id accepted from clients, longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// A layer which logs each request to the Inventory service along with its
+/// outcome and latency. Requests are served within a "request" span naming
+/// the method and the request id, which handlers record the SKU a request is
+/// for in (see record_sku), so that everything logged while serving it
+/// includes them. As with metrics, the outcome of streaming methods is known
+/// once the stream was opened.
+///
+/// Requests are identified by the x-request-id the client sent, or by a UUID
+/// generated for them when it sent none (or one which isn't usable). Handlers
+/// find the id in the request metadata, and it's echoed in the response's.
+#[derive(Debug, Clone, Default)]
+pub struct RequestLogLayer;
+
+impl<S> Layer<S> for RequestLogLayer {
+    type Service = RequestLog<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestLog { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestLog<S> {
+    inner: S,
+}
+
+impl<S, B, R> Service<http::Request<B>> for RequestLog<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<R>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // only requests to the Inventory service are logged, so that health
+        // probes don't drown them out
+        let method = request.uri().path();
+        if !method.starts_with(INVENTORY_PREFIX) {
+            return Box::pin(self.inner.call(request));
+        }
+
+        // the span is passed to the handler along with the request, so that
+        // it can record the SKU in it
+        let request_id = request_id(request.headers());
+        let span = tracing::info_span!(
+            "request",
+            method,
+            request_id = request_id.to_str().unwrap_or_default(),
+            sku = tracing::field::Empty
+        );
+        request
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, request_id.clone());
+        request.extensions_mut().insert(RequestSpan(span.clone()));
+        let start = Instant::now();
+        let response = span.in_scope(|| self.inner.call(request));
+
+        let served = async move {
+            let mut response = response.await;
+            if let Ok(response) = &mut response {
+                response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+            }
+
+            // errors returned by handlers are sent as trailers-only responses,
+            // so their status is found in the headers.
+            let code = match &response {
+                Ok(response) => match response.headers().get("grpc-status") {
+                    Some(status) => Code::from_bytes(status.as_bytes()),
+                    None => Code::Ok,
+                },
+                Err(_) => Code::Unknown,
+            };
+            let micros = start.elapsed().as_micros() as u64;
+            tracing::info!(code = ?code, micros, "request served");
+
+            response
+        };
+        Box::pin(served.instrument(span))
+    }
+}
+
+// the id of a request: the one the client sent when it's usable as it is, or
+// a generated UUID.
+fn request_id(headers: &http::HeaderMap) -> http::HeaderValue {
+    let sent = headers.get(REQUEST_ID_HEADER).filter(|request_id| {
+        request_id.to_str().is_ok_and(|request_id| {
+            !request_id.is_empty() && request_id.len() <= MAX_REQUEST_ID_LEN
+        })
+    });
+    match sent {
+        Some(request_id) => request_id.clone(),
+        None => Uuid::new_v4()
+            .to_string()
+            .parse()
+            .expect("UUIDs are valid header values"),
+    }
+}
+
+// the span a request is served within, see RequestLogLayer.
+#[derive(Debug, Clone)]
+struct RequestSpan(tracing::Span);
+
+/// Records the SKU a request is for in the span it's served within, see
+/// RequestLogLayer. Requests which weren't served through the layer (e.g. in
+/// tests) are left alone.
+pub fn record_sku<T>(request: &tonic::Request<T>, sku: &str) {
+    if let Some(RequestSpan(span)) = request.extensions().get() {
+        span.record("sku", sku);
+    }
+}
+
+// formats the fields of an event into a message, the "message" field comes
+// first and any others follow it as key=value pairs.
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.0);
+            let _ = write!(self.0, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{}", value));
+        } else {
+            let _ = write!(self.0, " {}={}", field.name(), value);
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tower::{Layer, Service, ServiceExt};
+    use tracing::level_filters::LevelFilter;
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::logging::{
+        record_sku, LogBroadcaster, LogLayer, RequestLogLayer, REQUEST_ID_HEADER,
+    };
+    use crate::store::LogLine;
+
+    // a subscriber publishing events at the info level or more severe to the
+    // broadcaster.
+    fn subscriber(logs: &LogBroadcaster) -> impl Subscriber {
+        tracing_subscriber::registry()
+            .with(LevelFilter::INFO)
+            .with(LogLayer::new(logs.clone()))
+    }
+
+    fn line(message: &str) -> LogLine {
+        LogLine {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    fn messages(lines: &[LogLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.message.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn tailing() {
+        let logs = LogBroadcaster::new(4);
+        for message in ["one", "two", "three", "four", "five"] {
+            logs.publish(line(message));
+        }
+
+        // only the most recent lines are kept, and the backlog limits them
+        let recent: Vec<LogLine> = logs.tail(10).take(4).collect().await;
+        assert_eq!(messages(&recent), ["two", "three", "four", "five"]);
+        let recent: Vec<LogLine> = logs.tail(1).take(1).collect().await;
+        assert_eq!(messages(&recent), ["five"]);
+
+        // live lines follow the backlog
+        let tail = logs.tail(1);
+        logs.publish(line("six"));
+        let lines: Vec<LogLine> = tail.take(2).collect().await;
+        assert_eq!(messages(&lines), ["five", "six"]);
+        assert!(lines.iter().all(|line| line.dropped == 0));
+
+        // a tail which falls behind misses the oldest lines rather than
+        // holding up publishing, and learns how many it missed.
+        let tail = logs.tail(0);
+        for message in ["seven", "eight", "nine", "ten", "eleven", "twelve"] {
+            logs.publish(line(message));
+        }
+        let lines: Vec<LogLine> = tail.take(4).collect().await;
+        assert_eq!(messages(&lines), ["nine", "ten", "eleven", "twelve"]);
+        assert_eq!(lines[0].dropped, 2);
+        assert_eq!(lines[1].dropped, 0);
+    }
+
+    #[test]
+    fn events() {
+        let logs = LogBroadcaster::new(10);
+        tracing::subscriber::with_default(subscriber(&logs), || {
+            tracing::info!(sku = "SKU", quantity = 42, "item added");
+            tracing::debug!("too verbose to be recorded");
+            tracing::warn!("running low");
+        });
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let lines: Vec<LogLine> = rt.block_on(logs.tail(10).take(2).collect());
+        assert_eq!(
+            messages(&lines),
+            ["item added sku=SKU quantity=42", "running low"]
+        );
+        assert_eq!(lines[0].level, "INFO");
+        assert_eq!(lines[1].level, "WARN");
+        assert_eq!(lines[0].target, module_path!());
+        assert!(lines[0].timestamp > 0);
+    }
+
+    #[test]
+    fn spans() {
+        let logs = LogBroadcaster::new(10);
+        tracing::subscriber::with_default(subscriber(&logs), || {
+            let request =
+                tracing::info_span!("request", method = "Get", sku = tracing::field::Empty);
+            let entered = request.enter();
+            request.record("sku", "SKU");
+            let attempt = tracing::info_span!("attempt", attempt = 2);
+            attempt.in_scope(|| tracing::info!("retrying"));
+            tracing::info!(found = true, "served");
+            drop(entered);
+            tracing::info!(parent: &request, "finished");
+        });
+
+        // the fields of the spans an event is within follow its own, whether
+        // it's within them by entering them or by naming its parent
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let lines: Vec<LogLine> = rt.block_on(logs.tail(10).take(3).collect());
+        assert_eq!(
+            messages(&lines),
+            [
+                "retrying method=Get sku=SKU attempt=2",
+                "served found=true method=Get sku=SKU",
+                "finished method=Get sku=SKU"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn request_logging() {
+        let logs = LogBroadcaster::new(10);
+        let _default = tracing::subscriber::set_default(subscriber(&logs));
+
+        // a handler which records the SKU and fails the request
+        let handler = tower::service_fn(|request: http::Request<()>| async move {
+            let request = tonic::Request::from_http(request);
+            record_sku(&request, "SKU");
+            tracing::info!("handling");
+            let response = tonic::Status::not_found("no such item").to_http();
+            Ok::<_, std::convert::Infallible>(response)
+        });
+        let mut service = RequestLogLayer.layer(handler);
+
+        for path in ["/store.Inventory/Get", "/grpc.health.v1.Health/Check"] {
+            let request = http::Request::builder()
+                .uri(path)
+                .header(REQUEST_ID_HEADER, "request-1")
+                .body(())
+                .unwrap();
+            service.ready().await.unwrap().call(request).await.unwrap();
+        }
+
+        // only the Inventory request is logged, along with its outcome
+        let lines: Vec<LogLine> = logs.tail(10).take(3).collect().await;
+        assert_eq!(
+            lines[0].message,
+            "handling method=/store.Inventory/Get request_id=request-1 sku=SKU"
+        );
+        assert!(lines[1]
+            .message
+            .starts_with("request served code=NotFound micros="));
+        assert!(lines[1]
+            .message
+            .ends_with(" method=/store.Inventory/Get request_id=request-1 sku=SKU"));
+        assert_eq!(lines[2].message, "handling");
+    }
+
+    #[tokio::test]
+    async fn request_ids() {
+        // a handler which reports the request id it was given
+        let handler = tower::service_fn(|request: http::Request<()>| async move {
+            let request = tonic::Request::from_http(request);
+            let request_id = request.metadata().get(REQUEST_ID_HEADER).unwrap();
+            let response = http::Response::builder()
+                .header("handled", request_id.to_str().unwrap())
+                .body(())
+                .unwrap();
+            Ok::<_, std::convert::Infallible>(response)
+        });
+        let mut service = RequestLogLayer.layer(handler);
+        let mut call = |request_id: Option<&str>| {
+            let mut request = http::Request::builder().uri("/store.Inventory/Get");
+            if let Some(request_id) = request_id {
+                request = request.header(REQUEST_ID_HEADER, request_id);
+            }
+            let request = request.body(()).unwrap();
+            let response = service.call(request);
+            async move {
+                let response = response.await.unwrap();
+                let handled = response.headers()["handled"].to_str().unwrap().to_string();
+                let echoed = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+                assert_eq!(handled, echoed);
+                handled
+            }
+        };
+
+        // ids sent by clients are used as they are
+        assert_eq!(call(Some("request-1")).await, "request-1");
+
+        // requests without a usable id are given a UUID, each their own
+        let generated = call(None).await;
+        assert_eq!(generated.len(), 36);
+        assert_ne!(call(None).await, generated);
+        assert_eq!(call(Some("")).await.len(), 36);
+        assert_eq!(call(Some(&"x".repeat(129))).await.len(), 36);
+    }
+}
@@ -1,32 +1,538 @@
+use clap::Parser;
+use std::net::SocketAddr;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UnixListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_stream::wrappers::UnixListenerStream;
+use tonic::codec::CompressionEncoding;
 use tonic::transport::Server;
+use tonic_health::ServingStatus;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
 
-use server::StoreInventory;
-use store::inventory_server::InventoryServer;
+use demo::audit::{AuditLog, DEFAULT_AUDIT_CAPACITY};
+use demo::auth::ApiKeyLayer;
+use demo::compression::{DecompressionGuard, DEFAULT_MAX_MESSAGE_SIZE};
+use demo::currency::RateTable;
+use demo::i18n::LocalizeLayer;
+use demo::logging::{LogLayer, RequestLogLayer};
+use demo::malformed::MalformedRequestLayer;
+use demo::metrics::MetricsLayer;
+use demo::pricing::{PriceEnding, SnapDirection};
+use demo::rest::RestGateway;
+use demo::routing::ReadOnlyHintLayer;
+use demo::server::{FieldLimits, StoreInventory};
+use demo::server::{
+    DEFAULT_MAX_DESCRIPTION_LEN, DEFAULT_MAX_NAME_LEN, DEFAULT_MAX_SKU_LEN,
+    DEFAULT_TOMBSTONE_RETENTION,
+};
+use demo::sidecar::Sidecar;
+use demo::storage::SqliteStorage;
+use demo::store::inventory_server::InventoryServer;
+use demo::version::{built_at, GIT_SHA, VERSION};
+use demo::{prometheus, FILE_DESCRIPTOR_SET};
 
-pub mod server;
-pub mod store;
+// -----------------------------------------------------------------------------
+// Server Options
+// -----------------------------------------------------------------------------
 
-mod store_proto {
-    include!("store.rs");
+// each option can also be set with the environment variable named alongside
+// it, for containers. Options given as flags take precedence over the
+// environment, which takes precedence over the defaults.
+#[derive(Debug, Parser)]
+struct Options {
+    /// the address (IP and port) to serve the inventory on, e.g. 0.0.0.0:9001
+    /// to serve it on all interfaces
+    #[clap(default_value = "127.0.0.1:9001", env = "STORE_LISTEN_ADDR", long)]
+    listen_addr: SocketAddr,
+    /// a Unix domain socket to serve the inventory on in place of the listen
+    /// address, e.g. /run/store.sock for co-located services. The socket is
+    /// removed when the server shuts down.
+    #[clap(env = "STORE_UDS", long)]
+    uds: Option<PathBuf>,
+    /// the API key Inventory requests must carry as a bearer token in their
+    /// authorization metadata. Requests are accepted without a key when it
+    /// isn't set.
+    #[clap(env = "STORE_API_KEY", hide_env_values = true, long)]
+    api_key: Option<String>,
+    /// accept read-only Inventory requests (e.g. Get and Watch) without the
+    /// API key, so that only changes to the inventory require it
+    #[clap(env = "STORE_ALLOW_ANONYMOUS_READS", long)]
+    allow_anonymous_reads: bool,
+    /// which logs to write and keep for tailing: the least severe level to
+    /// keep (error, warn, info, debug or trace), optionally followed by levels
+    /// for particular targets, e.g. info,h2=warn. See tracing-subscriber's
+    /// EnvFilter for the syntax.
+    #[clap(
+        default_value = "info",
+        env = "STORE_LOG_LEVEL",
+        long,
+        value_parser = parse_log_filter
+    )]
+    log_level: String,
+    /// the address (IP and port) to serve metrics for Prometheus to scrape on,
+    /// at /metrics. Metrics aren't served over HTTP unless this is set.
+    #[clap(env = "STORE_METRICS_ADDR", long)]
+    metrics_addr: Option<SocketAddr>,
+    /// the address (IP and port) to serve the REST gateway on, which offers
+    /// Get, Add, Remove and UpdatePrice as JSON over HTTP. It isn't served
+    /// unless this is set.
+    #[clap(env = "STORE_REST_ADDR", long)]
+    rest_addr: Option<SocketAddr>,
+    /// the address (IP and port) to serve the HTTP sidecar on, which offers
+    /// read-only access to items as JSON. It requires the API key as the
+    /// gRPC server does, and isn't served unless this is set.
+    #[clap(env = "STORE_SIDECAR_ADDR", long)]
+    sidecar_addr: Option<SocketAddr>,
+    /// how often to sweep the inventory for expired items, in seconds, which
+    /// is how long expired items can linger before they're removed
+    #[clap(
+        default_value = "10",
+        env = "STORE_EXPIRY_SWEEP_SECS",
+        long,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    expiry_sweep_secs: u64,
+    /// have concurrent gets for the same SKU share a single read of the
+    /// inventory, which helps read heavy workloads with hot items
+    #[clap(env = "STORE_COALESCE_GETS", long)]
+    coalesce_gets: bool,
+    /// how long removed items are remembered for ListChangedSince, in
+    /// seconds. Clients which last synced longer ago must sync everything.
+    #[clap(
+        default_value_t = DEFAULT_TOMBSTONE_RETENTION.as_secs(),
+        env = "STORE_TOMBSTONE_RETENTION_SECS",
+        long
+    )]
+    tombstone_retention_secs: u64,
+    /// send a heartbeat on Watch streams which have sent nothing else for
+    /// this many seconds, so that proxies don't close them for being idle.
+    /// Heartbeats aren't sent unless this is set.
+    #[clap(
+        env = "STORE_WATCH_HEARTBEAT_SECS",
+        long,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    watch_heartbeat_secs: Option<u64>,
+    /// the largest quantity items can be added with, so that imports of
+    /// garbage data (e.g. u32::MAX quantities) are rejected. Quantities
+    /// aren't capped unless this is set.
+    #[clap(env = "STORE_MAX_QUANTITY", long)]
+    max_quantity: Option<u32>,
+    /// the longest SKU items can be added with, in bytes
+    #[clap(default_value_t = DEFAULT_MAX_SKU_LEN, env = "STORE_MAX_SKU_LEN", long)]
+    max_sku_len: usize,
+    /// the longest name items can be added with, in bytes
+    #[clap(default_value_t = DEFAULT_MAX_NAME_LEN, env = "STORE_MAX_NAME_LEN", long)]
+    max_name_len: usize,
+    /// the longest description items can be added with, in bytes
+    #[clap(
+        default_value_t = DEFAULT_MAX_DESCRIPTION_LEN,
+        env = "STORE_MAX_DESCRIPTION_LEN",
+        long
+    )]
+    max_description_len: usize,
+    /// the largest HTTP/2 frame the server accepts, in bytes, between 16384
+    /// (the HTTP/2 default, used unless this is set) and 16777215
+    #[clap(
+        env = "STORE_MAX_FRAME_SIZE",
+        long,
+        value_parser = clap::value_parser!(u32).range(16_384..=16_777_215)
+    )]
+    max_frame_size: Option<u32>,
+    /// the number of recent changes to items to keep for admins to retrieve
+    /// with GetAuditLog
+    #[clap(default_value_t = DEFAULT_AUDIT_CAPACITY, env = "STORE_AUDIT_CAPACITY", long)]
+    audit_capacity: usize,
+    /// a file to append every change to items to, as a line of JSON. Changes
+    /// are only kept in memory unless this is set.
+    #[clap(env = "STORE_AUDIT_FILE", long)]
+    audit_file: Option<PathBuf>,
+    /// where to keep the items of the inventory, in memory (where they're lost
+    /// when the server stops) or in a SQLite database at --db-path
+    #[clap(default_value = "memory", env = "STORE_STORAGE", long, value_enum)]
+    storage: StorageKind,
+    /// the SQLite database to keep items in with --storage sqlite, which is
+    /// created if it doesn't exist
+    #[clap(default_value = "store.db", env = "STORE_DB_PATH", long)]
+    db_path: PathBuf,
+    /// a file of exchange rates, for displaying prices in other currencies
+    /// and totalling the value of items priced in several. See
+    /// RateTable::from_file for the format.
+    #[clap(env = "STORE_RATES_FILE", long)]
+    rates_file: Option<PathBuf>,
+    /// a price ending (e.g. 0.99) to snap converted prices to. Converted
+    /// prices aren't snapped unless this is set.
+    #[clap(env = "STORE_PRICE_ENDING", long)]
+    price_ending: Option<String>,
+    /// which way converted prices move when they're snapped to the price
+    /// ending, one of up, down or nearest
+    #[clap(default_value = "nearest", env = "STORE_PRICE_SNAP", long)]
+    price_snap: SnapDirection,
+    /// the token admin requests (e.g. toggling read-only maintenance mode)
+    /// must carry. Admin requests aren't accepted unless this is set.
+    #[clap(env = "STORE_ADMIN_TOKEN", hide_env_values = true, long)]
+    admin_token: Option<String>,
+    /// start the inventory in read-only maintenance mode
+    #[clap(env = "STORE_READ_ONLY", long)]
+    read_only: bool,
+    /// the largest request message accepted once decompressed, in bytes,
+    /// which guards against small compressed messages that expand enormously
+    #[clap(default_value_t = DEFAULT_MAX_MESSAGE_SIZE, env = "STORE_MAX_MESSAGE_SIZE", long)]
+    max_message_size: usize,
+}
+
+// validates a filter for the logs, see EnvFilter.
+fn parse_log_filter(filter: &str) -> Result<String, String> {
+    EnvFilter::try_new(filter).map_err(|err| err.to_string())?;
+    Ok(filter.into())
+}
 
-    pub(crate) const FILE_DESCRIPTOR_SET: &[u8] =
-        tonic::include_file_descriptor_set!("store_descriptor");
+// where the server keeps the items of the inventory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum StorageKind {
+    Memory,
+    Sqlite,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let addr = "127.0.0.1:9001".parse()?;
-    let inventory = StoreInventory::default();
+    // invalid options are reported by clap, along with the usage
+    let opts = Options::parse();
+    let addr = opts.listen_addr;
+    let mut inventory = match opts.storage {
+        StorageKind::Memory => StoreInventory::default(),
+        StorageKind::Sqlite => StoreInventory::with_storage(SqliteStorage::open(&opts.db_path)?),
+    };
+    if let Some(secs) = opts.watch_heartbeat_secs {
+        inventory = inventory.with_watch_heartbeat(Duration::from_secs(secs));
+    }
+    inventory =
+        inventory.with_tombstone_retention(Duration::from_secs(opts.tombstone_retention_secs));
+    if opts.coalesce_gets {
+        inventory = inventory.with_coalesced_gets();
+    }
+    if let Some(max_quantity) = opts.max_quantity {
+        inventory = inventory.with_max_quantity(max_quantity);
+    }
+    inventory = inventory.with_field_limits(FieldLimits {
+        sku: opts.max_sku_len,
+        name: opts.max_name_len,
+        description: opts.max_description_len,
+    });
+    let mut audit = AuditLog::new(opts.audit_capacity);
+    if let Some(path) = &opts.audit_file {
+        audit = audit.with_file(path)?;
+    }
+    inventory = inventory.with_audit_log(audit);
+
+    // exchange rates for displaying prices in other currencies, and totalling
+    // the value of items priced in several, can be loaded from a file.
+    if let Some(path) = &opts.rates_file {
+        inventory = inventory.with_rates(RateTable::from_file(path)?);
+    }
+
+    // converted prices can be snapped to a price ending (e.g. "0.99"), which
+    // by default moves them to whichever price with the ending is nearest.
+    if let Some(ending) = &opts.price_ending {
+        let ending = PriceEnding::parse(ending, opts.price_snap)
+            .ok_or_else(|| format!("invalid price ending: {}", ending))?;
+        inventory = inventory.with_price_ending(ending);
+    }
+
+    // admin requests (e.g. toggling read-only maintenance mode) are only
+    // accepted when an admin token is configured, and the server can be
+    // started in read-only mode for maintenance.
+    let admin_token = opts.admin_token.clone();
+    let admin_enabled = admin_token.is_some();
+    if let Some(token) = &admin_token {
+        inventory = inventory.with_admin_token(token.clone());
+    }
+
+    // Inventory requests can be required to carry an API key, the admin
+    // token is accepted in its place so admin requests only carry one token.
+    let api_key_layer = match opts.api_key.clone() {
+        Some(api_key) if api_key.is_empty() => return Err("the API key can't be empty".into()),
+        Some(api_key) => Some(
+            ApiKeyLayer::new(api_key)
+                .with_admin_token(admin_token)
+                .with_anonymous_reads(opts.allow_anonymous_reads),
+        ),
+        None => None,
+    };
+    inventory = inventory.with_read_only(opts.read_only);
+
+    // log lines are written to stdout and kept for admins to tail remotely.
+    tracing_subscriber::registry()
+        .with(EnvFilter::new(&opts.log_level))
+        .with(tracing_subscriber::fmt::layer())
+        .with(LogLayer::new(inventory.logs()))
+        .try_init()?;
+    tracing::info!(
+        version = VERSION,
+        built_at = built_at(),
+        git_sha = GIT_SHA.unwrap_or("unknown"),
+        "starting the inventory server"
+    );
+    tracing::info!(
+        listen_addr = %opts.listen_addr,
+        uds = ?opts.uds,
+        api_key = opts.api_key.is_some(),
+        allow_anonymous_reads = opts.allow_anonymous_reads,
+        log_level = %opts.log_level,
+        metrics_addr = ?opts.metrics_addr,
+        rest_addr = ?opts.rest_addr,
+        sidecar_addr = ?opts.sidecar_addr,
+        expiry_sweep_secs = opts.expiry_sweep_secs,
+        tombstone_retention_secs = opts.tombstone_retention_secs,
+        coalesce_gets = opts.coalesce_gets,
+        watch_heartbeat_secs = ?opts.watch_heartbeat_secs,
+        max_quantity = ?opts.max_quantity,
+        max_sku_len = opts.max_sku_len,
+        max_name_len = opts.max_name_len,
+        max_description_len = opts.max_description_len,
+        max_frame_size = ?opts.max_frame_size,
+        audit_capacity = opts.audit_capacity,
+        audit_file = ?opts.audit_file,
+        storage = ?opts.storage,
+        db_path = ?opts.db_path,
+        rates_file = ?opts.rates_file,
+        price_ending = ?opts.price_ending,
+        price_snap = ?opts.price_snap,
+        admin_enabled,
+        read_only = opts.read_only,
+        max_message_size = opts.max_message_size,
+        "effective configuration"
+    );
 
     let reflection_service = tonic_reflection::server::Builder::configure()
-        .register_encoded_file_descriptor_set(store_proto::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
         .build()
         .unwrap();
-    
-    Server::builder()
-        .add_service(InventoryServer::new(inventory))
+
+    // the standard gRPC health service, for probes such as grpc_health_probe.
+    // Checks for the server as a whole (no service name) and for the
+    // inventory (store.Inventory) both report serving once the server is up,
+    // and not serving once it's asked to shut down.
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+
+    let inventory = Arc::new(inventory);
+
+    // items with an expiry time are removed once it passes, by a sweep of
+    // the inventory which runs in the background.
+    let sweep_interval = Duration::from_secs(opts.expiry_sweep_secs);
+    tokio::spawn(inventory.clone().sweep_expired(sweep_interval));
+
+    // metrics are served on a port of their own, so that scraping them doesn't
+    // need the API key and they can be kept off the public interface.
+    if let Some(metrics_addr) = opts.metrics_addr {
+        let metrics = inventory.metrics();
+        tokio::spawn(async move {
+            tracing::info!("serving metrics on {}", metrics_addr);
+            if let Err(err) = prometheus::serve(metrics_addr, metrics).await {
+                tracing::error!("the metrics server failed: {}", err);
+            }
+        });
+    }
+
+    // the REST gateway changes the inventory too, so it requires the API key
+    // as the gRPC server does.
+    if let Some(rest_addr) = opts.rest_addr {
+        let mut gateway =
+            RestGateway::new(inventory.clone()).with_anonymous_reads(opts.allow_anonymous_reads);
+        if let Some(api_key) = &opts.api_key {
+            gateway = gateway.with_api_key(api_key);
+        }
+        tokio::spawn(async move {
+            tracing::info!("serving the REST gateway on {}", rest_addr);
+            if let Err(err) = gateway.serve(rest_addr).await {
+                tracing::error!("the REST gateway failed: {}", err);
+            }
+        });
+    }
+
+    // the HTTP sidecar offers read-only access to the inventory as JSON for
+    // integrations that can't speak gRPC, and requires the API key too.
+    if let Some(sidecar_addr) = opts.sidecar_addr {
+        let mut sidecar =
+            Sidecar::new(inventory.clone()).with_anonymous_reads(opts.allow_anonymous_reads);
+        if let Some(api_key) = &opts.api_key {
+            sidecar = sidecar.with_api_key(api_key);
+        }
+        tokio::spawn(async move {
+            tracing::info!("serving the HTTP sidecar on {}", sidecar_addr);
+            if let Err(err) = sidecar.serve(sidecar_addr).await {
+                tracing::error!("the HTTP sidecar failed: {}", err);
+            }
+        });
+    }
+
+    health_reporter
+        .set_service_status("", ServingStatus::Serving)
+        .await;
+    health_reporter
+        .set_serving::<InventoryServer<StoreInventory>>()
+        .await;
+    // both are reported not serving as soon as shutdown is asked for, so that
+    // probes stop sending traffic while in-flight requests finish.
+    let shutdown_requested = async move {
+        shutdown().await;
+        health_reporter
+            .set_service_status("", ServingStatus::NotServing)
+            .await;
+        health_reporter
+            .set_not_serving::<InventoryServer<StoreInventory>>()
+            .await;
+    };
+    // requests may be compressed with gzip, the only compression tonic 0.8
+    // supports, and responses are compressed for clients which accept it.
+    let router = Server::builder()
+        .max_frame_size(opts.max_frame_size)
+        .layer(MetricsLayer::new(inventory.metrics()))
+        .layer(RequestLogLayer)
+        .layer(LocalizeLayer)
+        .layer(MalformedRequestLayer)
+        .layer(ReadOnlyHintLayer)
+        .layer(tower::util::option_layer(api_key_layer))
+        .add_service(DecompressionGuard::new(
+            InventoryServer::from_arc(inventory)
+                .accept_compressed(CompressionEncoding::Gzip)
+                .send_compressed(CompressionEncoding::Gzip),
+            opts.max_message_size,
+        ))
         .add_service(reflection_service)
-        .serve(addr)
-        .await?;
+        .add_service(health_service);
+
+    // the inventory is served over TCP unless a Unix domain socket is given,
+    // which is removed again once the server is shut down
+    match &opts.uds {
+        Some(path) => {
+            let incoming = UnixListenerStream::new(bind_uds(path)?);
+            tracing::info!("serving the inventory on {}", path.display());
+            let served = router
+                .serve_with_incoming_shutdown(incoming, shutdown_requested)
+                .await;
+            std::fs::remove_file(path)?;
+            served?;
+        }
+        None => {
+            tracing::info!("serving the inventory on {}", addr);
+            router.serve_with_shutdown(addr, shutdown_requested).await?;
+        }
+    }
     Ok(())
 }
+
+// binds a Unix domain socket at the path. A socket left behind by a server
+// which didn't shut down cleanly is replaced, but sockets which are still
+// being served and any other files are left be.
+fn bind_uds(path: &Path) -> std::io::Result<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        let served = std::os::unix::net::UnixStream::connect(path).is_ok();
+        if metadata.file_type().is_socket() && !served {
+            std::fs::remove_file(path)?;
+        }
+    }
+    UnixListener::bind(path)
+}
+
+// resolves once the server is asked to shut down, by ctrl-c or SIGTERM.
+async fn shutdown() {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(err) => {
+            tracing::warn!("SIGTERM can't shut the server down: {}", err);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+    tracing::info!("shutting down");
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use demo::pricing::SnapDirection;
+
+    use crate::{bind_uds, Options};
+
+    #[test]
+    fn options_from_the_environment() {
+        // flags take precedence over the environment, which takes precedence
+        // over the defaults. This is the only test using these variables.
+        let opts = Options::try_parse_from(["server"]).unwrap();
+        assert_eq!(opts.listen_addr.to_string(), "127.0.0.1:9001");
+        assert_eq!(opts.watch_heartbeat_secs, None);
+        assert_eq!(opts.sidecar_addr, None);
+        assert_eq!(opts.price_snap, SnapDirection::Nearest);
+        assert!(!opts.read_only);
+
+        std::env::set_var("STORE_LISTEN_ADDR", "0.0.0.0:9101");
+        std::env::set_var("STORE_EXPIRY_SWEEP_SECS", "30");
+        std::env::set_var("STORE_WATCH_HEARTBEAT_SECS", "15");
+        std::env::set_var("STORE_PRICE_SNAP", "down");
+        std::env::set_var("STORE_READ_ONLY", "true");
+        std::env::set_var("STORE_MAX_MESSAGE_SIZE", "1024");
+        let opts = Options::try_parse_from(["server"]).unwrap();
+        assert_eq!(opts.listen_addr.to_string(), "0.0.0.0:9101");
+        assert_eq!(opts.expiry_sweep_secs, 30);
+        assert_eq!(opts.watch_heartbeat_secs, Some(15));
+        assert_eq!(opts.price_snap, SnapDirection::Down);
+        assert!(opts.read_only);
+        assert_eq!(opts.max_message_size, 1024);
+        let opts = Options::try_parse_from(["server", "--listen-addr", "127.0.0.1:9102"]).unwrap();
+        assert_eq!(opts.listen_addr.to_string(), "127.0.0.1:9102");
+
+        std::env::set_var("STORE_LISTEN_ADDR", "nowhere");
+        assert!(Options::try_parse_from(["server"]).is_err());
+        std::env::remove_var("STORE_LISTEN_ADDR");
+        std::env::remove_var("STORE_EXPIRY_SWEEP_SECS");
+        std::env::remove_var("STORE_WATCH_HEARTBEAT_SECS");
+        std::env::remove_var("STORE_PRICE_SNAP");
+        std::env::remove_var("STORE_READ_ONLY");
+        std::env::remove_var("STORE_MAX_MESSAGE_SIZE");
+    }
+
+    #[test]
+    fn log_filters() {
+        for filter in ["debug", "info,h2=warn", "warn,hyper=debug,h2=trace"] {
+            let opts = Options::try_parse_from(["server", "--log-level", filter]).unwrap();
+            assert_eq!(opts.log_level, filter);
+        }
+        for filter in ["h2=loud", "info,[request"] {
+            assert!(Options::try_parse_from(["server", "--log-level", filter]).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn unix_domain_sockets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.sock");
+
+        // the socket is created, and only replaced once it's left behind
+        let listener = bind_uds(&path).unwrap();
+        assert!(bind_uds(&path).is_err());
+        drop(listener);
+        assert!(path.exists());
+        assert!(bind_uds(&path).is_ok());
+
+        // while files other than sockets aren't replaced
+        let file = dir.path().join("store.txt");
+        std::fs::write(&file, "kept").unwrap();
+        assert!(bind_uds(&file).is_err());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "kept");
+    }
+}
@@ -0,0 +1,131 @@
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::{Code, Status};
+use tower::{Layer, Service};
+
+use crate::error::InventoryError;
+
+// -----------------------------------------------------------------------------
+// Malformed Requests
+// -----------------------------------------------------------------------------
+
+// the start of the message of the statuses tonic fails requests with when
+// their message can't be decoded, e.g. because a string isn't valid UTF-8.
+const DECODE_ERROR_PREFIX: &str = "failed to decode Protobuf message";
+
+/// A layer which reports request messages which couldn't be decoded (e.g.
+/// because a string field isn't valid UTF-8) as `invalid_argument`. They're
+/// otherwise reported as `internal`, which blames the server for what the
+/// client sent.
+#[derive(Debug, Clone, Default)]
+pub struct MalformedRequestLayer;
+
+impl<S> Layer<S> for MalformedRequestLayer {
+    type Service = MalformedRequest<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MalformedRequest { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MalformedRequest<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for MalformedRequest<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            // messages are decoded before the handler is called, so failures
+            // to decode them are sent as trailers-only responses.
+            let response = response.await?;
+            match Status::from_header_map(response.headers()) {
+                Some(status)
+                    if status.code() == Code::Internal
+                        && status.message().starts_with(DECODE_ERROR_PREFIX) =>
+                {
+                    tracing::debug!(error = status.message(), "malformed request message");
+                    Ok(Status::from(InventoryError::BadMessage).to_http())
+                }
+                _ => Ok(response),
+            }
+        })
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use hyper::Body;
+    use std::sync::Arc;
+    use tower::{Layer, ServiceExt};
+
+    use crate::error::InventoryError;
+    use crate::malformed::MalformedRequestLayer;
+    use crate::server::StoreInventory;
+    use crate::store::inventory_server::InventoryServer;
+
+    // sends a unary request with the given encoded message to the inventory,
+    // returning the status it's answered with.
+    async fn send(method: &str, message: &[u8]) -> tonic::Status {
+        let mut body = vec![0];
+        body.extend((message.len() as u32).to_be_bytes());
+        body.extend(message);
+        let request = http::Request::post(format!("/store.Inventory/{}", method))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(Body::from(body))
+            .unwrap();
+
+        let inventory = InventoryServer::from_arc(Arc::new(StoreInventory::default()));
+        let response = MalformedRequestLayer
+            .layer(inventory)
+            .oneshot(request)
+            .await
+            .unwrap();
+        tonic::Status::from_header_map(response.headers()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn malformed_requests() {
+        // a GetRequest whose SKU (field 2) isn't valid UTF-8
+        let status = send("Get", &[0x12, 0x02, 0xff, 0xfe]).await;
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadMessage)
+        );
+
+        // a message which ends part way through a field
+        let status = send("Get", &[0x12, 0x05, b'a']).await;
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadMessage)
+        );
+
+        // other errors are passed on as they are
+        let status = send("Get", &[0x12, 0x01, b'a']).await;
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+    }
+}
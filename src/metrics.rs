@@ -0,0 +1,551 @@
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::Code;
+use tower::{Layer, Service};
+
+use crate::routing;
+
+// -----------------------------------------------------------------------------
+// Metrics
+// -----------------------------------------------------------------------------
+
+/// The upper bounds of the buckets latencies are counted in since the server
+/// started, in microseconds. Latencies above the last bound are only counted
+/// in the total.
+pub const LATENCY_BOUNDS_MICROS: [u64; 14] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 1_000_000,
+    2_500_000, 10_000_000,
+];
+
+// the number of gRPC status codes, which are numbered from 0 (OK).
+const CODES: usize = 17;
+
+/// Metrics about the requests served for a single method. All values are
+/// updated with atomics, so recording never takes a lock.
+#[derive(Debug, Default)]
+pub struct MethodMetrics {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    // the number of requests which ended with each status code, indexed by
+    // the code's number.
+    codes: [AtomicU64; CODES],
+    total_latency_micros: AtomicU64,
+    max_latency_micros: AtomicU64,
+    // the number of requests whose latency was within each of the bounds
+    // (and above the previous one) since the server started.
+    bounded_latencies: [AtomicU64; LATENCY_BOUNDS_MICROS.len()],
+    latencies: LatencyWindow,
+}
+
+/// A point in time copy of the metrics for a method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSnapshot {
+    pub method: &'static str,
+    pub requests: u64,
+    pub errors: u64,
+    pub mean_latency_micros: u64,
+    pub max_latency_micros: u64,
+    pub total_latency_micros: u64,
+    /// The number of requests which ended with each status code, for the
+    /// codes which any request ended with.
+    pub codes: Vec<(Code, u64)>,
+    /// The number of requests whose latency was at most each bound of
+    /// LATENCY_BOUNDS_MICROS, in the same order.
+    pub latency_buckets: Vec<u64>,
+}
+
+/// Latency percentiles for a method over the recent window, in microseconds.
+/// Percentiles are accurate to within 1/8th of their value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub method: &'static str,
+    pub requests: u64,
+    pub p50_micros: u64,
+    pub p90_micros: u64,
+    pub p99_micros: u64,
+}
+
+/// Metrics for the server, shared between the layer which records requests
+/// and the handlers which report them.
+#[derive(Debug)]
+pub struct Metrics {
+    // the metrics for each Inventory method, keyed by its gRPC path. The
+    // methods are known up front, so the map itself is never modified.
+    methods: HashMap<&'static str, MethodMetrics>,
+    // the watch streams which are currently open, keyed by the order they
+    // were opened in. Stream ids are chosen by clients so they may repeat.
+    watchers: Mutex<HashMap<u64, Watcher>>,
+    next_watcher: AtomicU64,
+    // when the metrics were created, which latency windows are relative to.
+    started: Instant,
+}
+
+/// A watch stream which is currently open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watcher {
+    pub stream_id: String,
+    pub sku: String,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            methods: routing::inventory_methods()
+                .map(|method| (method, MethodMetrics::default()))
+                .collect(),
+            watchers: Mutex::new(HashMap::new()),
+            next_watcher: AtomicU64::new(0),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Records a request to the method at the given gRPC path which ended
+    /// with the given status code, requests for unknown methods are ignored.
+    pub fn record(&self, method: &str, latency_micros: u64, code: Code) {
+        let metrics = match self.methods.get(method) {
+            Some(metrics) => metrics,
+            None => return,
+        };
+
+        metrics.requests.fetch_add(1, Ordering::Relaxed);
+        if code != Code::Ok {
+            metrics.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(count) = metrics.codes.get(code as usize) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+        let bound = LATENCY_BOUNDS_MICROS.partition_point(|bound| *bound < latency_micros);
+        if let Some(count) = metrics.bounded_latencies.get(bound) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+        metrics
+            .total_latency_micros
+            .fetch_add(latency_micros, Ordering::Relaxed);
+        metrics
+            .max_latency_micros
+            .fetch_max(latency_micros, Ordering::Relaxed);
+        metrics
+            .latencies
+            .record(self.started.elapsed().as_secs(), latency_micros);
+    }
+
+    /// Takes a snapshot of the metrics of each method, ordered by method.
+    pub fn snapshot(&self) -> Vec<MethodSnapshot> {
+        let mut snapshots: Vec<MethodSnapshot> = self
+            .methods
+            .iter()
+            .map(|(method, metrics)| {
+                let requests = metrics.requests.load(Ordering::Relaxed);
+                let total = metrics.total_latency_micros.load(Ordering::Relaxed);
+                let codes = metrics
+                    .codes
+                    .iter()
+                    .enumerate()
+                    .map(|(code, count)| (Code::from(code as i32), count.load(Ordering::Relaxed)))
+                    .filter(|(_, count)| *count > 0)
+                    .collect();
+                let latency_buckets = metrics
+                    .bounded_latencies
+                    .iter()
+                    .scan(0, |cumulative, count| {
+                        *cumulative += count.load(Ordering::Relaxed);
+                        Some(*cumulative)
+                    })
+                    .collect();
+                MethodSnapshot {
+                    method,
+                    requests,
+                    errors: metrics.errors.load(Ordering::Relaxed),
+                    mean_latency_micros: total.checked_div(requests).unwrap_or_default(),
+                    max_latency_micros: metrics.max_latency_micros.load(Ordering::Relaxed),
+                    total_latency_micros: total,
+                    codes,
+                    latency_buckets,
+                }
+            })
+            .collect();
+        snapshots.sort_by_key(|snapshot| snapshot.method);
+        snapshots
+    }
+
+    /// Summarizes the latency of each method which served requests within the
+    /// last LATENCY_WINDOW_SECS, ordered by method.
+    pub fn latency_summaries(&self) -> Vec<LatencySummary> {
+        let now = self.started.elapsed().as_secs();
+        let mut summaries: Vec<LatencySummary> = self
+            .methods
+            .iter()
+            .filter_map(|(method, metrics)| {
+                let histogram = metrics.latencies.histogram(now);
+                let requests = histogram.iter().sum();
+                (requests > 0).then(|| LatencySummary {
+                    method,
+                    requests,
+                    p50_micros: percentile(&histogram, 0.50),
+                    p90_micros: percentile(&histogram, 0.90),
+                    p99_micros: percentile(&histogram, 0.99),
+                })
+            })
+            .collect();
+        summaries.sort_by_key(|summary| summary.method);
+        summaries
+    }
+
+    /// Retrieves the number of watch streams which are currently open.
+    pub fn active_watchers(&self) -> u64 {
+        self.watchers.lock().unwrap().len() as u64
+    }
+
+    /// Retrieves the watch streams which are currently open, in the order
+    /// they were opened.
+    pub fn watchers(&self) -> Vec<Watcher> {
+        let watchers = self.watchers.lock().unwrap();
+        let mut watchers: Vec<(&u64, &Watcher)> = watchers.iter().collect();
+        watchers.sort_by_key(|(order, _)| **order);
+        watchers
+            .into_iter()
+            .map(|(_, watcher)| watcher.clone())
+            .collect()
+    }
+
+    /// Registers a watch stream as open for as long as the returned guard
+    /// lives.
+    pub fn watcher(self: &Arc<Self>, stream_id: &str, sku: &str) -> WatcherGuard {
+        let id = self.next_watcher.fetch_add(1, Ordering::Relaxed);
+        let watcher = Watcher {
+            stream_id: stream_id.into(),
+            sku: sku.into(),
+        };
+        self.watchers.lock().unwrap().insert(id, watcher);
+        WatcherGuard {
+            metrics: self.clone(),
+            id,
+        }
+    }
+}
+
+/// Registers a watch stream as open until it's dropped.
+#[derive(Debug)]
+pub struct WatcherGuard {
+    metrics: Arc<Metrics>,
+    id: u64,
+}
+
+impl Drop for WatcherGuard {
+    fn drop(&mut self) {
+        self.metrics.watchers.lock().unwrap().remove(&self.id);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Latency Windows
+// -----------------------------------------------------------------------------
+
+/// How far back latency summaries look, in seconds.
+pub const LATENCY_WINDOW_SECS: u64 = 60;
+
+// the window is split into slots which are reused as it rolls forward, so it
+// covers between LATENCY_WINDOW_SECS and one slot less of requests.
+const LATENCY_SLOTS: u64 = 6;
+const LATENCY_SLOT_SECS: u64 = LATENCY_WINDOW_SECS / LATENCY_SLOTS;
+
+// latencies are counted in log-linear buckets: values below 16 get a bucket
+// each, and every power of two above that is split into 8 buckets. Values
+// beyond 2^40 microseconds (about 12 days) share the last bucket.
+const SUB_BUCKETS: u32 = 8;
+const MAX_EXPONENT: u32 = 40;
+const LATENCY_BUCKETS: usize = (16 + (MAX_EXPONENT - 3) * SUB_BUCKETS) as usize;
+
+/// A histogram of the latencies recorded over a rolling window. Recording
+/// only uses atomics, a slot which has rolled out of the window is cleared by
+/// whichever recording claims it first. Recordings racing with the clearing
+/// may be lost, which only skews the percentiles by a request.
+#[derive(Debug)]
+struct LatencyWindow {
+    slots: Vec<LatencySlot>,
+}
+
+#[derive(Debug)]
+struct LatencySlot {
+    // the index of the slot-length period since the metrics were created
+    // which the counts are for, plus one so that 0 marks an unused slot.
+    period: AtomicU64,
+    counts: Vec<AtomicU64>,
+}
+
+impl Default for LatencyWindow {
+    fn default() -> Self {
+        let slots = (0..LATENCY_SLOTS)
+            .map(|_| LatencySlot {
+                period: AtomicU64::new(0),
+                counts: (0..LATENCY_BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            })
+            .collect();
+        LatencyWindow { slots }
+    }
+}
+
+impl LatencyWindow {
+    // records a latency at the given number of seconds since the metrics
+    // were created.
+    fn record(&self, now_secs: u64, latency_micros: u64) {
+        let period = now_secs / LATENCY_SLOT_SECS + 1;
+        let slot = &self.slots[(period % LATENCY_SLOTS) as usize];
+
+        let current = slot.period.load(Ordering::Acquire);
+        if current != period
+            && slot
+                .period
+                .compare_exchange(current, period, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            for count in &slot.counts {
+                count.store(0, Ordering::Relaxed);
+            }
+        }
+        slot.counts[bucket(latency_micros)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    // sums the counts of the slots within the window ending at the given
+    // number of seconds since the metrics were created.
+    fn histogram(&self, now_secs: u64) -> Vec<u64> {
+        let period = now_secs / LATENCY_SLOT_SECS + 1;
+        let mut histogram = vec![0; LATENCY_BUCKETS];
+        for slot in &self.slots {
+            let slot_period = slot.period.load(Ordering::Acquire);
+            if slot_period == 0 || slot_period + LATENCY_SLOTS <= period {
+                continue;
+            }
+            for (total, count) in histogram.iter_mut().zip(&slot.counts) {
+                *total += count.load(Ordering::Relaxed);
+            }
+        }
+        histogram
+    }
+}
+
+// finds the bucket a latency is counted in.
+fn bucket(latency_micros: u64) -> usize {
+    if latency_micros < 16 {
+        return latency_micros as usize;
+    }
+    let exponent = (63 - latency_micros.leading_zeros()).min(MAX_EXPONENT);
+    let latency_micros = latency_micros.min((1 << (MAX_EXPONENT + 1)) - 1);
+    let sub_bucket = (latency_micros >> (exponent - 3)) as u32 - SUB_BUCKETS;
+    (16 + (exponent - 4) * SUB_BUCKETS + sub_bucket) as usize
+}
+
+// the largest latency counted in a bucket.
+fn bucket_max(bucket: usize) -> u64 {
+    if bucket < 16 {
+        return bucket as u64;
+    }
+    let exponent = (bucket as u32 - 16) / SUB_BUCKETS + 4;
+    let sub_bucket = (bucket as u64 - 16) % SUB_BUCKETS as u64;
+    ((SUB_BUCKETS as u64 + sub_bucket + 1) << (exponent - 3)) - 1
+}
+
+// finds the latency which the given fraction of the requests counted in a
+// histogram took at most, reported as the largest latency of its bucket.
+fn percentile(histogram: &[u64], fraction: f64) -> u64 {
+    let total: u64 = histogram.iter().sum();
+    let rank = ((total as f64 * fraction).ceil() as u64).max(1);
+    let mut seen = 0;
+    for (bucket, count) in histogram.iter().enumerate() {
+        seen += count;
+        if seen >= rank {
+            return bucket_max(bucket);
+        }
+    }
+    0
+}
+
+// -----------------------------------------------------------------------------
+// Metrics Layer
+// -----------------------------------------------------------------------------
+
+/// A layer which records the number, outcome and latency of the requests to
+/// the Inventory service. The latency of streaming methods is the time until
+/// the stream was opened, and only errors returned before it was opened are
+/// counted.
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    metrics: Arc<Metrics>,
+}
+
+impl MetricsLayer {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        MetricsLayer { metrics }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: Arc<Metrics>,
+}
+
+impl<S, B, R> Service<http::Request<B>> for MetricsService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<R>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request.uri().path().to_string();
+        let metrics = self.metrics.clone();
+        let start = Instant::now();
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response.await;
+
+            // errors returned by handlers are sent as trailers-only responses,
+            // so their status is found in the headers. Other responses are
+            // successful, at least until their stream ends.
+            let code = match &response {
+                Ok(response) => response
+                    .headers()
+                    .get("grpc-status")
+                    .and_then(|status| status.to_str().ok()?.parse::<i32>().ok())
+                    .map_or(Code::Ok, Code::from),
+                Err(_) => Code::Unknown,
+            };
+            let latency = start.elapsed().as_micros() as u64;
+            metrics.record(&method, latency, code);
+
+            response
+        })
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use crate::metrics::{
+        bucket, bucket_max, percentile, LatencyWindow, Metrics, LATENCY_BOUNDS_MICROS,
+        LATENCY_BUCKETS, LATENCY_WINDOW_SECS,
+    };
+
+    #[test]
+    fn recording() {
+        let metrics = std::sync::Arc::new(Metrics::default());
+        metrics.record("/store.Inventory/Get", 100, Code::Ok);
+        metrics.record("/store.Inventory/Get", 300, Code::NotFound);
+        metrics.record("/store.Unknown/Get", 500, Code::Ok);
+
+        let snapshots = metrics.snapshot();
+        let get = snapshots
+            .iter()
+            .find(|snapshot| snapshot.method == "/store.Inventory/Get")
+            .unwrap();
+        assert_eq!(get.requests, 2);
+        assert_eq!(get.errors, 1);
+        assert_eq!(get.mean_latency_micros, 200);
+        assert_eq!(get.max_latency_micros, 300);
+        assert_eq!(get.codes, [(Code::Ok, 1), (Code::NotFound, 1)]);
+
+        // latency buckets are cumulative, and include latencies at their bound
+        assert_eq!(get.latency_buckets.len(), LATENCY_BOUNDS_MICROS.len());
+        assert_eq!(get.latency_buckets[..3], [1, 1, 2]);
+        assert_eq!(get.latency_buckets.last(), Some(&2));
+        assert!(snapshots
+            .iter()
+            .all(|snapshot| !snapshot.method.contains("Unknown")));
+
+        // a reconnected stream may share its id with one not yet closed
+        let first = metrics.watcher("stream", "A");
+        let second = metrics.watcher("stream", "A");
+        let other = metrics.watcher("other", "B");
+        assert_eq!(metrics.active_watchers(), 3);
+        drop(first);
+        let watchers = metrics.watchers();
+        let ids: Vec<&str> = watchers.iter().map(|w| w.stream_id.as_str()).collect();
+        assert_eq!(ids, ["stream", "other"]);
+        drop((second, other));
+        assert_eq!(metrics.active_watchers(), 0);
+    }
+
+    #[test]
+    fn latency_buckets() {
+        // every latency is within its bucket, which is at most 1/8th wide
+        for latency in (0..100_000).chain([1 << 39, (1 << 41) - 1]) {
+            let bucket = bucket(latency);
+            assert!(bucket_max(bucket) >= latency, "{}", latency);
+            assert!(
+                bucket == 0 || bucket_max(bucket - 1) < latency,
+                "{}",
+                latency
+            );
+            assert!(bucket_max(bucket) - latency <= latency / 8, "{}", latency);
+        }
+        assert_eq!(bucket(u64::MAX), LATENCY_BUCKETS - 1);
+
+        let mut histogram = vec![0; LATENCY_BUCKETS];
+        for latency in 1..=100 {
+            histogram[bucket(latency)] += 1;
+        }
+        assert_eq!(percentile(&histogram, 0.5), 51);
+        assert_eq!(percentile(&histogram, 0.9), 95);
+        assert_eq!(percentile(&histogram, 0.99), 103);
+        assert_eq!(percentile(&vec![0; LATENCY_BUCKETS], 0.5), 0);
+    }
+
+    #[test]
+    fn latency_windows() {
+        let window = LatencyWindow::default();
+        window.record(0, 10);
+        window.record(LATENCY_WINDOW_SECS / 2, 10);
+        window.record(LATENCY_WINDOW_SECS / 2, 11);
+        assert_eq!(window.histogram(LATENCY_WINDOW_SECS / 2)[10], 2);
+        assert_eq!(window.histogram(LATENCY_WINDOW_SECS / 2)[11], 1);
+
+        // latencies roll out of the window as time passes, and their slots
+        // are reused
+        let later = LATENCY_WINDOW_SECS + 1;
+        assert_eq!(window.histogram(later)[10], 1);
+        window.record(later, 12);
+        assert_eq!(window.histogram(later)[10], 1);
+        assert_eq!(window.histogram(later)[12], 1);
+        assert!(window.histogram(later * 3).iter().all(|count| *count == 0));
+
+        let metrics = Metrics::default();
+        metrics.record("/store.Inventory/Get", 100, Code::Ok);
+        metrics.record("/store.Inventory/Get", 300, Code::Internal);
+        let summaries = metrics.latency_summaries();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].method, "/store.Inventory/Get");
+        assert_eq!(summaries[0].requests, 2);
+        assert_eq!(summaries[0].p50_micros, 103);
+        assert_eq!(summaries[0].p99_micros, 319);
+    }
+}
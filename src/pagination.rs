@@ -0,0 +1,73 @@
+use std::fmt::Write;
+
+// -----------------------------------------------------------------------------
+// Page Tokens
+// -----------------------------------------------------------------------------
+
+// the version of the page token format, which is the first field of every
+// token so that the format can change without misreading older tokens.
+const TOKEN_VERSION: &str = "1";
+
+/// The position a paginated listing of the inventory resumes from. Listing
+/// resumes strictly after the last SKU of the previous page, rather than from
+/// an offset, so that concurrent changes to the inventory can't cause items
+/// to be skipped or repeated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageToken {
+    /// The last SKU returned by the previous page.
+    pub after: String,
+}
+
+impl PageToken {
+    /// Encodes the token into an opaque string for clients to send back. The
+    /// SKU is hex encoded so that tokens are always valid metadata and URL
+    /// components, regardless of the characters in it.
+    pub fn encode(&self) -> String {
+        let mut token = format!("{}.", TOKEN_VERSION);
+        for byte in self.after.as_bytes() {
+            let _ = write!(token, "{:02x}", byte);
+        }
+        token
+    }
+
+    /// Decodes a token previously produced by `encode`, returning None if the
+    /// token is malformed or from an unsupported version.
+    pub fn decode(token: &str) -> Option<Self> {
+        let (version, sku) = token.split_once('.')?;
+        if version != TOKEN_VERSION || sku.len() % 2 != 0 {
+            return None;
+        }
+
+        let bytes = (0..sku.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(sku.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        let after = String::from_utf8(bytes).ok()?;
+        Some(PageToken { after })
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::pagination::PageToken;
+
+    #[test]
+    fn page_tokens() {
+        for after in ["SKU1000", "", "ünïcödé/sku with spaces"] {
+            let token = PageToken {
+                after: after.into(),
+            };
+            let encoded = token.encode();
+            assert!(encoded.is_ascii());
+            assert_eq!(PageToken::decode(&encoded), Some(token));
+        }
+
+        for malformed in ["", "SKU1000", "1.abc", "1.zz", "2.414243", "1.ff"] {
+            assert_eq!(PageToken::decode(malformed), None, "{}", malformed);
+        }
+    }
+}
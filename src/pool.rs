@@ -0,0 +1,346 @@
+use futures::future::BoxFuture;
+use rand::Rng;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::UnixStream;
+use tokio::sync::Mutex;
+use tonic::body::BoxBody;
+use tonic::codegen::http::Uri;
+use tonic::codegen::StdError;
+use tonic::transport::{Body, Channel, Endpoint, Error};
+use tower::{service_fn, Service};
+
+use crate::store::inventory_client::InventoryClient;
+
+// -----------------------------------------------------------------------------
+// Backoff
+// -----------------------------------------------------------------------------
+
+/// How a pool retries connecting to the server: it makes up to `attempts`
+/// attempts, waiting between them for a time which doubles from `min` up to
+/// `max`. Each wait is randomly shortened by up to half (jitter), so that
+/// clients which lost the server together don't all reconnect together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub attempts: u32,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            attempts: 5,
+            min: Duration::from_millis(100),
+            max: Duration::from_secs(5),
+        }
+    }
+}
+
+impl Backoff {
+    /// The wait before the given retry (counting from 0), which is between
+    /// half and all of `min * 2^retry`, capped at `max`.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.checked_pow(retry).unwrap_or(u32::MAX);
+        let ceiling = self
+            .min
+            .checked_mul(factor)
+            .map_or(self.max, |delay| delay.min(self.max));
+        let half = ceiling / 2;
+        half + half.mul_f64(rand::thread_rng().gen())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Client Pool
+// -----------------------------------------------------------------------------
+
+/// A source of inventory clients which share a single connection to the
+/// server, for applications which make many calls over their lifetime. The
+/// connection is made on first use, retrying with the pool's backoff when
+/// the server can't be reached, and clients are then handed out over it.
+/// Calls made through the clients are multiplexed over the connection. Once
+/// a call fails to reach the server the connection is dropped, and the next
+/// client asked for connects again with the backoff.
+///
+/// ```no_run
+/// use demo::pool::ClientPool;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let pool = ClientPool::new("http://127.0.0.1:9001")?;
+/// let mut client = pool.get().await?;
+/// let count = client.count(demo::store::CountRequest {}).await?;
+/// println!("{} items", count.into_inner().count);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientPool {
+    endpoint: Endpoint,
+    socket: Option<PathBuf>,
+    backoff: Backoff,
+    // the connection shared by the clients along with the number it was
+    // made as, which is None until the first client is asked for and while
+    // connecting again fails. Clones of the pool share it too.
+    channel: Arc<Mutex<Option<(u64, Channel)>>>,
+    // the number of the latest connection a call failed to reach the server
+    // over, which is made again rather than handed out.
+    failed: Arc<AtomicU64>,
+}
+
+impl ClientPool {
+    /// Creates a pool for the server at the given address, e.g.
+    /// "http://127.0.0.1:9001". Nothing is connected until a client is asked
+    /// for.
+    pub fn new<D>(dst: D) -> Result<Self, Error>
+    where
+        D: TryInto<Endpoint>,
+        D::Error: Into<StdError>,
+    {
+        Ok(ClientPool::with_endpoint(Endpoint::new(dst)?))
+    }
+
+    /// Creates a pool for an endpoint, e.g. one configured with timeouts.
+    pub fn with_endpoint(endpoint: Endpoint) -> Self {
+        ClientPool {
+            endpoint,
+            socket: None,
+            backoff: Backoff::default(),
+            channel: Arc::new(Mutex::new(None)),
+            failed: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Connects through the Unix domain socket at the path rather than over
+    /// TCP, in which case the endpoint only names the server in the
+    /// authority of requests (e.g. "http://localhost").
+    pub fn with_uds(mut self, socket: impl Into<PathBuf>) -> Self {
+        self.socket = Some(socket.into());
+        self
+    }
+
+    /// Sets how connecting to the server is retried.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// How connecting to the server is retried.
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// Retrieves a client which sends its calls over the pool's connection,
+    /// connecting first if the pool isn't connected yet, or if a call failed
+    /// to reach the server over the connection. The error of the last
+    /// attempt is returned when every attempt to connect failed, and the next
+    /// call tries again.
+    pub async fn get(&self) -> Result<InventoryClient<PooledChannel>, Error> {
+        Ok(InventoryClient::new(self.channel().await?))
+    }
+
+    /// Retrieves the pool's connection, connecting first as get does, e.g.
+    /// to wrap it in a client with interceptors or an InventoryApi.
+    pub async fn channel(&self) -> Result<PooledChannel, Error> {
+        // the lock is held while connecting, so that concurrent callers wait
+        // for the one connection rather than each making their own
+        let mut channel = self.channel.lock().await;
+        let number = match channel.as_ref() {
+            Some((number, _)) if *number == self.failed.load(Ordering::SeqCst) => number + 1,
+            Some((number, channel)) => return Ok(self.pooled(*number, channel.clone())),
+            None => 1,
+        };
+        *channel = None;
+        let connected = self.connect().await?;
+        *channel = Some((number, connected.clone()));
+        Ok(self.pooled(number, connected))
+    }
+
+    fn pooled(&self, number: u64, channel: Channel) -> PooledChannel {
+        PooledChannel {
+            channel,
+            number,
+            failed: self.failed.clone(),
+        }
+    }
+
+    // connects to the endpoint, retrying failed attempts with the backoff
+    // until they run out.
+    async fn connect(&self) -> Result<Channel, Error> {
+        let mut attempt = 0;
+        loop {
+            let connected = match &self.socket {
+                Some(socket) => {
+                    let socket = socket.clone();
+                    let connector = service_fn(move |_: Uri| UnixStream::connect(socket.clone()));
+                    self.endpoint.connect_with_connector(connector).await
+                }
+                None => self.endpoint.connect().await,
+            };
+            let err = match connected {
+                Ok(channel) => return Ok(channel),
+                Err(err) => err,
+            };
+            attempt += 1;
+            if attempt >= self.backoff.attempts {
+                return Err(err);
+            }
+            tokio::time::sleep(self.backoff.delay(attempt - 1)).await;
+        }
+    }
+}
+
+/// The connection of a pool as it's handed out to clients. Calls which fail
+/// to reach the server (with a transport error, rather than a status the
+/// server sent) have the pool connect again for the next client it hands
+/// out, while clients already holding the connection keep using it.
+#[derive(Debug, Clone)]
+pub struct PooledChannel {
+    channel: Channel,
+    number: u64,
+    failed: Arc<AtomicU64>,
+}
+
+impl PooledChannel {
+    // has the pool connect again rather than hand out this connection.
+    fn fail(&self) {
+        self.failed.fetch_max(self.number, Ordering::SeqCst);
+    }
+}
+
+impl Service<http::Request<BoxBody>> for PooledChannel {
+    type Response = http::Response<Body>;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let ready = self.channel.poll_ready(cx);
+        if let Poll::Ready(Err(_)) = ready {
+            self.fail();
+        }
+        ready
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        let response = self.channel.call(request);
+        let pooled = self.clone();
+        Box::pin(async move {
+            let response = response.await;
+            if response.is_err() {
+                pooled.fail();
+            }
+            response
+        })
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::pool::{Backoff, ClientPool};
+    use crate::store::CountRequest;
+
+    #[test]
+    fn jittered_backoff() {
+        let backoff = Backoff {
+            attempts: 40,
+            min: Duration::from_millis(100),
+            max: Duration::from_secs(5),
+        };
+
+        // each wait is between half and all of the doubled wait, up to the
+        // limit, and they aren't all the same
+        for (retry, ceiling) in [
+            (0, 100),
+            (1, 200),
+            (2, 400),
+            (5, 3200),
+            (6, 5000),
+            (39, 5000),
+        ] {
+            let ceiling = Duration::from_millis(ceiling);
+            for _ in 0..100 {
+                let delay = backoff.delay(retry);
+                assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?}", delay);
+            }
+        }
+        let delays: Vec<Duration> = (0..100).map(|_| backoff.delay(3)).collect();
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+    }
+
+    #[tokio::test]
+    async fn connect_attempts_run_out() {
+        // nothing listens on the port once the listener is dropped
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let backoff = Backoff {
+            attempts: 3,
+            min: Duration::from_millis(20),
+            max: Duration::from_secs(1),
+        };
+        let pool = ClientPool::new(format!("http://{}", addr))
+            .unwrap()
+            .with_backoff(backoff);
+
+        // two waits are made between the three attempts, of at least half of
+        // 20 and 40 milliseconds
+        let started = Instant::now();
+        assert!(pool.get().await.is_err());
+        assert!(started.elapsed() >= Duration::from_millis(30));
+
+        // and failed connections aren't kept, so the next call tries again
+        assert!(pool.channel.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_calls_reconnect() {
+        // a listener accepting connections in place of a server, which goes
+        // away once the pool is connected
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let backoff = Backoff {
+            attempts: 2,
+            min: Duration::from_millis(1),
+            max: Duration::from_millis(1),
+        };
+        let pool = ClientPool::new(format!("http://{}", addr))
+            .unwrap()
+            .with_backoff(backoff);
+        let accepted = tokio::spawn(async move { listener.accept().await.map(|_| ()) });
+        let mut client = pool.get().await.unwrap();
+        accepted.await.unwrap().unwrap();
+
+        // the connection is handed out until a call fails to reach the
+        // server over it, after which the pool connects again
+        assert!(pool.get().await.is_ok());
+        assert!(client.count(CountRequest {}).await.is_err());
+        assert!(pool.get().await.is_err());
+        assert!(pool.channel.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn unix_domain_sockets() {
+        // a socket accepting connections in place of a server
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("store.sock");
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        let accepted =
+            tokio::spawn(async move { listener.accept().await.map(|(stream, _)| stream) });
+
+        let pool = ClientPool::new("http://localhost")
+            .unwrap()
+            .with_uds(&socket);
+        assert!(pool.channel().await.is_ok());
+        assert!(accepted.await.unwrap().is_ok());
+    }
+}
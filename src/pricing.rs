@@ -0,0 +1,180 @@
+use std::str::FromStr;
+
+// -----------------------------------------------------------------------------
+// Cents
+// -----------------------------------------------------------------------------
+
+/// Converts a price to the nearest whole number of cents. Prices are floats on
+/// the wire, which can't represent most decimal prices exactly (e.g. 2.49), so
+/// prices are compared in cents rather than as floats.
+pub fn to_cents(price: f32) -> i64 {
+    (f64::from(price) * 100.0).round() as i64
+}
+
+/// Rounds a price to the nearest cent. Unlike to_cents, prices too large to
+/// count in cents are kept as they are.
+pub fn round_to_cent(price: f32) -> f32 {
+    ((f64::from(price) * 100.0).round() / 100.0) as f32
+}
+
+// -----------------------------------------------------------------------------
+// Price Endings
+// -----------------------------------------------------------------------------
+
+/// Which way a price moves when it's snapped to a price ending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapDirection {
+    /// Snap to the closest price with the ending at or above the price.
+    Up,
+    /// Snap to the closest price with the ending at or below the price.
+    Down,
+    /// Snap to whichever of the two is closer, ties snap up.
+    Nearest,
+}
+
+impl FromStr for SnapDirection {
+    type Err = String;
+
+    fn from_str(direction: &str) -> Result<Self, Self::Err> {
+        match direction.to_lowercase().as_str() {
+            "up" => Ok(SnapDirection::Up),
+            "down" => Ok(SnapDirection::Down),
+            "nearest" => Ok(SnapDirection::Nearest),
+            _ => Err(format!("unknown snap direction: {}", direction)),
+        }
+    }
+}
+
+/// A "psychological" price ending (e.g. .99 or .95) which computed prices are
+/// snapped to. This is distinct from rounding to a number of decimal places:
+/// the whole part of the price may change, while the cents never do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceEnding {
+    cents: u32,
+    direction: SnapDirection,
+}
+
+impl PriceEnding {
+    /// Creates a price ending of the given cents (e.g. 99 for .99), returns
+    /// None if the cents aren't below 100.
+    pub fn new(cents: u32, direction: SnapDirection) -> Option<Self> {
+        (cents < 100).then_some(PriceEnding { cents, direction })
+    }
+
+    /// Parses a price ending written as a fraction of a unit (e.g. "0.99" or
+    /// ".95"), returns None if it's not a fraction below 1.
+    pub fn parse(ending: &str, direction: SnapDirection) -> Option<Self> {
+        let ending: f64 = ending.trim().parse().ok()?;
+        if !(0.0..1.0).contains(&ending) {
+            return None;
+        }
+        PriceEnding::new((ending * 100.0).round() as u32, direction)
+    }
+}
+
+/// Snaps a price to the given ending. The price is first taken to the nearest
+/// cent, and prices which already have the ending are left alone. Otherwise
+/// the candidates are the closest prices with the ending below and above it
+/// (4.37 with an ending of .99 has the candidates 3.99 and 4.99), and the
+/// ending's direction picks between them. Snapped prices never go below zero,
+/// so prices lower than the ending itself always snap up to it.
+pub fn snap_price(value: f32, ending: PriceEnding) -> f32 {
+    // work in whole cents, so that floating point error can't move a price
+    // which already has the ending
+    let cents = to_cents(value);
+    let ending_cents = i64::from(ending.cents);
+
+    let mut below = cents.div_euclid(100) * 100 + ending_cents;
+    if below > cents {
+        below -= 100;
+    }
+    if below == cents {
+        return value;
+    }
+    let above = below + 100;
+
+    let snapped = match ending.direction {
+        _ if below < 0 => above,
+        SnapDirection::Up => above,
+        SnapDirection::Down => below,
+        SnapDirection::Nearest if above - cents <= cents - below => above,
+        SnapDirection::Nearest => below,
+    };
+    (snapped as f64 / 100.0) as f32
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::pricing::{round_to_cent, snap_price, to_cents, PriceEnding, SnapDirection};
+
+    fn ending(cents: u32, direction: SnapDirection) -> PriceEnding {
+        PriceEnding::new(cents, direction).unwrap()
+    }
+
+    #[test]
+    fn snapping() {
+        let up = ending(99, SnapDirection::Up);
+        let down = ending(99, SnapDirection::Down);
+        let nearest = ending(99, SnapDirection::Nearest);
+
+        // the direction picks between the candidates on either side
+        assert_eq!(snap_price(4.37, up), 4.99);
+        assert_eq!(snap_price(4.37, down), 3.99);
+        assert_eq!(snap_price(4.37, nearest), 3.99);
+        assert_eq!(snap_price(4.62, nearest), 4.99);
+
+        // ties between the candidates snap up
+        assert_eq!(snap_price(4.49, nearest), 4.99);
+
+        // prices which already have the ending are unchanged
+        for ending in [up, down, nearest] {
+            assert_eq!(snap_price(4.99, ending), 4.99);
+            assert_eq!(snap_price(4.9901, ending), 4.9901);
+        }
+
+        // prices never snap below zero
+        assert_eq!(snap_price(0.5, down), 0.99);
+        assert_eq!(snap_price(0.0, nearest), 0.99);
+
+        // other endings, including whole prices
+        assert_eq!(snap_price(12.10, ending(95, SnapDirection::Down)), 11.95);
+        assert_eq!(snap_price(12.10, ending(0, SnapDirection::Nearest)), 12.0);
+        assert_eq!(snap_price(12.10, ending(0, SnapDirection::Up)), 13.0);
+    }
+
+    #[test]
+    fn cents() {
+        assert_eq!(to_cents(2.49), 249);
+        assert_eq!(to_cents(0.1 + 0.2), 30);
+        assert_eq!(to_cents(2.494), 249);
+        assert_eq!(to_cents(2.496), 250);
+        assert_eq!(to_cents(f32::NAN), 0);
+        assert_eq!(round_to_cent(2.494), 2.49);
+        assert_eq!(round_to_cent(0.1 + 0.2), 0.3);
+        assert_eq!(round_to_cent(f32::MAX), f32::MAX);
+    }
+
+    #[test]
+    fn endings() {
+        assert_eq!(
+            PriceEnding::parse("0.99", SnapDirection::Up),
+            PriceEnding::new(99, SnapDirection::Up)
+        );
+        assert_eq!(
+            PriceEnding::parse(".95", SnapDirection::Up),
+            PriceEnding::new(95, SnapDirection::Up)
+        );
+        assert_eq!(PriceEnding::parse("1.99", SnapDirection::Up), None);
+        assert_eq!(PriceEnding::parse("-0.01", SnapDirection::Up), None);
+        assert_eq!(PriceEnding::parse("cheap", SnapDirection::Up), None);
+        assert_eq!(PriceEnding::new(100, SnapDirection::Up), None);
+
+        assert_eq!("UP".parse(), Ok(SnapDirection::Up));
+        assert_eq!("nearest".parse(), Ok(SnapDirection::Nearest));
+        assert!("sideways".parse::<SnapDirection>().is_err());
+    }
+}
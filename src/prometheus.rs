@@ -0,0 +1,181 @@
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::metrics::{Metrics, LATENCY_BOUNDS_MICROS};
+use crate::routing::INVENTORY_PREFIX;
+
+// -----------------------------------------------------------------------------
+// Prometheus Exporter
+// -----------------------------------------------------------------------------
+
+// the content type of the Prometheus text exposition format.
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4";
+
+/// Serves the server's metrics for Prometheus to scrape at `GET /metrics`, in
+/// the text exposition format:
+///
+///  - `store_requests_total` counts the requests to each Inventory method by
+///    the status code they ended with, for alerting on error rates.
+///  - `store_request_duration_seconds` is a histogram of their latencies.
+///  - `store_active_watchers` is the number of open watch streams.
+///
+/// Streaming methods are measured as they are by MetricsLayer.
+pub async fn serve(addr: SocketAddr, metrics: Arc<Metrics>) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let metrics = metrics.clone();
+                async move { Ok::<_, Infallible>(handle(&metrics, request)) }
+            }))
+        }
+    });
+
+    hyper::Server::bind(&addr).serve(make_service).await
+}
+
+fn handle(metrics: &Metrics, request: Request<Body>) -> Response<Body> {
+    let status = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            return Response::builder()
+                .header(CONTENT_TYPE, TEXT_FORMAT)
+                .body(Body::from(render(metrics)))
+                .unwrap();
+        }
+        (_, "/metrics") => StatusCode::METHOD_NOT_ALLOWED,
+        _ => StatusCode::NOT_FOUND,
+    };
+
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Renders the metrics in the Prometheus text exposition format.
+pub fn render(metrics: &Metrics) -> String {
+    let snapshots = metrics.snapshot();
+    let label = |method: &str| method.trim_start_matches(INVENTORY_PREFIX).to_string();
+    let mut text = String::new();
+
+    // writing to a String never fails
+    let _ = writeln!(
+        text,
+        "# HELP store_requests_total Requests to the Inventory service by method and status code."
+    );
+    let _ = writeln!(text, "# TYPE store_requests_total counter");
+    for snapshot in &snapshots {
+        for (code, count) in &snapshot.codes {
+            let _ = writeln!(
+                text,
+                "store_requests_total{{method=\"{}\",code=\"{:?}\"}} {}",
+                label(snapshot.method),
+                code,
+                count
+            );
+        }
+    }
+
+    let _ = writeln!(
+        text,
+        "# HELP store_request_duration_seconds Latency of requests to the Inventory service."
+    );
+    let _ = writeln!(text, "# TYPE store_request_duration_seconds histogram");
+    for snapshot in snapshots.iter().filter(|snapshot| snapshot.requests > 0) {
+        let method = label(snapshot.method);
+        for (bound, count) in LATENCY_BOUNDS_MICROS.iter().zip(&snapshot.latency_buckets) {
+            let _ = writeln!(
+                text,
+                "store_request_duration_seconds_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                method,
+                *bound as f64 / 1e6,
+                count
+            );
+        }
+        let _ = writeln!(
+            text,
+            "store_request_duration_seconds_bucket{{method=\"{}\",le=\"+Inf\"}} {}",
+            method, snapshot.requests
+        );
+        let _ = writeln!(
+            text,
+            "store_request_duration_seconds_sum{{method=\"{}\"}} {}",
+            method,
+            snapshot.total_latency_micros as f64 / 1e6
+        );
+        let _ = writeln!(
+            text,
+            "store_request_duration_seconds_count{{method=\"{}\"}} {}",
+            method, snapshot.requests
+        );
+    }
+
+    let _ = writeln!(
+        text,
+        "# HELP store_active_watchers Watch streams which are currently open."
+    );
+    let _ = writeln!(text, "# TYPE store_active_watchers gauge");
+    let _ = writeln!(text, "store_active_watchers {}", metrics.active_watchers());
+
+    text
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Body, Method, Request, StatusCode};
+    use tonic::Code;
+
+    use crate::metrics::Metrics;
+    use crate::prometheus::{handle, render};
+
+    #[test]
+    fn exposition() {
+        let metrics = std::sync::Arc::new(Metrics::default());
+        metrics.record("/store.Inventory/Get", 100, Code::Ok);
+        metrics.record("/store.Inventory/Get", 3_000, Code::NotFound);
+        let _watcher = metrics.watcher("stream", "SKU");
+        let text = render(&metrics);
+
+        for line in [
+            "store_requests_total{method=\"Get\",code=\"Ok\"} 1",
+            "store_requests_total{method=\"Get\",code=\"NotFound\"} 1",
+            "store_request_duration_seconds_bucket{method=\"Get\",le=\"0.0001\"} 1",
+            "store_request_duration_seconds_bucket{method=\"Get\",le=\"0.0025\"} 1",
+            "store_request_duration_seconds_bucket{method=\"Get\",le=\"0.005\"} 2",
+            "store_request_duration_seconds_bucket{method=\"Get\",le=\"+Inf\"} 2",
+            "store_request_duration_seconds_sum{method=\"Get\"} 0.0031",
+            "store_request_duration_seconds_count{method=\"Get\"} 2",
+            "store_active_watchers 1",
+        ] {
+            assert!(text.lines().any(|l| l == line), "{}", line);
+        }
+
+        // methods which weren't called aren't reported
+        assert!(!text.contains("method=\"Add\""));
+    }
+
+    #[test]
+    fn routes() {
+        let metrics = Metrics::default();
+        let request = |method: Method, path: &str| {
+            let request = Request::builder().method(method).uri(path);
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = handle(&metrics, request(Method::GET, "/metrics"));
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = handle(&metrics, request(Method::POST, "/metrics"));
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let response = handle(&metrics, request(Method::GET, "/items"));
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,407 @@
+use http_body::{LengthLimitError, Limited};
+use hyper::header::{ALLOW, AUTHORIZATION, CONTENT_TYPE, LOCATION, WWW_AUTHENTICATE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Serialize;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::{Code, Status};
+
+use crate::json::{parse_item, parse_price_change};
+use crate::server::{constant_time_eq, StoreInventory};
+use crate::store::inventory_server::Inventory;
+use crate::store::{GetRequest, RemoveRequest};
+
+// -----------------------------------------------------------------------------
+// REST Gateway
+// -----------------------------------------------------------------------------
+
+// the largest request body accepted, which is plenty for a single item.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// A REST front end to the inventory for clients which can't speak gRPC,
+/// which maps requests onto the Inventory methods in the manner of
+/// grpc-gateway. Request and response bodies are JSON following the proto3
+/// JSON mapping:
+///
+///  - `GET /items/{sku}` retrieves an Item (Get).
+///  - `POST /items` adds the Item in the body (Add), responding 201.
+///  - `DELETE /items/{sku}` removes an Item, responding 404 if it didn't
+///    exist (a strict Remove).
+///  - `PATCH /items/{sku}/price` changes an Item's price (UpdatePrice), with
+///    a body such as `{"price": 2.49}`.
+///
+/// Errors are reported as `{"error": "..."}` with the HTTP status the gRPC
+/// status code maps to, see [`http_status`].
+#[derive(Debug, Clone)]
+pub struct RestGateway {
+    inventory: Arc<StoreInventory>,
+    api_key: Option<String>,
+    anonymous_reads: bool,
+}
+
+impl RestGateway {
+    /// Creates a gateway which calls into the given inventory.
+    pub fn new(inventory: Arc<StoreInventory>) -> Self {
+        RestGateway {
+            inventory,
+            api_key: None,
+            anonymous_reads: false,
+        }
+    }
+
+    /// Requires every request to carry the given API key as a bearer token in
+    /// its authorization header, as the gRPC server does.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Accepts reads (`GET` requests) without the API key, so that only
+    /// changes to the inventory require it.
+    pub fn with_anonymous_reads(mut self, anonymous_reads: bool) -> Self {
+        self.anonymous_reads = anonymous_reads;
+        self
+    }
+
+    /// Serves the gateway on the given address.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), hyper::Error> {
+        let gateway = Arc::new(self);
+        let make_service = make_service_fn(move |_| {
+            let gateway = gateway.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let gateway = gateway.clone();
+                    async move { Ok::<_, Infallible>(gateway.handle(request).await) }
+                }))
+            }
+        });
+
+        hyper::Server::bind(&addr).serve(make_service).await
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        if !authorized(&request, self.api_key.as_deref(), self.anonymous_reads) {
+            return unauthorized();
+        }
+
+        let segments: Vec<_> = match request.uri().path().strip_prefix("/items") {
+            Some("" | "/") => Vec::new(),
+            Some(path) => match path.strip_prefix('/') {
+                Some(path) => path.split('/').collect(),
+                None => return error(StatusCode::NOT_FOUND, "not found"),
+            },
+            None => return error(StatusCode::NOT_FOUND, "not found"),
+        };
+        let sku = segments
+            .first()
+            .map(|sku| percent_decode_str(sku).decode_utf8_lossy().into_owned());
+
+        match (segments.as_slice(), request.method()) {
+            ([], &Method::POST) => self.add(request).await,
+            ([], _) => method_not_allowed("POST"),
+            ([_], &Method::GET) => self.get(sku.unwrap()).await,
+            ([_], &Method::DELETE) => self.remove(sku.unwrap()).await,
+            ([_], _) => method_not_allowed("GET, DELETE"),
+            ([_, "price"], &Method::PATCH) => self.update_price(sku.unwrap(), request).await,
+            ([_, "price"], _) => method_not_allowed("PATCH"),
+            _ => error(StatusCode::NOT_FOUND, "not found"),
+        }
+    }
+
+    async fn get(&self, sku: String) -> Response<Body> {
+        let request = tonic::Request::new(GetRequest {
+            sku,
+            ..Default::default()
+        });
+        match self.inventory.get(request).await {
+            Ok(item) => json(StatusCode::OK, &item.into_inner()),
+            Err(status) => status_error(&status),
+        }
+    }
+
+    async fn add(&self, request: Request<Body>) -> Response<Body> {
+        let body = match read_body(request).await {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+        let item = match parse_item(&body) {
+            Ok(item) => item,
+            Err(err) => return error(StatusCode::BAD_REQUEST, &err),
+        };
+
+        match self.inventory.add(tonic::Request::new(item)).await {
+            Ok(response) => {
+                let response = response.into_inner();
+                let sku = response
+                    .item
+                    .as_ref()
+                    .and_then(|item| item.identifier.as_ref())
+                    .map(|identifier| identifier.sku.as_str())
+                    .unwrap_or_default();
+                let location = format!("/items/{}", utf8_percent_encode(sku, NON_ALPHANUMERIC));
+                let mut created = json(StatusCode::CREATED, &response);
+                created
+                    .headers_mut()
+                    .insert(LOCATION, location.parse().unwrap());
+                created
+            }
+            Err(status) => status_error(&status),
+        }
+    }
+
+    async fn remove(&self, sku: String) -> Response<Body> {
+        let request = tonic::Request::new(RemoveRequest { sku, strict: true });
+        match self.inventory.remove(request).await {
+            Ok(response) => json(StatusCode::OK, &response.into_inner()),
+            Err(status) => status_error(&status),
+        }
+    }
+
+    async fn update_price(&self, sku: String, request: Request<Body>) -> Response<Body> {
+        let body = match read_body(request).await {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+        let mut change = match parse_price_change(&body) {
+            Ok(change) => change,
+            Err(err) => return error(StatusCode::BAD_REQUEST, &err),
+        };
+        // the SKU comes from the path, and the body can't contradict it
+        if !change.sku.is_empty() && change.sku != sku {
+            return error(StatusCode::BAD_REQUEST, "the SKU doesn't match the path");
+        }
+        change.sku = sku;
+
+        match self
+            .inventory
+            .update_price(tonic::Request::new(change))
+            .await
+        {
+            Ok(response) => json(StatusCode::OK, &response.into_inner()),
+            Err(status) => status_error(&status),
+        }
+    }
+}
+
+/// The HTTP status a gRPC status code maps to, following grpc-gateway.
+pub fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        // nginx's "client closed request", which grpc-gateway uses too
+        Code::Cancelled => StatusCode::from_u16(499).unwrap(),
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// reads the whole body of a request as text, or the response rejecting it.
+async fn read_body(request: Request<Body>) -> Result<String, Response<Body>> {
+    let body = Limited::new(request.into_body(), MAX_BODY_SIZE);
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.is::<LengthLimitError>() => {
+            return Err(error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "the request body is too large",
+            ))
+        }
+        Err(_) => {
+            return Err(error(
+                StatusCode::BAD_REQUEST,
+                "the request body was cut short",
+            ))
+        }
+    };
+    String::from_utf8(bytes.to_vec())
+        .map_err(|_| error(StatusCode::BAD_REQUEST, "the request body isn't UTF-8"))
+}
+
+/// Checks that an HTTP request carries the API key as a bearer token in its
+/// authorization header, when one is required. Reads (`GET` requests) are
+/// authorized without it when anonymous reads are allowed.
+pub(crate) fn authorized(
+    request: &Request<Body>,
+    api_key: Option<&str>,
+    anonymous_reads: bool,
+) -> bool {
+    let api_key = match api_key {
+        Some(api_key) => api_key,
+        None => return true,
+    };
+    if anonymous_reads && request.method() == Method::GET {
+        return true;
+    }
+    let authorization = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+        Some(token) => constant_time_eq(token.as_bytes(), api_key.as_bytes()),
+        None => false,
+    }
+}
+
+/// The response to requests which aren't authorized, see [`authorized`].
+pub(crate) fn unauthorized() -> Response<Body> {
+    let mut response = error(StatusCode::UNAUTHORIZED, "missing or invalid API key");
+    response
+        .headers_mut()
+        .insert(WWW_AUTHENTICATE, "Bearer".parse().unwrap());
+    response
+}
+
+fn json(code: StatusCode, message: &impl Serialize) -> Response<Body> {
+    let mut json = serde_json::to_vec(message).expect("messages encode as JSON");
+    json.push(b'\n');
+    Response::builder()
+        .status(code)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(json))
+        .unwrap()
+}
+
+fn error(code: StatusCode, message: &str) -> Response<Body> {
+    json(code, &serde_json::json!({ "error": message }))
+}
+
+fn status_error(status: &Status) -> Response<Body> {
+    error(http_status(status.code()), status.message())
+}
+
+fn method_not_allowed(allow: &'static str) -> Response<Body> {
+    let mut response = error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+    response.headers_mut().insert(ALLOW, allow.parse().unwrap());
+    response
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::{AUTHORIZATION, LOCATION};
+    use hyper::{Body, Method, Request, StatusCode};
+    use std::println as info;
+    use std::sync::Arc;
+
+    use crate::rest::RestGateway;
+    use crate::server::StoreInventory;
+
+    async fn request(
+        gateway: &RestGateway,
+        method: Method,
+        path: &str,
+        body: &str,
+    ) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = gateway.handle(request).await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn items() {
+        let gateway = RestGateway::new(Arc::new(StoreInventory::default()));
+        let item = r#"{"identifier": {"sku": "C/D"}, "stock": {"price": 1.79, "quantity": 42}}"#;
+
+        info!("adding an item, which is created at its own path");
+        let add = Request::builder()
+            .method(Method::POST)
+            .uri("/items")
+            .body(Body::from(item))
+            .unwrap();
+        let response = gateway.handle(add).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[LOCATION], "/items/C%2FD");
+
+        info!("retrieving the item, and changing its price");
+        let (status, body) = request(&gateway, Method::GET, "/items/C%2FD", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains(r#""stock":{"price":1.79,"quantity":42,"currency":"USD"}"#));
+        let (status, body) = request(
+            &gateway,
+            Method::PATCH,
+            "/items/C%2FD/price",
+            r#"{"price": 2.49}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let (_, body) = request(&gateway, Method::GET, "/items/C%2FD", "").await;
+        assert!(body.contains(r#""price":2.49"#));
+
+        info!("verifying errors map onto HTTP statuses");
+        let (status, _) = request(&gateway, Method::POST, "/items", item).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = request(&gateway, Method::POST, "/items", "{").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, body) = request(
+            &gateway,
+            Method::PATCH,
+            "/items/C%2FD/price",
+            r#"{"sku": "OTHER", "price": 2.49}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        let (status, _) = request(&gateway, Method::PATCH, "/items/C%2FD/price", "[]").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = request(&gateway, Method::GET, "/items/MISSING", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = request(&gateway, Method::PUT, "/items/C%2FD", "").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        let (status, _) = request(&gateway, Method::GET, "/other", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let large = "x".repeat(super::MAX_BODY_SIZE + 1);
+        let (status, _) = request(&gateway, Method::POST, "/items", &large).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        info!("removing the item, which can then only be reported missing");
+        let (status, _) = request(&gateway, Method::DELETE, "/items/C%2FD", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = request(&gateway, Method::DELETE, "/items/C%2FD", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn api_key() {
+        let gateway = RestGateway::new(Arc::new(StoreInventory::default())).with_api_key("secret");
+
+        info!("verifying requests without the API key are rejected");
+        let (status, _) = request(&gateway, Method::GET, "/items/A", "").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        info!("verifying requests with the API key are served");
+        let get = Request::builder()
+            .uri("/items/A")
+            .header(AUTHORIZATION, "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+        let response = gateway.handle(get).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        info!("verifying only reads are served without it when allowed");
+        let gateway = gateway.with_anonymous_reads(true);
+        let (status, _) = request(&gateway, Method::GET, "/items/A", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = request(&gateway, Method::DELETE, "/items/A", "").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
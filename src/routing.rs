@@ -0,0 +1,223 @@
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+// -----------------------------------------------------------------------------
+// Method Classification
+// -----------------------------------------------------------------------------
+
+// the response metadata header which hints whether the request was read-only,
+// so that a proxy in a replicated setup can learn which requests could be
+// routed to replicas.
+const READ_ONLY_HEADER: &str = "x-read-only";
+
+/// The path prefix shared by the gRPC paths of all the Inventory methods.
+pub const INVENTORY_PREFIX: &str = "/store.Inventory/";
+
+// Inventory methods which never modify the inventory.
+const READ_ONLY_METHODS: &[&str] = &[
+    "/store.Inventory/Get",
+    "/store.Inventory/Watch",
+    "/store.Inventory/ListChangedSince",
+    "/store.Inventory/GetImage",
+    "/store.Inventory/GetMany",
+    "/store.Inventory/List",
+    "/store.Inventory/Sample",
+    "/store.Inventory/GetMetrics",
+    "/store.Inventory/TailLogs",
+    "/store.Inventory/GetAccessStats",
+    "/store.Inventory/GroupByPriceBucket",
+    "/store.Inventory/GetLatencySummary",
+    "/store.Inventory/ListByAttribute",
+    "/store.Inventory/Search",
+    "/store.Inventory/InventoryStats",
+    "/store.Inventory/WatchAll",
+    "/store.Inventory/Count",
+    "/store.Inventory/ListByTag",
+    "/store.Inventory/ListLowStock",
+    "/store.Inventory/GetAuditLog",
+    "/store.Inventory/GetAndWatch",
+    "/store.Inventory/Validate",
+    "/store.Inventory/GetServerInfo",
+    "/store.Inventory/BatchGet",
+    "/store.Inventory/ListByWeightRange",
+];
+
+// Inventory methods which modify the inventory.
+const MUTATING_METHODS: &[&str] = &[
+    "/store.Inventory/Add",
+    "/store.Inventory/BatchAdd",
+    "/store.Inventory/Remove",
+    "/store.Inventory/UpdateQuantity",
+    "/store.Inventory/UpdatePrice",
+    "/store.Inventory/UploadImage",
+    "/store.Inventory/SwapStock",
+    "/store.Inventory/SetReadOnly",
+    "/store.Inventory/ApplyBatch",
+    "/store.Inventory/CheckConsistency",
+    "/store.Inventory/LoadSnapshot",
+    "/store.Inventory/BulkUpdatePrice",
+    "/store.Inventory/UpdateInformation",
+    "/store.Inventory/Upsert",
+    "/store.Inventory/Reserve",
+    "/store.Inventory/Release",
+    "/store.Inventory/SetQuantity",
+    "/store.Inventory/BatchRemove",
+    "/store.Inventory/SetReorderThreshold",
+    "/store.Inventory/RenameSku",
+];
+
+/// Lists the gRPC paths of all the Inventory methods.
+pub fn inventory_methods() -> impl Iterator<Item = &'static str> {
+    READ_ONLY_METHODS.iter().chain(MUTATING_METHODS).copied()
+}
+
+/// Reports whether the method at the given gRPC path (e.g.
+/// "/store.Inventory/Get") only reads from the inventory. Unknown methods are
+/// conservatively considered mutating.
+pub fn is_read_only(method_path: &str) -> bool {
+    READ_ONLY_METHODS.contains(&method_path)
+}
+
+// -----------------------------------------------------------------------------
+// Read-Only Hint Layer
+// -----------------------------------------------------------------------------
+
+/// A layer which hints in the response metadata whether an Inventory request
+/// was read-only or mutating.
+#[derive(Debug, Clone, Default)]
+pub struct ReadOnlyHintLayer;
+
+impl<S> Layer<S> for ReadOnlyHintLayer {
+    type Service = ReadOnlyHint<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReadOnlyHint { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReadOnlyHint<S> {
+    inner: S,
+}
+
+impl<S, B, R> Service<http::Request<B>> for ReadOnlyHint<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<R>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // only methods of the Inventory service are classified
+        let path = request.uri().path();
+        let hint = if READ_ONLY_METHODS.contains(&path) || MUTATING_METHODS.contains(&path) {
+            Some(if is_read_only(path) { "true" } else { "false" })
+        } else {
+            None
+        };
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let mut response = response.await?;
+            if let Some(hint) = hint {
+                response
+                    .headers_mut()
+                    .insert(READ_ONLY_HEADER, http::HeaderValue::from_static(hint));
+            }
+            Ok(response)
+        })
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+    use prost_types::FileDescriptorSet;
+
+    use crate::routing::{is_read_only, MUTATING_METHODS, READ_ONLY_METHODS};
+    use crate::FILE_DESCRIPTOR_SET;
+
+    #[test]
+    fn method_classification() {
+        let expected = [
+            ("/store.Inventory/Add", false),
+            ("/store.Inventory/BatchAdd", false),
+            ("/store.Inventory/Remove", false),
+            ("/store.Inventory/Get", true),
+            ("/store.Inventory/UpdateQuantity", false),
+            ("/store.Inventory/UpdatePrice", false),
+            ("/store.Inventory/Watch", true),
+            ("/store.Inventory/ListChangedSince", true),
+            ("/store.Inventory/UploadImage", false),
+            ("/store.Inventory/GetImage", true),
+            ("/store.Inventory/GetMany", true),
+            ("/store.Inventory/SwapStock", false),
+            ("/store.Inventory/SetReadOnly", false),
+            ("/store.Inventory/List", true),
+            ("/store.Inventory/Sample", true),
+            ("/store.Inventory/GetMetrics", true),
+            ("/store.Inventory/ApplyBatch", false),
+            ("/store.Inventory/TailLogs", true),
+            ("/store.Inventory/GetAccessStats", true),
+            ("/store.Inventory/GroupByPriceBucket", true),
+            ("/store.Inventory/CheckConsistency", false),
+            ("/store.Inventory/LoadSnapshot", false),
+            ("/store.Inventory/GetLatencySummary", true),
+            ("/store.Inventory/BulkUpdatePrice", false),
+            ("/store.Inventory/UpdateInformation", false),
+            ("/store.Inventory/ListByAttribute", true),
+            ("/store.Inventory/Search", true),
+            ("/store.Inventory/InventoryStats", true),
+            ("/store.Inventory/Upsert", false),
+            ("/store.Inventory/Reserve", false),
+            ("/store.Inventory/Release", false),
+            ("/store.Inventory/WatchAll", true),
+            ("/store.Inventory/Count", true),
+            ("/store.Inventory/SetQuantity", false),
+            ("/store.Inventory/BatchRemove", false),
+            ("/store.Inventory/ListByTag", true),
+            ("/store.Inventory/SetReorderThreshold", false),
+            ("/store.Inventory/ListLowStock", true),
+            ("/store.Inventory/RenameSku", false),
+            ("/store.Inventory/GetAuditLog", true),
+            ("/store.Inventory/GetAndWatch", true),
+            ("/store.Inventory/Validate", true),
+            ("/store.Inventory/GetServerInfo", true),
+            ("/store.Inventory/BatchGet", true),
+            ("/store.Inventory/ListByWeightRange", true),
+        ];
+        for (method, read_only) in expected {
+            assert_eq!(is_read_only(method), read_only, "{}", method);
+        }
+
+        // every method the Inventory service offers must be classified
+        let descriptors = FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).unwrap();
+        let store = descriptors
+            .file
+            .iter()
+            .filter(|file| file.package() == "store");
+        for service in store.flat_map(|file| &file.service) {
+            for method in &service.method {
+                let path = format!("/store.{}/{}", service.name(), method.name());
+                assert!(
+                    READ_ONLY_METHODS.contains(&path.as_str())
+                        || MUTATING_METHODS.contains(&path.as_str()),
+                    "{} is not classified as read-only or mutating",
+                    path
+                );
+                assert!(expected.iter().any(|(method, _)| *method == path));
+            }
+        }
+    }
+}
@@ -1,607 +1,8065 @@
-use futures::Stream;
+use futures::future::{BoxFuture, Shared};
+use futures::{FutureExt, Stream, StreamExt};
+use prost::Message;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::borrow::BorrowMut;
-use std::collections::HashMap;
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{mpsc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::time::Instant;
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use tonic::{Request, Response, Status};
+use tonic::{Request, Response, Status, Streaming};
 
+use crate::audit::AuditLog;
+use crate::currency::{is_known_currency, RateTable, BASE_CURRENCY};
+use crate::details::{bad_request, within};
+use crate::error::InventoryError;
+use crate::logging::{record_sku, LogBroadcaster};
+use crate::metrics::{Metrics, LATENCY_WINDOW_SECS};
+use crate::pagination::PageToken;
+use crate::pricing::{round_to_cent, snap_price, to_cents, PriceEnding};
+use crate::storage::{MemoryStorage, Storage};
+use crate::store::get_many_result::Outcome;
 use crate::store::inventory_server::Inventory;
+use crate::store::operation::Operation as BatchOperation;
+use crate::store::price_change_result::Outcome as PriceOutcome;
+use crate::store::sample_request::Weighting;
+use crate::store::watch_event::Event as WatchedEvent;
 use crate::store::{
-    InventoryChangeResponse, InventoryUpdateResponse, Item, ItemIdentifier, PriceChangeRequest,
-    QuantityChangeRequest,
+    AccessStatsRequest, AccessStatsResponse, ApplyBatchRequest, AttributeFilter, AuditLogRequest,
+    AuditLogResponse, Availability, BatchGetRequest, BatchGetResponse, BatchRemoveRequest,
+    BatchRemoveResponse, BulkPriceRequest, BulkPriceResponse, ChangedSinceRequest,
+    ChangedSinceResponse, ConsistencyIssue, ConsistencyReport, ConsistencyRequest, ConvertedPrice,
+    CountRequest, CountResponse, GetManyRequest, GetManyResponse, GetManyResult, GetRequest,
+    Heartbeat, ImageChunk, InformationChangeRequest, InventoryChangeResponse,
+    InventoryStatsRequest, InventoryStatsResponse, InventoryUpdateResponse, Item, ItemAccesses,
+    ItemEvent, ItemIdentifier, ItemImage, ItemInformation, ItemPhysical, ItemStock,
+    LatencySummaryRequest, LatencySummaryResponse, ListByAttributeResponse, ListByTagResponse,
+    ListByWeightRangeResponse, ListLowStockResponse, ListRequest, ListResponse,
+    LoadSnapshotRequest, LoadSnapshotResponse, LogLine, LowStockRequest, MethodLatency,
+    MethodMetrics, MetricsRequest, MetricsResponse, Operation, PriceBucket, PriceBucketsRequest,
+    PriceBucketsResponse, PriceChangeRequest, PriceChangeResult, QuantityChangeRequest,
+    ReadOnlyRequest, RemoveRequest, RenameSkuRequest, ReorderThresholdRequest, ReservationRequest,
+    SampleRequest, SampleResponse, SearchRequest, SearchResponse, ServerInfo, ServerInfoRequest,
+    SetQuantityRequest, SwapStockRequest, TagFilter, TailLogsRequest, WatchAllRequest, WatchEvent,
+    WatchRequest, Watcher, WeightRangeRequest,
 };
+use crate::version::{built_at, GIT_SHA, VERSION};
 
 // -----------------------------------------------------------------------------
-// Error Messages
+// Consistency Issues
 // -----------------------------------------------------------------------------
 
-const BAD_PRICE_ERR: &str = "provided PRICE was invalid";
-const DUP_PRICE_ERR: &str = "item is already at this price";
-const DUP_ITEM_ERR: &str = "item already exists in inventory";
-const EMPTY_QUANT_ERR: &str = "invalid quantity of 0 provided";
-const EMPTY_SKU_ERR: &str = "provided SKU was empty";
-const NO_ID_ERR: &str = "no ID or SKU provided for item";
-const NO_ITEM_ERR: &str = "the item requested was not found";
-const NO_STOCK_ERR: &str = "no stock provided for item";
-const UNSUFF_INV_ERR: &str = "not enough inventory for quantity change";
+pub(crate) const MISMATCHED_SKU_ISSUE: &str = "item is stored under a different SKU than its own";
+pub(crate) const NO_STOCK_ISSUE: &str = "item has no stock";
+pub(crate) const ORPHAN_ACCESSES_ISSUE: &str = "access count belongs to a missing item";
+pub(crate) const ORPHAN_IMAGE_ISSUE: &str = "image belongs to a missing item";
+pub(crate) const ORPHAN_RESERVATION_ISSUE: &str = "reservation belongs to a missing item";
+pub(crate) const OVER_RESERVED_ISSUE: &str = "more stock is reserved than the item has";
+pub(crate) const STALE_TOMBSTONE_ISSUE: &str = "item is marked as removed but is present";
 
 // -----------------------------------------------------------------------------
-// InventoryServer Implementation
+// Item Images
 // -----------------------------------------------------------------------------
 
+// the largest image which can be stored for an item, in bytes.
+const MAX_IMAGE_SIZE: usize = 1024 * 1024;
+
+// the size of the chunks images are streamed back to clients in, in bytes.
+const IMAGE_CHUNK_SIZE: usize = 64 * 1024;
+
+// the content types accepted for item images.
+const IMAGE_CONTENT_TYPES: &[&str] = &["image/gif", "image/jpeg", "image/png", "image/webp"];
+
+// an item's image, which is stored separately from the item itself to avoid
+// bloating the inventory (which gets cloned and scanned) with binary data.
 #[derive(Debug)]
-pub struct StoreInventory {
-    inventory: Arc<Mutex<HashMap<String, Item>>>,
+struct Image {
+    content_type: String,
+    data: Vec<u8>,
 }
 
-impl Default for StoreInventory {
+// -----------------------------------------------------------------------------
+// Item Fields
+// -----------------------------------------------------------------------------
+
+/// The longest SKU, name and description items are accepted with by default,
+/// in bytes.
+pub const DEFAULT_MAX_SKU_LEN: usize = 128;
+pub const DEFAULT_MAX_NAME_LEN: usize = 256;
+pub const DEFAULT_MAX_DESCRIPTION_LEN: usize = 4096;
+
+/// The longest text fields items are accepted with, in bytes, so that a client
+/// can't store (and have every scan of the inventory copy) megabytes of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLimits {
+    pub sku: usize,
+    pub name: usize,
+    pub description: usize,
+}
+
+impl Default for FieldLimits {
     fn default() -> Self {
-        StoreInventory {
-            inventory: Arc::new(Mutex::new(HashMap::<String, Item>::new())),
+        FieldLimits {
+            sku: DEFAULT_MAX_SKU_LEN,
+            name: DEFAULT_MAX_NAME_LEN,
+            description: DEFAULT_MAX_DESCRIPTION_LEN,
         }
     }
 }
 
-#[tonic::async_trait]
-impl Inventory for StoreInventory {
-    async fn add(
-        &self,
-        request: Request<Item>,
-    ) -> Result<Response<InventoryChangeResponse>, Status> {
-        let item = request.into_inner();
+// -----------------------------------------------------------------------------
+// Item Attributes
+// -----------------------------------------------------------------------------
 
-        // validate SKU, verify that it's present and not empty
-        let sku = match item.identifier.as_ref() {
-            Some(id) if id.sku == "" => return Err(Status::invalid_argument(EMPTY_SKU_ERR)),
-            Some(id) => id.sku.to_owned(),
-            None => return Err(Status::invalid_argument(NO_ID_ERR)),
-        };
+// the most attributes an item can have.
+const MAX_ATTRIBUTES: usize = 32;
+
+// the longest attribute key and value, in bytes.
+const MAX_ATTRIBUTE_KEY_LEN: usize = 64;
+const MAX_ATTRIBUTE_VALUE_LEN: usize = 256;
+
+// -----------------------------------------------------------------------------
+// Item Tags
+// -----------------------------------------------------------------------------
+
+// the most tags an item can have.
+const MAX_TAGS: usize = 32;
+
+// the longest tag, in bytes.
+const MAX_TAG_LEN: usize = 64;
+
+// -----------------------------------------------------------------------------
+// Change Notifications
+// -----------------------------------------------------------------------------
 
-        // validate stock, verify its present and price is not negative or $0.00
-        match item.stock.as_ref() {
-            Some(stock) if stock.price <= 0.00 => {
-                return Err(Status::invalid_argument(BAD_PRICE_ERR))
+// the number of changes kept for each watch which hasn't received them yet.
+// Watches which fall further behind catch up with their item as it is now.
+const CHANGE_CAPACITY: usize = 1024;
+
+// a change to an item, which is published to watches as it's made. The item
+// is None when it was removed.
+#[derive(Debug, Clone)]
+struct ItemChange {
+    sku: String,
+    item: Option<Item>,
+}
+
+// -----------------------------------------------------------------------------
+// Get Coalescing
+// -----------------------------------------------------------------------------
+
+// a read of an item which is shared by a batch of concurrent gets.
+type InFlightGet = Shared<BoxFuture<'static, Result<Option<Item>, InventoryError>>>;
+
+// a registry of in-flight reads of items, keyed by SKU. Concurrent gets for
+// the same SKU join the in-flight read rather than each locking the shard and
+// cloning the item themselves.
+#[derive(Default)]
+struct GetCoalescer {
+    in_flight: Arc<std::sync::Mutex<HashMap<String, InFlightGet>>>,
+}
+
+impl fmt::Debug for GetCoalescer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let in_flight = self.in_flight.lock().unwrap().len();
+        f.debug_struct("GetCoalescer")
+            .field("in_flight", &in_flight)
+            .finish()
+    }
+}
+
+impl GetCoalescer {
+    // retrieves a copy of an item, joining the in-flight read for the SKU if
+    // there is one or starting a new one otherwise.
+    async fn get(
+        &self,
+        shards: &Arc<Vec<Shard>>,
+        sku: &str,
+    ) -> Result<Option<Item>, InventoryError> {
+        let read = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(sku) {
+                Some(read) => read.clone(),
+                None => {
+                    // the read runs as a task of its own, so that it goes on
+                    // (and lets go of the shard lock) even if every get
+                    // waiting on it is cancelled
+                    let task = tokio::spawn(read_item(
+                        shards.clone(),
+                        self.in_flight.clone(),
+                        sku.into(),
+                    ));
+                    let read = task
+                        .map(|read| read.unwrap_or(Err(InventoryError::StorageFailure)))
+                        .boxed()
+                        .shared();
+                    in_flight.insert(sku.into(), read.clone());
+                    read
+                }
             }
-            Some(_) => {}
-            None => return Err(Status::invalid_argument(NO_STOCK_ERR)),
         };
+        read.await
+    }
+}
 
-        // if the item is already present don't allow the duplicate
-        let mut map = self.inventory.lock().await;
-        if let Some(_) = map.get(&sku) {
-            return Err(Status::already_exists(DUP_ITEM_ERR));
-        }
+// reads an item on behalf of a batch of coalesced gets. The batch is closed to
+// new gets only once the shard lock is held, so the read always happens after
+// every get in the batch arrived and none of them can see a stale item: a get
+// arriving after the batch closed starts a new read.
+async fn read_item(
+    shards: Arc<Vec<Shard>>,
+    in_flight: Arc<std::sync::Mutex<HashMap<String, InFlightGet>>>,
+    sku: String,
+) -> Result<Option<Item>, InventoryError> {
+    let map = shard_for(&shards, &sku).read().await;
+    in_flight.lock().unwrap().remove(&sku);
+    map.get(&sku).await
+}
 
-        // add the item to the inventory
-        map.insert(sku.into(), item);
+// -----------------------------------------------------------------------------
+// Sampling
+// -----------------------------------------------------------------------------
 
-        Ok(Response::new(InventoryChangeResponse {
-            status: "success".into(),
-        }))
+// the largest number of items a client can request in a sample.
+const MAX_SAMPLE_SIZE: usize = 1000;
+
+// an item which is a candidate for a sample, along with its random key.
+struct Candidate {
+    key: f64,
+    item: Item,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
     }
+}
 
-    async fn remove(
-        &self,
-        request: Request<ItemIdentifier>,
-    ) -> Result<Response<InventoryChangeResponse>, Status> {
-        let identifier = request.into_inner();
+impl Eq for Candidate {}
 
-        // don't allow empty SKU
-        if identifier.sku == "" {
-            return Err(Status::invalid_argument(EMPTY_SKU_ERR));
-        }
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
 
-        // remove the item (if present)
-        let mut map = self.inventory.lock().await;
-        let msg = match map.remove(&identifier.sku) {
-            Some(_) => "success: item was removed",
-            None => "success: item didn't exist",
-        };
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.key.total_cmp(&other.key)
+    }
+}
 
-        Ok(Response::new(InventoryChangeResponse {
-            status: msg.into(),
-        }))
+// a weighted random sample of a fixed number of items, chosen in a single pass
+// over the inventory without collecting its keys first. Each item is given the
+// key ln(u)/weight for a uniformly random u, and the items with the largest
+// keys make up the sample (Efraimidis and Spirakis' A-Res algorithm).
+struct Reservoir {
+    size: usize,
+    rng: StdRng,
+    // the candidates with the smallest key at the top, so that it's the one
+    // replaced when an item with a larger key comes along.
+    candidates: BinaryHeap<Reverse<Candidate>>,
+}
+
+impl Reservoir {
+    fn new(size: usize, rng: StdRng) -> Self {
+        Reservoir {
+            size,
+            rng,
+            candidates: BinaryHeap::with_capacity(size),
+        }
     }
 
-    async fn get(&self, request: Request<ItemIdentifier>) -> Result<Response<Item>, Status> {
-        let identifier = request.into_inner();
+    // offers an item for the sample, items without a positive weight are
+    // never chosen.
+    fn offer(&mut self, item: &Item, weight: f64) {
+        if self.size == 0 || !(weight > 0.0 && weight.is_finite()) {
+            return;
+        }
 
-        // don't allow empty SKU
-        if identifier.sku == "" {
-            return Err(Status::invalid_argument(EMPTY_SKU_ERR));
+        let key = self.rng.gen::<f64>().ln() / weight;
+        if self.candidates.len() < self.size {
+            let item = item.clone();
+            self.candidates.push(Reverse(Candidate { key, item }));
+        } else if matches!(self.candidates.peek(), Some(Reverse(min)) if key > min.key) {
+            let item = item.clone();
+            self.candidates.pop();
+            self.candidates.push(Reverse(Candidate { key, item }));
         }
+    }
 
-        // retrieve the item if it exists
-        let map = self.inventory.lock().await;
-        let item = match map.get(&identifier.sku) {
-            Some(item) => item,
-            None => return Err(Status::not_found(NO_ITEM_ERR)),
-        };
+    // retrieves the sampled items, in descending order of their keys.
+    fn into_items(self) -> Vec<Item> {
+        self.candidates
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(candidate)| candidate.item)
+            .collect()
+    }
+}
 
-        Ok(Response::new(item.clone()))
+// determines how heavily an item is weighted when sampling.
+fn sample_weight(item: &Item, weighting: Weighting) -> f64 {
+    let stock = item.stock.as_ref();
+    match weighting {
+        Weighting::Uniform => 1.0,
+        Weighting::Quantity => stock.map_or(0.0, |stock| stock.quantity as f64),
+        Weighting::Value => stock.map_or(0.0, |stock| stock.price as f64 * stock.quantity as f64),
     }
+}
 
-    async fn update_quantity(
-        &self,
-        request: Request<QuantityChangeRequest>,
-    ) -> Result<Response<InventoryUpdateResponse>, Status> {
-        let change = request.into_inner();
+// -----------------------------------------------------------------------------
+// InventoryServer Implementation
+// -----------------------------------------------------------------------------
 
-        // don't allow empty SKU
-        if change.sku == "" {
-            return Err(Status::invalid_argument(EMPTY_SKU_ERR));
-        }
+// the metadata header clients use to request prices converted to a currency.
+const CURRENCY_HEADER: &str = "x-currency";
 
-        // quantity changes with no actual change don't make sense, inform user
-        if change.change == 0 {
-            return Err(Status::invalid_argument(EMPTY_QUANT_ERR));
-        }
+// the metadata header which reports the index of the operation which failed
+// a batch.
+const FAILED_OPERATION_HEADER: &str = "x-failed-operation";
 
-        // retrieve the current inventory item data
-        let mut map = self.inventory.lock().await;
-        let item = match map.get_mut(&change.sku) {
-            Some(item) => item,
-            None => return Err(Status::not_found(NO_ITEM_ERR)),
-        };
+// the metadata header which reports the index of the item which failed to
+// load from a snapshot.
+const FAILED_ITEM_HEADER: &str = "x-failed-item";
 
-        // retrieve the stock mutable so we can update the quantity
-        let mut stock = match item.stock.borrow_mut() {
-            Some(stock) => stock,
-            None => return Err(Status::internal(NO_STOCK_ERR)),
-        };
+// the metadata header clients identify watch streams with, see Watch.
+const STREAM_ID_HEADER: &str = "x-stream-id";
+
+// the longest stream id a client can identify a watch stream with.
+const MAX_STREAM_ID_LEN: usize = 128;
+
+// the metadata header requests carry their credentials (the API key, or the
+// admin token for admin requests) in, as a bearer token (e.g. "Bearer <token>").
+pub(crate) const AUTHORIZATION_HEADER: &str = "authorization";
+
+// the number of items in a page of a listing when the client doesn't choose.
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+// the largest number of items a client can request in a page of a listing.
+const MAX_PAGE_SIZE: usize = 1000;
+
+// the number of items reported by GetAccessStats when the client doesn't
+// choose.
+const DEFAULT_ACCESS_STATS_LIMIT: usize = 10;
+
+// the largest number of items a client can request GetAccessStats for.
+const MAX_ACCESS_STATS_LIMIT: usize = 1000;
+
+// the cumulative size of the items in a batch that's accepted by default, in
+// bytes.
+const DEFAULT_BATCH_BUDGET: usize = 8 * 1024 * 1024;
+
+// the number of shards the inventory is split across by default.
+const DEFAULT_SHARDS: usize = 16;
+
+/// How long removed items are remembered for ListChangedSince by default,
+/// after which the expiry sweep forgets them.
+pub const DEFAULT_TOMBSTONE_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+// the inventory is split into shards which each have their own lock, so that
+// operations on unrelated SKUs don't contend with one another. Single item
+// operations only lock the shard their SKU hashes to, while operations that
+// span multiple items must lock shards in ascending index order to avoid
+// deadlocks. Reads (e.g. get, list and watch polls) share the lock, so only
+// changes to a shard wait for one another. Each shard keeps its items in its
+// own storage, which keeps them ordered by SKU so that listing can resume from
+// any point in the inventory.
+type Shard = RwLock<Box<dyn Storage>>;
+
+// the stream of events sent to a watch of a single item.
+type WatchEventStream = Pin<Box<dyn Stream<Item = Result<WatchEvent, Status>> + Send>>;
 
-        // validate and then handle the quantity change
-        stock.quantity = match change.change {
-            // handle negative numbers as stock reduction
-            change if change < 0 => {
-                if change.abs() as u32 > stock.quantity {
-                    return Err(Status::resource_exhausted(UNSUFF_INV_ERR));
+// the shards read locked for a read spanning multiple items, see
+// StoreInventory::read_shards.
+struct ReadShards<'a> {
+    shards: usize,
+    guards: Vec<(usize, RwLockReadGuard<'a, Box<dyn Storage>>)>,
+}
+
+impl ReadShards<'_> {
+    // retrieves the storage of the locked shard responsible for the given
+    // SKU, which must be one of the SKUs the shards were locked for.
+    fn map(&self, sku: &str) -> &dyn Storage {
+        let index = shard_index(self.shards, sku);
+        let (_, guard) = self
+            .guards
+            .iter()
+            .find(|(locked, _)| *locked == index)
+            .expect("shard for SKU was not locked");
+        guard.as_ref()
+    }
+}
+
+// the shards locked for an operation spanning multiple items, see
+// StoreInventory::lock_shards.
+struct LockedShards<'a> {
+    shards: usize,
+    guards: Vec<(usize, RwLockWriteGuard<'a, Box<dyn Storage>>)>,
+}
+
+impl LockedShards<'_> {
+    // retrieves the storage of the locked shard responsible for the given
+    // SKU, which must be one of the SKUs the shards were locked for.
+    fn map(&mut self, sku: &str) -> &mut dyn Storage {
+        let index = shard_index(self.shards, sku);
+        let (_, guard) = self
+            .guards
+            .iter_mut()
+            .find(|(locked, _)| *locked == index)
+            .expect("shard for SKU was not locked");
+        guard.as_mut()
+    }
+
+    // stores the item of each change under its SKU, or removes the item
+    // stored under it for None, as one: if any change fails, those already
+    // made are undone before the error is returned. Returns the items as
+    // they were before each change.
+    async fn commit<S: AsRef<str>>(
+        &mut self,
+        changes: &[(S, Option<Item>)],
+    ) -> Result<Vec<Option<Item>>, Status> {
+        let mut undo = Vec::with_capacity(changes.len());
+        for (sku, item) in changes {
+            let sku = sku.as_ref();
+            let map = self.map(sku);
+            let previous = match item {
+                Some(item) => map.insert(sku.into(), item.clone()).await,
+                None => map.remove(sku).await,
+            };
+            match previous {
+                Ok(previous) => undo.push((sku, previous)),
+                Err(err) => {
+                    self.undo(undo).await;
+                    return Err(err.into());
                 }
-                stock.quantity - change.abs() as u32
             }
-            // handle positive numbers as stock increases
-            change => stock.quantity + change as u32,
-        };
+        }
 
-        Ok(Response::new(InventoryUpdateResponse {
-            status: "success".into(),
-            price: stock.price,
-            quantity: stock.quantity,
-        }))
+        Ok(undo.into_iter().map(|(_, previous)| previous).collect())
     }
 
-    async fn update_price(
-        &self,
-        request: Request<PriceChangeRequest>,
-    ) -> Result<Response<InventoryUpdateResponse>, Status> {
-        let change = request.into_inner();
+    // restores the items a failed commit changed, latest first. Items which
+    // can't be restored are logged, the storage logs why.
+    async fn undo(&mut self, undo: Vec<(&str, Option<Item>)>) {
+        for (sku, previous) in undo.into_iter().rev() {
+            let map = self.map(sku);
+            let restored = match previous {
+                Some(item) => map.insert(sku.into(), item).await,
+                None => map.remove(sku).await,
+            };
+            if restored.is_err() {
+                tracing::error!(sku, "failed to undo a change to an item");
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StoreInventory {
+    inventory: Arc<Vec<Shard>>,
+    // tombstones for removed items, mapping each SKU to the time (in unix
+    // epoch milliseconds) it was removed so that incremental sync clients can
+    // learn about removals. Tombstones older than the retention are pruned
+    // by the expiry sweep, so that the map doesn't grow without bound.
+    removed: Arc<Mutex<HashMap<String, u64>>>,
+    tombstone_retention: Duration,
+    // the time (in unix epoch milliseconds) of the latest removal whose
+    // tombstone was pruned, changes since before it can't be fully reported.
+    pruned_removals: AtomicU64,
+    // images for items, keyed by SKU. Whenever both are needed the shard lock
+    // for the SKU must be taken first.
+    images: Arc<Mutex<HashMap<String, Image>>>,
+    // the quantity of each item's stock which is reserved, keyed by SKU.
+    // Whenever both are needed the shard lock for the SKU must be taken first.
+    reservations: Arc<Mutex<HashMap<String, u32>>>,
+    // the number of times each item was fetched since it was added, keyed by
+    // SKU. Counters are bumped under the read lock, so the write lock is only
+    // taken for the first fetch of an item and when it's removed.
+    accesses: Arc<std::sync::RwLock<HashMap<String, AtomicU64>>>,
+    // when enabled, concurrent gets for the same SKU share a single read.
+    coalescer: Option<GetCoalescer>,
+    // exchange rates used to convert prices into the currency requested by
+    // clients, for display only.
+    rates: RateTable,
+    // when set, computed prices (e.g. conversions) are snapped to this price
+    // ending rather than being shown to the cent.
+    price_ending: Option<PriceEnding>,
+    // when set, all requests which would change the inventory are rejected
+    // while reads and watches continue to be served.
+    read_only: AtomicBool,
+    // the token admin requests must present, admin requests are rejected
+    // when there is none.
+    admin_token: Option<String>,
+    // the largest cumulative size of the items in a batch, in bytes.
+    batch_budget: usize,
+    // the largest quantity an item can be added with, which guards against
+    // imports of garbage data. Quantity changes to items aren't limited.
+    max_quantity: u32,
+    // the longest text fields items can be added with.
+    field_limits: FieldLimits,
+    // changes to items, published while the shard lock for the item is held
+    // so that the changes to each item are seen in the order they were made.
+    changes: broadcast::Sender<ItemChange>,
+    // when set, watches which have sent nothing for this long send a
+    // heartbeat, so that proxies don't close them for being idle.
+    watch_heartbeat: Option<Duration>,
+    // metrics about the requests served, which are recorded by the
+    // MetricsLayer and reported by GetMetrics.
+    metrics: Arc<Metrics>,
+    // recent and live log lines of the server, which are recorded by the
+    // LogLayer and streamed by TailLogs.
+    logs: LogBroadcaster,
+    // changes made to items, which are retrieved by GetAuditLog.
+    audit: AuditLog,
+    // when the inventory was created, which is when the server started.
+    started: Instant,
+}
+
+impl Default for StoreInventory {
+    fn default() -> Self {
+        StoreInventory::with_shards(DEFAULT_SHARDS)
+    }
+}
+
+impl StoreInventory {
+    /// Creates an empty inventory split across the given number of shards,
+    /// a single shard results in one global lock for the whole inventory.
+    pub fn with_shards(shards: usize) -> Self {
+        let shards = (0..shards.max(1))
+            .map(|_| RwLock::new(Box::new(MemoryStorage::default()) as Box<dyn Storage>))
+            .collect();
+        StoreInventory::from_shards(shards)
+    }
+
+    /// Creates an inventory which keeps its items in the given storage. The
+    /// inventory isn't sharded, so a single lock is held around the storage.
+    pub fn with_storage(storage: impl Storage + 'static) -> Self {
+        StoreInventory::from_shards(vec![RwLock::new(Box::new(storage))])
+    }
 
-        // don't allow empty SKU
-        if change.sku == "" {
-            return Err(Status::invalid_argument(EMPTY_SKU_ERR));
+    fn from_shards(shards: Vec<Shard>) -> Self {
+        StoreInventory {
+            inventory: Arc::new(shards),
+            removed: Arc::new(Mutex::new(HashMap::<String, u64>::new())),
+            tombstone_retention: DEFAULT_TOMBSTONE_RETENTION,
+            pruned_removals: AtomicU64::new(0),
+            images: Arc::new(Mutex::new(HashMap::<String, Image>::new())),
+            reservations: Arc::new(Mutex::new(HashMap::<String, u32>::new())),
+            accesses: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            coalescer: None,
+            rates: RateTable::default(),
+            price_ending: None,
+            read_only: AtomicBool::new(false),
+            admin_token: None,
+            batch_budget: DEFAULT_BATCH_BUDGET,
+            max_quantity: u32::MAX,
+            field_limits: FieldLimits::default(),
+            changes: broadcast::channel(CHANGE_CAPACITY).0,
+            watch_heartbeat: None,
+            metrics: Arc::new(Metrics::default()),
+            logs: LogBroadcaster::default(),
+            audit: AuditLog::default(),
+            started: Instant::now(),
         }
+    }
+
+    /// Retrieves the metrics for the inventory, which the MetricsLayer needs
+    /// to record requests for GetMetrics to report.
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    /// Retrieves the log lines of the server, which the LogLayer needs
+    /// to record events for TailLogs to stream.
+    pub fn logs(&self) -> LogBroadcaster {
+        self.logs.clone()
+    }
+
+    /// Sets where changes to items are recorded, see AuditLog for the sinks.
+    /// Recent changes are kept in memory by default.
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = audit;
+        self
+    }
 
-        // $0.00 disallowed and negatives don't make sense, inform the user
-        if change.price <= 0.0 {
-            return Err(Status::invalid_argument(BAD_PRICE_ERR));
+    /// Sets the largest cumulative size (in bytes, as encoded on the wire) of
+    /// the items streamed in a single batch. Batches are rejected as soon as
+    /// they exceed it.
+    pub fn with_batch_budget(mut self, bytes: usize) -> Self {
+        self.batch_budget = bytes;
+        self
+    }
+
+    /// Rejects items which are added with a quantity above the cap, e.g. the
+    /// u32::MAX quantities garbage imports hold. There's no cap by default.
+    pub fn with_max_quantity(mut self, max_quantity: u32) -> Self {
+        self.max_quantity = max_quantity;
+        self
+    }
+
+    /// Rejects items whose SKU, name or description is longer than the
+    /// limits, whether they're added or changed to have it.
+    pub fn with_field_limits(mut self, limits: FieldLimits) -> Self {
+        self.field_limits = limits;
+        self
+    }
+
+    /// Sets the token admin requests (e.g. SetReadOnly) must present as a
+    /// bearer token in the "authorization" metadata header.
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+
+    /// Starts the inventory in read-only maintenance mode, which can be left
+    /// with the SetReadOnly admin request.
+    pub fn with_read_only(self, read_only: bool) -> Self {
+        self.read_only.store(read_only, Ordering::SeqCst);
+        self
+    }
+
+    /// Sets the exchange rates used to convert prices into the currency
+    /// clients request via the "x-currency" metadata header.
+    pub fn with_rates(mut self, rates: RateTable) -> Self {
+        self.rates = rates;
+        self
+    }
+
+    /// Snaps the prices the inventory computes, such as converted prices, to
+    /// a price ending (e.g. .99), see snap_price for the direction rules.
+    /// Stored prices are never snapped.
+    pub fn with_price_ending(mut self, ending: PriceEnding) -> Self {
+        self.price_ending = Some(ending);
+        self
+    }
+
+    /// Enables coalescing of concurrent gets for the same SKU, so that only
+    /// one read of the inventory happens for them and the result is shared.
+    /// This helps read heavy workloads with hot items.
+    pub fn with_coalesced_gets(mut self) -> Self {
+        self.coalescer = Some(GetCoalescer::default());
+        self
+    }
+
+    /// Sets how long removed items are remembered for ListChangedSince. The
+    /// expiry sweep forgets removals older than this, and clients asking for
+    /// changes since before a forgotten removal are told to sync again.
+    pub fn with_tombstone_retention(mut self, retention: Duration) -> Self {
+        self.tombstone_retention = retention;
+        self
+    }
+
+    /// Sends a heartbeat on watches which have sent nothing else for the
+    /// interval, which keeps them from being closed as idle by proxies.
+    pub fn with_watch_heartbeat(mut self, interval: Duration) -> Self {
+        self.watch_heartbeat = Some(interval);
+        self
+    }
+
+    // retrieves the shard responsible for the given SKU.
+    fn shard(&self, sku: &str) -> &Shard {
+        shard_for(&self.inventory, sku)
+    }
+
+    // retrieves copies of the items matching the filter, sorted by SKU. Each
+    // shard is only locked while it's scanned.
+    async fn filter_items(
+        &self,
+        filter: impl Fn(&Item) -> bool + Send + Sync,
+    ) -> Result<Vec<Item>, InventoryError> {
+        let mut items = Vec::new();
+        for shard in self.inventory.iter() {
+            let map = shard.read().await;
+            map.scan(&mut |_, item| {
+                if filter(item) {
+                    items.push(item.clone());
+                }
+            })
+            .await?;
         }
+        items.sort_by(|a, b| item_sku(a).cmp(item_sku(b)));
+        Ok(items)
+    }
 
-        // retrieve the current inventory item data
-        let mut map = self.inventory.lock().await;
-        let item = match map.get_mut(&change.sku) {
-            Some(item) => item,
-            None => return Err(Status::not_found(NO_ITEM_ERR)),
-        };
+    // rejects requests which would change the inventory while it's in
+    // read-only maintenance mode.
+    fn check_writable(&self) -> Result<(), InventoryError> {
+        if self.read_only.load(Ordering::SeqCst) {
+            return Err(InventoryError::ReadOnly);
+        }
+        Ok(())
+    }
 
-        // retrieve the stock mutable so we can update the quantity
-        let mut stock = match item.stock.borrow_mut() {
-            Some(stock) => stock,
-            None => return Err(Status::internal(NO_STOCK_ERR)),
+    // verifies that a request carries the admin token as its bearer token.
+    fn check_admin<T>(&self, request: &Request<T>) -> Result<(), InventoryError> {
+        let expected = match &self.admin_token {
+            Some(token) => token,
+            None => return Err(InventoryError::AdminDisabled),
         };
 
-        // let the client know if they requested to change the price to the
-        // price that is already currently set
-        if stock.price == change.price {
-            return Err(Status::invalid_argument(DUP_PRICE_ERR));
+        let token = request
+            .metadata()
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match token {
+            Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+            _ => Err(InventoryError::BadAdminToken),
         }
+    }
 
-        // update the item unit price
-        stock.price = change.price;
+    // counts a fetch of an item towards its access statistics.
+    fn record_access(&self, sku: &str) {
+        if let Some(accesses) = self.accesses.read().unwrap().get(sku) {
+            accesses.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
 
-        Ok(Response::new(InventoryUpdateResponse {
-            status: "success".into(),
-            price: stock.price,
-            quantity: stock.quantity,
-        }))
+        let mut accesses = self.accesses.write().unwrap();
+        accesses
+            .entry(sku.into())
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    // snaps a price converted from one currency into another to the
+    // configured price ending, prices in the item's own currency weren't
+    // computed and are shown as they're stored.
+    fn snap_converted(&self, price: f32, from: &str, to: &str) -> f32 {
+        match self.price_ending {
+            Some(ending) if !from.eq_ignore_ascii_case(to) => snap_price(price, ending),
+            _ => price,
+        }
     }
 
-    type WatchStream = Pin<Box<dyn Stream<Item = Result<Item, Status>> + Send>>;
+    // publishes a change to an item to the watches, which must be done while
+    // the shard lock for the item is held. Items are only cloned when there
+    // are watches to receive them.
+    fn notify(&self, sku: &str, item: Option<&Item>) {
+        if self.changes.receiver_count() > 0 {
+            let change = ItemChange {
+                sku: sku.into(),
+                item: item.cloned(),
+            };
+            // sending only fails when the last watch ended since the check
+            let _ = self.changes.send(change);
+        }
+    }
 
-    async fn watch(
+    // streams changes to an item to a watch, optionally starting with the
+    // item as it was when the watch started. Used by Watch and GetAndWatch.
+    async fn watch_item(
         &self,
-        request: Request<ItemIdentifier>,
-    ) -> Result<Response<Self::WatchStream>, Status> {
-        // retrieve the relevant item and get a baseline
+        request: Request<WatchRequest>,
+        initial: bool,
+    ) -> Result<Response<WatchEventStream>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        let stream_id = watch_stream_id(&request)?;
+
+        // retrieve the relevant item as a baseline. Changes are published
+        // under the shard lock, so subscribing to them under it too means the
+        // watch receives exactly the changes made after the baseline.
         let id = request.into_inner();
-        let mut item = self.get(Request::new(id.clone())).await?.into_inner();
+        validate_sku(&id.sku)?;
+        let (mut item, mut changes) = {
+            let map = self.shard(&id.sku).read().await;
+            match map.get(&id.sku).await? {
+                Some(item) => (item, self.changes.subscribe()),
+                None => return Err(InventoryError::NoItem.into()),
+            }
+        };
+        self.record_access(&id.sku);
 
         // the channel will be our stream back to the client, we'll send copies
-        // of the requested item any time we notice a change to it in the
-        // inventory.
+        // of the requested item any time it's changed in the inventory,
+        // starting with the baseline if the client asked for it. Clients
+        // resuming from another version are sent it too, as the changes they
+        // missed aren't kept.
         let (tx, rx) = mpsc::unbounded_channel();
+        let stale = id
+            .from_version
+            .is_some_and(|version| version != item.version);
+        if initial || stale {
+            let event = WatchEvent {
+                event: Some(WatchedEvent::Item(item.clone())),
+            };
+            // sending can't fail, the receiver is still held here
+            let _ = tx.send(Ok(event));
+        }
 
-        // we'll loop and poll new copies of the item until either the client
-        // closes the connection, or an error occurs.
+        // we'll forward changes to the item until either the client closes
+        // the connection, the item is removed, or an error occurs.
         let inventory = self.inventory.clone();
+        let heartbeat = self.watch_heartbeat;
+        let watcher = self.metrics.watcher(&stream_id, &id.sku);
+        let task_stream_id = stream_id.clone();
+        tracing::info!(stream_id = %stream_id, sku = %id.sku, "watch started");
         tokio::spawn(async move {
+            // the watch is registered as open until this task ends
+            let _watcher = watcher;
+            let stream_id = task_stream_id;
+            let mut last_sent = Instant::now();
             loop {
-                // it's somewhat basic, but for this demo we'll just check the
-                // item every second for any changes.
-                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                let change = tokio::select! {
+                    _ = tx.closed() => {
+                        tracing::info!(stream_id = %stream_id, "watch ended by the client");
+                        return;
+                    }
+                    _ = heartbeat_due(heartbeat, last_sent) => {
+                        let event = WatchEvent {
+                            event: Some(WatchedEvent::Heartbeat(Heartbeat {})),
+                        };
+                        if tx.send(Ok(event)).is_err() {
+                            return;
+                        }
+                        last_sent = Instant::now();
+                        continue;
+                    }
+                    change = changes.recv() => change,
+                };
+
+                let item_refresh = match change {
+                    Ok(change) if change.sku == id.sku => change.item,
+                    Ok(_) => continue,
+                    // the watch fell behind and missed changes, catch up with
+                    // the item as it is now. The changes still queued are
+                    // older than that, so they're dropped by resubscribing.
+                    Err(RecvError::Lagged(_)) => {
+                        changes = changes.resubscribe();
+                        let map = shard_for(&inventory, &id.sku).read().await;
+                        match map.get(&id.sku).await {
+                            Ok(item) => item,
+                            Err(err) => {
+                                let _ = tx.send(Err(err.into()));
+                                return;
+                            }
+                        }
+                    }
+                    Err(RecvError::Closed) => return,
+                };
 
-                // pull a fresh copy of the item in the inventory
-                let map = inventory.lock().await;
-                let item_refresh = match map.get(&id.sku) {
+                let item_refresh = match item_refresh {
                     Some(item) => item,
                     // the item has been removed from the inventory. Let the
                     // client know, and stop the stream.
                     None => {
-                        if let Err(err) = tx.send(Err(Status::not_found(NO_ITEM_ERR))) {
-                            println!("ERROR: failed to update stream client: {:?}", err);
+                        if let Err(err) = tx.send(Err(InventoryError::NoItem.into())) {
+                            tracing::error!(
+                                stream_id = %stream_id,
+                                error = ?err,
+                                "failed to update stream client"
+                            );
                         }
+                        tracing::info!(stream_id = %stream_id, "watch ended, the item was removed");
                         return;
                     }
                 };
 
-                // check to see if the item has changed since we last saw it,
-                // and if it has inform the client via the stream.
-                if item_refresh != &item {
-                    if let Err(err) = tx.send(Ok(item_refresh.clone())) {
-                        println!("ERROR: failed to update stream client: {:?}", err);
+                // changes which leave the item as the client last saw it (e.g.
+                // when catching up) aren't sent again.
+                if item_refresh != item {
+                    let event = WatchEvent {
+                        event: Some(WatchedEvent::Item(item_refresh.clone())),
+                    };
+                    if let Err(err) = tx.send(Ok(event)) {
+                        tracing::error!(
+                            stream_id = %stream_id,
+                            error = ?err,
+                            "failed to update stream client"
+                        );
                         return;
                     }
+                    last_sent = Instant::now();
                 }
 
                 // cache the most recent copy of the item
-                item = item_refresh.clone()
+                item = item_refresh
             }
         });
 
         let stream = UnboundedReceiverStream::new(rx);
-        Ok(Response::new(Box::pin(stream) as Self::WatchStream))
+        let response = Response::new(Box::pin(stream) as WatchEventStream);
+        Ok(with_stream_id(response, &stream_id))
     }
-}
 
-// -----------------------------------------------------------------------------
-// Testing
-// -----------------------------------------------------------------------------
+    // adds a validated item to the inventory, rejecting it if an item with the
+    // same SKU is already present, and returns the item as it was stored.
+    async fn insert_item(&self, sku: String, mut item: Item) -> Result<Item, Status> {
+        // if the item is already present don't allow the duplicate
+        let mut map = self.shard(&sku).write().await;
+        if map.contains(&sku).await? {
+            return Err(InventoryError::DuplicateItem.into());
+        }
 
-#[cfg(test)]
-mod tests {
-    use std::println as info;
-    use std::sync::Once;
+        // add the item to the inventory, it's no longer considered removed if
+        // it was previously.
+        normalize_item(&mut item);
+        item.last_updated = now_millis();
+        item.version = next_version(None);
+        map.insert(sku.clone(), item.clone()).await?;
+        self.removed.lock().await.remove(&sku);
+        self.notify(&sku, Some(&item));
 
-    use anyhow::Error;
-    use tonic::{
-        transport::{Channel, Server},
-        Request,
-    };
+        Ok(item)
+    }
 
-    use uuid::Uuid;
+    // rejects changing an item's quantity to less than the stock which is
+    // reserved of it. It's checked under the item's shard lock, so that the
+    // reservations can't change in between.
+    async fn check_reserved(&self, sku: &str, quantity: u32) -> Result<(), Status> {
+        let reservations = self.reservations.lock().await;
+        match reservations.get(sku) {
+            Some(reserved) if quantity < *reserved => Err(InventoryError::ReservedStock.into()),
+            _ => Ok(()),
+        }
+    }
 
-    use crate::{
-        server,
-        server::StoreInventory,
-        store::{
-            inventory_client::InventoryClient, inventory_server::InventoryServer, Item,
-            ItemIdentifier, ItemStock, PriceChangeRequest, QuantityChangeRequest,
-        },
-    };
+    // reserves (or releases) some of an item's stock, returning the item's
+    // resulting availability.
+    async fn change_reservation(
+        &self,
+        reservation: ReservationRequest,
+        reserve: bool,
+    ) -> Result<Availability, Status> {
+        let sku = reservation.sku;
+        validate_sku(&sku)?;
+        if reservation.quantity == 0 {
+            return Err(bad_request("quantity", InventoryError::EmptyQuantity));
+        }
 
-    // -------------------------------------------------------------------------
-    // Test Setup
-    // -------------------------------------------------------------------------
+        // the shard lock is held throughout, so that the item's quantity
+        // can't change while it's compared against the reservations
+        let map = self.shard(&sku).read().await;
+        let total = match map.get(&sku).await? {
+            Some(item) => match item.stock {
+                Some(stock) => stock.quantity,
+                None => return Err(InventoryError::NoStockToUpdate.into()),
+            },
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        let mut reservations = self.reservations.lock().await;
+        let current = reservations.get(&sku).copied().unwrap_or_default();
+        let reserved = if reserve {
+            match current.checked_add(reservation.quantity) {
+                Some(reserved) if reserved <= total => reserved,
+                _ => return Err(InventoryError::OverReserve.into()),
+            }
+        } else {
+            match current.checked_sub(reservation.quantity) {
+                Some(reserved) => reserved,
+                None => return Err(InventoryError::OverRelease.into()),
+            }
+        };
+        match reserved {
+            0 => reservations.remove(&sku),
+            reserved => reservations.insert(sku, reserved),
+        };
 
-    static SERVER_INIT: Once = Once::new();
-    async fn get_client() -> InventoryClient<Channel> {
-        SERVER_INIT.call_once(|| {
-            tokio::spawn(async {
-                let addr = "127.0.0.1:8080".parse().unwrap();
-                let inventory = StoreInventory::default();
-                Server::builder()
-                    .add_service(InventoryServer::new(inventory))
-                    .serve(addr)
+        Ok(Availability {
+            total,
+            reserved,
+            available: total.saturating_sub(reserved),
+        })
+    }
+
+    // locks the shards responsible for the given SKUs in ascending index
+    // order, locking shards shared by several SKUs only once.
+    async fn lock_shards(&self, skus: &[&str]) -> LockedShards<'_> {
+        let indexes = self.shard_indexes(skus);
+        let mut guards = Vec::with_capacity(indexes.len());
+        for index in indexes {
+            guards.push((index, self.inventory[index].write().await));
+        }
+
+        let shards = self.inventory.len();
+        LockedShards { shards, guards }
+    }
+
+    // locks every shard in ascending index order, as lock_shards does.
+    async fn lock_all_shards(&self) -> LockedShards<'_> {
+        let mut guards = Vec::with_capacity(self.inventory.len());
+        for (index, shard) in self.inventory.iter().enumerate() {
+            guards.push((index, shard.write().await));
+        }
+
+        let shards = self.inventory.len();
+        LockedShards { shards, guards }
+    }
+
+    // read locks the shards responsible for the given SKUs, as lock_shards
+    // write locks them.
+    async fn read_shards(&self, skus: &[&str]) -> ReadShards<'_> {
+        let indexes = self.shard_indexes(skus);
+        let mut guards = Vec::with_capacity(indexes.len());
+        for index in indexes {
+            guards.push((index, self.inventory[index].read().await));
+        }
+
+        let shards = self.inventory.len();
+        ReadShards { shards, guards }
+    }
+
+    // the indexes of the shards responsible for the given SKUs, in ascending
+    // order and without duplicates.
+    fn shard_indexes(&self, skus: &[&str]) -> Vec<usize> {
+        let shards = self.inventory.len();
+        let mut indexes: Vec<usize> = skus.iter().map(|sku| shard_index(shards, sku)).collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+        indexes
+    }
+
+    /// Removes the items whose expiry time has passed, along with their
+    /// images and reservations, returning how many were removed. Watches of
+    /// the items are ended as they are when items are removed by clients.
+    /// Tombstones older than the retention are pruned along the way.
+    pub async fn drain_expired(&self) -> usize {
+        let now = now_millis();
+        let mut drained = 0;
+        self.prune_tombstones(now).await;
+
+        // shards are swept one at a time, so that the rest of the inventory
+        // stays available while each one is. Items the storage fails to scan
+        // or remove are left for the next sweep, the storage logs why.
+        for shard in self.inventory.iter() {
+            let mut map = shard.write().await;
+            let mut expired = Vec::new();
+            let scanned = map
+                .scan(&mut |sku, item| {
+                    if item.expires_at.is_some_and(|expires| expires <= now) {
+                        expired.push(sku.to_string());
+                    }
+                })
+                .await;
+            if scanned.is_err() {
+                continue;
+            }
+            let mut removed = Vec::with_capacity(expired.len());
+            for sku in expired {
+                let item = match map.remove(&sku).await {
+                    Ok(Some(item)) => item,
+                    _ => continue,
+                };
+                self.images.lock().await.remove(&sku);
+                self.reservations.lock().await.remove(&sku);
+                self.accesses.write().unwrap().remove(&sku);
+                self.notify(&sku, None);
+                self.removed.lock().await.insert(sku.clone(), now);
+                tracing::info!(sku = %sku, "expired item removed");
+                removed.push((sku, item));
+            }
+            drop(map);
+            drained += removed.len();
+            for (sku, item) in removed {
+                self.audit.record(now, "Expire", &sku, Some(item), None);
+            }
+        }
+
+        drained
+    }
+
+    // forgets the removals which are older than the tombstone retention,
+    // remembering the latest of them so that stale syncs can be rejected.
+    async fn prune_tombstones(&self, now: u64) {
+        let retention = self.tombstone_retention.as_millis() as u64;
+        let cutoff = now.saturating_sub(retention);
+        let mut removed = self.removed.lock().await;
+        let mut latest_pruned = 0;
+        removed.retain(|_, removed_at| {
+            let keep = *removed_at > cutoff;
+            if !keep {
+                latest_pruned = latest_pruned.max(*removed_at);
+            }
+            keep
+        });
+        self.pruned_removals
+            .fetch_max(latest_pruned, Ordering::SeqCst);
+    }
+
+    /// Periodically removes expired items from the inventory, sweeping it
+    /// once every interval for as long as the server runs.
+    pub async fn sweep_expired(self: Arc<Self>, interval: Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            self.drain_expired().await;
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Inventory for StoreInventory {
+    async fn add(
+        &self,
+        request: Request<Item>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        record_sku(&request, item_sku(request.get_ref()));
+        self.check_writable()?;
+        let item = request.into_inner();
+        let sku = validate_item(&item, self.max_quantity, self.field_limits)?;
+        let item = self.insert_item(sku.clone(), item).await?;
+        let now = item.last_updated;
+        self.audit
+            .record(now, "Add", &sku, None, Some(item.clone()));
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: "success".into(),
+            item: Some(item),
+            ..Default::default()
+        }))
+    }
+
+    async fn upsert(
+        &self,
+        request: Request<Item>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        record_sku(&request, item_sku(request.get_ref()));
+        self.check_writable()?;
+        let mut item = request.into_inner();
+        let sku = validate_item(&item, self.max_quantity, self.field_limits)?;
+
+        // a replaced item loses everything kept alongside it, as it would if
+        // it was removed and added again
+        let mut map = self.shard(&sku).write().await;
+        let previous = map.get(&sku).await?;
+        let replaced = previous.is_some();
+        normalize_item(&mut item);
+        item.last_updated = now_millis();
+        item.version = next_version(previous.as_ref());
+        map.insert(sku.clone(), item.clone()).await?;
+        if replaced {
+            self.images.lock().await.remove(&sku);
+            self.reservations.lock().await.remove(&sku);
+            self.accesses.write().unwrap().remove(&sku);
+        }
+        self.removed.lock().await.remove(&sku);
+        self.notify(&sku, Some(&item));
+        drop(map);
+        let now = item.last_updated;
+        self.audit
+            .record(now, "Upsert", &sku, previous, Some(item.clone()));
+
+        let status = if replaced {
+            "success: item was replaced"
+        } else {
+            "success: item was added"
+        };
+        Ok(Response::new(InventoryChangeResponse {
+            status: status.into(),
+            item: Some(item),
+            replaced,
+            ..Default::default()
+        }))
+    }
+
+    async fn validate(
+        &self,
+        request: Request<Item>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        record_sku(&request, item_sku(request.get_ref()));
+        let mut item = request.into_inner();
+        validate_item(&item, self.max_quantity, self.field_limits)?;
+
+        // the item is returned as it would be stored, but the inventory is
+        // never touched, so validation works while it's read-only too
+        normalize_item(&mut item);
+        Ok(Response::new(InventoryChangeResponse {
+            status: "success: item is valid".into(),
+            item: Some(item),
+            ..Default::default()
+        }))
+    }
+
+    async fn batch_add(
+        &self,
+        request: Request<Streaming<Item>>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        self.check_writable()?;
+        let mut stream = request.into_inner();
+
+        // items are validated as they arrive, and the batch is rejected as
+        // soon as its cumulative size exceeds the budget rather than after the
+        // client finished sending it. Valid items are only added once the
+        // whole batch has arrived, so a rejected batch adds nothing.
+        let mut size = 0;
+        let mut staged = Vec::new();
+        let mut failed = 0;
+        while let Some(item) = stream.message().await? {
+            size += item.encoded_len();
+            if size > self.batch_budget {
+                return Err(InventoryError::BigBatch.into());
+            }
+            match validate_item(&item, self.max_quantity, self.field_limits) {
+                Ok(sku) => staged.push((sku, item)),
+                Err(_) => failed += 1,
+            }
+        }
+
+        let mut added = 0;
+        for (sku, item) in staged {
+            match self.insert_item(sku.clone(), item).await {
+                Ok(item) => {
+                    let now = item.last_updated;
+                    self.audit.record(now, "BatchAdd", &sku, None, Some(item));
+                    added += 1;
+                }
+                Err(_) => failed += 1,
+            }
+        }
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: format!("success: {} added, {} failed", added, failed),
+            ..Default::default()
+        }))
+    }
+
+    async fn apply_batch(
+        &self,
+        request: Request<ApplyBatchRequest>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        self.check_writable()?;
+        let operations = request.into_inner().operations;
+
+        // validate what can be validated without the inventory before taking
+        // any locks, and find the SKUs the batch touches
+        let mut skus = Vec::with_capacity(operations.len());
+        for (index, operation) in operations.iter().enumerate() {
+            let sku = validate_operation(operation, self.max_quantity, self.field_limits)
+                .map_err(|err| failed_operation(index, err))?;
+            skus.push(sku);
+        }
+
+        // the locks for every item in the batch are held while the operations
+        // are staged against copies of the items, and until the changes are
+        // committed. Nothing is changed if any operation fails.
+        let sku_refs: Vec<&str> = skus.iter().map(String::as_str).collect();
+        let mut shards = self.lock_shards(&sku_refs).await;
+        let mut staged: HashMap<&str, Option<Item>> = HashMap::new();
+        let mut previous: HashMap<&str, Option<Item>> = HashMap::new();
+        let mut replaced: HashSet<&str> = HashSet::new();
+        for (index, (operation, sku)) in operations.into_iter().zip(&skus).enumerate() {
+            let item = match staged.entry(sku) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let item = shards.map(sku).get(sku).await?;
+                    previous.insert(sku, item.clone());
+                    entry.insert(item)
+                }
+            };
+            if matches!(operation.operation, Some(BatchOperation::Remove(_))) && item.is_some() {
+                replaced.insert(sku);
+            }
+            stage_operation(item, operation).map_err(|err| failed_operation(index, err.into()))?;
+        }
+
+        // commit the changes to the items which changed as one, so that
+        // either all of them are made or, if the storage fails, none are.
+        let now = now_millis();
+        let mut changes = Vec::with_capacity(staged.len());
+        for (sku, item) in staged {
+            let previous = previous.remove(sku).flatten();
+            let item = match item {
+                Some(item) if previous.as_ref() == Some(&item) => continue,
+                None if previous.is_none() => continue,
+                Some(mut item) => {
+                    item.last_updated = now;
+                    item.version = next_version(previous.as_ref());
+                    Some(item)
+                }
+                None => None,
+            };
+            changes.push((sku, previous, item));
+        }
+        let commit: Vec<_> = changes
+            .iter()
+            .map(|(sku, _, item)| (*sku, item.clone()))
+            .collect();
+        shards.commit(&commit).await?;
+
+        // items which were removed lose their images, reservations and access
+        // counts even if they were added again later in the batch.
+        let mut removed = self.removed.lock().await;
+        let mut images = self.images.lock().await;
+        let mut reservations = self.reservations.lock().await;
+        for sku in replaced {
+            images.remove(sku);
+            reservations.remove(sku);
+            self.accesses.write().unwrap().remove(sku);
+        }
+        for (sku, _, item) in &changes {
+            match item {
+                Some(_) => removed.remove(*sku),
+                None => removed.insert(sku.to_string(), now),
+            };
+            self.notify(sku, item.as_ref());
+        }
+        drop((removed, images, reservations, shards));
+        for (sku, before, after) in changes {
+            self.audit.record(now, "ApplyBatch", sku, before, after);
+        }
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: format!("success: {} operations applied", skus.len()),
+            ..Default::default()
+        }))
+    }
+
+    async fn remove(
+        &self,
+        request: Request<RemoveRequest>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        self.check_writable()?;
+        let identifier = request.into_inner();
+
+        validate_sku(&identifier.sku)?;
+
+        // remove the item (if present) along with its image, and leave a
+        // tombstone behind for it
+        let mut map = self.shard(&identifier.sku).write().await;
+        let removed = map.remove(&identifier.sku).await?;
+        let existed = removed.is_some();
+        let msg = match removed {
+            Some(item) => {
+                self.images.lock().await.remove(&identifier.sku);
+                self.reservations.lock().await.remove(&identifier.sku);
+                self.accesses.write().unwrap().remove(&identifier.sku);
+                self.notify(&identifier.sku, None);
+                let now = now_millis();
+                self.removed
+                    .lock()
                     .await
-                    .unwrap();
+                    .insert(identifier.sku.clone(), now);
+                drop(map);
+                self.audit
+                    .record(now, "Remove", &identifier.sku, Some(item), None);
+                "success: item was removed"
+            }
+            None if identifier.strict => return Err(InventoryError::NoItem.into()),
+            None => "success: item didn't exist",
+        };
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: msg.into(),
+            existed,
+            ..Default::default()
+        }))
+    }
+
+    async fn batch_remove(
+        &self,
+        request: Request<BatchRemoveRequest>,
+    ) -> Result<Response<BatchRemoveResponse>, Status> {
+        self.check_writable()?;
+        let batch = request.into_inner();
+        if batch.encoded_len() > self.batch_budget {
+            return Err(InventoryError::BigBatch.into());
+        }
+        for sku in &batch.skus {
+            validate_sku(sku)?;
+        }
+
+        // the shards for the whole batch are locked once, rather than for
+        // each SKU, and the items are removed together, then cleaned up after
+        // as they are by Remove.
+        let skus: Vec<&str> = batch.skus.iter().map(String::as_str).collect();
+        let mut shards = self.lock_shards(&skus).await;
+        let commit: Vec<_> = skus.iter().map(|sku| (*sku, None)).collect();
+        let previous = shards.commit(&commit).await?;
+
+        let mut images = self.images.lock().await;
+        let mut reservations = self.reservations.lock().await;
+        let mut removed = self.removed.lock().await;
+        let now = now_millis();
+        let mut response = BatchRemoveResponse::default();
+        let mut changes = Vec::with_capacity(skus.len());
+        for (sku, item) in skus.into_iter().zip(previous) {
+            let item = match item {
+                Some(item) => item,
+                None => {
+                    response.missing += 1;
+                    continue;
+                }
+            };
+            images.remove(sku);
+            reservations.remove(sku);
+            self.accesses.write().unwrap().remove(sku);
+            self.notify(sku, None);
+            removed.insert(sku.into(), now);
+            response.removed += 1;
+            changes.push((sku, item));
+        }
+        drop((removed, images, reservations, shards));
+        for (sku, item) in changes {
+            self.audit.record(now, "BatchRemove", sku, Some(item), None);
+        }
+
+        Ok(Response::new(response))
+    }
+
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<Item>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        let currency = requested_currency(&request);
+        let get = request.into_inner();
+
+        validate_sku(&get.sku)?;
+
+        // retrieve the item if it exists, sharing the read with concurrent
+        // gets for the same item if coalescing is enabled. Reservations are
+        // read under the same lock as the item, so they're never coalesced.
+        let (item, reserved) = match &self.coalescer {
+            Some(coalescer) if !get.include_reservations => {
+                (coalescer.get(&self.inventory, &get.sku).await?, None)
+            }
+            _ => {
+                let map = self.shard(&get.sku).read().await;
+                let reserved = match get.include_reservations {
+                    true => {
+                        let reservations = self.reservations.lock().await;
+                        Some(reservations.get(&get.sku).copied().unwrap_or_default())
+                    }
+                    false => None,
+                };
+                (map.get(&get.sku).await?, reserved)
+            }
+        };
+
+        let mut item = match item {
+            Some(item) => item,
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        self.record_access(&get.sku);
+
+        // report how much of the stock is available if the client asked
+        if let (Some(reserved), Some(stock)) = (reserved, item.stock.as_ref()) {
+            item.availability = Some(Availability {
+                total: stock.quantity,
+                reserved,
+                available: stock.quantity.saturating_sub(reserved),
+            });
+        }
+
+        // convert the price for display if the client requested a currency
+        if let Some(currency) = currency {
+            if let Some(stock) = item.stock.as_ref() {
+                let from = stock_currency(stock);
+                let price = match self.rates.exchange(stock.price, from, &currency) {
+                    Some(price) => self.snap_converted(price, from, &currency),
+                    None => return Err(InventoryError::UnknownCurrency.into()),
+                };
+                item.converted = Some(ConvertedPrice { price, currency });
+            }
+        }
+
+        Ok(Response::new(item))
+    }
+
+    async fn update_quantity(
+        &self,
+        request: Request<QuantityChangeRequest>,
+    ) -> Result<Response<InventoryUpdateResponse>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        self.check_writable()?;
+        let change = request.into_inner();
+        validate_quantity_change(&change)?;
+
+        // retrieve the current inventory item data
+        let mut map = self.shard(&change.sku).write().await;
+        let mut item = match map.get(&change.sku).await? {
+            Some(item) => item,
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        let before = item.clone();
+        let (price, quantity) = change_quantity(&mut item, change.change)?;
+        self.check_reserved(&change.sku, quantity).await?;
+        item.last_updated = now_millis();
+        item.version += 1;
+        map.update(&change.sku, item.clone()).await?;
+        self.notify(&change.sku, Some(&item));
+        let after = item;
+        drop(map);
+        let (now, operation) = (after.last_updated, "UpdateQuantity");
+        self.audit
+            .record(now, operation, &change.sku, Some(before), Some(after));
+
+        Ok(Response::new(InventoryUpdateResponse {
+            status: "success".into(),
+            price,
+            quantity,
+        }))
+    }
+
+    async fn set_quantity(
+        &self,
+        request: Request<SetQuantityRequest>,
+    ) -> Result<Response<InventoryUpdateResponse>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        self.check_writable()?;
+        let set = request.into_inner();
+        validate_sku(&set.sku)?;
+
+        // the quantity is set under the write lock, so unlike reading it and
+        // sending the difference, no concurrent change can slip in between
+        let mut map = self.shard(&set.sku).write().await;
+        let mut item = match map.get(&set.sku).await? {
+            Some(item) => item,
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        let before = item.clone();
+        let stock = match item.stock.as_mut() {
+            Some(stock) => stock,
+            None => return Err(InventoryError::NoStockToUpdate.into()),
+        };
+        stock.quantity = set.quantity;
+        let price = stock.price;
+        self.check_reserved(&set.sku, set.quantity).await?;
+        item.last_updated = now_millis();
+        item.version += 1;
+        map.update(&set.sku, item.clone()).await?;
+        self.notify(&set.sku, Some(&item));
+        drop(map);
+        let (now, operation) = (item.last_updated, "SetQuantity");
+        self.audit
+            .record(now, operation, &set.sku, Some(before), Some(item));
+
+        Ok(Response::new(InventoryUpdateResponse {
+            status: "success".into(),
+            price,
+            quantity: set.quantity,
+        }))
+    }
+
+    async fn set_reorder_threshold(
+        &self,
+        request: Request<ReorderThresholdRequest>,
+    ) -> Result<Response<InventoryUpdateResponse>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        self.check_writable()?;
+        let set = request.into_inner();
+        validate_sku(&set.sku)?;
+
+        let mut map = self.shard(&set.sku).write().await;
+        let mut item = match map.get(&set.sku).await? {
+            Some(item) => item,
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        let before = item.clone();
+        let stock = match item.stock.as_mut() {
+            Some(stock) => stock,
+            None => return Err(InventoryError::NoStockToUpdate.into()),
+        };
+        stock.reorder_threshold = set.threshold;
+        let (price, quantity) = (stock.price, stock.quantity);
+        item.last_updated = now_millis();
+        item.version += 1;
+        map.update(&set.sku, item.clone()).await?;
+        self.notify(&set.sku, Some(&item));
+        drop(map);
+        let (now, operation) = (item.last_updated, "SetReorderThreshold");
+        self.audit
+            .record(now, operation, &set.sku, Some(before), Some(item));
+
+        Ok(Response::new(InventoryUpdateResponse {
+            status: "success".into(),
+            price,
+            quantity,
+        }))
+    }
+
+    async fn update_price(
+        &self,
+        request: Request<PriceChangeRequest>,
+    ) -> Result<Response<InventoryUpdateResponse>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        self.check_writable()?;
+        let change = request.into_inner();
+        validate_price_change(&change)?;
+
+        // retrieve the current inventory item data
+        let mut map = self.shard(&change.sku).write().await;
+        let mut item = match map.get(&change.sku).await? {
+            Some(item) => item,
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        let before = item.clone();
+        let changed = change_price(&mut item, &change)?.is_some();
+        let status = match changed {
+            true => {
+                item.last_updated = now_millis();
+                item.version += 1;
+                map.update(&change.sku, item.clone()).await?;
+                self.notify(&change.sku, Some(&item));
+                "success"
+            }
+            // the item is already at the price, which isn't a change to
+            // timestamp, tell watches about or audit
+            false => "success: no change",
+        };
+        let after = item;
+        drop(map);
+        if changed {
+            let (now, operation) = (after.last_updated, "UpdatePrice");
+            self.audit.record(
+                now,
+                operation,
+                &change.sku,
+                Some(before),
+                Some(after.clone()),
+            );
+        }
+        let stock = after.stock.unwrap_or_default();
+
+        Ok(Response::new(InventoryUpdateResponse {
+            status: status.into(),
+            price: stock.price,
+            quantity: stock.quantity,
+        }))
+    }
+
+    async fn bulk_update_price(
+        &self,
+        request: Request<BulkPriceRequest>,
+    ) -> Result<Response<BulkPriceResponse>, Status> {
+        self.check_writable()?;
+        let changes = request.into_inner().changes;
+
+        // each change is applied on its own and reports its own outcome, so
+        // that a reprice succeeds overall even when some items are missing or
+        // already at their target price.
+        let now = now_millis();
+        let mut results = Vec::with_capacity(changes.len());
+        for change in changes {
+            let failed = |sku: String, price: f32, err: &str| PriceChangeResult {
+                sku,
+                outcome: PriceOutcome::Failed.into(),
+                price,
+                error: err.into(),
+            };
+            if let Err(err) = validate_price_change(&change) {
+                results.push(failed(change.sku, 0.0, err.message()));
+                continue;
+            }
+
+            let mut map = self.shard(&change.sku).write().await;
+            let mut item = match map.get(&change.sku).await? {
+                Some(item) => item,
+                None => {
+                    results.push(failed(change.sku, 0.0, InventoryError::NoItem.message()));
+                    continue;
+                }
+            };
+            let before = item.clone();
+            let current = item.stock.as_ref().map(|stock| stock.price);
+            let result = match change_price(&mut item, &change) {
+                Ok(Some((price, _))) => {
+                    item.last_updated = now;
+                    item.version += 1;
+                    map.update(&change.sku, item.clone()).await?;
+                    self.notify(&change.sku, Some(&item));
+                    drop(map);
+                    let operation = "BulkUpdatePrice";
+                    self.audit
+                        .record(now, operation, &change.sku, Some(before), Some(item));
+                    PriceChangeResult {
+                        sku: change.sku,
+                        outcome: PriceOutcome::Updated.into(),
+                        price,
+                        error: String::new(),
+                    }
+                }
+                Ok(None) => PriceChangeResult {
+                    sku: change.sku,
+                    outcome: PriceOutcome::Unchanged.into(),
+                    price: current.unwrap_or_default(),
+                    error: String::new(),
+                },
+                Err(err) => failed(change.sku, current.unwrap_or_default(), err.message()),
+            };
+            results.push(result);
+        }
+
+        Ok(Response::new(BulkPriceResponse { results }))
+    }
+
+    async fn update_information(
+        &self,
+        request: Request<InformationChangeRequest>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        self.check_writable()?;
+        let change = request.into_inner();
+
+        validate_sku(&change.sku)?;
+        if let Some(information) = &change.information {
+            validate_information(information, self.field_limits)?;
+        }
+
+        // apply the change to a copy of the item, so that the item is left
+        // unchanged if the resulting attributes are invalid
+        let mut map = self.shard(&change.sku).write().await;
+        let mut item = match map.get(&change.sku).await? {
+            Some(item) => item,
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        let before = item.clone();
+        let mut attributes = item.attributes.clone();
+        for (key, value) in change.attributes {
+            match value.is_empty() {
+                true => attributes.remove(&key),
+                false => attributes.insert(key, value),
+            };
+        }
+        validate_attributes(&attributes)?;
+
+        // information which changes neither the name nor the description
+        // doesn't give the item any
+        if let Some(changed) = change.information {
+            if changed.name.is_some() || changed.description.is_some() {
+                let information = item.information.get_or_insert_with(Default::default);
+                if changed.name.is_some() {
+                    information.name = changed.name;
+                }
+                if changed.description.is_some() {
+                    information.description = changed.description;
+                }
+            }
+        }
+        item.attributes = attributes;
+
+        // the update leaves the item as it was (e.g. it's empty, or sets the
+        // values the item already has), which isn't a change to timestamp,
+        // tell watches about or audit
+        if item == before {
+            return Ok(Response::new(InventoryChangeResponse {
+                status: "success: no change".into(),
+                ..Default::default()
+            }));
+        }
+        item.last_updated = now_millis();
+        item.version += 1;
+        map.update(&change.sku, item.clone()).await?;
+        self.notify(&change.sku, Some(&item));
+        drop(map);
+        let (now, operation) = (item.last_updated, "UpdateInformation");
+        self.audit
+            .record(now, operation, &change.sku, Some(before), Some(item));
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: "success".into(),
+            ..Default::default()
+        }))
+    }
+
+    type WatchStream = WatchEventStream;
+
+    async fn watch(
+        &self,
+        request: Request<WatchRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        self.watch_item(request, false).await
+    }
+
+    type GetAndWatchStream = WatchEventStream;
+
+    async fn get_and_watch(
+        &self,
+        request: Request<ItemIdentifier>,
+    ) -> Result<Response<Self::GetAndWatchStream>, Status> {
+        let request = request.map(|id| WatchRequest {
+            sku: id.sku,
+            from_version: None,
+        });
+        self.watch_item(request, true).await
+    }
+
+    type WatchAllStream = Pin<Box<dyn Stream<Item = Result<ItemEvent, Status>> + Send>>;
+
+    async fn watch_all(
+        &self,
+        request: Request<WatchAllRequest>,
+    ) -> Result<Response<Self::WatchAllStream>, Status> {
+        let stream_id = watch_stream_id(&request)?;
+        let mut changes = self.changes.subscribe();
+
+        // every change is forwarded until either the client closes the
+        // connection, or the watch falls behind.
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watcher = self.metrics.watcher(&stream_id, "");
+        let task_stream_id = stream_id.clone();
+        tracing::info!(stream_id = %stream_id, "watch of all items started");
+        tokio::spawn(async move {
+            // the watch is registered as open until this task ends
+            let _watcher = watcher;
+            let stream_id = task_stream_id;
+            loop {
+                let change = tokio::select! {
+                    _ = tx.closed() => {
+                        tracing::info!(stream_id = %stream_id, "watch ended by the client");
+                        return;
+                    }
+                    change = changes.recv() => change,
+                };
+
+                let event = match change {
+                    Ok(ItemChange {
+                        item: Some(item), ..
+                    }) => Ok(ItemEvent {
+                        item: Some(item),
+                        removed: false,
+                    }),
+                    Ok(ItemChange { sku, item: None }) => Ok(ItemEvent {
+                        item: Some(Item {
+                            identifier: Some(ItemIdentifier { sku }),
+                            ..Default::default()
+                        }),
+                        removed: true,
+                    }),
+                    // unlike a watch of a single item, there's no way to catch
+                    // up with the removals which were missed. The client is
+                    // told, so that it can start again from a fresh listing.
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(stream_id = %stream_id, missed, "watch fell behind");
+                        Err(InventoryError::WatchLagged.into())
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+
+                let lagged = event.is_err();
+                if let Err(err) = tx.send(event) {
+                    tracing::error!(
+                        stream_id = %stream_id,
+                        error = ?err,
+                        "failed to update stream client"
+                    );
+                    return;
+                }
+                if lagged {
+                    return;
+                }
+            }
+        });
+
+        let stream = UnboundedReceiverStream::new(rx);
+        let response = Response::new(Box::pin(stream) as Self::WatchAllStream);
+        Ok(with_stream_id(response, &stream_id))
+    }
+
+    async fn list_changed_since(
+        &self,
+        request: Request<ChangedSinceRequest>,
+    ) -> Result<Response<ChangedSinceResponse>, Status> {
+        let since = request.into_inner().since;
+
+        // removals older than the tombstone retention are forgotten, so a
+        // client which last synced before one can't be told about it. A since
+        // of 0 is a full sync, which has no removals to miss.
+        if since > 0 && since < self.pruned_removals.load(Ordering::SeqCst) {
+            return Err(InventoryError::StaleSync.into());
+        }
+
+        // timestamps are unix epoch milliseconds and the boundary is
+        // exclusive: only changes strictly newer than "since" are reported.
+        // Shards are scanned one at a time, so this isn't a point in time
+        // snapshot of the whole inventory.
+        let mut items = Vec::new();
+        for shard in self.inventory.iter() {
+            let map = shard.read().await;
+            map.scan(&mut |_, item| {
+                if item.last_updated > since {
+                    items.push(item.clone());
+                }
+            })
+            .await?;
+        }
+
+        let removed = self.removed.lock().await;
+        let removed_skus = removed
+            .iter()
+            .filter(|(_, removed_at)| **removed_at > since)
+            .map(|(sku, _)| sku.clone())
+            .collect();
+
+        Ok(Response::new(ChangedSinceResponse {
+            items,
+            removed_skus,
+        }))
+    }
+
+    async fn upload_image(
+        &self,
+        request: Request<Streaming<ImageChunk>>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        self.check_writable()?;
+        let mut stream = request.into_inner();
+
+        // the first chunk identifies the item and the type of the image
+        let first = match stream.message().await? {
+            Some(chunk) => chunk,
+            None => return Err(InventoryError::EmptyImage.into()),
+        };
+        validate_sku(&first.sku)?;
+        if !IMAGE_CONTENT_TYPES.contains(&first.content_type.as_str()) {
+            return Err(InventoryError::BadContentType.into());
+        }
+
+        // collect the image data, rejecting it as soon as it grows too large
+        // rather than waiting for the client to finish sending it.
+        let mut data = first.data;
+        if data.len() > MAX_IMAGE_SIZE {
+            return Err(InventoryError::BigImage.into());
+        }
+        while let Some(chunk) = stream.message().await? {
+            if data.len() + chunk.data.len() > MAX_IMAGE_SIZE {
+                return Err(InventoryError::BigImage.into());
+            }
+            data.extend(chunk.data);
+        }
+        if data.is_empty() {
+            return Err(InventoryError::EmptyImage.into());
+        }
+
+        // images can only be stored for items in the inventory
+        let mut map = self.shard(&first.sku).write().await;
+        let mut item = match map.get(&first.sku).await? {
+            Some(item) => item,
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        let before = item.clone();
+
+        // reference the image from the item, and store the image data
+        item.image = Some(ItemImage {
+            content_type: first.content_type.clone(),
+            size: data.len() as u64,
+        });
+        item.last_updated = now_millis();
+        item.version += 1;
+        map.update(&first.sku, item.clone()).await?;
+        let image = Image {
+            content_type: first.content_type,
+            data,
+        };
+        self.images.lock().await.insert(first.sku.clone(), image);
+        self.notify(&first.sku, Some(&item));
+        drop(map);
+        let (now, operation) = (item.last_updated, "UploadImage");
+        self.audit
+            .record(now, operation, &first.sku, Some(before), Some(item));
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: "success".into(),
+            ..Default::default()
+        }))
+    }
+
+    type GetImageStream = Pin<Box<dyn Stream<Item = Result<ImageChunk, Status>> + Send>>;
+
+    async fn get_image(
+        &self,
+        request: Request<ItemIdentifier>,
+    ) -> Result<Response<Self::GetImageStream>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        let identifier = request.into_inner();
+
+        validate_sku(&identifier.sku)?;
+
+        // retrieve the image if it exists
+        let images = self.images.lock().await;
+        let image = match images.get(&identifier.sku) {
+            Some(image) => image,
+            None => return Err(InventoryError::NoImage.into()),
+        };
+
+        // split the image into chunks, only the first of which identifies the
+        // item and the type of the image.
+        let chunks: Vec<ImageChunk> = image
+            .data
+            .chunks(IMAGE_CHUNK_SIZE)
+            .enumerate()
+            .map(|(i, data)| {
+                let (sku, content_type) = match i {
+                    0 => (identifier.sku.clone(), image.content_type.clone()),
+                    _ => (String::new(), String::new()),
+                };
+                ImageChunk {
+                    sku,
+                    content_type,
+                    data: data.to_vec(),
+                }
+            })
+            .collect();
+
+        let stream = tokio_stream::iter(chunks.into_iter().map(Ok));
+        Ok(Response::new(Box::pin(stream) as Self::GetImageStream))
+    }
+
+    async fn get_many(
+        &self,
+        request: Request<GetManyRequest>,
+    ) -> Result<Response<GetManyResponse>, Status> {
+        let skus = request.into_inner().skus;
+
+        // report an outcome for every SKU rather than failing the whole
+        // request, so that clients can reconcile exactly what they asked for.
+        // The shards for the valid SKUs are locked once, so that the items
+        // are all read at the same moment.
+        let valid: Vec<&str> = skus
+            .iter()
+            .map(String::as_str)
+            .filter(|sku| validate_sku(sku).is_ok())
+            .collect();
+        let shards = self.read_shards(&valid).await;
+        let mut results = Vec::with_capacity(skus.len());
+        for sku in skus {
+            if validate_sku(&sku).is_err() {
+                results.push(GetManyResult {
+                    sku,
+                    outcome: Outcome::Invalid.into(),
+                    item: None,
+                });
+                continue;
+            }
+
+            let item = shards.map(&sku).get(&sku).await?;
+            let outcome = match item {
+                Some(_) => Outcome::Found,
+                None => Outcome::NotFound,
+            };
+            results.push(GetManyResult {
+                sku,
+                outcome: outcome.into(),
+                item,
+            });
+        }
+
+        Ok(Response::new(GetManyResponse { results }))
+    }
+
+    async fn batch_get(
+        &self,
+        request: Request<BatchGetRequest>,
+    ) -> Result<Response<BatchGetResponse>, Status> {
+        let batch = request.into_inner();
+        if batch.encoded_len() > self.batch_budget {
+            return Err(InventoryError::BigBatch.into());
+        }
+        for sku in &batch.skus {
+            validate_sku(sku)?;
+        }
+
+        // the items are read as GetMany reads them, once the SKUs listed
+        // more than once are left out
+        let mut seen = HashSet::new();
+        let skus = batch
+            .skus
+            .into_iter()
+            .filter(|sku| seen.insert(sku.clone()))
+            .collect();
+        let request = Request::new(GetManyRequest { skus });
+        let results = self.get_many(request).await?.into_inner().results;
+        let mut response = BatchGetResponse::default();
+        for result in results {
+            match result.item {
+                Some(item) => response.items.push(item),
+                None => response.missing.push(result.sku),
+            }
+        }
+
+        Ok(Response::new(response))
+    }
+
+    async fn swap_stock(
+        &self,
+        request: Request<SwapStockRequest>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        self.check_writable()?;
+        let swap = request.into_inner();
+
+        // don't allow empty SKUs, or swapping an item with itself
+        validate_sku(&swap.first_sku)?;
+        validate_sku(&swap.second_sku)?;
+        if swap.first_sku == swap.second_sku {
+            return Err(InventoryError::SameSku.into());
+        }
+
+        // hold the locks for both items for the whole swap, and make sure
+        // both exist with stock to swap before changing either of them
+        let mut shards = self.lock_shards(&[&swap.first_sku, &swap.second_sku]).await;
+        let mut items = Vec::with_capacity(2);
+        for sku in [&swap.first_sku, &swap.second_sku] {
+            match shards.map(sku).get(sku).await? {
+                Some(item) if item.stock.is_none() => {
+                    return Err(InventoryError::NoStockToUpdate.into())
+                }
+                Some(item) => items.push(item),
+                None => return Err(InventoryError::NoItem.into()),
+            }
+        }
+
+        // exchange the stock of the items, changing both of them or neither
+        let now = now_millis();
+        let stocks: Vec<_> = items.iter().rev().map(|item| item.stock.clone()).collect();
+        let skus = [&swap.first_sku, &swap.second_sku];
+        let mut changes = Vec::with_capacity(2);
+        for ((sku, mut item), stock) in skus.into_iter().zip(items).zip(stocks) {
+            let before = item.clone();
+            item.stock = stock;
+            item.last_updated = now;
+            item.version += 1;
+            changes.push((sku, before, item));
+        }
+        let commit: Vec<_> = changes
+            .iter()
+            .map(|(sku, _, item)| (sku, Some(item.clone())))
+            .collect();
+        shards.commit(&commit).await?;
+        for (sku, _, item) in &changes {
+            self.notify(sku, Some(item));
+        }
+        drop(shards);
+        for (sku, before, after) in changes {
+            self.audit
+                .record(now, "SwapStock", sku, Some(before), Some(after));
+        }
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: "success".into(),
+            ..Default::default()
+        }))
+    }
+
+    async fn rename_sku(
+        &self,
+        request: Request<RenameSkuRequest>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        record_sku(&request, &request.get_ref().old_sku);
+        self.check_writable()?;
+        let rename = request.into_inner();
+
+        validate_sku(&rename.old_sku).map_err(|err| within("old_sku", err))?;
+        validate_sku(&rename.new_sku).map_err(|err| within("new_sku", err))?;
+        validate_sku_len(&rename.new_sku, self.field_limits)
+            .map_err(|err| within("new_sku", err))?;
+        if rename.old_sku == rename.new_sku {
+            return Err(bad_request("new_sku", InventoryError::SameRename));
+        }
+
+        // hold the locks for both SKUs for the whole rename, so that the item
+        // is never missing from, nor present twice in, the inventory
+        let mut shards = self.lock_shards(&[&rename.old_sku, &rename.new_sku]).await;
+        if shards
+            .map(&rename.new_sku)
+            .contains(&rename.new_sku)
+            .await?
+        {
+            return Err(InventoryError::DuplicateItem.into());
+        }
+        let mut item = match shards.map(&rename.old_sku).get(&rename.old_sku).await? {
+            Some(item) => item,
+            None => return Err(InventoryError::NoItem.into()),
+        };
+        let before = item.clone();
+
+        // the item is moved to the new SKU as one change, so that if the
+        // storage fails it's left under the old one
+        let (old_sku, new_sku) = (rename.old_sku, rename.new_sku);
+        let now = now_millis();
+        item.identifier = Some(ItemIdentifier {
+            sku: new_sku.clone(),
+        });
+        item.last_updated = now;
+        item.version += 1;
+        let commit = [(&old_sku, None), (&new_sku, Some(item.clone()))];
+        shards.commit(&commit).await?;
+
+        // everything kept alongside the item moves with it
+        let mut images = self.images.lock().await;
+        if let Some(image) = images.remove(&old_sku) {
+            images.insert(new_sku.clone(), image);
+        }
+        drop(images);
+        let mut reservations = self.reservations.lock().await;
+        if let Some(reserved) = reservations.remove(&old_sku) {
+            reservations.insert(new_sku.clone(), reserved);
+        }
+        drop(reservations);
+        {
+            let mut accesses = self.accesses.write().unwrap();
+            if let Some(count) = accesses.remove(&old_sku) {
+                accesses.insert(new_sku.clone(), count);
+            }
+        }
+
+        // the old SKU is removed as far as watches and sync clients can tell
+        let mut removed = self.removed.lock().await;
+        removed.insert(old_sku.clone(), now);
+        removed.remove(&new_sku);
+        drop(removed);
+        self.notify(&old_sku, None);
+        self.notify(&new_sku, Some(&item));
+        drop(shards);
+
+        // the rename is audited as the removal of the old SKU and the
+        // addition of the new one
+        let operation = "RenameSku";
+        self.audit
+            .record(now, operation, &old_sku, Some(before), None);
+        self.audit
+            .record(now, operation, &new_sku, None, Some(item.clone()));
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: "success".into(),
+            item: Some(item),
+            ..Default::default()
+        }))
+    }
+
+    async fn reserve(
+        &self,
+        request: Request<ReservationRequest>,
+    ) -> Result<Response<Availability>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        self.check_writable()?;
+        let availability = self.change_reservation(request.into_inner(), true).await?;
+        Ok(Response::new(availability))
+    }
+
+    async fn release(
+        &self,
+        request: Request<ReservationRequest>,
+    ) -> Result<Response<Availability>, Status> {
+        record_sku(&request, &request.get_ref().sku);
+        self.check_writable()?;
+        let availability = self.change_reservation(request.into_inner(), false).await?;
+        Ok(Response::new(availability))
+    }
+
+    async fn set_read_only(
+        &self,
+        request: Request<ReadOnlyRequest>,
+    ) -> Result<Response<InventoryChangeResponse>, Status> {
+        self.check_admin(&request)?;
+        let read_only = request.into_inner().read_only;
+
+        // requests already past their writable check may still complete, so
+        // the mode is only guaranteed for requests which arrive after this.
+        self.read_only.store(read_only, Ordering::SeqCst);
+        tracing::info!(read_only, "read-only maintenance mode changed");
+
+        Ok(Response::new(InventoryChangeResponse {
+            status: "success".into(),
+            ..Default::default()
+        }))
+    }
+
+    async fn list(&self, request: Request<ListRequest>) -> Result<Response<ListResponse>, Status> {
+        let list = request.into_inner();
+        let page_size = match list.page_size as usize {
+            0 => DEFAULT_PAGE_SIZE,
+            size => size.min(MAX_PAGE_SIZE),
+        };
+
+        // resume after the last SKU of the previous page, if there was one
+        let after = match list.page_token.as_str() {
+            "" => None,
+            token => match PageToken::decode(token) {
+                Some(token) => Some(token.after),
+                None => return Err(bad_request("page_token", InventoryError::BadPageToken)),
+            },
+        };
+
+        // the page is made of the lowest SKUs after the cursor across all the
+        // shards, one more than the page size is kept to learn whether there
+        // are any further pages.
+        let mut items = Vec::new();
+        for shard in self.inventory.iter() {
+            let map = shard.read().await;
+            items.extend(map.range(after.as_deref(), page_size + 1).await?);
+        }
+        items.sort_by(|a, b| item_sku(a).cmp(item_sku(b)));
+
+        let next_page_token = if items.len() > page_size {
+            items.truncate(page_size);
+            let after = items.last().map(item_sku).unwrap_or_default().to_string();
+            PageToken { after }.encode()
+        } else {
+            String::new()
+        };
+
+        Ok(Response::new(ListResponse {
+            items,
+            next_page_token,
+        }))
+    }
+
+    async fn sample(
+        &self,
+        request: Request<SampleRequest>,
+    ) -> Result<Response<SampleResponse>, Status> {
+        let sample = request.into_inner();
+        let weighting = sample.weighting();
+        let rng = match sample.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        // shards and the items in them are always visited in the same order,
+        // so a seeded sample of an unchanged inventory is reproducible.
+        let mut reservoir = Reservoir::new((sample.count as usize).min(MAX_SAMPLE_SIZE), rng);
+        for shard in self.inventory.iter() {
+            let map = shard.read().await;
+            map.scan(&mut |_, item| reservoir.offer(item, sample_weight(item, weighting)))
+                .await?;
+        }
+
+        Ok(Response::new(SampleResponse {
+            items: reservoir.into_items(),
+        }))
+    }
+
+    async fn get_metrics(
+        &self,
+        _: Request<MetricsRequest>,
+    ) -> Result<Response<MetricsResponse>, Status> {
+        let methods = self
+            .metrics
+            .snapshot()
+            .into_iter()
+            .map(|snapshot| MethodMetrics {
+                method: snapshot.method.into(),
+                requests: snapshot.requests,
+                errors: snapshot.errors,
+                mean_latency_micros: snapshot.mean_latency_micros,
+                max_latency_micros: snapshot.max_latency_micros,
+            })
+            .collect();
+
+        // each shard is only locked long enough to read its length
+        let mut items = 0;
+        for shard in self.inventory.iter() {
+            items += shard.read().await.len().await? as u64;
+        }
+
+        Ok(Response::new(MetricsResponse {
+            methods,
+            items,
+            active_watchers: self.metrics.active_watchers(),
+            watchers: self
+                .metrics
+                .watchers()
+                .into_iter()
+                .map(|watcher| Watcher {
+                    stream_id: watcher.stream_id,
+                    sku: watcher.sku,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn list_by_attribute(
+        &self,
+        request: Request<AttributeFilter>,
+    ) -> Result<Response<ListByAttributeResponse>, Status> {
+        let filter = request.into_inner();
+        if filter.key.is_empty() {
+            return Err(bad_request("key", InventoryError::BadAttribute));
+        }
+
+        let items = self
+            .filter_items(|item| item.attributes.get(&filter.key) == Some(&filter.value))
+            .await?;
+
+        Ok(Response::new(ListByAttributeResponse { items }))
+    }
+
+    async fn list_by_tag(
+        &self,
+        request: Request<TagFilter>,
+    ) -> Result<Response<ListByTagResponse>, Status> {
+        let filter = request.into_inner();
+        if filter.tag.is_empty() {
+            return Err(bad_request("tag", InventoryError::BadTag));
+        }
+
+        let items = self
+            .filter_items(|item| item.tags.contains(&filter.tag))
+            .await?;
+
+        Ok(Response::new(ListByTagResponse { items }))
+    }
+
+    async fn list_low_stock(
+        &self,
+        _request: Request<LowStockRequest>,
+    ) -> Result<Response<ListLowStockResponse>, Status> {
+        let items = self.filter_items(is_low_stock).await?;
+
+        Ok(Response::new(ListLowStockResponse { items }))
+    }
+
+    async fn list_by_weight_range(
+        &self,
+        request: Request<WeightRangeRequest>,
+    ) -> Result<Response<ListByWeightRangeResponse>, Status> {
+        let range = request.into_inner();
+        if range.min_grams.is_nan() || range.min_grams < 0.0 {
+            return Err(bad_request("min_grams", InventoryError::BadWeightRange));
+        }
+        let max_grams = range.max_grams.unwrap_or(f32::INFINITY);
+        if max_grams.is_nan() || max_grams < range.min_grams {
+            return Err(bad_request("max_grams", InventoryError::BadWeightRange));
+        }
+
+        let items = self
+            .filter_items(|item| is_in_weight_range(item, range.min_grams, max_grams))
+            .await?;
+
+        Ok(Response::new(ListByWeightRangeResponse { items }))
+    }
+
+    async fn search(
+        &self,
+        request: Request<SearchRequest>,
+    ) -> Result<Response<SearchResponse>, Status> {
+        // an empty query would match every item with information
+        let query = request.into_inner().query.to_lowercase();
+        if query.is_empty() {
+            return Err(bad_request("query", InventoryError::EmptyQuery));
+        }
+
+        let items = self
+            .filter_items(|item| matches_query(item, &query))
+            .await?;
+
+        Ok(Response::new(SearchResponse { items }))
+    }
+
+    async fn inventory_stats(
+        &self,
+        _: Request<InventoryStatsRequest>,
+    ) -> Result<Response<InventoryStatsResponse>, Status> {
+        // every shard is locked, so that the totals are of a single moment
+        let mut shards = Vec::with_capacity(self.inventory.len());
+        for shard in self.inventory.iter() {
+            shards.push(shard.read().await);
+        }
+
+        // values are summed in whole cents for each currency, in 128 bits so
+        // that the largest price times the largest quantity can be summed over
+        // billions of items, and only converted to a float once summed.
+        let mut items = 0u64;
+        let mut units = 0u64;
+        let mut values: BTreeMap<String, u128> = BTreeMap::new();
+        for map in &shards {
+            map.scan(&mut |_, item| {
+                items += 1;
+                if let Some(stock) = &item.stock {
+                    units += u64::from(stock.quantity);
+                    let value = to_cents(stock.price).max(0) as u128 * u128::from(stock.quantity);
+                    let cents = values.entry(stock_currency(stock).to_string()).or_default();
+                    *cents = cents.saturating_add(value);
+                }
+            })
+            .await?;
+        }
+
+        // values in a single currency are reported as they are, while values
+        // in several are exchanged into the base currency to be summed
+        let (currency, total_value) = match values.len() {
+            0 => (BASE_CURRENCY.to_string(), 0.0),
+            1 => {
+                let (currency, cents) = values.into_iter().next().unwrap();
+                (currency, cents as f64 / 100.0)
+            }
+            _ => {
+                let mut cents = 0.0;
+                for (currency, value) in values {
+                    match self.rates.rate(&currency) {
+                        Some(rate) => cents += value as f64 / f64::from(rate),
+                        None => return Err(InventoryError::MixedCurrencies.into()),
+                    }
+                }
+                (BASE_CURRENCY.to_string(), cents.round() / 100.0)
+            }
+        };
+
+        Ok(Response::new(InventoryStatsResponse {
+            items,
+            units,
+            total_value,
+            currency,
+        }))
+    }
+
+    async fn count(&self, _: Request<CountRequest>) -> Result<Response<CountResponse>, Status> {
+        // every shard is locked, so that the count is of a single moment
+        let mut shards = Vec::with_capacity(self.inventory.len());
+        for shard in self.inventory.iter() {
+            shards.push(shard.read().await);
+        }
+
+        let mut count = 0;
+        for map in &shards {
+            count += map.len().await? as u64;
+        }
+        Ok(Response::new(CountResponse { count }))
+    }
+
+    async fn get_server_info(
+        &self,
+        _: Request<ServerInfoRequest>,
+    ) -> Result<Response<ServerInfo>, Status> {
+        Ok(Response::new(ServerInfo {
+            version: VERSION.into(),
+            built_at: built_at(),
+            git_sha: GIT_SHA.unwrap_or_default().into(),
+            uptime_ms: self.started.elapsed().as_millis() as u64,
+        }))
+    }
+
+    async fn get_latency_summary(
+        &self,
+        _: Request<LatencySummaryRequest>,
+    ) -> Result<Response<LatencySummaryResponse>, Status> {
+        let methods = self
+            .metrics
+            .latency_summaries()
+            .into_iter()
+            .map(|summary| MethodLatency {
+                method: summary.method.into(),
+                requests: summary.requests,
+                p50_micros: summary.p50_micros,
+                p90_micros: summary.p90_micros,
+                p99_micros: summary.p99_micros,
+            })
+            .collect();
+
+        Ok(Response::new(LatencySummaryResponse {
+            methods,
+            window_seconds: LATENCY_WINDOW_SECS,
+        }))
+    }
+
+    type TailLogsStream = Pin<Box<dyn Stream<Item = Result<LogLine, Status>> + Send>>;
+
+    async fn tail_logs(
+        &self,
+        request: Request<TailLogsRequest>,
+    ) -> Result<Response<Self::TailLogsStream>, Status> {
+        self.check_admin(&request)?;
+        let backlog = request.into_inner().backlog as usize;
+
+        let stream = self.logs.tail(backlog).map(Ok);
+        Ok(Response::new(Box::pin(stream) as Self::TailLogsStream))
+    }
+
+    async fn get_audit_log(
+        &self,
+        request: Request<AuditLogRequest>,
+    ) -> Result<Response<AuditLogResponse>, Status> {
+        self.check_admin(&request)?;
+        let limit = request.into_inner().limit as usize;
+
+        Ok(Response::new(AuditLogResponse {
+            entries: self.audit.recent(limit),
+        }))
+    }
+
+    async fn get_access_stats(
+        &self,
+        request: Request<AccessStatsRequest>,
+    ) -> Result<Response<AccessStatsResponse>, Status> {
+        let limit = match request.into_inner().limit as usize {
+            0 => DEFAULT_ACCESS_STATS_LIMIT,
+            limit => limit.min(MAX_ACCESS_STATS_LIMIT),
+        };
+
+        // rank the counters before looking at the inventory, so that fetches
+        // aren't held up while shards are locked.
+        let mut ranked: Vec<(String, u64)> = self
+            .accesses
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(sku, accesses)| (sku.clone(), accesses.load(Ordering::Relaxed)))
+            .collect();
+        ranked.sort_by(|(a_sku, a), (b_sku, b)| b.cmp(a).then_with(|| a_sku.cmp(b_sku)));
+
+        // a fetch racing with the removal of its item can leave a counter
+        // behind for it, which isn't reported.
+        let mut items = Vec::with_capacity(limit.min(ranked.len()));
+        for (sku, accesses) in ranked {
+            if items.len() == limit {
+                break;
+            }
+            if self.shard(&sku).read().await.contains(&sku).await? {
+                items.push(ItemAccesses { sku, accesses });
+            }
+        }
+
+        Ok(Response::new(AccessStatsResponse { items }))
+    }
+
+    async fn check_consistency(
+        &self,
+        request: Request<ConsistencyRequest>,
+    ) -> Result<Response<ConsistencyReport>, Status> {
+        self.check_admin(&request)?;
+        let repair = request.into_inner().repair;
+        if repair {
+            self.check_writable()?;
+        }
+
+        // every shard is locked, followed by the structures kept alongside
+        // the items, so that nothing changes while they're compared. The
+        // items themselves are never repaired, only reported.
+        let mut shards = Vec::with_capacity(self.inventory.len());
+        for shard in self.inventory.iter() {
+            shards.push(shard.read().await);
+        }
+
+        // the quantity of each item's stock (if it has any) keyed by the SKU
+        // it's stored under, and the SKUs whose item has a different SKU.
+        let mut quantities = BTreeMap::new();
+        let mut mismatched = HashSet::new();
+        for map in &shards {
+            map.scan(&mut |sku, item| {
+                let quantity = item.stock.as_ref().map(|stock| stock.quantity);
+                quantities.insert(sku.to_string(), quantity);
+                if item_sku(item) != sku {
+                    mismatched.insert(sku.to_string());
+                }
+            })
+            .await?;
+        }
+        let item = |sku: &str| quantities.get(sku);
+        let mut removed = self.removed.lock().await;
+        let mut images = self.images.lock().await;
+        let mut reservations = self.reservations.lock().await;
+        let mut accesses = self.accesses.write().unwrap();
+
+        let mut issues = Vec::new();
+        let mut found = |sku: &str, description: &str, repaired: bool| {
+            tracing::warn!(sku, repaired, "inconsistency found: {}", description);
+            issues.push(ConsistencyIssue {
+                sku: sku.into(),
+                description: description.into(),
+                repaired,
+            });
+        };
+
+        for (sku, quantity) in &quantities {
+            if mismatched.contains(sku) {
+                found(sku, MISMATCHED_SKU_ISSUE, false);
+            }
+            if quantity.is_none() {
+                found(sku, NO_STOCK_ISSUE, false);
+            }
+            if removed.contains_key(sku) {
+                found(sku, STALE_TOMBSTONE_ISSUE, repair);
+            }
+        }
+
+        for sku in images.keys().filter(|sku| item(sku).is_none()) {
+            found(sku, ORPHAN_IMAGE_ISSUE, repair);
+        }
+
+        for (sku, reserved) in reservations.iter_mut() {
+            match item(sku) {
+                None => found(sku, ORPHAN_RESERVATION_ISSUE, repair),
+                Some(quantity) => {
+                    let quantity = quantity.unwrap_or_default();
+                    if *reserved > quantity {
+                        found(sku, OVER_RESERVED_ISSUE, repair);
+                        if repair {
+                            *reserved = quantity;
+                        }
+                    }
+                }
+            }
+        }
+
+        for sku in accesses.keys().filter(|sku| item(sku).is_none()) {
+            found(sku, ORPHAN_ACCESSES_ISSUE, repair);
+        }
+
+        if repair {
+            removed.retain(|sku, _| item(sku).is_none());
+            images.retain(|sku, _| item(sku).is_some());
+            reservations.retain(|sku, _| item(sku).is_some());
+            accesses.retain(|sku, _| item(sku).is_some());
+        }
+
+        issues.sort_by(|a, b| a.sku.cmp(&b.sku));
+        Ok(Response::new(ConsistencyReport { issues }))
+    }
+
+    async fn load_snapshot(
+        &self,
+        request: Request<LoadSnapshotRequest>,
+    ) -> Result<Response<LoadSnapshotResponse>, Status> {
+        self.check_admin(&request)?;
+        self.check_writable()?;
+        let items = request.into_inner().items;
+
+        // the new inventory is built and validated off to the side, so that
+        // the existing inventory is untouched if any item is invalid.
+        let now = now_millis();
+        let mut replacement = BTreeMap::new();
+        for (index, mut item) in items.into_iter().enumerate() {
+            let sku = validate_item(&item, self.max_quantity, self.field_limits)
+                .map_err(|err| failed_item(index, err))?;
+            if replacement.contains_key(&sku) {
+                let err = Status::from(InventoryError::DuplicateItem);
+                return Err(failed_item(index, err));
+            }
+            normalize_item(&mut item);
+            item.last_updated = now;
+            replacement.insert(sku, item);
+        }
+
+        // every shard is locked and the replacement is swapped in as one
+        // change, so that if the storage fails the previous items are left as
+        // they were.
+        let mut shards = self.lock_all_shards().await;
+        let mut previous = HashMap::new();
+        for (_, map) in shards.guards.iter() {
+            map.scan(&mut |sku, item| {
+                previous.insert(sku.to_string(), item.clone());
+            })
+            .await?;
+        }
+        let replaced = previous.len() as u32;
+        let loaded = replacement.len() as u32;
+        let mut changes: Vec<(String, Option<Item>)> = previous
+            .keys()
+            .filter(|sku| !replacement.contains_key(*sku))
+            .map(|sku| (sku.clone(), None))
+            .collect();
+
+        // items replacing ones with the same SKU carry on their versions
+        for (sku, mut item) in replacement {
+            item.version = next_version(previous.get(&sku));
+            changes.push((sku, Some(item)));
+        }
+        shards.commit(&changes).await?;
+
+        // nothing kept alongside the previous items carries over, and those
+        // which aren't in the snapshot are left tombstones
+        let mut removed = self.removed.lock().await;
+        let mut images = self.images.lock().await;
+        let mut reservations = self.reservations.lock().await;
+        let mut accesses = self.accesses.write().unwrap();
+        for (sku, item) in &changes {
+            match item {
+                Some(_) => removed.remove(sku),
+                None => removed.insert(sku.clone(), now),
+            };
+            self.notify(sku, item.as_ref());
+        }
+        images.clear();
+        reservations.clear();
+        accesses.clear();
+        drop((accesses, reservations, images, removed, shards));
+        for (sku, after) in changes {
+            let before = previous.remove(&sku);
+            self.audit.record(now, "LoadSnapshot", &sku, before, after);
+        }
+
+        tracing::info!(loaded, replaced, "the inventory was replaced by a snapshot");
+        Ok(Response::new(LoadSnapshotResponse { loaded, replaced }))
+    }
+
+    async fn group_by_price_bucket(
+        &self,
+        request: Request<PriceBucketsRequest>,
+    ) -> Result<Response<PriceBucketsResponse>, Status> {
+        let boundaries = request.into_inner().boundaries;
+        let ascending = boundaries.windows(2).all(|pair| pair[0] < pair[1]);
+        if !ascending || boundaries.iter().any(|boundary| !boundary.is_finite()) {
+            return Err(InventoryError::BadBuckets.into());
+        }
+
+        let mut buckets: Vec<PriceBucket> = (0..=boundaries.len())
+            .map(|index| PriceBucket {
+                min: index.checked_sub(1).map(|index| boundaries[index]),
+                max: boundaries.get(index).copied(),
+                ..Default::default()
+            })
+            .collect();
+
+        // a single pass over the inventory, each shard is only locked while
+        // its items are counted. An item belongs to the bucket after the last
+        // boundary it's at or above.
+        for shard in self.inventory.iter() {
+            let map = shard.read().await;
+            map.scan(&mut |_, item| {
+                if let Some(stock) = &item.stock {
+                    let index = boundaries.partition_point(|boundary| *boundary <= stock.price);
+                    let bucket = &mut buckets[index];
+                    bucket.count += 1;
+                    bucket.total_value += stock.price as f64 * stock.quantity as f64;
+                }
+            })
+            .await?;
+        }
+
+        Ok(Response::new(PriceBucketsResponse { buckets }))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Helper Functions
+// -----------------------------------------------------------------------------
+
+// retrieves the currency a client requested prices be converted into, if any.
+fn requested_currency<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(CURRENCY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|currency| currency.trim().to_uppercase())
+        .filter(|currency| !currency.is_empty())
+}
+
+// waits until a watch which last sent something at the given time is due a
+// heartbeat, which is never when heartbeats are disabled.
+async fn heartbeat_due(interval: Option<Duration>, last_sent: Instant) {
+    match interval {
+        Some(interval) => tokio::time::sleep_until(last_sent + interval).await,
+        None => std::future::pending().await,
+    }
+}
+
+// identifies a watch stream, so that it can be correlated with the streams
+// the client reconnects with. An id is generated when the client didn't
+// supply one.
+fn watch_stream_id<T>(request: &Request<T>) -> Result<String, InventoryError> {
+    match request.metadata().get(STREAM_ID_HEADER) {
+        Some(stream_id) => match stream_id.to_str() {
+            Ok(stream_id) if !stream_id.is_empty() && stream_id.len() <= MAX_STREAM_ID_LEN => {
+                Ok(stream_id.to_string())
+            }
+            _ => Err(InventoryError::BadStreamId),
+        },
+        None => Ok(format!("{:016x}", rand::random::<u64>())),
+    }
+}
+
+// echoes the id of a watch stream in the response metadata.
+fn with_stream_id<T>(mut response: Response<T>, stream_id: &str) -> Response<T> {
+    // the id is visible ASCII, as it was either generated or taken from a
+    // header value which could be converted to a string.
+    let stream_id = stream_id
+        .parse()
+        .expect("stream id is a valid header value");
+    response.metadata_mut().insert(STREAM_ID_HEADER, stream_id);
+    response
+}
+
+// validates a SKU provided by a client, which can't be empty.
+#[allow(clippy::result_large_err)]
+fn validate_sku(sku: &str) -> Result<(), Status> {
+    if sku.is_empty() {
+        return Err(bad_request("sku", InventoryError::EmptySku));
+    }
+
+    Ok(())
+}
+
+// validates a price provided by a client. $0.00 is disallowed and negatives
+// don't make sense, once rounded to the cent.
+#[allow(clippy::result_large_err)]
+fn validate_price(price: f32) -> Result<(), Status> {
+    if to_cents(price) <= 0 {
+        return Err(bad_request("price", InventoryError::BadPrice));
+    }
+
+    Ok(())
+}
+
+// validates an item which is to be added to the inventory with at most the
+// given quantity and within the field limits, returning its SKU.
+#[allow(clippy::result_large_err)]
+fn validate_item(item: &Item, max_quantity: u32, limits: FieldLimits) -> Result<String, Status> {
+    // validate SKU, verify that it's present and valid
+    let sku = match item.identifier.as_ref() {
+        Some(id) => {
+            validate_sku(&id.sku).map_err(|err| within("identifier", err))?;
+            validate_sku_len(&id.sku, limits).map_err(|err| within("identifier", err))?;
+            id.sku.to_owned()
+        }
+        None => return Err(bad_request("identifier", InventoryError::NoId)),
+    };
+
+    // validate stock, verify its present and its price and quantity are valid
+    let stock = match item.stock.as_ref() {
+        Some(stock) => stock,
+        None => return Err(bad_request("stock", InventoryError::NoStock)),
+    };
+    validate_price(stock.price).map_err(|err| within("stock", err))?;
+    if !stock.currency.is_empty() && !is_known_currency(&stock.currency) {
+        return Err(bad_request("stock.currency", InventoryError::BadCurrency));
+    }
+    if stock.quantity > max_quantity {
+        return Err(bad_request(
+            "stock.quantity",
+            InventoryError::BigInitialQuantity,
+        ));
+    }
+
+    if let Some(information) = &item.information {
+        validate_information(information, limits)?;
+    }
+    validate_attributes(&item.attributes)?;
+    validate_tags(&item.tags)?;
+    if let Some(physical) = &item.physical {
+        validate_physical(physical)?;
+    }
+
+    Ok(sku)
+}
+
+// validates that a SKU an item is to be given isn't longer than the limit.
+#[allow(clippy::result_large_err)]
+fn validate_sku_len(sku: &str, limits: FieldLimits) -> Result<(), Status> {
+    if sku.len() > limits.sku {
+        return Err(bad_request("sku", InventoryError::BigSku));
+    }
+
+    Ok(())
+}
+
+// validates that the name and description an item is to be given aren't
+// longer than the limits.
+#[allow(clippy::result_large_err)]
+fn validate_information(information: &ItemInformation, limits: FieldLimits) -> Result<(), Status> {
+    let longer =
+        |text: &Option<String>, limit| text.as_ref().is_some_and(|text| text.len() > limit);
+    if longer(&information.name, limits.name) {
+        return Err(bad_request("information.name", InventoryError::BigName));
+    }
+    if longer(&information.description, limits.description) {
+        return Err(bad_request(
+            "information.description",
+            InventoryError::BigDescription,
+        ));
+    }
+
+    Ok(())
+}
+
+// validates the attributes of an item, which are limited in number and size.
+#[allow(clippy::result_large_err)]
+fn validate_attributes(attributes: &HashMap<String, String>) -> Result<(), Status> {
+    if attributes.keys().any(String::is_empty) {
+        return Err(bad_request("attributes", InventoryError::BadAttribute));
+    }
+
+    let too_big = attributes.iter().any(|(key, value)| {
+        key.len() > MAX_ATTRIBUTE_KEY_LEN || value.len() > MAX_ATTRIBUTE_VALUE_LEN
+    });
+    if too_big || attributes.len() > MAX_ATTRIBUTES {
+        return Err(bad_request("attributes", InventoryError::BigAttributes));
+    }
+
+    Ok(())
+}
+
+// validates the tags of an item, which are limited in number and size.
+#[allow(clippy::result_large_err)]
+fn validate_tags(tags: &[String]) -> Result<(), Status> {
+    if tags.iter().any(String::is_empty) {
+        return Err(bad_request("tags", InventoryError::BadTag));
+    }
+
+    let too_big = tags.iter().any(|tag| tag.len() > MAX_TAG_LEN);
+    if too_big || tags.len() > MAX_TAGS {
+        return Err(bad_request("tags", InventoryError::BigTags));
+    }
+
+    Ok(())
+}
+
+// validates the weight and dimensions of an item, which can't be negative.
+#[allow(clippy::result_large_err)]
+fn validate_physical(physical: &ItemPhysical) -> Result<(), Status> {
+    let measures = [
+        physical.weight_grams,
+        physical.length_mm,
+        physical.width_mm,
+        physical.height_mm,
+    ];
+    if measures
+        .iter()
+        .any(|measure| measure.is_nan() || *measure < 0.0)
+    {
+        return Err(bad_request("physical", InventoryError::BadPhysical));
+    }
+
+    Ok(())
+}
+
+// validates the parts of a quantity change which don't depend on the item.
+#[allow(clippy::result_large_err)]
+fn validate_quantity_change(change: &QuantityChangeRequest) -> Result<(), Status> {
+    validate_sku(&change.sku)?;
+
+    // quantity changes with no actual change don't make sense, inform user
+    if change.change == 0 {
+        return Err(bad_request("change", InventoryError::EmptyQuantity));
+    }
+
+    Ok(())
+}
+
+// applies a quantity change to an item's stock, returning the resulting price
+// and quantity. The item is left unchanged if the change is rejected.
+fn change_quantity(item: &mut Item, change: i32) -> Result<(f32, u32), InventoryError> {
+    // retrieve the stock mutable so we can update the quantity. Added items
+    // always have stock, but items which came from elsewhere (e.g. a snapshot
+    // written by another version) might not, which is the item's fault rather
+    // than the server's.
+    let stock = match item.stock.borrow_mut() {
+        Some(stock) => stock,
+        None => return Err(InventoryError::NoStockToUpdate),
+    };
+
+    // validate and then handle the quantity change
+    stock.quantity = match change {
+        // handle negative numbers as stock reduction
+        change if change < 0 => {
+            if change.unsigned_abs() > stock.quantity {
+                return Err(InventoryError::InsufficientInventory);
+            }
+            stock.quantity - change.unsigned_abs()
+        }
+        // handle positive numbers as stock increases, which can't take the
+        // quantity past the largest one that can be stored
+        change => match stock.quantity.checked_add(change as u32) {
+            Some(quantity) => quantity,
+            None => return Err(InventoryError::BigQuantity),
+        },
+    };
+
+    Ok((stock.price, stock.quantity))
+}
+
+// validates the parts of a price change which don't depend on the item.
+#[allow(clippy::result_large_err)]
+fn validate_price_change(change: &PriceChangeRequest) -> Result<(), Status> {
+    validate_sku(&change.sku)?;
+    validate_price(change.price)
+}
+
+// normalizes an item a client sent which is to be stored. The fields the
+// server manages are cleared, as clients can't set them: the image is only
+// set by uploading one (see upload_image), while prices are only converted and
+// availability only reported as items are retrieved.
+fn normalize_item(item: &mut Item) {
+    item.image = None;
+    item.converted = None;
+    item.availability = None;
+    normalize_stock(item);
+}
+
+// normalizes the stock of an item which is to be stored: the price is rounded
+// to the cent, as fractions of a cent aren't kept, and the currency is kept in
+// upper case.
+fn normalize_stock(item: &mut Item) {
+    if let Some(stock) = item.stock.as_mut() {
+        stock.price = round_to_cent(stock.price);
+        stock.currency = stock_currency(stock).to_uppercase();
+    }
+}
+
+// the currency the price of an item's stock is in.
+fn stock_currency(stock: &ItemStock) -> &str {
+    match stock.currency.as_str() {
+        "" => BASE_CURRENCY,
+        currency => currency,
+    }
+}
+
+// the version of an item which is replacing the given one, versions count up
+// from 1 for as long as the SKU is in use.
+fn next_version(previous: Option<&Item>) -> u64 {
+    previous.map_or(1, |item| item.version + 1)
+}
+
+// applies a price change to an item's stock, returning the resulting price
+// and quantity, or None if the item is already at the price. The item is left
+// unchanged if the change is rejected, including when it's no longer at the
+// price the change expected.
+fn change_price(
+    item: &mut Item,
+    change: &PriceChangeRequest,
+) -> Result<Option<(f32, u32)>, InventoryError> {
+    let price = change.price;
+    // retrieve the stock mutable so we can update the price, see
+    // change_quantity for items without stock
+    let stock = match item.stock.borrow_mut() {
+        Some(stock) => stock,
+        None => return Err(InventoryError::NoStockToUpdate),
+    };
+
+    // prices are only ever changed within the currency the item is priced in
+    if let Some(currency) = &change.currency {
+        if !currency.eq_ignore_ascii_case(stock_currency(stock)) {
+            return Err(InventoryError::CurrencyMismatch);
+        }
+    }
+
+    // the change was based on a price which has since been changed, applying
+    // it would lose that change
+    if let Some(expected) = change.expected_price {
+        if to_cents(stock.price) != to_cents(expected) {
+            return Err(InventoryError::StalePrice);
+        }
+    }
+
+    // changing the price to the price that is already set changes nothing.
+    // Prices are compared in cents, as the floats the same price is sent as
+    // may differ.
+    if to_cents(stock.price) == to_cents(price) {
+        return Ok(None);
+    }
+
+    // update the item unit price
+    stock.price = round_to_cent(price);
+
+    Ok(Some((stock.price, stock.quantity)))
+}
+
+// validates the parts of a batch operation which don't depend on the item it's
+// for, returning the SKU of the item.
+#[allow(clippy::result_large_err)]
+fn validate_operation(
+    operation: &Operation,
+    max_quantity: u32,
+    limits: FieldLimits,
+) -> Result<String, Status> {
+    match &operation.operation {
+        Some(BatchOperation::Add(item)) => validate_item(item, max_quantity, limits),
+        Some(BatchOperation::Remove(id)) => {
+            validate_sku(&id.sku)?;
+            Ok(id.sku.clone())
+        }
+        Some(BatchOperation::UpdatePrice(change)) => {
+            validate_price_change(change)?;
+            Ok(change.sku.clone())
+        }
+        Some(BatchOperation::UpdateQuantity(change)) => {
+            validate_quantity_change(change)?;
+            Ok(change.sku.clone())
+        }
+        None => Err(InventoryError::NoOperation.into()),
+    }
+}
+
+// applies a validated batch operation to a staged copy of the item it's for,
+// which is None if the item isn't in the inventory.
+fn stage_operation(item: &mut Option<Item>, operation: Operation) -> Result<(), InventoryError> {
+    match (operation.operation, item.as_mut()) {
+        (Some(BatchOperation::Add(_)), Some(_)) => Err(InventoryError::DuplicateItem),
+        (Some(BatchOperation::Add(mut new)), None) => {
+            normalize_item(&mut new);
+            *item = Some(new);
+            Ok(())
+        }
+        (Some(BatchOperation::Remove(_)), _) => {
+            *item = None;
+            Ok(())
+        }
+        (Some(BatchOperation::UpdatePrice(change)), Some(item)) => {
+            change_price(item, &change).map(|_| ())
+        }
+        (Some(BatchOperation::UpdateQuantity(change)), Some(item)) => {
+            change_quantity(item, change.change).map(|_| ())
+        }
+        (_, None) => Err(InventoryError::NoItem),
+        (None, Some(_)) => Err(InventoryError::NoOperation),
+    }
+}
+
+// reports which operation of a batch failed in the metadata of its error.
+fn failed_operation(index: usize, err: Status) -> Status {
+    let mut metadata = err.metadata().clone();
+    metadata.insert(FAILED_OPERATION_HEADER, index.into());
+    let details = err.details().to_vec().into();
+    Status::with_details_and_metadata(err.code(), err.message(), details, metadata)
+}
+
+// reports which item of a snapshot failed to load in the metadata of its
+// error.
+fn failed_item(index: usize, err: Status) -> Status {
+    let mut metadata = err.metadata().clone();
+    metadata.insert(FAILED_ITEM_HEADER, index.into());
+    let details = err.details().to_vec().into();
+    Status::with_details_and_metadata(err.code(), err.message(), details, metadata)
+}
+
+// retrieves the SKU of an item, which is empty if it has no identifier.
+fn item_sku(item: &Item) -> &str {
+    item.identifier
+        .as_ref()
+        .map(|id| id.sku.as_str())
+        .unwrap_or_default()
+}
+
+// reports whether the item's quantity has fallen to its reorder threshold.
+// Items without stock or a threshold are never low on stock.
+fn is_low_stock(item: &Item) -> bool {
+    match &item.stock {
+        Some(ItemStock {
+            quantity,
+            reorder_threshold: Some(threshold),
+            ..
+        }) => quantity <= threshold,
+        _ => false,
+    }
+}
+
+// reports whether the item's name or description contains the query, which
+// must already be lowercase. Items without information never match.
+fn matches_query(item: &Item, query: &str) -> bool {
+    let information = match &item.information {
+        Some(information) => information,
+        None => return false,
+    };
+    [&information.name, &information.description]
+        .into_iter()
+        .flatten()
+        .any(|text| text.to_lowercase().contains(query))
+}
+
+// reports whether the item weighs between the given bounds, inclusive. Items
+// without physical attributes are never in range.
+fn is_in_weight_range(item: &Item, min_grams: f32, max_grams: f32) -> bool {
+    item.physical
+        .as_ref()
+        .is_some_and(|physical| (min_grams..=max_grams).contains(&physical.weight_grams))
+}
+
+// retrieves the shard responsible for the given SKU out of a set of shards.
+fn shard_for<'a>(shards: &'a [Shard], sku: &str) -> &'a Shard {
+    &shards[shard_index(shards.len(), sku)]
+}
+
+// determines the index of the shard responsible for the given SKU, out of the
+// given number of shards.
+fn shard_index(shards: usize, sku: &str) -> usize {
+    let mut hasher = DefaultHasher::new();
+    sku.hash(&mut hasher);
+    hasher.finish() as usize % shards
+}
+
+// compares two byte strings in time which only depends on their length, so
+// that secrets can't be guessed byte by byte from response timings.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// returns the current time in milliseconds since the unix epoch, which is the
+// precision used for all item timestamps.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::println as info;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Once};
+    use std::time::{Duration, Instant};
+
+    use anyhow::Error;
+    use futures::StreamExt;
+    use tokio::sync::broadcast;
+    use tonic::{
+        transport::{Channel, Server},
+        Request, Response, Status,
+    };
+
+    use uuid::Uuid;
+
+    use crate::{
+        client::InventoryApi,
+        currency::RateTable,
+        details,
+        error::InventoryError,
+        i18n::LocalizeLayer,
+        metrics::MetricsLayer,
+        pool::ClientPool,
+        pricing::{PriceEnding, SnapDirection},
+        routing::ReadOnlyHintLayer,
+        server,
+        server::{validate_price, validate_sku, FieldLimits, Image, StoreInventory},
+        storage::{MemoryStorage, SqliteStorage, Storage},
+        store::{
+            get_many_result::Outcome,
+            inventory_client::InventoryClient,
+            inventory_server::{Inventory, InventoryServer},
+            operation::Operation as BatchOperation,
+            price_change_result::Outcome as PriceOutcome,
+            sample_request::Weighting,
+            watch_event::Event as WatchedEvent,
+            AccessStatsRequest, ApplyBatchRequest, AttributeFilter, AuditLogRequest, Availability,
+            BatchGetRequest, BatchRemoveRequest, BulkPriceRequest, ChangedSinceRequest,
+            ConsistencyReport, ConsistencyRequest, ConvertedPrice, CountRequest, GetManyRequest,
+            GetRequest, Heartbeat, ImageChunk, InformationChangeRequest, InventoryStatsRequest,
+            Item, ItemIdentifier, ItemImage, ItemInformation, ItemPhysical, ItemStock,
+            LatencySummaryRequest, ListByAttributeResponse, ListRequest, LoadSnapshotRequest,
+            LogLine, LowStockRequest, MetricsRequest, MetricsResponse, Operation,
+            PriceBucketsRequest, PriceChangeRequest, QuantityChangeRequest, ReadOnlyRequest,
+            RemoveRequest, RenameSkuRequest, ReorderThresholdRequest, ReservationRequest,
+            SampleRequest, SearchRequest, SearchResponse, ServerInfoRequest, SetQuantityRequest,
+            SwapStockRequest, TagFilter, TailLogsRequest, WatchAllRequest, WatchEvent,
+            WatchRequest, WeightRangeRequest,
+        },
+    };
+
+    // -------------------------------------------------------------------------
+    // Test Setup
+    // -------------------------------------------------------------------------
+
+    static SERVER_INIT: Once = Once::new();
+    async fn get_client() -> InventoryClient<Channel> {
+        // the server gets its own runtime, as each test's runtime is dropped
+        // when that test completes.
+        SERVER_INIT.call_once(|| {
+            std::thread::spawn(|| {
+                let runtime = tokio::runtime::Runtime::new().unwrap();
+                runtime.block_on(async {
+                    let addr = "127.0.0.1:8080".parse().unwrap();
+                    let inventory = StoreInventory::default();
+                    Server::builder()
+                        .layer(MetricsLayer::new(inventory.metrics()))
+                        .layer(LocalizeLayer)
+                        .layer(ReadOnlyHintLayer)
+                        .add_service(InventoryServer::new(inventory))
+                        .serve(addr)
+                        .await
+                        .unwrap();
+                });
+            });
+        });
+
+        loop {
+            match InventoryClient::connect("http://127.0.0.1:8080").await {
+                Ok(client) => return client,
+                Err(_) => println!("waiting for server connection"),
+            };
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // Tests
+    // -------------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn inventory_management() -> Result<(), Error> {
+        let mut client = get_client().await;
+
+        // ---------------------------------------------------------------------
+        // test adding items
+        // ---------------------------------------------------------------------
+
+        info!("adding a single item to the inventory");
+        let sku = Uuid::new_v4().to_string();
+        let item_id = ItemIdentifier { sku: sku.clone() };
+        let item_stock = ItemStock {
+            price: 1.79,
+            quantity: 42,
+            ..Default::default()
+        };
+        let item = Item {
+            identifier: Some(item_id.to_owned()),
+            stock: Some(item_stock.to_owned()),
+            information: None,
+            ..Default::default()
+        };
+        let request = Request::new(item.clone());
+        let response = client.add(request).await?.into_inner();
+        assert_eq!(response.status, "success");
+
+        info!("verifying the item is returned as it was stored, priced in USD");
+        let added = response.item.unwrap();
+        assert_eq!(added.identifier, item.identifier);
+        let stock = ItemStock {
+            currency: "USD".into(),
+            ..item_stock.clone()
+        };
+        assert_eq!(added.stock, Some(stock));
+        assert_ne!(added.last_updated, 0);
+
+        info!("verifying that items with an blank SKU are rejected");
+        let bad_item = Item {
+            identifier: Some(ItemIdentifier { sku: "".into() }),
+            stock: Some(item_stock.clone()),
+            information: None,
+            ..Default::default()
+        };
+        let request = Request::new(bad_item);
+        let response = client.add(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::EmptySku)
+        );
+
+        info!("verifying that items with no ID are rejected");
+        let bad_item = Item {
+            identifier: None,
+            stock: Some(item_stock.clone()),
+            information: None,
+            ..Default::default()
+        };
+        let request = Request::new(bad_item);
+        let response = client.add(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::NoId)
+        );
+
+        info!("verifying that items marked as $0.00 in cost are rejected");
+        let bad_item = Item {
+            identifier: Some(ItemIdentifier { sku: "FREE".into() }),
+            stock: Some(ItemStock {
+                price: 0.00,
+                quantity: 42,
+                ..Default::default()
+            }),
+            information: None,
+            ..Default::default()
+        };
+        let request = Request::new(bad_item);
+        let response = client.add(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::BadPrice)
+        );
+
+        info!("verifying that items with no stock information are rejected");
+        let bad_item = Item {
+            identifier: Some(ItemIdentifier { sku: "NONE".into() }),
+            stock: None,
+            information: None,
+            ..Default::default()
+        };
+        let request = Request::new(bad_item);
+        let response = client.add(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::NoStock)
+        );
+
+        info!("verifying that duplicate items are rejected");
+        let request = Request::new(item.clone());
+        let response = client.add(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::DuplicateItem)
+        );
+
+        info!("adding a 1000 generic items to the inventory");
+        for i in 1000..2000 {
+            let item_id = ItemIdentifier {
+                sku: format!("SKU{}", i),
+            };
+            let item = Item {
+                identifier: Some(item_id),
+                stock: Some(item_stock.clone()),
+                information: None,
+                ..Default::default()
+            };
+
+            let request = Request::new(item);
+            let response = client.add(request).await?;
+            assert_eq!(response.into_inner().status, "success");
+        }
+
+        // ---------------------------------------------------------------------
+        // test updating an item's quantity
+        // ---------------------------------------------------------------------
+
+        info!("reducing item inventory by 35 units");
+        let request = Request::new(QuantityChangeRequest {
+            sku: sku.clone(),
+            change: -35,
+        });
+        let response = client.update_quantity(request).await?;
+        assert_eq!(response.into_inner().status, "success");
+
+        info!("verifying quantity change");
+        let request = get_request(sku.clone());
+        let quantity = item_quantity(&client.get(request).await?.into_inner());
+        assert_eq!(quantity, 7);
+
+        info!("increasing item inventory by 7 units");
+        let request = Request::new(QuantityChangeRequest {
+            sku: sku.clone(),
+            change: 7,
+        });
+        let response = client.update_quantity(request).await?;
+        assert_eq!(response.into_inner().status, "success");
+
+        info!("verifying quantity updates for no-SKU items are rejected");
+        let request = Request::new(QuantityChangeRequest {
+            sku: "".into(),
+            change: 1024,
+        });
+        let response = client.update_quantity(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::EmptySku)
+        );
+
+        info!("verifying quantity updates that introduce no change are rejected");
+        let request = Request::new(QuantityChangeRequest {
+            sku: sku.clone(),
+            change: 0,
+        });
+        let response = client.update_quantity(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::EmptyQuantity)
+        );
+
+        info!("verifying quantity updates for non-existent items are rejected");
+        let request = Request::new(QuantityChangeRequest {
+            sku: "DOESNTEXIST".into(),
+            change: 4098,
+        });
+        let response = client.update_quantity(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::NoItem)
+        );
+
+        info!("verifying quantity updates that would reduce below 0 are rejected");
+        let request = Request::new(QuantityChangeRequest {
+            sku: sku.clone(),
+            change: -15,
+        });
+        let response = client.update_quantity(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::InsufficientInventory)
+        );
+
+        info!("verifying current item quantity");
+        let request = get_request(sku.clone());
+        let quantity = item_quantity(&client.get(request).await?.into_inner());
+        assert_eq!(quantity, 14);
+
+        // ---------------------------------------------------------------------
+        // test updating an item's price
+        // ---------------------------------------------------------------------
+
+        info!("increasing the price of an item to $2.49");
+        let request = Request::new(PriceChangeRequest {
+            sku: item_id.sku.clone(),
+            price: 2.49,
+            ..Default::default()
+        });
+        let response = client.update_price(request).await?;
+        assert_eq!(response.into_inner().status, "success");
+
+        info!("verifying price updates for items with no SKU are rejected");
+        let request = Request::new(PriceChangeRequest {
+            sku: "".into(),
+            price: 9.99,
+            ..Default::default()
+        });
+        let response = client.update_price(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::EmptySku)
+        );
+
+        info!("verifying price updates to $0.00 are rejected");
+        let request = Request::new(PriceChangeRequest {
+            sku: sku.clone(),
+            price: 0.00,
+            ..Default::default()
+        });
+        let response = client.update_price(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::BadPrice)
+        );
+
+        info!("verifying price updates to a negative value are rejected");
+        let request = Request::new(PriceChangeRequest {
+            sku: sku.clone(),
+            price: -8096.64,
+            ..Default::default()
+        });
+        let response = client.update_price(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::BadPrice)
+        );
+
+        info!("verifying price updates to a non-existent item are rejected");
+        let request = Request::new(PriceChangeRequest {
+            sku: "DOESNTEXIST".into(),
+            price: 299.99,
+            ..Default::default()
+        });
+        let response = client.update_price(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::NoItem)
+        );
+
+        info!("verifying price updates to the price already set change nothing");
+        let request = Request::new(PriceChangeRequest {
+            sku: sku.clone(),
+            price: 2.49,
+            ..Default::default()
+        });
+        let response = client.update_price(request).await?.into_inner();
+        assert_eq!(response.status, "success: no change");
+        assert_eq!(response.price, 2.49);
+
+        info!("verifying current item price");
+        let request = get_request(sku.clone());
+        let price = item_price(&client.get(request).await?.into_inner());
+        assert_eq!(price, 2.49);
+
+        // ---------------------------------------------------------------------
+        // test retrieving items
+        // ---------------------------------------------------------------------
+
+        info!("verifying that retrievals of items with no SKU are rejected");
+        let request = get_request("");
+        let response = client.get(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::EmptySku)
+        );
+
+        info!("verifying that retrievals of items which don't exist are rejected");
+        let request = get_request("DOESNTEXIST");
+        let response = client.get(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::NoItem)
+        );
+
+        // ---------------------------------------------------------------------
+        // test watching items
+        // ---------------------------------------------------------------------
+
+        info!("watching an item, and changing its quantity and price");
+        let request = watch_request(item_id.sku.clone());
+        let mut stream = client.watch(request).await?.into_inner();
+        let timeout = Duration::from_secs(1);
+        let request = Request::new(QuantityChangeRequest {
+            sku: sku.clone(),
+            change: 1,
+        });
+        client.update_quantity(request).await?;
+        let request = Request::new(PriceChangeRequest {
+            sku: sku.clone(),
+            price: 2.99,
+            ..Default::default()
+        });
+        client.update_price(request).await?;
+
+        info!("verifying the watch yields the changed item after each change");
+        for (quantity, price) in [(15, 2.49), (15, 2.99)] {
+            let changed = tokio::time::timeout(timeout, stream.next())
+                .await?
+                .unwrap()?;
+            let changed = watched_item(changed);
+            assert_eq!(
+                (item_quantity(&changed), item_price(&changed)),
+                (quantity, price)
+            );
+        }
+
+        info!("verifying a watch whose client went away doesn't disturb the others");
+        let request = watch_request(item_id.sku.clone());
+        let abandoned = client.watch(request).await?.into_inner();
+        drop(abandoned);
+        let request = Request::new(QuantityChangeRequest {
+            sku: sku.clone(),
+            change: -1,
+        });
+        client.update_quantity(request).await?;
+        let changed = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()?;
+        assert_eq!(item_quantity(&watched_item(changed)), 14);
+
+        // ---------------------------------------------------------------------
+        // test removing items
+        // ---------------------------------------------------------------------
+
+        info!("removing all added items");
+        let request = remove_request(item_id.sku.clone());
+        let response = client.remove(request).await?.into_inner();
+        assert_eq!(response.status, "success: item was removed");
+        assert!(response.existed);
+        for i in 1000..2000 {
+            let request = remove_request(format!("SKU{}", i));
+            let response = client.remove(request).await?;
+            assert_eq!(response.into_inner().status, "success: item was removed");
+        }
+
+        info!("verifying removing the watched item ends its watch as not found");
+        let status = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        assert!(tokio::time::timeout(timeout, stream.next())
+            .await?
+            .is_none());
+
+        info!("verifying removing items with no SKU is rejected");
+        let request = remove_request("");
+        let response = client.remove(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::EmptySku)
+        );
+
+        info!("verifying removing non-existent items succeeds, but is reported");
+        let request = remove_request(item_id.sku.clone());
+        let response = client.remove(request).await?.into_inner();
+        assert_eq!(response.status, "success: item didn't exist");
+        assert!(!response.existed);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_changed_since() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let cutoff = 1_000_000;
+
+        info!("seeding items updated just before, at, and just after the cutoff");
+        {
+            for (sku, last_updated) in [
+                ("BEFORE", cutoff - 1),
+                ("AT", cutoff),
+                ("AFTER", cutoff + 1),
+            ] {
+                let item = Item {
+                    identifier: Some(ItemIdentifier { sku: sku.into() }),
+                    stock: Some(ItemStock {
+                        price: 1.79,
+                        quantity: 42,
+                        ..Default::default()
+                    }),
+                    last_updated,
+                    ..Default::default()
+                };
+                let mut map = inventory.shard(sku).write().await;
+                map.insert(sku.into(), item).await.unwrap();
+            }
+
+            let mut removed = inventory.removed.lock().await;
+            removed.insert("REMOVED_BEFORE".into(), cutoff - 1);
+            removed.insert("REMOVED_AFTER".into(), cutoff + 1);
+        }
+
+        info!("verifying only changes strictly newer than the cutoff are returned");
+        let request = Request::new(ChangedSinceRequest { since: cutoff });
+        let changes = inventory.list_changed_since(request).await?.into_inner();
+        let skus: Vec<String> = changes
+            .items
+            .iter()
+            .map(|item| item.identifier.as_ref().unwrap().sku.clone())
+            .collect();
+        assert_eq!(skus, vec!["AFTER".to_string()]);
+        assert_eq!(changes.removed_skus, vec!["REMOVED_AFTER".to_string()]);
+
+        info!("verifying that adding, updating and removing items bumps their timestamps");
+        let request = remove_request("BEFORE");
+        inventory.remove(request).await?;
+        let request = Request::new(PriceChangeRequest {
+            sku: "AT".into(),
+            price: 2.49,
+            ..Default::default()
+        });
+        inventory.update_price(request).await?;
+        let request = Request::new(ChangedSinceRequest { since: cutoff });
+        let changes = inventory.list_changed_since(request).await?.into_inner();
+        assert_eq!(changes.items.len(), 2);
+        assert!(changes.removed_skus.contains(&"BEFORE".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tombstone_retention() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_tombstone_retention(Duration::from_secs(60));
+        let now = server::now_millis();
+        let changed_since =
+            |since: u64| inventory.list_changed_since(Request::new(ChangedSinceRequest { since }));
+
+        info!("seeding tombstones inside and outside of the retention");
+        {
+            let mut removed = inventory.removed.lock().await;
+            removed.insert("OLD".into(), now - 120_000);
+            removed.insert("RECENT".into(), now - 1_000);
+        }
+
+        info!("verifying the sweep prunes old tombstones only");
+        inventory.drain_expired().await;
+        let removed = inventory.removed.lock().await.clone();
+        assert_eq!(removed.keys().collect::<Vec<_>>(), ["RECENT"]);
+
+        info!("verifying syncs from before a pruned removal are rejected");
+        let status = changed_since(now - 180_000).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::StaleSync)
+        );
+        let changes = changed_since(now - 60_000).await?.into_inner();
+        assert_eq!(changes.removed_skus, ["RECENT"]);
+        let changes = changed_since(0).await?.into_inner();
+        assert_eq!(changes.removed_skus, ["RECENT"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_pages() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let add = |sku: String| {
+            Request::new(Item {
+                identifier: Some(ItemIdentifier { sku }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        };
+
+        info!("adding items to list");
+        for i in 0..20 {
+            inventory.add(add(format!("SKU{:02}", i))).await?;
+        }
+
+        info!("listing the first page");
+        let request = Request::new(ListRequest {
+            page_size: 5,
+            page_token: String::new(),
+        });
+        let page = inventory.list(request).await?.into_inner();
+        let mut listed: Vec<String> = page.items.iter().map(item_sku).collect();
+        assert_eq!(listed, ["SKU00", "SKU01", "SKU02", "SKU03", "SKU04"]);
+
+        info!("changing the inventory behind and ahead of the listing");
+        for sku in ["SKU02", "SKU07"] {
+            let request = remove_request(sku);
+            inventory.remove(request).await?;
+        }
+        for sku in ["SKU03A", "SKU10A"] {
+            inventory.add(add(sku.into())).await?;
+        }
+
+        info!("listing the remaining pages");
+        let mut page_token = page.next_page_token;
+        while !page_token.is_empty() {
+            let request = Request::new(ListRequest {
+                page_size: 5,
+                page_token,
+            });
+            let page = inventory.list(request).await?.into_inner();
+            assert!(page.items.len() <= 5);
+            listed.extend(page.items.iter().map(item_sku));
+            page_token = page.next_page_token;
+        }
+
+        info!("verifying items present throughout were listed exactly once, in order");
+        let mut expected: Vec<String> = (0..20)
+            .filter(|i| *i != 7)
+            .map(|i| format!("SKU{:02}", i))
+            .collect();
+        expected.push("SKU10A".into());
+        expected.sort();
+        assert_eq!(listed, expected);
+
+        info!("verifying invalid page tokens are rejected");
+        let request = Request::new(ListRequest {
+            page_size: 5,
+            page_token: "SKU05".into(),
+        });
+        let response = inventory.list(request).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::BadPageToken)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sample() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+
+        info!("adding items to sample, one of them out of stock");
+        for (sku, quantity) in [("EMPTY", 0), ("LIGHT", 1), ("HEAVY", 99)] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        for i in 0..20 {
+            let item = Item {
+                identifier: Some(ItemIdentifier {
+                    sku: format!("SKU{}", i),
+                }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 1,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        let sample = |count, weighting: Weighting, seed| {
+            Request::new(SampleRequest {
+                count,
+                weighting: weighting.into(),
+                seed,
+            })
+        };
+
+        info!("verifying samples have the requested number of distinct items");
+        let request = sample(5, Weighting::Uniform, None);
+        let mut skus: Vec<String> = inventory
+            .sample(request)
+            .await?
+            .into_inner()
+            .items
+            .iter()
+            .map(item_sku)
+            .collect();
+        skus.sort();
+        skus.dedup();
+        assert_eq!(skus.len(), 5);
+        let request = sample(100, Weighting::Uniform, None);
+        assert_eq!(
+            inventory.sample(request).await?.into_inner().items.len(),
+            23
+        );
+        let request = sample(0, Weighting::Uniform, None);
+        assert!(inventory
+            .sample(request)
+            .await?
+            .into_inner()
+            .items
+            .is_empty());
+
+        info!("verifying seeded samples are reproducible");
+        for weighting in [Weighting::Uniform, Weighting::Quantity, Weighting::Value] {
+            let first = inventory.sample(sample(5, weighting, Some(42))).await?;
+            let second = inventory.sample(sample(5, weighting, Some(42))).await?;
+            assert_eq!(first.into_inner(), second.into_inner());
+        }
+
+        info!("verifying weighted samples favor heavier items and skip unweighted ones");
+        let mut heavy_first = 0;
+        for seed in 0..100 {
+            let request = sample(23, Weighting::Quantity, Some(seed));
+            let items = inventory.sample(request).await?.into_inner().items;
+            assert_eq!(items.len(), 22);
+            assert!(items.iter().all(|item| item_sku(item) != "EMPTY"));
+            if item_sku(&items[0]) == "HEAVY" {
+                heavy_first += 1;
+            }
+        }
+        assert!(
+            heavy_first > 50,
+            "heavy item was first {} times",
+            heavy_first
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn batch_add() -> Result<(), Error> {
+        let mut client = get_client().await;
+        let item = |sku: String, price: f32, description: String| Item {
+            identifier: Some(ItemIdentifier { sku }),
+            stock: Some(ItemStock {
+                price,
+                quantity: 42,
+                ..Default::default()
+            }),
+            information: Some(ItemInformation {
+                name: None,
+                description: Some(description),
+            }),
+            ..Default::default()
+        };
+
+        info!("adding a batch of items, some of them invalid");
+        let batch = Uuid::new_v4().to_string();
+        let items: Vec<Item> = (0..10)
+            .map(|i| {
+                let price = if i % 5 == 0 { 0.0 } else { 1.79 };
+                item(format!("{}-{}", batch, i), price, String::new())
+            })
+            .collect();
+        let response = client.batch_add(tokio_stream::iter(items)).await?;
+        assert_eq!(response.into_inner().status, "success: 8 added, 2 failed");
+        let request = get_request(format!("{}-1", batch));
+        client.get(request).await?;
+
+        info!("verifying batches exceeding the budget are rejected before they're fully sent");
+        const ITEMS: usize = 400;
+        let sent = Arc::new(AtomicUsize::new(0));
+        let batch = Uuid::new_v4().to_string();
+        let items = {
+            let (sent, batch) = (sent.clone(), batch.clone());
+            futures::stream::iter(0..ITEMS).map(move |i| {
+                sent.fetch_add(1, Ordering::SeqCst);
+                item(format!("{}-{}", batch, i), 1.79, "x".repeat(100_000))
+            })
+        };
+        let status = client.batch_add(items).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BigBatch)
+        );
+        let sent = sent.load(Ordering::SeqCst);
+        assert!(sent < ITEMS / 2, "{} of {} items were sent", sent, ITEMS);
+
+        info!("verifying a rejected batch adds nothing");
+        let request = get_request(format!("{}-0", batch));
+        let response = client.get(request).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::NoItem)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn metrics() -> Result<(), Error> {
+        let mut client = get_client().await;
+        let get_metrics = |metrics: &MetricsResponse| {
+            let get = metrics
+                .methods
+                .iter()
+                .find(|method| method.method == "/store.Inventory/Get");
+            get.cloned().unwrap()
+        };
+
+        info!("retrieving a baseline of the metrics");
+        let request = Request::new(MetricsRequest {});
+        let before = client.get_metrics(request).await?.into_inner();
+
+        info!("adding and watching an item, and making some failing requests");
+        let sku = Uuid::new_v4().to_string();
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: sku.clone() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        client.add(Request::new(item)).await?;
+        client.get(get_request(sku.clone())).await?;
+        for _ in 0..3 {
+            let request = get_request("DOESNTEXIST");
+            client.get(request).await.unwrap_err();
+        }
+        let request = watch_request(sku.clone());
+        let _stream = client.watch(request).await?;
+
+        info!("verifying the requests, items and watchers were counted");
+        let request = Request::new(MetricsRequest {});
+        let after = client.get_metrics(request).await?.into_inner();
+        let (get_before, get_after) = (get_metrics(&before), get_metrics(&after));
+        assert!(get_after.requests >= get_before.requests + 4);
+        assert!(get_after.errors >= get_before.errors + 3);
+        assert!(get_after.max_latency_micros >= get_after.mean_latency_micros);
+        assert!(after.items >= 1);
+        assert!(after.active_watchers >= 1);
+
+        info!("verifying latency percentiles were recorded by the same layer");
+        let request = Request::new(LatencySummaryRequest {});
+        let summary = client.get_latency_summary(request).await?.into_inner();
+        assert_eq!(summary.window_seconds, 60);
+        let get = summary
+            .methods
+            .iter()
+            .find(|method| method.method == "/store.Inventory/Get")
+            .unwrap();
+        assert!(get.requests >= 4);
+        assert!(get.p50_micros <= get.p90_micros && get.p90_micros <= get.p99_micros);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn localized_errors() -> Result<(), Error> {
+        let mut client = get_client().await;
+
+        info!("verifying errors are translated when a supported language is requested");
+        let mut request = get_request("DOESNTEXIST");
+        request
+            .metadata_mut()
+            .insert("accept-language", "fr-CA, es;q=0.9, en;q=0.8".parse()?);
+        let status = client.get(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        assert_eq!(status.message(), "no se encontró el artículo solicitado");
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+        assert_eq!(status.metadata().get("x-error-code").unwrap(), "NO_ITEM");
+
+        info!("verifying errors default to English for unsupported languages");
+        let mut request = get_request("DOESNTEXIST");
+        request
+            .metadata_mut()
+            .insert("accept-language", "fr".parse()?);
+        let status = client.get(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn item_images() -> Result<(), Error> {
+        let mut client = get_client().await;
+
+        info!("adding an item to attach an image to");
+        let sku = Uuid::new_v4().to_string();
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: sku.clone() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        client.add(Request::new(item)).await?;
+
+        info!("uploading an image for the item in multiple chunks");
+        let image: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+        let chunks = image_chunks(&sku, "image/png", &image, 30_000);
+        let response = client.upload_image(tokio_stream::iter(chunks)).await?;
+        assert_eq!(response.into_inner().status, "success");
+
+        info!("verifying the item references the image");
+        let request = get_request(sku.clone());
+        let item = client.get(request).await?.into_inner();
+        let reference = item.image.unwrap();
+        assert_eq!(reference.content_type, "image/png");
+        assert_eq!(reference.size, image.len() as u64);
+
+        info!("downloading the image and verifying it's intact");
+        let request = Request::new(ItemIdentifier { sku: sku.clone() });
+        let mut stream = client.get_image(request).await?.into_inner();
+        let first = stream.next().await.unwrap()?;
+        assert_eq!(first.sku, sku);
+        assert_eq!(first.content_type, "image/png");
+        let mut downloaded = first.data;
+        while let Some(chunk) = stream.next().await {
+            downloaded.extend(chunk?.data);
+        }
+        assert_eq!(downloaded, image);
+
+        info!("verifying images with unsupported content types are rejected");
+        let chunks = image_chunks(&sku, "text/html", &image, 30_000);
+        let response = client.upload_image(tokio_stream::iter(chunks)).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::BadContentType)
+        );
+
+        info!("verifying images larger than the maximum size are rejected");
+        let oversized = vec![0; server::MAX_IMAGE_SIZE + 1];
+        let chunks = image_chunks(&sku, "image/png", &oversized, 30_000);
+        let response = client.upload_image(tokio_stream::iter(chunks)).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::BigImage)
+        );
+
+        info!("verifying images for non-existent items are rejected");
+        let chunks = image_chunks("DOESNTEXIST", "image/png", &image, 30_000);
+        let response = client.upload_image(tokio_stream::iter(chunks)).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::NoItem)
+        );
+
+        info!("verifying the image is removed along with the item");
+        let request = remove_request(sku.clone());
+        client.remove(request).await?;
+        let request = Request::new(ItemIdentifier { sku: sku.clone() });
+        let response = client.get_image(request).await;
+        assert_eq!(
+            InventoryError::from_status(&response.err().unwrap()),
+            Some(InventoryError::NoImage)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn server_managed_fields() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_admin_token("secret");
+        let item = |sku: &str| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            image: Some(ItemImage {
+                content_type: "image/png".into(),
+                size: 1024,
+            }),
+            converted: Some(ConvertedPrice {
+                currency: "EUR".into(),
+                price: 0.89,
+            }),
+            availability: Some(Availability {
+                total: 42,
+                reserved: 2,
+                available: 40,
+            }),
+            ..Default::default()
+        };
+        let get = |sku: &str| inventory.get(get_request(sku));
+        let managed = |item: Item| (item.image, item.converted, item.availability);
+
+        info!("verifying fields the server manages are ignored when adding items");
+        let added = inventory.add(Request::new(item("ADDED"))).await?;
+        assert_eq!(
+            managed(added.into_inner().item.unwrap()),
+            (None, None, None)
+        );
+        inventory.upsert(Request::new(item("UPSERTED"))).await?;
+        let request = Request::new(ApplyBatchRequest {
+            operations: vec![Operation {
+                operation: Some(BatchOperation::Add(item("BATCHED"))),
+            }],
+        });
+        inventory.apply_batch(request).await?;
+        for sku in ["ADDED", "UPSERTED", "BATCHED"] {
+            let item = get(sku).await?.into_inner();
+            assert_eq!(managed(item), (None, None, None), "{}", sku);
+        }
+        let validated = inventory.validate(Request::new(item("VALIDATED"))).await?;
+        assert_eq!(
+            managed(validated.into_inner().item.unwrap()),
+            (None, None, None)
+        );
+
+        info!("verifying they're ignored in snapshots too");
+        let mut request = Request::new(LoadSnapshotRequest {
+            items: vec![item("LOADED")],
+        });
+        let authorization = "Bearer secret".parse().unwrap();
+        request
+            .metadata_mut()
+            .insert("authorization", authorization);
+        inventory.load_snapshot(request).await?;
+        let loaded = get("LOADED").await?.into_inner();
+        assert_eq!(managed(loaded), (None, None, None));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_many() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+
+        info!("adding items to retrieve");
+        for sku in ["FIRST", "SECOND"] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+
+        info!("verifying each SKU is reported in request order with its outcome");
+        let skus = ["SECOND", "DOESNTEXIST", "", "FIRST"];
+        let request = Request::new(GetManyRequest {
+            skus: skus.iter().map(|sku| sku.to_string()).collect(),
+        });
+        let results = inventory.get_many(request).await?.into_inner().results;
+        let outcomes: Vec<(&str, Outcome)> = results
+            .iter()
+            .map(|result| (result.sku.as_str(), result.outcome()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("SECOND", Outcome::Found),
+                ("DOESNTEXIST", Outcome::NotFound),
+                ("", Outcome::Invalid),
+                ("FIRST", Outcome::Found),
+            ]
+        );
+
+        info!("verifying only found SKUs carry their item");
+        let found: Vec<Option<String>> = results
+            .iter()
+            .map(|result| {
+                let item = result.item.as_ref()?;
+                Some(item.identifier.as_ref()?.sku.clone())
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![Some("SECOND".into()), None, None, Some("FIRST".into())]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn currency_conversion() -> Result<(), Error> {
+        let rates = RateTable::new([("EUR", 0.5), ("JPY", 150.0)]);
+        let inventory = StoreInventory::default().with_rates(rates);
+
+        info!("adding an item priced in the base currency");
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "PRICED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 2.49,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+
+        info!("verifying prices aren't converted unless requested");
+        let request = get_request("PRICED");
+        let item = inventory.get(request).await?.into_inner();
+        assert_eq!(item.converted, None);
+
+        info!("verifying prices are converted and rounded to the requested currency");
+        for (currency, converted) in [("EUR", 1.25), ("jpy", 373.5), ("USD", 2.49)] {
+            let mut request = get_request("PRICED");
+            request
+                .metadata_mut()
+                .insert("x-currency", currency.parse()?);
+            let item = inventory.get(request).await?.into_inner();
+            let expected = ConvertedPrice {
+                currency: currency.to_uppercase(),
+                price: converted,
+            };
+            assert_eq!(item.converted, Some(expected));
+            assert_eq!(item_price(&item), 2.49);
+        }
+
+        info!("verifying conversion doesn't change the stored price");
+        let request = get_request("PRICED");
+        let item = inventory.get(request).await?.into_inner();
+        assert_eq!(item_price(&item), 2.49);
+        assert_eq!(item.converted, None);
+
+        info!("verifying converted prices snap to a price ending when configured");
+        let rates = RateTable::new([("EUR", 0.5), ("JPY", 150.0)]);
+        let ending = PriceEnding::new(99, SnapDirection::Down).unwrap();
+        let snapping = StoreInventory::default()
+            .with_rates(rates)
+            .with_price_ending(ending);
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "PRICED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 8.74,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        snapping.add(Request::new(item)).await?;
+        for (currency, converted) in [("EUR", 3.99), ("JPY", 1310.99), ("USD", 8.74)] {
+            let mut request = get_request("PRICED");
+            request
+                .metadata_mut()
+                .insert("x-currency", currency.parse()?);
+            let item = snapping.get(request).await?.into_inner();
+            let price = item.converted.as_ref().map(|converted| converted.price);
+            assert_eq!(price, Some(converted), "{}", currency);
+            assert_eq!(item_price(&item), 8.74);
+        }
+
+        info!("verifying currencies without an exchange rate are rejected");
+        let mut request = get_request("PRICED");
+        request.metadata_mut().insert("x-currency", "GBP".parse()?);
+        let response = inventory.get(request).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::UnknownCurrency)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reservations_in_get() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_coalesced_gets();
+
+        info!("adding items, one of them with outstanding reservations");
+        for sku in ["RESERVED", "UNRESERVED"] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 10,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        inventory
+            .reservations
+            .lock()
+            .await
+            .insert("RESERVED".into(), 4);
+
+        info!("verifying availability isn't reported unless requested");
+        let item = inventory.get(get_request("RESERVED")).await?.into_inner();
+        assert_eq!(item.availability, None);
+        assert_eq!(item_quantity(&item), 10);
+
+        info!("verifying availability accounts for reservations when requested");
+        let availability = |sku: &str| {
+            Request::new(GetRequest {
+                sku: sku.into(),
+                include_reservations: true,
+            })
+        };
+        for (sku, reserved, available) in [("RESERVED", 4, 6), ("UNRESERVED", 0, 10)] {
+            let item = inventory.get(availability(sku)).await?.into_inner();
+            let expected = Availability {
+                total: 10,
+                reserved,
+                available,
+            };
+            assert_eq!(item.availability, Some(expected));
+        }
+
+        info!("verifying stock can only be reduced down to the reserved quantity");
+        let change = |change| {
+            Request::new(QuantityChangeRequest {
+                sku: "RESERVED".into(),
+                change,
+            })
+        };
+        let status = inventory.update_quantity(change(-8)).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::ReservedStock)
+        );
+        inventory.update_quantity(change(-6)).await?;
+        let item = inventory.get(availability("RESERVED")).await?.into_inner();
+        let expected = Availability {
+            total: 4,
+            reserved: 4,
+            available: 0,
+        };
+        assert_eq!(item.availability, Some(expected));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn apply_batch() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = |sku: &str| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let operation = |operation| Operation {
+            operation: Some(operation),
+        };
+
+        info!("adding items for the batches to change");
+        for sku in ["EXISTING", "DOOMED"] {
+            inventory.add(Request::new(item(sku))).await?;
+        }
+        let snapshot = inventory.list(Request::new(ListRequest::default())).await?;
+
+        info!("verifying a batch with a failing operation changes nothing");
+        let request = Request::new(ApplyBatchRequest {
+            operations: vec![
+                operation(BatchOperation::Add(item("NEW"))),
+                operation(BatchOperation::UpdatePrice(PriceChangeRequest {
+                    sku: "EXISTING".into(),
+                    price: 2.49,
+                    ..Default::default()
+                })),
+                operation(BatchOperation::UpdateQuantity(QuantityChangeRequest {
+                    sku: "EXISTING".into(),
+                    change: -1000,
+                })),
+                operation(BatchOperation::Remove(ItemIdentifier {
+                    sku: "DOOMED".into(),
+                })),
+            ],
+        });
+        let status = inventory.apply_batch(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::InsufficientInventory)
+        );
+        assert_eq!(status.metadata().get("x-failed-operation").unwrap(), "2");
+        let after = inventory.list(Request::new(ListRequest::default())).await?;
+        assert_eq!(after.into_inner(), snapshot.into_inner());
+
+        info!("verifying invalid operations are rejected before anything is applied");
+        let request = Request::new(ApplyBatchRequest {
+            operations: vec![
+                operation(BatchOperation::Add(item("NEW"))),
+                Operation { operation: None },
+            ],
+        });
+        let status = inventory.apply_batch(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoOperation)
+        );
+        assert_eq!(status.metadata().get("x-failed-operation").unwrap(), "1");
+        let response = inventory.get(get_request("NEW")).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::NoItem)
+        );
+
+        info!("verifying a successful batch applies every operation in order");
+        let request = Request::new(ApplyBatchRequest {
+            operations: vec![
+                operation(BatchOperation::Add(item("NEW"))),
+                operation(BatchOperation::UpdateQuantity(QuantityChangeRequest {
+                    sku: "NEW".into(),
+                    change: -40,
+                })),
+                operation(BatchOperation::UpdatePrice(PriceChangeRequest {
+                    sku: "EXISTING".into(),
+                    price: 2.49,
+                    ..Default::default()
+                })),
+                operation(BatchOperation::Remove(ItemIdentifier {
+                    sku: "DOOMED".into(),
+                })),
+            ],
+        });
+        inventory.apply_batch(request).await?;
+        let new = inventory.get(get_request("NEW")).await?.into_inner();
+        assert_eq!(item_quantity(&new), 2);
+        let existing = inventory.get(get_request("EXISTING")).await?.into_inner();
+        assert_eq!(item_price(&existing), 2.49);
+        let response = inventory.get(get_request("DOOMED")).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::NoItem)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn swap_stock() -> Result<(), Error> {
+        let inventory = Arc::new(StoreInventory::default());
+
+        info!("adding items with different stock to swap");
+        for (sku, price, quantity) in [("LEFT", 1.79, 1), ("RIGHT", 2.49, 2)] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price,
+                    quantity,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+
+        info!("verifying the stock of the items is exchanged");
+        let request = Request::new(SwapStockRequest {
+            first_sku: "LEFT".into(),
+            second_sku: "RIGHT".into(),
+        });
+        inventory.swap_stock(request).await?;
+        let stock = |sku: &'static str| {
+            let inventory = inventory.clone();
+            async move {
+                let request = get_request(sku);
+                let item = inventory.get(request).await.unwrap().into_inner();
+                (item_price(&item), item_quantity(&item))
+            }
+        };
+        assert_eq!(stock("LEFT").await, (2.49, 2));
+        assert_eq!(stock("RIGHT").await, (1.79, 1));
+
+        info!("verifying concurrent swaps never lose or duplicate stock");
+        let mut swappers = Vec::new();
+        for i in 0..100 {
+            let inventory = inventory.clone();
+            swappers.push(tokio::spawn(async move {
+                // alternate the order of the SKUs to exercise lock ordering
+                let (first_sku, second_sku) = match i % 2 {
+                    0 => ("LEFT".into(), "RIGHT".into()),
+                    _ => ("RIGHT".into(), "LEFT".into()),
+                };
+                let request = Request::new(SwapStockRequest {
+                    first_sku,
+                    second_sku,
+                });
+                inventory.swap_stock(request).await.unwrap();
+            }));
+        }
+        for swapper in swappers {
+            swapper.await?;
+        }
+        assert_eq!(stock("LEFT").await, (2.49, 2));
+        assert_eq!(stock("RIGHT").await, (1.79, 1));
+
+        info!("verifying a swap with a missing item leaves both items unchanged");
+        for (first_sku, second_sku) in [("LEFT", "DOESNTEXIST"), ("DOESNTEXIST", "RIGHT")] {
+            let request = Request::new(SwapStockRequest {
+                first_sku: first_sku.into(),
+                second_sku: second_sku.into(),
+            });
+            let response = inventory.swap_stock(request).await;
+            assert_eq!(
+                InventoryError::from_status(&response.unwrap_err()),
+                Some(InventoryError::NoItem)
+            );
+        }
+        assert_eq!(stock("LEFT").await, (2.49, 2));
+        assert_eq!(stock("RIGHT").await, (1.79, 1));
+
+        info!("verifying an item can't be swapped with itself");
+        let request = Request::new(SwapStockRequest {
+            first_sku: "LEFT".into(),
+            second_sku: "LEFT".into(),
+        });
+        let response = inventory.swap_stock(request).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::SameSku)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_only_mode() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_admin_token("secret");
+
+        info!("adding an item before entering maintenance");
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: "KEPT".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item.clone())).await?;
+
+        info!("verifying the mode can't be toggled without admin credentials");
+        let set_read_only = |read_only: bool, token: Option<&str>| {
+            let mut request = Request::new(ReadOnlyRequest { read_only });
+            if let Some(token) = token {
+                let authorization = format!("Bearer {}", token).parse().unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", authorization);
+            }
+            request
+        };
+        for token in [None, Some("wrong")] {
+            let response = inventory.set_read_only(set_read_only(true, token)).await;
+            let status = response.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+            assert_eq!(
+                InventoryError::from_status(&status),
+                Some(InventoryError::BadAdminToken)
+            );
+        }
+        let unconfigured = StoreInventory::default();
+        let response = unconfigured
+            .set_read_only(set_read_only(true, Some("secret")))
+            .await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::AdminDisabled)
+        );
+
+        info!("entering read-only maintenance mode");
+        let request = set_read_only(true, Some("secret"));
+        inventory.set_read_only(request).await?;
+
+        info!("verifying reads and watches are still served");
+        let request = get_request("KEPT");
+        assert_eq!(
+            item_quantity(&inventory.get(request).await?.into_inner()),
+            42
+        );
+        let request = watch_request("KEPT");
+        inventory.watch(request).await?;
+
+        info!("verifying writes are rejected as unavailable");
+        let new_item = Item {
+            identifier: Some(ItemIdentifier { sku: "NEW".into() }),
+            ..item.clone()
+        };
+        let rejections = [
+            inventory.add(Request::new(new_item)).await.unwrap_err(),
+            inventory.remove(remove_request("KEPT")).await.unwrap_err(),
+            inventory
+                .update_quantity(Request::new(QuantityChangeRequest {
+                    sku: "KEPT".into(),
+                    change: 1,
+                }))
+                .await
+                .unwrap_err(),
+            inventory
+                .update_price(Request::new(PriceChangeRequest {
+                    sku: "KEPT".into(),
+                    price: 2.49,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap_err(),
+        ];
+        for status in rejections {
+            assert_eq!(status.code(), tonic::Code::Unavailable);
+            assert_eq!(
+                InventoryError::from_status(&status),
+                Some(InventoryError::ReadOnly)
+            );
+        }
+        let request = get_request("KEPT");
+        assert_eq!(
+            item_quantity(&inventory.get(request).await?.into_inner()),
+            42
+        );
+
+        info!("verifying writes are accepted again after leaving maintenance");
+        let request = set_read_only(false, Some("secret"));
+        inventory.set_read_only(request).await?;
+        let request = Request::new(QuantityChangeRequest {
+            sku: "KEPT".into(),
+            change: 1,
+        });
+        inventory.update_quantity(request).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn access_stats() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        async fn access_stats(inventory: &StoreInventory, limit: u32) -> Vec<(String, u64)> {
+            let request = Request::new(AccessStatsRequest { limit });
+            let response = inventory.get_access_stats(request).await.unwrap();
+            response
+                .into_inner()
+                .items
+                .into_iter()
+                .map(|item| (item.sku, item.accesses))
+                .collect()
+        }
+
+        info!("adding items which haven't been fetched yet");
+        for sku in ["A", "B", "C"] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        assert!(access_stats(&inventory, 0).await.is_empty());
+
+        info!("fetching items with gets and watches");
+        for sku in ["A", "B", "A", "C", "A"] {
+            inventory.get(get_request(sku)).await?;
+        }
+        drop(inventory.watch(watch_request("B")).await?);
+        let response = inventory.get(get_request("MISSING")).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::NoItem)
+        );
+
+        info!("verifying the most fetched items are reported first");
+        let expected = [("A".into(), 3), ("B".into(), 2), ("C".into(), 1)];
+        assert_eq!(access_stats(&inventory, 0).await, expected);
+        assert_eq!(access_stats(&inventory, 2).await, expected[..2]);
+
+        info!("verifying counts survive changes but not removal");
+        let request = Request::new(PriceChangeRequest {
+            sku: "A".into(),
+            price: 2.49,
+            ..Default::default()
+        });
+        inventory.update_price(request).await?;
+        assert_eq!(access_stats(&inventory, 1).await, expected[..1]);
+        let request = remove_request("A");
+        inventory.remove(request).await?;
+        assert_eq!(access_stats(&inventory, 0).await, expected[1..]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn price_buckets() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let buckets = |boundaries: &[f32]| {
+            let boundaries = boundaries.to_vec();
+            Request::new(PriceBucketsRequest { boundaries })
+        };
+
+        info!("adding items, some priced exactly on a boundary");
+        for (sku, price, quantity) in [
+            ("CHEAP", 0.5, 10),
+            ("ON_ONE", 1.0, 3),
+            ("UNDER_FIVE", 4.5, 2),
+            ("ON_FIVE", 5.0, 1),
+            ("PRICEY", 25.0, 4),
+        ] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price,
+                    quantity,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+
+        info!("verifying items are counted in the bucket their price starts");
+        let response = inventory
+            .group_by_price_bucket(buckets(&[1.0, 5.0, 20.0]))
+            .await?
+            .into_inner();
+        let summary: Vec<_> = response
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.min, bucket.max, bucket.count, bucket.total_value))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (None, Some(1.0), 1, 5.0),
+                (Some(1.0), Some(5.0), 2, 12.0),
+                (Some(5.0), Some(20.0), 1, 5.0),
+                (Some(20.0), None, 1, 100.0),
+            ]
+        );
+
+        info!("verifying no boundaries make a single bucket");
+        let response = inventory.group_by_price_bucket(buckets(&[])).await?;
+        let bucket = &response.into_inner().buckets[0];
+        assert_eq!((bucket.min, bucket.max, bucket.count), (None, None, 5));
+
+        info!("verifying boundaries must be finite and strictly ascending");
+        for boundaries in [
+            &[5.0, 1.0][..],
+            &[1.0, 1.0],
+            &[1.0, f32::NAN],
+            &[f32::INFINITY],
+        ] {
+            let response = inventory.group_by_price_bucket(buckets(boundaries)).await;
+            let status = response.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+            assert_eq!(
+                InventoryError::from_status(&status),
+                Some(InventoryError::BadBuckets)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_stream_ids() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let watch = |stream_id: Option<&str>| {
+            let mut request = watch_request("SKU");
+            if let Some(stream_id) = stream_id {
+                let stream_id = stream_id.parse().unwrap();
+                request.metadata_mut().insert("x-stream-id", stream_id);
+            }
+            request
+        };
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: "SKU".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+
+        info!("verifying client supplied stream ids are echoed back");
+        let first = inventory.watch(watch(Some("reconnecting"))).await?;
+        assert_eq!(first.metadata().get("x-stream-id").unwrap(), "reconnecting");
+
+        info!("verifying stream ids are generated when not supplied");
+        let second = inventory.watch(watch(None)).await?;
+        let generated = second.metadata().get("x-stream-id").unwrap().to_str()?;
+        assert_eq!(generated.len(), 16);
+
+        info!("verifying the open streams are registered by their ids");
+        let request = Request::new(MetricsRequest {});
+        let metrics = inventory.get_metrics(request).await?.into_inner();
+        let ids: Vec<&str> = metrics
+            .watchers
+            .iter()
+            .map(|w| w.stream_id.as_str())
+            .collect();
+        assert_eq!(ids, ["reconnecting", generated]);
+        assert!(metrics.watchers.iter().all(|watcher| watcher.sku == "SKU"));
+
+        info!("verifying invalid stream ids are rejected");
+        let long = "x".repeat(200);
+        for stream_id in ["", long.as_str()] {
+            let status = match inventory.watch(watch(Some(stream_id))).await {
+                Ok(_) => panic!("watch opened with stream id {:?}", stream_id),
+                Err(status) => status,
+            };
+            assert_eq!(
+                InventoryError::from_status(&status),
+                Some(InventoryError::BadStreamId)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn consistency() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_admin_token("secret");
+        let check = |repair: bool| {
+            let mut request = Request::new(ConsistencyRequest { repair });
+            let authorization = "Bearer secret".parse().unwrap();
+            request
+                .metadata_mut()
+                .insert("authorization", authorization);
+            request
+        };
+        let item = |sku: &str, stock: Option<ItemStock>| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock,
+            ..Default::default()
+        };
+
+        info!("verifying a consistent inventory has no issues");
+        let stock = ItemStock {
+            price: 1.79,
+            quantity: 5,
+            ..Default::default()
+        };
+        inventory
+            .add(Request::new(item("KEPT", Some(stock.clone()))))
+            .await?;
+        inventory.get(get_request("KEPT")).await?;
+        let report = inventory.check_consistency(check(false)).await?;
+        assert!(report.into_inner().issues.is_empty());
+
+        info!("verifying checks require admin credentials");
+        let request = Request::new(ConsistencyRequest { repair: false });
+        let status = inventory.check_consistency(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadAdminToken)
+        );
+
+        info!("corrupting the structures kept alongside the items");
+        let image = Image {
+            content_type: "image/png".into(),
+            data: vec![1, 2, 3],
+        };
+        inventory.images.lock().await.insert("GONE".into(), image);
+        let mut reservations = inventory.reservations.lock().await;
+        reservations.insert("KEPT".into(), 10);
+        reservations.insert("GONE".into(), 1);
+        drop(reservations);
+        inventory.removed.lock().await.insert("KEPT".into(), 1);
+        inventory
+            .accesses
+            .write()
+            .unwrap()
+            .insert("GONE".into(), 1.into());
+        let mut map = inventory.shard("STOCKLESS").write().await;
+        map.insert("STOCKLESS".into(), item("STOCKLESS", None))
+            .await
+            .unwrap();
+        drop(map);
+
+        let expected = [
+            ("GONE", server::ORPHAN_IMAGE_ISSUE, true),
+            ("GONE", server::ORPHAN_RESERVATION_ISSUE, true),
+            ("GONE", server::ORPHAN_ACCESSES_ISSUE, true),
+            ("KEPT", server::STALE_TOMBSTONE_ISSUE, true),
+            ("KEPT", server::OVER_RESERVED_ISSUE, true),
+            ("STOCKLESS", server::NO_STOCK_ISSUE, false),
+        ];
+        let summary = |report: ConsistencyReport| -> Vec<(String, String, bool)> {
+            report
+                .issues
+                .into_iter()
+                .map(|issue| (issue.sku, issue.description, issue.repaired))
+                .collect()
+        };
+        let expect = |repair: bool| -> Vec<(String, String, bool)> {
+            expected
+                .iter()
+                .map(|(sku, issue, repairable)| {
+                    (sku.to_string(), issue.to_string(), *repairable && repair)
+                })
+                .collect()
+        };
+
+        info!("verifying the corruption is detected without being repaired");
+        let report = inventory.check_consistency(check(false)).await?;
+        assert_eq!(summary(report.into_inner()), expect(false));
+        let report = inventory.check_consistency(check(false)).await?;
+        assert_eq!(summary(report.into_inner()), expect(false));
+
+        info!("repairing the corruption");
+        let report = inventory.check_consistency(check(true)).await?;
+        assert_eq!(summary(report.into_inner()), expect(true));
+        assert!(inventory.images.lock().await.is_empty());
+        assert_eq!(
+            *inventory.reservations.lock().await,
+            HashMap::from([("KEPT".into(), 5)])
+        );
+        assert!(inventory.removed.lock().await.is_empty());
+        assert!(!inventory.accesses.read().unwrap().contains_key("GONE"));
+
+        info!("verifying only what can't be repaired remains");
+        let report = inventory.check_consistency(check(false)).await?;
+        let remaining = vec![("STOCKLESS".into(), server::NO_STOCK_ISSUE.into(), false)];
+        assert_eq!(summary(report.into_inner()), remaining);
+
+        info!("verifying repairs are refused in read-only mode");
+        inventory.read_only.store(true, Ordering::SeqCst);
+        let status = inventory.check_consistency(check(true)).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::ReadOnly)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn load_snapshot() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_admin_token("secret");
+        let load = |items: Vec<Item>| {
+            let mut request = Request::new(LoadSnapshotRequest { items });
+            let authorization = "Bearer secret".parse().unwrap();
+            request
+                .metadata_mut()
+                .insert("authorization", authorization);
+            request
+        };
+        let item = |sku: &str, quantity: u32| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let list = || inventory.list(Request::new(ListRequest::default()));
+
+        info!("adding items for the snapshot to replace");
+        for sku in ["KEPT", "REPLACED"] {
+            inventory.add(Request::new(item(sku, 1))).await?;
+        }
+        let mut reservations = inventory.reservations.lock().await;
+        reservations.insert("KEPT".into(), 1);
+        drop(reservations);
+        let before = list().await?.into_inner();
+
+        info!("verifying a snapshot with an invalid item changes nothing");
+        let mut invalid = item("INVALID", 1);
+        invalid.stock = None;
+        let items = vec![item("KEPT", 5), item("NEW", 5), invalid];
+        let status = inventory.load_snapshot(load(items)).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoStock)
+        );
+        assert_eq!(status.metadata().get("x-failed-item").unwrap(), "2");
+        assert_eq!(list().await?.into_inner(), before);
+        assert_eq!(inventory.reservations.lock().await.len(), 1);
+
+        info!("verifying a snapshot with duplicate SKUs changes nothing");
+        let items = vec![item("NEW", 5), item("KEPT", 5), item("NEW", 6)];
+        let status = inventory.load_snapshot(load(items)).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::DuplicateItem)
+        );
+        assert_eq!(status.metadata().get("x-failed-item").unwrap(), "2");
+        assert_eq!(list().await?.into_inner(), before);
+
+        info!("verifying snapshots require admin credentials and a writable inventory");
+        let request = Request::new(LoadSnapshotRequest::default());
+        let status = inventory.load_snapshot(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadAdminToken)
+        );
+        inventory.read_only.store(true, Ordering::SeqCst);
+        let status = inventory.load_snapshot(load(vec![])).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::ReadOnly)
+        );
+        inventory.read_only.store(false, Ordering::SeqCst);
+        assert_eq!(list().await?.into_inner(), before);
+
+        info!("replacing the inventory with a snapshot");
+        let items = vec![item("NEW", 5), item("KEPT", 7)];
+        let response = inventory.load_snapshot(load(items)).await?.into_inner();
+        assert_eq!(response.loaded, 2);
+        assert_eq!(response.replaced, 2);
+        let skus: Vec<String> = list()
+            .await?
+            .into_inner()
+            .items
+            .iter()
+            .map(item_sku)
+            .collect();
+        assert_eq!(skus, ["KEPT", "NEW"]);
+        let kept = inventory.get(get_request("KEPT")).await?.into_inner();
+        assert_eq!(kept.stock.unwrap().quantity, 7);
+        assert!(inventory.reservations.lock().await.is_empty());
+
+        info!("verifying items missing from the snapshot were removed");
+        let status = inventory.get(get_request("REPLACED")).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+        let removed = inventory.removed.lock().await;
+        assert_eq!(removed.keys().collect::<Vec<_>>(), ["REPLACED"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bulk_update_price() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let change = |sku: &str, price: f32| PriceChangeRequest {
+            sku: sku.into(),
+            price,
+            ..Default::default()
+        };
+
+        info!("adding items to reprice, some of which are already at the target");
+        for (sku, price) in [("CHEAP", 1.79), ("TARGET", 2.49), ("ALSO", 2.49)] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        let before = inventory.get(get_request("TARGET")).await?.into_inner();
+
+        info!("verifying a single update to the current price changes nothing");
+        let request = Request::new(change("TARGET", 2.49));
+        let response = inventory.update_price(request).await?.into_inner();
+        assert_eq!(response.status, "success: no change");
+
+        info!("repricing the set, including missing and invalid changes");
+        // the change for ALSO is to the same price once rounded to the cent,
+        // and the price it reports is the one stored
+        let request = Request::new(BulkPriceRequest {
+            changes: vec![
+                change("CHEAP", 2.49),
+                change("TARGET", 2.49),
+                change("MISSING", 2.49),
+                change("ALSO", 2.491),
+                change("CHEAP", -1.0),
+            ],
+        });
+        let response = inventory.bulk_update_price(request).await?.into_inner();
+        let results: Vec<(&str, PriceOutcome, f32, &str)> = response
+            .results
+            .iter()
+            .map(|result| {
+                let sku = result.sku.as_str();
+                (sku, result.outcome(), result.price, result.error.as_str())
+            })
+            .collect();
+        let (missing, bad_price) = (
+            InventoryError::NoItem.message(),
+            InventoryError::BadPrice.message(),
+        );
+        assert_eq!(
+            results,
+            [
+                ("CHEAP", PriceOutcome::Updated, 2.49, ""),
+                ("TARGET", PriceOutcome::Unchanged, 2.49, ""),
+                ("MISSING", PriceOutcome::Failed, 0.0, missing),
+                ("ALSO", PriceOutcome::Unchanged, 2.49, ""),
+                ("CHEAP", PriceOutcome::Failed, 0.0, bad_price),
+            ]
+        );
+
+        info!("verifying only the updated items changed");
+        let cheap = inventory.get(get_request("CHEAP")).await?.into_inner();
+        assert_eq!(item_price(&cheap), 2.49);
+        let after = inventory.get(get_request("TARGET")).await?.into_inner();
+        assert_eq!(after, before);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn attributes() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let attributes = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        let item = |sku: &str, pairs: &[(&str, &str)]| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            attributes: attributes(pairs),
+            ..Default::default()
+        };
+        let filter = |key: &str, value: &str| {
+            Request::new(AttributeFilter {
+                key: key.into(),
+                value: value.into(),
+            })
+        };
+        let skus = |response: ListByAttributeResponse| -> Vec<String> {
+            response.items.iter().map(item_sku).collect()
+        };
+
+        info!("adding items with attributes");
+        let acme = [("brand", "Acme"), ("weight", "2kg")];
+        inventory.add(Request::new(item("B", &acme))).await?;
+        inventory.add(Request::new(item("A", &acme[..1]))).await?;
+        inventory
+            .add(Request::new(item("C", &[("brand", "Other")])))
+            .await?;
+        inventory.add(Request::new(item("D", &[]))).await?;
+        let a = inventory.get(get_request("A")).await?.into_inner();
+        assert_eq!(a.attributes, attributes(&[("brand", "Acme")]));
+
+        info!("filtering items by attribute");
+        let found = inventory.list_by_attribute(filter("brand", "Acme")).await?;
+        assert_eq!(skus(found.into_inner()), ["A", "B"]);
+        let found = inventory.list_by_attribute(filter("weight", "2kg")).await?;
+        assert_eq!(skus(found.into_inner()), ["B"]);
+        let found = inventory.list_by_attribute(filter("brand", "acme")).await?;
+        assert!(found.into_inner().items.is_empty());
+        let found = inventory.list_by_attribute(filter("color", "")).await?;
+        assert!(found.into_inner().items.is_empty());
+        let status = inventory
+            .list_by_attribute(filter("", "Acme"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadAttribute)
+        );
+
+        info!("verifying attributes are limited in number and size");
+        let many: Vec<(String, String)> = (0..=32)
+            .map(|n| (format!("key{}", n), "value".to_string()))
+            .collect();
+        let long_key = "k".repeat(65);
+        let long_value = "v".repeat(257);
+        let invalid = [
+            (many.clone(), InventoryError::BigAttributes),
+            (
+                vec![(long_key, "value".into())],
+                InventoryError::BigAttributes,
+            ),
+            (
+                vec![("key".into(), long_value)],
+                InventoryError::BigAttributes,
+            ),
+            (
+                vec![("".into(), "value".into())],
+                InventoryError::BadAttribute,
+            ),
+        ];
+        for (pairs, err) in invalid {
+            let mut invalid = item("INVALID", &[]);
+            invalid.attributes = pairs.into_iter().collect();
+            let status = inventory.add(Request::new(invalid)).await.unwrap_err();
+            assert_eq!(InventoryError::from_status(&status), Some(err));
+        }
+        let mut largest = item("LARGEST", &[]);
+        largest.attributes = many.into_iter().skip(1).collect();
+        inventory.add(Request::new(largest)).await?;
+
+        info!("updating the information and attributes of an item");
+        let request = Request::new(InformationChangeRequest {
+            sku: "B".into(),
+            information: Some(ItemInformation {
+                name: Some("anvil".into()),
+                description: None,
+            }),
+            attributes: attributes(&[("brand", "Other"), ("weight", ""), ("color", "black")]),
+        });
+        inventory.update_information(request).await?;
+        let b = inventory.get(get_request("B")).await?.into_inner();
+        let expected = attributes(&[("brand", "Other"), ("color", "black")]);
+        assert_eq!(b.attributes, expected);
+        assert_eq!(b.information.unwrap().name.as_deref(), Some("anvil"));
+        let found = inventory
+            .list_by_attribute(filter("brand", "Other"))
+            .await?;
+        assert_eq!(skus(found.into_inner()), ["B", "C"]);
+
+        info!("verifying updates which leave the item as it was change nothing");
+        let before = inventory.get(get_request("B")).await?.into_inner();
+        let unchanged = [
+            InformationChangeRequest {
+                sku: "B".into(),
+                ..Default::default()
+            },
+            InformationChangeRequest {
+                sku: "B".into(),
+                information: Some(ItemInformation {
+                    name: Some("anvil".into()),
+                    description: None,
+                }),
+                attributes: attributes(&[("brand", "Other"), ("weight", "")]),
+            },
+        ];
+        for change in unchanged {
+            let response = inventory.update_information(Request::new(change)).await?;
+            assert_eq!(response.into_inner().status, "success: no change");
+        }
+        let after = inventory.get(get_request("B")).await?.into_inner();
+        assert_eq!(after, before);
+
+        info!("verifying updates which exceed the limits change nothing");
+        let request = Request::new(InformationChangeRequest {
+            sku: "LARGEST".into(),
+            information: Some(ItemInformation {
+                name: Some("renamed".into()),
+                description: None,
+            }),
+            attributes: attributes(&[("one", "too many")]),
+        });
+        let status = inventory.update_information(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BigAttributes)
+        );
+        let largest = inventory.get(get_request("LARGEST")).await?.into_inner();
+        assert_eq!(largest.attributes.len(), 32);
+        assert_eq!(largest.information, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_notifications() -> Result<(), Error> {
+        // a small capacity, so that a watch which isn't polled falls behind
+        let inventory = StoreInventory {
+            changes: broadcast::channel(2).0,
+            ..Default::default()
+        };
+        let change = |sku: &str| {
+            Request::new(QuantityChangeRequest {
+                sku: sku.into(),
+                change: 1,
+            })
+        };
+        for sku in ["WATCHED", "OTHER"] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        let request = watch_request("WATCHED");
+        let mut stream = inventory.watch(request).await?.into_inner();
+        let timeout = Duration::from_millis(100);
+
+        info!("verifying changes are delivered as they're made");
+        inventory.update_quantity(change("WATCHED")).await?;
+        let changed = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()?;
+        assert_eq!(item_quantity(&watched_item(changed)), 43);
+
+        info!("verifying changes to other items aren't delivered");
+        inventory.update_quantity(change("OTHER")).await?;
+        assert!(tokio::time::timeout(timeout, stream.next()).await.is_err());
+
+        info!("verifying a watch which fell behind catches up with the latest state");
+        for _ in 0..10 {
+            inventory.update_quantity(change("WATCHED")).await?;
+        }
+        let changed = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()?;
+        assert_eq!(item_quantity(&watched_item(changed)), 53);
+        assert!(tokio::time::timeout(timeout, stream.next()).await.is_err());
+
+        info!("verifying removing the item ends the watch");
+        let request = remove_request("WATCHED");
+        inventory.remove(request).await?;
+        let status = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+        assert!(tokio::time::timeout(timeout, stream.next())
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_and_watch() -> Result<(), Error> {
+        let inventory = Arc::new(StoreInventory::default());
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "WATCHED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+        let request = || {
+            Request::new(ItemIdentifier {
+                sku: "WATCHED".into(),
+            })
+        };
+        let timeout = Duration::from_millis(100);
+
+        info!("verifying the item is sent before its changes while it's being changed");
+        let changer = inventory.clone();
+        let changes = tokio::spawn(async move {
+            for _ in 0..100 {
+                let request = Request::new(QuantityChangeRequest {
+                    sku: "WATCHED".into(),
+                    change: 1,
+                });
+                changer.update_quantity(request).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+        });
+        tokio::task::yield_now().await;
+        let mut stream = inventory.get_and_watch(request()).await?.into_inner();
+        changes.await?;
+        let mut quantities = Vec::new();
+        while let Ok(Some(event)) = tokio::time::timeout(timeout, stream.next()).await {
+            quantities.push(item_quantity(&watched_item(event?)));
+        }
+
+        // every change after the first item is sent, and none before it
+        let first = quantities[0];
+        assert_eq!(quantities, (first..=100).collect::<Vec<_>>());
+
+        info!("verifying a missing item can't be watched");
+        let request = Request::new(ItemIdentifier {
+            sku: "MISSING".into(),
+        });
+        let status = inventory.get_and_watch(request).await.err().unwrap();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_from_version() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "WATCHED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 0,
+                ..Default::default()
+            }),
+            version: 42,
+            ..Default::default()
+        };
+        async fn get(inventory: &StoreInventory) -> Item {
+            let request = get_request("WATCHED");
+            inventory.get(request).await.unwrap().into_inner()
+        }
+        let watch = |from_version: Option<u64>| {
+            Request::new(WatchRequest {
+                sku: "WATCHED".into(),
+                from_version,
+            })
+        };
+        let change = || {
+            Request::new(QuantityChangeRequest {
+                sku: "WATCHED".into(),
+                change: 1,
+            })
+        };
+        let timeout = Duration::from_millis(100);
+
+        info!("verifying versions are assigned by the server and incremented");
+        inventory.add(Request::new(item.clone())).await?;
+        assert_eq!(get(&inventory).await.version, 1);
+        inventory.update_quantity(change()).await?;
+        assert_eq!(get(&inventory).await.version, 2);
+        inventory.upsert(Request::new(item.clone())).await?;
+        assert_eq!(get(&inventory).await.version, 3);
+
+        info!("verifying a watch from the current version is only sent changes");
+        let mut stream = inventory.watch(watch(Some(3))).await?.into_inner();
+        inventory.update_quantity(change()).await?;
+        let changed = watched_item(stream.next().await.unwrap()?);
+        assert_eq!((changed.version, item_quantity(&changed)), (4, 1));
+
+        info!("verifying a watch from a stale version is sent the item first");
+        let mut stream = inventory.watch(watch(Some(2))).await?.into_inner();
+        let current = watched_item(stream.next().await.unwrap()?);
+        assert_eq!(current, get(&inventory).await);
+        assert!(tokio::time::timeout(timeout, stream.next()).await.is_err());
+
+        info!("verifying a watch without a version is only sent changes");
+        let mut stream = inventory.watch(watch(None)).await?.into_inner();
+        assert!(tokio::time::timeout(timeout, stream.next()).await.is_err());
+
+        info!("verifying an item added again is sent to a watch from before");
+        inventory.remove(remove_request("WATCHED")).await?;
+        inventory.add(Request::new(item)).await?;
+        assert_eq!(get(&inventory).await.version, 1);
+        let mut stream = inventory.watch(watch(Some(4))).await?.into_inner();
+        let current = watched_item(stream.next().await.unwrap()?);
+        assert_eq!(current.version, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = |sku: &str, name: Option<&str>, description: Option<&str>| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            information: Some(ItemInformation {
+                name: name.map(String::from),
+                description: description.map(String::from),
+            }),
+            ..Default::default()
+        };
+        let search = |query: &str| {
+            Request::new(SearchRequest {
+                query: query.into(),
+            })
+        };
+        let skus = |response: SearchResponse| -> Vec<String> {
+            response.items.iter().map(item_sku).collect()
+        };
+
+        info!("adding items with and without information");
+        let items = [
+            item("C", Some("Red Apple"), None),
+            item("A", None, Some("a crisp apple")),
+            item("B", Some("Banana"), Some("not an APPLE")),
+            item("D", None, None),
+            Item {
+                information: None,
+                ..item("E", None, None)
+            },
+        ];
+        for item in items {
+            inventory.add(Request::new(item)).await?;
+        }
+
+        info!("searching names and descriptions, ignoring case");
+        let found = inventory.search(search("Apple")).await?;
+        assert_eq!(skus(found.into_inner()), ["A", "B", "C"]);
+        let found = inventory.search(search("banana")).await?;
+        assert_eq!(skus(found.into_inner()), ["B"]);
+        let found = inventory.search(search("cherry")).await?;
+        assert!(found.into_inner().items.is_empty());
+
+        info!("verifying empty queries are rejected");
+        let status = inventory.search(search("")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::EmptyQuery)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decimal_prices() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let change = |price: f32| {
+            Request::new(PriceChangeRequest {
+                sku: "DECIMAL".into(),
+                price,
+                ..Default::default()
+            })
+        };
+        let price = |item: &Item| item.stock.as_ref().unwrap().price;
+
+        info!("adding an item at a price which isn't exact as a float");
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "DECIMAL".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 0.1 + 0.2,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let added = inventory.add(Request::new(item)).await?.into_inner();
+        assert_eq!(price(&added.item.unwrap()), 0.3);
+
+        info!("verifying the same price is unchanged however it's computed");
+        let response = inventory.update_price(change(0.3)).await?.into_inner();
+        assert_eq!(response.status, "success: no change");
+
+        info!("updating the price cleanly, keeping it to the cent");
+        let updated = inventory.update_price(change(2.0 + 0.49)).await?;
+        assert_eq!(updated.into_inner().price, 2.49);
+        for same in [2.49, 2.494, 2.485 + 0.001] {
+            let response = inventory.update_price(change(same)).await?.into_inner();
+            assert_eq!(response.status, "success: no change");
+        }
+        let item = inventory.get(get_request("DECIMAL")).await?.into_inner();
+        assert_eq!(price(&item), 2.49);
+
+        info!("verifying prices which round to nothing are rejected");
+        let status = inventory.update_price(change(0.004)).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadPrice)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn inventory_stats() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = |sku: &str, price: f32, quantity: u32| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let stats = || async {
+            let request = Request::new(InventoryStatsRequest {});
+            inventory
+                .inventory_stats(request)
+                .await
+                .map(Response::into_inner)
+        };
+
+        info!("verifying an empty inventory has no value");
+        let empty = stats().await?;
+        assert_eq!((empty.items, empty.units, empty.total_value), (0, 0, 0.0));
+
+        info!("summing the value of the inventory in cents");
+        inventory.add(Request::new(item("A", 2.49, 10))).await?;
+        inventory.add(Request::new(item("B", 0.1 + 0.2, 3))).await?;
+        inventory.add(Request::new(item("C", 5.0, 0))).await?;
+        let totals = stats().await?;
+        assert_eq!((totals.items, totals.units), (3, 13));
+        assert_eq!(totals.total_value, 25.8);
+        assert_eq!(totals.currency, "USD");
+
+        info!("verifying the largest prices and quantities don't overflow");
+        for sku in ["D", "E", "F"] {
+            let request = Request::new(item(sku, f32::MAX, u32::MAX));
+            inventory.add(request).await?;
+        }
+        let totals = stats().await?;
+        assert_eq!(totals.units, 13 + 3 * u64::from(u32::MAX));
+        let largest = 3 * i64::MAX as u128 * u128::from(u32::MAX) + 2580;
+        assert_eq!(totals.total_value, largest as f64 / 100.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn currencies() -> Result<(), Error> {
+        let rates = RateTable::new([("EUR", 0.5)]);
+        let inventory = StoreInventory::default().with_rates(rates);
+        let item = |sku: &str, price: f32, currency: &str| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price,
+                quantity: 2,
+                currency: currency.into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let stats = || async {
+            let request = Request::new(InventoryStatsRequest {});
+            inventory
+                .inventory_stats(request)
+                .await
+                .map(Response::into_inner)
+        };
+
+        info!("verifying items can only be priced in known currencies");
+        let status = inventory
+            .add(Request::new(item("A", 1.0, "XYZ")))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadCurrency)
+        );
+        let violations = details::field_violations(&status);
+        assert_eq!(violations[0].field, "stock.currency");
+
+        info!("verifying currencies are stored in upper case");
+        inventory.add(Request::new(item("A", 1.25, "eur"))).await?;
+        let stored = inventory.get(get_request("A")).await?.into_inner();
+        assert_eq!(stored.stock.unwrap().currency, "EUR");
+
+        info!("verifying a single currency is totalled in that currency");
+        let totals = stats().await?;
+        assert_eq!((totals.total_value, totals.currency.as_str()), (2.5, "EUR"));
+
+        info!("verifying prices can't change the currency of an item");
+        let change = |currency: Option<&str>| {
+            Request::new(PriceChangeRequest {
+                sku: "A".into(),
+                price: 1.5,
+                currency: currency.map(String::from),
+                ..Default::default()
+            })
+        };
+        let status = inventory
+            .update_price(change(Some("USD")))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::CurrencyMismatch)
+        );
+        inventory.update_price(change(Some("eur"))).await?;
+        inventory.update_price(change(None)).await?;
+
+        info!("verifying prices are converted from the currency of the item");
+        let mut request = get_request("A");
+        request.metadata_mut().insert("x-currency", "USD".parse()?);
+        let converted = inventory.get(request).await?.into_inner().converted;
+        let expected = ConvertedPrice {
+            currency: "USD".into(),
+            price: 3.0,
+        };
+        assert_eq!(converted, Some(expected));
+
+        info!("verifying mixed currencies are totalled in the base currency");
+        inventory.add(Request::new(item("B", 1.79, ""))).await?;
+        let totals = stats().await?;
+        assert_eq!(
+            (totals.total_value, totals.currency.as_str()),
+            (9.58, "USD")
+        );
+
+        info!("verifying currencies without rates can't be totalled");
+        inventory.add(Request::new(item("C", 1.0, "GBP"))).await?;
+        let status = stats().await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::MixedCurrencies)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upsert() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = |quantity| Item {
+            identifier: Some(ItemIdentifier {
+                sku: "UPSERT".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        info!("verifying upserting a new item adds it");
+        let response = inventory.upsert(Request::new(item(10))).await?.into_inner();
+        assert!(!response.replaced);
+        assert_eq!(item_quantity(&response.item.unwrap()), 10);
+        inventory
+            .reservations
+            .lock()
+            .await
+            .insert("UPSERT".into(), 4);
+
+        info!("verifying upserting an existing item replaces it");
+        let response = inventory.upsert(Request::new(item(3))).await?.into_inner();
+        assert!(response.replaced);
+        assert_eq!(response.status, "success: item was replaced");
+        let stored = inventory.get(get_request("UPSERT")).await?.into_inner();
+        assert_eq!(item_quantity(&stored), 3);
+
+        info!("verifying the replaced item's reservations were dropped");
+        assert!(!inventory.reservations.lock().await.contains_key("UPSERT"));
+
+        info!("verifying Add still rejects existing items");
+        let status = inventory.add(Request::new(item(1))).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::DuplicateItem)
+        );
+
+        info!("verifying upserted items are validated");
+        let mut bad = item(1);
+        bad.stock = None;
+        assert!(inventory.upsert(Request::new(bad)).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn validate() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_max_quantity(100);
+        let item = |sku: &str, price, quantity| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price,
+                quantity,
+                currency: "eur".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        info!("verifying valid items are returned as they'd be stored");
+        let response = inventory
+            .validate(Request::new(item("VALID", 1.789, 10)))
+            .await?
+            .into_inner();
+        assert_eq!(response.status, "success: item is valid");
+        let validated = response.item.unwrap();
+        assert_eq!(item_price(&validated), 1.79);
+        assert_eq!(validated.stock.unwrap().currency, "EUR");
+        assert_eq!((validated.last_updated, validated.version), (0, 0));
+
+        info!("verifying validated items aren't added");
+        let status = inventory.get(get_request("VALID")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        info!("verifying items Add rejects are rejected the same way");
+        let mut stockless = item("STOCKLESS", 1.79, 10);
+        stockless.stock = None;
+        for invalid in [
+            item("", 1.79, 10),
+            item("NEGATIVE", -1.0, 10),
+            item("MANY", 1.79, 101),
+            stockless,
+        ] {
+            let validated = inventory.validate(Request::new(invalid.clone())).await;
+            let added = inventory.add(Request::new(invalid)).await;
+            let (validated, added) = (validated.unwrap_err(), added.unwrap_err());
+            assert_eq!(validated.code(), tonic::Code::InvalidArgument);
+            assert_eq!(
+                InventoryError::from_status(&validated),
+                InventoryError::from_status(&added)
+            );
+        }
+
+        info!("verifying items are validated regardless of the inventory");
+        inventory.add(Request::new(item("VALID", 1.79, 10))).await?;
+        inventory.read_only.store(true, Ordering::SeqCst);
+        inventory
+            .validate(Request::new(item("VALID", 1.79, 10)))
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn quantity_overflow() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let quantity = u32::MAX - 10;
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "LARGE".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+
+        info!("verifying increases past the largest quantity are rejected");
+        let request = Request::new(QuantityChangeRequest {
+            sku: "LARGE".into(),
+            change: i32::MAX,
+        });
+        let status = inventory.update_quantity(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::OutOfRange);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BigQuantity)
+        );
+
+        info!("verifying the rejected change left the quantity alone");
+        let stored = inventory.get(get_request("LARGE")).await?.into_inner();
+        assert_eq!(item_quantity(&stored), quantity);
+
+        info!("verifying increases up to the largest quantity are accepted");
+        let request = Request::new(QuantityChangeRequest {
+            sku: "LARGE".into(),
+            change: 10,
+        });
+        let response = inventory.update_quantity(request).await?.into_inner();
+        assert_eq!(response.quantity, u32::MAX);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reserve_and_release() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "ORDERED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 10,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+        let reservation = |quantity| {
+            Request::new(ReservationRequest {
+                sku: "ORDERED".into(),
+                quantity,
+            })
+        };
+
+        info!("reserving some of an item's stock");
+        let availability = inventory.reserve(reservation(4)).await?.into_inner();
+        let expected = Availability {
+            total: 10,
+            reserved: 4,
+            available: 6,
+        };
+        assert_eq!(availability, expected);
+
+        info!("verifying reserved stock isn't removed from the item");
+        let stored = inventory.get(get_request("ORDERED")).await?.into_inner();
+        assert_eq!(item_quantity(&stored), 10);
+
+        info!("verifying more than the available stock can't be reserved");
+        let status = inventory.reserve(reservation(7)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::OverReserve)
+        );
+        let availability = inventory.reserve(reservation(6)).await?.into_inner();
+        assert_eq!(availability.available, 0);
+
+        info!("verifying the quantity can't drop below the reserved stock");
+        let request = Request::new(QuantityChangeRequest {
+            sku: "ORDERED".into(),
+            change: -1,
+        });
+        let status = inventory.update_quantity(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::ReservedStock)
+        );
+        let set = |quantity| {
+            Request::new(SetQuantityRequest {
+                sku: "ORDERED".into(),
+                quantity,
+            })
+        };
+        let status = inventory.set_quantity(set(9)).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::ReservedStock)
+        );
+        let stored = inventory.get(get_request("ORDERED")).await?.into_inner();
+        assert_eq!(item_quantity(&stored), 10);
+        inventory.set_quantity(set(12)).await?;
+        inventory.set_quantity(set(10)).await?;
+
+        info!("releasing reserved stock");
+        let availability = inventory.release(reservation(10)).await?.into_inner();
+        assert_eq!(availability.reserved, 0);
+        assert_eq!(availability.available, 10);
+        assert!(inventory.reservations.lock().await.is_empty());
+
+        info!("verifying more than the reserved stock can't be released");
+        let status = inventory.release(reservation(1)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::OverRelease)
+        );
+
+        info!("verifying invalid reservations are rejected");
+        let status = inventory.reserve(reservation(0)).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::EmptyQuantity)
+        );
+        let request = Request::new(ReservationRequest {
+            sku: "DOESNTEXIST".into(),
+            quantity: 1,
+        });
+        let status = inventory.reserve(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_all() -> Result<(), Error> {
+        // a small capacity, so that a watch which isn't polled falls behind
+        let inventory = StoreInventory {
+            changes: broadcast::channel(4).0,
+            ..Default::default()
+        };
+        let mut stream = inventory
+            .watch_all(Request::new(WatchAllRequest {}))
+            .await?
+            .into_inner();
+        let timeout = Duration::from_millis(100);
+        let item = |sku: &str| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        info!("verifying additions and changes to any item are delivered");
+        inventory.add(Request::new(item("FIRST"))).await?;
+        inventory.add(Request::new(item("SECOND"))).await?;
+        let request = Request::new(QuantityChangeRequest {
+            sku: "FIRST".into(),
+            change: 1,
+        });
+        inventory.update_quantity(request).await?;
+        let mut events = Vec::new();
+        for _ in 0..3 {
+            let event = tokio::time::timeout(timeout, stream.next()).await?;
+            let event = event.unwrap()?;
+            assert!(!event.removed);
+            events.push(event.item.unwrap());
+        }
+        let skus: Vec<String> = events.iter().map(item_sku).collect();
+        assert_eq!(skus, ["FIRST", "SECOND", "FIRST"]);
+        assert_eq!(item_quantity(&events[2]), 43);
+
+        info!("verifying removals are delivered as such");
+        inventory.remove(remove_request("SECOND")).await?;
+        let event = tokio::time::timeout(timeout, stream.next()).await?;
+        let event = event.unwrap()?;
+        assert!(event.removed);
+        assert_eq!(item_sku(&event.item.unwrap()), "SECOND");
+
+        info!("verifying a watch which fell behind is ended");
+        for _ in 0..10 {
+            let request = Request::new(QuantityChangeRequest {
+                sku: "FIRST".into(),
+                change: 1,
+            });
+            inventory.update_quantity(request).await?;
+        }
+        let status = loop {
+            match tokio::time::timeout(timeout, stream.next()).await?.unwrap() {
+                Ok(_) => continue,
+                Err(status) => break status,
+            }
+        };
+        assert_eq!(status.code(), tonic::Code::DataLoss);
+        assert!(tokio::time::timeout(timeout, stream.next())
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn items_without_stock() -> Result<(), Error> {
+        let rates = RateTable::new([("EUR", 0.5)]);
+        let inventory = StoreInventory::default().with_rates(rates);
+
+        info!("storing an item without stock, which Add would have rejected");
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "NOSTOCK".into(),
+            }),
+            ..Default::default()
+        };
+        inventory
+            .shard("NOSTOCK")
+            .write()
+            .await
+            .insert("NOSTOCK".into(), item.clone())
+            .await
+            .unwrap();
+
+        info!("verifying quantity and price updates are rejected as client errors");
+        let request = Request::new(QuantityChangeRequest {
+            sku: "NOSTOCK".into(),
+            change: 1,
+        });
+        let status = inventory.update_quantity(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoStockToUpdate)
+        );
+
+        let request = Request::new(PriceChangeRequest {
+            sku: "NOSTOCK".into(),
+            price: 2.49,
+            ..Default::default()
+        });
+        let status = inventory.update_price(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoStockToUpdate)
+        );
+
+        info!("verifying reservations and swaps are rejected the same way");
+        let request = Request::new(ReservationRequest {
+            sku: "NOSTOCK".into(),
+            quantity: 1,
+        });
+        let status = inventory.reserve(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoStockToUpdate)
+        );
+
+        let stocked = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "STOCKED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(stocked)).await?;
+        let request = Request::new(SwapStockRequest {
+            first_sku: "STOCKED".into(),
+            second_sku: "NOSTOCK".into(),
+        });
+        let status = inventory.swap_stock(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoStockToUpdate)
+        );
+        let stored = inventory.get(get_request("STOCKED")).await?.into_inner();
+        assert_eq!(item_quantity(&stored), 42);
+
+        info!("verifying the item was left as it was");
+        let stored = inventory.get(get_request("NOSTOCK")).await?.into_inner();
+        assert_eq!(stored, item);
+
+        info!("verifying reads which look at stock pass over the item");
+        let mut request = Request::new(GetRequest {
+            sku: "NOSTOCK".into(),
+            include_reservations: true,
+        });
+        request.metadata_mut().insert("x-currency", "EUR".parse()?);
+        let stored = inventory.get(request).await?.into_inner();
+        assert_eq!((stored.availability, stored.converted), (None, None));
+        let request = Request::new(LowStockRequest {});
+        let low = inventory.list_low_stock(request).await?.into_inner();
+        assert!(low.items.is_empty());
+        let request = Request::new(InventoryStatsRequest {});
+        inventory.inventory_stats(request).await?;
+        let request = Request::new(PriceBucketsRequest {
+            boundaries: vec![1.0],
+        });
+        inventory.group_by_price_bucket(request).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn server_info() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let info = || async {
+            let request = Request::new(ServerInfoRequest {});
+            inventory
+                .get_server_info(request)
+                .await
+                .map(|r| r.into_inner())
+        };
+
+        info!("verifying the build is reported");
+        let first = info().await?;
+        assert_eq!(first.version, env!("CARGO_PKG_VERSION"));
+        assert!(first.built_at > 0);
+        assert_eq!(
+            first.git_sha,
+            option_env!("STORE_GIT_SHA").unwrap_or_default()
+        );
+
+        info!("verifying the uptime counts from when the inventory was created");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let second = info().await?;
+        assert!(second.uptime_ms >= first.uptime_ms + 20);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn count() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let count = || async {
+            let request = Request::new(CountRequest {});
+            inventory.count(request).await.map(|r| r.into_inner().count)
+        };
+
+        info!("verifying an empty inventory has no items");
+        assert_eq!(count().await?, 0);
+
+        info!("verifying items are counted across shards as they're added and removed");
+        for index in 0..100 {
+            let item = Item {
+                identifier: Some(ItemIdentifier {
+                    sku: format!("SKU{}", index),
+                }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 1,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        assert_eq!(count().await?, 100);
+        inventory.remove(remove_request("SKU0")).await?;
+        assert_eq!(count().await?, 99);
+
+        Ok(())
+    }
+
+    #[test]
+    fn skus_and_prices() {
+        info!("verifying SKUs must not be empty");
+        assert!(validate_sku("SKU").is_ok());
+        let status = validate_sku("").unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::EmptySku)
+        );
+
+        info!("verifying prices must be at least a cent once rounded");
+        for price in [0.01, 0.006, 1.79, f32::MAX] {
+            assert!(validate_price(price).is_ok(), "{}", price);
+        }
+        for price in [0.0, 0.004, -1.0, f32::NAN, f32::NEG_INFINITY] {
+            let status = validate_price(price).unwrap_err();
+            let err = InventoryError::from_status(&status);
+            assert_eq!(err, Some(InventoryError::BadPrice), "{}", price);
+        }
+    }
+
+    #[tokio::test]
+    async fn set_quantity() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "COUNTED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+        let set = |sku: &str, quantity| {
+            Request::new(SetQuantityRequest {
+                sku: sku.into(),
+                quantity,
+            })
+        };
+
+        info!("setting the quantity to a known count and reading it back");
+        for quantity in [7, 0, u32::MAX] {
+            let response = inventory.set_quantity(set("COUNTED", quantity)).await?;
+            assert_eq!(response.into_inner().quantity, quantity);
+            let stored = inventory.get(get_request("COUNTED")).await?.into_inner();
+            assert_eq!(item_quantity(&stored), quantity);
+        }
+
+        info!("verifying empty SKUs and missing items are rejected");
+        let status = inventory.set_quantity(set("", 1)).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::EmptySku)
+        );
+        let status = inventory
+            .set_quantity(set("DOESNTEXIST", 1))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expiring_items() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        for (sku, expires_at) in [
+            ("EXPIRED", Some(1)),
+            ("FRESH", Some(u64::MAX)),
+            ("LASTING", None),
+        ] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                expires_at,
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        let request = watch_request("EXPIRED");
+        let mut stream = inventory.watch(request).await?.into_inner();
+
+        info!("verifying only the expired item is drained");
+        assert_eq!(inventory.drain_expired().await, 1);
+        let status = inventory.get(get_request("EXPIRED")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        for sku in ["FRESH", "LASTING"] {
+            inventory.get(get_request(sku)).await?;
+        }
+
+        info!("verifying watches of the expired item are ended");
+        let timeout = Duration::from_millis(100);
+        let status = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        info!("verifying nothing is left to drain");
+        assert_eq!(inventory.drain_expired().await, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_disconnects() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "WATCHED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+
+        info!("opening watches of the item and of the whole inventory");
+        let request = watch_request("WATCHED");
+        let watch = inventory.watch(request).await?.into_inner();
+        let request = Request::new(WatchAllRequest {});
+        let watch_all = inventory.watch_all(request).await?.into_inner();
+        assert_eq!(inventory.metrics.active_watchers(), 2);
+
+        info!("verifying the watches end once their clients go away");
+        drop(watch);
+        drop(watch_all);
+        let ended = async {
+            while inventory.metrics.active_watchers() > 0 {
+                tokio::task::yield_now().await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), ended).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn batch_get() -> Result<(), Error> {
+        let inventory = StoreInventory::with_shards(4).with_batch_budget(64);
+        for sku in ["FIRST", "SECOND", "THIRD"] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        let batch = |skus: &[&str]| {
+            let skus = skus.iter().map(|sku| sku.to_string()).collect();
+            Request::new(BatchGetRequest { skus })
+        };
+
+        info!("verifying found and missing items are reported in request order");
+        let response = inventory
+            .batch_get(batch(&["THIRD", "MISSING", "FIRST", "THIRD", "GONE"]))
+            .await?
+            .into_inner();
+        let found: Vec<String> = response.items.iter().map(item_sku).collect();
+        assert_eq!(found, ["THIRD", "FIRST"]);
+        assert_eq!(response.missing, ["MISSING", "GONE"]);
+
+        info!("verifying a batch with an invalid SKU is rejected");
+        let status = inventory
+            .batch_get(batch(&["FIRST", ""]))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        info!("verifying batches over the budget are rejected");
+        let status = inventory
+            .batch_get(batch(&["FIRST"; 16]))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BigBatch)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn batch_remove() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        for sku in ["FIRST", "SECOND", "KEPT"] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        let batch = |skus: &[&str]| {
+            let skus = skus.iter().map(|sku| sku.to_string()).collect();
+            Request::new(BatchRemoveRequest { skus })
+        };
+
+        info!("verifying a batch with an invalid SKU removes nothing");
+        let status = inventory
+            .batch_remove(batch(&["FIRST", ""]))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        inventory.get(get_request("FIRST")).await?;
+
+        info!("verifying removed and missing items are counted");
+        let response = inventory
+            .batch_remove(batch(&["FIRST", "SECOND", "FIRST", "MISSING"]))
+            .await?
+            .into_inner();
+        assert_eq!((response.removed, response.missing), (2, 2));
+        for sku in ["FIRST", "SECOND"] {
+            let status = inventory.get(get_request(sku)).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::NotFound);
+            assert!(inventory.removed.lock().await.contains_key(sku));
+        }
+        inventory.get(get_request("KEPT")).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tags() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = |sku: &str, tags: &[&str]| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        let list = |tag: &str| {
+            let request = Request::new(TagFilter { tag: tag.into() });
+            let inventory = &inventory;
+            async move {
+                let items = inventory.list_by_tag(request).await?.into_inner().items;
+                Ok::<Vec<String>, tonic::Status>(items.iter().map(item_sku).collect())
+            }
+        };
+
+        info!("adding items with tags");
+        inventory
+            .add(Request::new(item("B", &["produce", "organic"])))
+            .await?;
+        inventory.add(Request::new(item("A", &["produce"]))).await?;
+        inventory.add(Request::new(item("C", &[]))).await?;
+        let b = inventory.get(get_request("B")).await?.into_inner();
+        assert_eq!(b.tags, ["produce", "organic"]);
+
+        info!("filtering items by tag");
+        assert_eq!(list("produce").await?, ["A", "B"]);
+        assert_eq!(list("organic").await?, ["B"]);
+        assert!(list("Produce").await?.is_empty());
+        let status = list("").await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BadTag)
+        );
+
+        info!("verifying tags are limited in number and size");
+        let many: Vec<String> = (0..=32).map(|n| format!("tag{}", n)).collect();
+        let many: Vec<&str> = many.iter().map(String::as_str).collect();
+        let long = "t".repeat(65);
+        for (tags, err) in [
+            (many, InventoryError::BigTags),
+            (vec![long.as_str()], InventoryError::BigTags),
+            (vec!["produce", ""], InventoryError::BadTag),
+        ] {
+            let request = Request::new(item("INVALID", &tags));
+            let status = inventory.add(request).await.unwrap_err();
+            assert_eq!(InventoryError::from_status(&status), Some(err));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn physical() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = |sku: &str, physical: Option<ItemPhysical>| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            physical,
+            ..Default::default()
+        };
+        let weighing = |weight_grams: f32| {
+            Some(ItemPhysical {
+                weight_grams,
+                length_mm: 80.0,
+                width_mm: 80.0,
+                height_mm: 75.5,
+            })
+        };
+        let list = |min_grams: f32, max_grams: Option<f32>| {
+            let request = Request::new(WeightRangeRequest {
+                min_grams,
+                max_grams,
             });
+            let inventory = &inventory;
+            async move {
+                let items = inventory
+                    .list_by_weight_range(request)
+                    .await?
+                    .into_inner()
+                    .items;
+                Ok::<Vec<String>, tonic::Status>(items.iter().map(item_sku).collect())
+            }
+        };
+
+        info!("adding items with weights and dimensions");
+        inventory
+            .add(Request::new(item("C", weighing(500.0))))
+            .await?;
+        inventory
+            .add(Request::new(item("A", weighing(180.0))))
+            .await?;
+        inventory
+            .add(Request::new(item("B", weighing(0.0))))
+            .await?;
+        inventory.add(Request::new(item("D", None))).await?;
+        let a = inventory.get(get_request("A")).await?.into_inner();
+        assert_eq!(a.physical, weighing(180.0));
+
+        info!("listing items by weight");
+        assert_eq!(list(0.0, None).await?, ["A", "B", "C"]);
+        assert_eq!(list(180.0, Some(500.0)).await?, ["A", "C"]);
+        assert_eq!(list(180.0, Some(180.0)).await?, ["A"]);
+        assert!(list(501.0, None).await?.is_empty());
+        for (min_grams, max_grams) in [(-1.0, None), (f32::NAN, None), (2.0, Some(1.0))] {
+            let status = list(min_grams, max_grams).await.unwrap_err();
+            assert_eq!(
+                InventoryError::from_status(&status),
+                Some(InventoryError::BadWeightRange)
+            );
+        }
+
+        info!("verifying weights and dimensions can't be negative");
+        let negative = [
+            ItemPhysical {
+                weight_grams: -1.0,
+                ..Default::default()
+            },
+            ItemPhysical {
+                height_mm: -0.5,
+                ..Default::default()
+            },
+            ItemPhysical {
+                length_mm: f32::NAN,
+                ..Default::default()
+            },
+        ];
+        for physical in negative {
+            let request = Request::new(item("INVALID", Some(physical)));
+            let status = inventory.add(request).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+            assert_eq!(
+                InventoryError::from_status(&status),
+                Some(InventoryError::BadPhysical)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn field_violations() -> Result<(), Error> {
+        let mut client = get_client().await;
+        let fields = |status: tonic::Status| -> Vec<(String, String)> {
+            let violations = details::field_violations(&status).into_iter();
+            violations.map(|v| (v.field, v.description)).collect()
+        };
+        let violation = |field: &str, description: &str| vec![(field.into(), description.into())];
+
+        info!("verifying invalid items name the invalid field");
+        let item = |sku: &str, price: f32| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price,
+                quantity: 1,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let status = client.add(item("", 1.79)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            fields(status),
+            violation("identifier.sku", InventoryError::EmptySku.message())
+        );
+        let status = client.add(item("FIELDS", 0.0)).await.unwrap_err();
+        assert_eq!(
+            fields(status),
+            violation("stock.price", InventoryError::BadPrice.message())
+        );
+        let status = client.add(Item::default()).await.unwrap_err();
+        assert_eq!(
+            fields(status),
+            violation("identifier", InventoryError::NoId.message())
+        );
+
+        info!("verifying invalid requests name the invalid field");
+        let request = Request::new(PriceChangeRequest {
+            sku: "FIELDS".into(),
+            price: -1.0,
+            ..Default::default()
         });
+        let status = client.update_price(request).await.unwrap_err();
+        assert_eq!(
+            fields(status),
+            violation("price", InventoryError::BadPrice.message())
+        );
 
-        loop {
-            match InventoryClient::connect("http://127.0.0.1:8080").await {
-                Ok(client) => return client,
-                Err(_) => println!("waiting for server connection"),
-            };
+        info!("verifying the details are kept alongside the failed operation");
+        let request = Request::new(ApplyBatchRequest {
+            operations: vec![Operation {
+                operation: Some(BatchOperation::Add(item("", 1.79))),
+            }],
+        });
+        let status = client.apply_batch(request).await.unwrap_err();
+        assert_eq!(status.metadata().get("x-failed-operation").unwrap(), "0");
+        assert_eq!(
+            fields(status),
+            violation("identifier.sku", InventoryError::EmptySku.message())
+        );
+
+        info!("verifying errors which aren't about a field have no violations");
+        let status = client.get(get_request("MISSING")).await.unwrap_err();
+        assert!(fields(status).is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stale_price_updates() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: "CAS".into() }),
+            stock: Some(ItemStock {
+                price: 1.99,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+        let change = |price: f32, expected_price: f32| PriceChangeRequest {
+            sku: "CAS".into(),
+            price,
+            expected_price: Some(expected_price),
+            ..Default::default()
+        };
+
+        info!("updating the price from the price two clients both read");
+        let response = inventory
+            .update_price(Request::new(change(2.49, 1.99)))
+            .await?;
+        assert_eq!(response.into_inner().price, 2.49);
+
+        info!("verifying the second client's update from the stale price is rejected");
+        let request = Request::new(change(2.79, 1.99));
+        let status = inventory.update_price(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::StalePrice)
+        );
+        let stored = inventory.get(get_request("CAS")).await?.into_inner();
+        assert_eq!(stored.stock.unwrap().price, 2.49);
+
+        info!("verifying the update applies once based on the current price");
+        let response = inventory
+            .update_price(Request::new(change(2.79, 2.49)))
+            .await?;
+        assert_eq!(response.into_inner().price, 2.79);
+
+        info!("verifying bulk updates honor the expected price too");
+        let request = Request::new(BulkPriceRequest {
+            changes: vec![change(2.99, 2.49)],
+        });
+        let results = inventory
+            .bulk_update_price(request)
+            .await?
+            .into_inner()
+            .results;
+        assert_eq!(results[0].outcome(), PriceOutcome::Failed);
+        assert_eq!(results[0].error, InventoryError::StalePrice.message());
+        assert_eq!(results[0].price, 2.79);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_heartbeats() -> Result<(), Error> {
+        let interval = Duration::from_millis(50);
+        let inventory = StoreInventory::default().with_watch_heartbeat(interval);
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: "IDLE".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+        let request = watch_request("IDLE");
+        let mut stream = inventory.watch(request).await?.into_inner();
+        let timeout = Duration::from_secs(1);
+
+        info!("verifying an idle watch sends heartbeats");
+        for _ in 0..2 {
+            let event = tokio::time::timeout(timeout, stream.next())
+                .await?
+                .unwrap()?;
+            assert_eq!(event.event, Some(WatchedEvent::Heartbeat(Heartbeat {})));
         }
+
+        info!("verifying changes are still delivered between heartbeats");
+        let request = Request::new(QuantityChangeRequest {
+            sku: "IDLE".into(),
+            change: 1,
+        });
+        inventory.update_quantity(request).await?;
+        let changed = loop {
+            let event = tokio::time::timeout(timeout, stream.next())
+                .await?
+                .unwrap()?;
+            if let Some(WatchedEvent::Item(item)) = event.event {
+                break item;
+            }
+        };
+        assert_eq!(item_quantity(&changed), 43);
+
+        info!("verifying watches send no heartbeats unless configured to");
+        let inventory = StoreInventory::default();
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: "IDLE".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+        let request = watch_request("IDLE");
+        let mut stream = inventory.watch(request).await?.into_inner();
+        assert!(tokio::time::timeout(interval * 3, stream.next())
+            .await
+            .is_err());
+
+        Ok(())
     }
 
-    // -------------------------------------------------------------------------
-    // Tests
-    // -------------------------------------------------------------------------
+    #[tokio::test]
+    async fn low_stock() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let item = |sku: &str, quantity: u32, reorder_threshold: Option<u32>| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity,
+                reorder_threshold,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let low_stock = || async {
+            let response = inventory.list_low_stock(Request::new(LowStockRequest {}));
+            let items = response.await.unwrap().into_inner().items;
+            items.iter().map(item_sku).collect::<Vec<_>>()
+        };
+
+        info!("adding items above, at and below their thresholds, and without one");
+        for item in [
+            item("ABOVE", 10, Some(5)),
+            item("BELOW", 2, Some(5)),
+            item("AT", 5, Some(5)),
+            item("EMPTY", 0, Some(0)),
+            item("UNTRACKED", 0, None),
+        ] {
+            inventory.add(Request::new(item)).await?;
+        }
+        assert_eq!(low_stock().await, ["AT", "BELOW", "EMPTY"]);
+
+        info!("verifying items are listed once their quantity falls to the threshold");
+        let request = Request::new(SetQuantityRequest {
+            sku: "ABOVE".into(),
+            quantity: 5,
+        });
+        inventory.set_quantity(request).await?;
+        assert_eq!(low_stock().await, ["ABOVE", "AT", "BELOW", "EMPTY"]);
+
+        info!("verifying thresholds can be changed and cleared");
+        let threshold = |sku: &str, threshold: Option<u32>| {
+            Request::new(ReorderThresholdRequest {
+                sku: sku.into(),
+                threshold,
+            })
+        };
+        inventory
+            .set_reorder_threshold(threshold("ABOVE", Some(4)))
+            .await?;
+        inventory
+            .set_reorder_threshold(threshold("AT", None))
+            .await?;
+        inventory
+            .set_reorder_threshold(threshold("UNTRACKED", Some(1)))
+            .await?;
+        assert_eq!(low_stock().await, ["BELOW", "EMPTY", "UNTRACKED"]);
+        let stored = inventory.get(get_request("AT")).await?.into_inner();
+        assert_eq!(stored.stock.unwrap().reorder_threshold, None);
+
+        info!("verifying thresholds of missing items are rejected");
+        let status = inventory
+            .set_reorder_threshold(threshold("DOESNTEXIST", Some(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        Ok(())
+    }
 
     #[tokio::test]
-    async fn inventory_management() -> Result<(), Error> {
-        let mut client = get_client().await;
+    async fn max_quantity() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_max_quantity(1000);
+        let item = |sku: &str, quantity: u32| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
 
-        // ---------------------------------------------------------------------
-        // test adding items
-        // ---------------------------------------------------------------------
+        info!("verifying items can be added with up to the maximum quantity");
+        inventory.add(Request::new(item("CAPPED", 1000))).await?;
 
-        info!("adding a single item to the inventory");
+        info!("verifying items above the maximum quantity are rejected");
+        for quantity in [1001, u32::MAX] {
+            let status = inventory
+                .add(Request::new(item("GARBAGE", quantity)))
+                .await
+                .unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+            assert_eq!(
+                InventoryError::from_status(&status),
+                Some(InventoryError::BigInitialQuantity)
+            );
+            assert_eq!(
+                details::field_violations(&status)[0].field,
+                "stock.quantity"
+            );
+        }
+        let status = inventory.get(get_request("GARBAGE")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        info!("verifying the cap applies to upserts and batches too");
+        let status = inventory
+            .upsert(Request::new(item("CAPPED", 1001)))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BigInitialQuantity)
+        );
+        let request = Request::new(ApplyBatchRequest {
+            operations: vec![Operation {
+                operation: Some(BatchOperation::Add(item("GARBAGE", 1001))),
+            }],
+        });
+        let status = inventory.apply_batch(request).await.unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::BigInitialQuantity)
+        );
+
+        info!("verifying quantity changes to added items aren't capped");
+        let request = Request::new(QuantityChangeRequest {
+            sku: "CAPPED".into(),
+            change: 1,
+        });
+        assert_eq!(
+            inventory
+                .update_quantity(request)
+                .await?
+                .into_inner()
+                .quantity,
+            1001
+        );
+
+        info!("verifying there's no cap by default");
+        let inventory = StoreInventory::default();
+        inventory.add(Request::new(item("HUGE", u32::MAX))).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn field_limits() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_field_limits(FieldLimits {
+            sku: 8,
+            name: 16,
+            description: 32,
+        });
+        let item = |sku: &str, name: &str, description: &str| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            information: Some(ItemInformation {
+                name: Some(name.into()),
+                description: Some(description.into()),
+            }),
+            ..Default::default()
+        };
+        let rejected = |status: tonic::Status, error: InventoryError, field: &str| {
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+            assert_eq!(InventoryError::from_status(&status), Some(error));
+            assert_eq!(details::field_violations(&status)[0].field, field);
+        };
+
+        info!("verifying items can be added with fields up to the limits");
+        let longest = item(&"S".repeat(8), &"n".repeat(16), &"d".repeat(32));
+        inventory.add(Request::new(longest)).await?;
+
+        info!("verifying items with over-long fields are rejected");
+        let status = inventory
+            .add(Request::new(item(&"S".repeat(9), "name", "description")))
+            .await
+            .unwrap_err();
+        rejected(status, InventoryError::BigSku, "identifier.sku");
+        let status = inventory
+            .add(Request::new(item("NAME", &"n".repeat(17), "description")))
+            .await
+            .unwrap_err();
+        rejected(status, InventoryError::BigName, "information.name");
+        let description = "d".repeat(4 * 1024 * 1024);
+        let status = inventory
+            .upsert(Request::new(item("DESC", "name", &description)))
+            .await
+            .unwrap_err();
+        rejected(
+            status,
+            InventoryError::BigDescription,
+            "information.description",
+        );
+        let status = inventory.get(get_request("DESC")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        info!("verifying items can't be changed to have over-long fields");
+        let request = Request::new(InformationChangeRequest {
+            sku: "S".repeat(8),
+            information: Some(ItemInformation {
+                name: None,
+                description: Some(description),
+            }),
+            ..Default::default()
+        });
+        let status = inventory.update_information(request).await.unwrap_err();
+        rejected(
+            status,
+            InventoryError::BigDescription,
+            "information.description",
+        );
+        let request = Request::new(RenameSkuRequest {
+            old_sku: "S".repeat(8),
+            new_sku: "S".repeat(9),
+        });
+        let status = inventory.rename_sku(request).await.unwrap_err();
+        rejected(status, InventoryError::BigSku, "new_sku.sku");
+
+        info!("verifying the limits apply by default");
+        let inventory = StoreInventory::default();
+        let status = inventory
+            .add(Request::new(item(&"S".repeat(129), "name", "description")))
+            .await
+            .unwrap_err();
+        rejected(status, InventoryError::BigSku, "identifier.sku");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn typed_client() -> Result<(), Error> {
+        // the typed client is in the library, and talks to the server over the
+        // wire like any other client
+        get_client().await;
+        let mut api = InventoryApi::connect("http://127.0.0.1:8080").await?;
         let sku = Uuid::new_v4().to_string();
-        let item_id = ItemIdentifier { sku: sku.clone() };
-        let item_stock = ItemStock {
-            price: 1.79,
-            quantity: 42,
+
+        info!("adding an item and reading it back");
+        let added = api.add_item(sku.clone(), 1.789, 42).await?;
+        assert_eq!(added.stock.unwrap().price, 1.79);
+        let item = api.get_item(sku.clone()).await?.unwrap();
+        assert_eq!(item.stock.unwrap().quantity, 42);
+
+        info!("verifying changes are streamed until the item is removed");
+        let mut changes = api.watch_item(sku.clone()).await?.unwrap();
+        assert_eq!(api.update_quantity(sku.clone(), -2).await?, Some(40));
+        assert_eq!(api.update_price(sku.clone(), 2.49).await?, Some(2.49));
+        let timeout = Duration::from_secs(1);
+        for (quantity, price) in [(40, 1.79), (40, 2.49)] {
+            let changed = tokio::time::timeout(timeout, changes.next())
+                .await?
+                .unwrap()?
+                .stock
+                .unwrap();
+            assert_eq!((changed.quantity, changed.price), (quantity, price));
+        }
+        assert!(api.remove_item(sku.clone()).await?);
+        assert!(tokio::time::timeout(timeout, changes.next())
+            .await?
+            .is_none());
+
+        info!("verifying missing items are reported as None");
+        assert_eq!(api.get_item(sku.clone()).await?, None);
+        assert!(!api.remove_item(sku.clone()).await?);
+        assert_eq!(api.update_quantity(sku.clone(), 1).await?, None);
+        assert_eq!(api.update_price(sku.clone(), 1.0).await?, None);
+        assert!(api.watch_item(sku.clone()).await?.is_none());
+
+        info!("verifying other errors are returned");
+        let status = api.add_item("", 1.79, 42).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn client_pool() -> Result<(), Error> {
+        // the pool is in the library too, and hands out clients which share
+        // one connection
+        get_client().await;
+        let pool = ClientPool::new("http://127.0.0.1:8080")?;
+        let sku = Uuid::new_v4().to_string();
+
+        info!("adding an item through a client from the pool");
+        let item = crate::store::Item {
+            identifier: Some(crate::store::ItemIdentifier { sku: sku.clone() }),
+            stock: Some(crate::store::ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
         };
-        let item = Item {
-            identifier: Some(item_id.to_owned()),
-            stock: Some(item_stock.to_owned()),
-            information: None,
+        pool.get().await?.add(item).await?;
+
+        info!("verifying concurrent calls through clones of the pool");
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                let sku = sku.clone();
+                tokio::spawn(async move {
+                    let request = crate::store::QuantityChangeRequest { sku, change: 1 };
+                    pool.get().await.unwrap().update_quantity(request).await
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await??;
+        }
+
+        info!("verifying the calls were all made");
+        let request = crate::store::GetRequest {
+            sku: sku.clone(),
+            include_reservations: false,
         };
-        let request = Request::new(item.clone());
-        let response = client.add(request).await?;
-        assert_eq!(response.into_inner().status, "success");
+        let item = pool.get().await?.get(request).await?.into_inner();
+        assert_eq!(item.stock.unwrap().quantity, 50);
 
-        info!("verifying that items with an blank SKU are rejected");
-        let bad_item = Item {
-            identifier: Some(ItemIdentifier { sku: "".into() }),
-            stock: Some(item_stock.clone()),
-            information: None,
+        let mut api = InventoryApi::new(pool.channel().await?);
+        assert!(api.remove_item(sku).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename_sku() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let rename = |old_sku: &str, new_sku: &str| {
+            Request::new(RenameSkuRequest {
+                old_sku: old_sku.into(),
+                new_sku: new_sku.into(),
+            })
         };
-        let request = Request::new(bad_item);
-        let response = client.add(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::EMPTY_SKU_ERR);
+        for (sku, quantity) in [("OLD", 42), ("TAKEN", 7)] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        let request = watch_request("OLD");
+        let mut stream = inventory.watch(request).await?.into_inner();
+        let timeout = Duration::from_millis(100);
 
-        info!("verifying that items with no ID are rejected");
-        let bad_item = Item {
-            identifier: None,
-            stock: Some(item_stock.clone()),
-            information: None,
+        info!("verifying an item can't be renamed onto an existing item");
+        let status = inventory
+            .rename_sku(rename("OLD", "TAKEN"))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::AlreadyExists);
+        assert_eq!(
+            item_quantity(&inventory.get(get_request("TAKEN")).await?.into_inner()),
+            7
+        );
+
+        info!("verifying a missing item can't be renamed");
+        let status = inventory
+            .rename_sku(rename("DOESNTEXIST", "NEW"))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        info!("verifying an item can't be renamed to its own SKU");
+        let status = inventory
+            .rename_sku(rename("OLD", "OLD"))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::SameRename)
+        );
+
+        info!("verifying a renamed item moves to its new SKU");
+        let renamed = inventory
+            .rename_sku(rename("OLD", "NEW"))
+            .await?
+            .into_inner();
+        assert_eq!(item_sku(&renamed.item.unwrap()), "NEW");
+        let item = inventory.get(get_request("NEW")).await?.into_inner();
+        assert_eq!(item_sku(&item), "NEW");
+        assert_eq!(item_quantity(&item), 42);
+        let status = inventory.get(get_request("OLD")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        info!("verifying watches of the old SKU see the item removed");
+        let status = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn strict_remove() -> Result<(), Error> {
+        let inventory = StoreInventory::default();
+        let strict_remove = |sku: &str| {
+            Request::new(RemoveRequest {
+                sku: sku.into(),
+                strict: true,
+            })
         };
-        let request = Request::new(bad_item);
-        let response = client.add(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::NO_ID_ERR);
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "REMOVED".into(),
+            }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
 
-        info!("verifying that items marked as $0.00 in cost are rejected");
-        let bad_item = Item {
-            identifier: Some(ItemIdentifier { sku: "FREE".into() }),
+        info!("verifying a strict remove of an existing item succeeds");
+        let response = inventory.remove(strict_remove("REMOVED")).await?;
+        assert!(response.into_inner().existed);
+        let status = inventory.get(get_request("REMOVED")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        info!("verifying a strict remove of a missing item fails");
+        let status = inventory
+            .remove(strict_remove("REMOVED"))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
+
+        info!("verifying a lenient remove of a missing item still succeeds");
+        let response = inventory.remove(remove_request("REMOVED")).await?;
+        assert!(!response.into_inner().existed);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn audit_log() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_admin_token("secret");
+        let audit_log = |limit: u32| {
+            let mut request = Request::new(AuditLogRequest { limit });
+            let authorization = "Bearer secret".parse().unwrap();
+            request
+                .metadata_mut()
+                .insert("authorization", authorization);
+            request
+        };
+
+        info!("making a change of each audited kind");
+        let item = Item {
+            identifier: Some(ItemIdentifier {
+                sku: "AUDITED".into(),
+            }),
             stock: Some(ItemStock {
-                price: 0.00,
+                price: 1.79,
                 quantity: 42,
+                ..Default::default()
             }),
-            information: None,
+            ..Default::default()
         };
-        let request = Request::new(bad_item);
-        let response = client.add(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::BAD_PRICE_ERR);
+        inventory.add(Request::new(item)).await?;
+        let request = Request::new(QuantityChangeRequest {
+            sku: "AUDITED".into(),
+            change: -2,
+        });
+        inventory.update_quantity(request).await?;
+        let request = Request::new(PriceChangeRequest {
+            sku: "AUDITED".into(),
+            price: 2.49,
+            ..Default::default()
+        });
+        inventory.update_price(request).await?;
+        let request = remove_request("AUDITED");
+        inventory.remove(request).await?;
 
-        info!("verifying that items with no stock information are rejected");
-        let bad_item = Item {
-            identifier: Some(ItemIdentifier { sku: "NONE".into() }),
-            stock: None,
-            information: None,
+        info!("verifying each change was audited with the item before and after it");
+        let entries = inventory
+            .get_audit_log(audit_log(0))
+            .await?
+            .into_inner()
+            .entries;
+        let operations: Vec<&str> = entries.iter().map(|e| e.operation.as_str()).collect();
+        assert_eq!(
+            operations,
+            ["Add", "UpdateQuantity", "UpdatePrice", "Remove"]
+        );
+        assert!(entries.iter().all(|entry| entry.sku == "AUDITED"));
+        assert!(entries[0].before.is_none());
+        assert_eq!(item_quantity(entries[0].after.as_ref().unwrap()), 42);
+        assert_eq!(item_quantity(entries[1].before.as_ref().unwrap()), 42);
+        assert_eq!(item_quantity(entries[1].after.as_ref().unwrap()), 40);
+        assert_eq!(item_price(entries[2].before.as_ref().unwrap()), 1.79);
+        assert_eq!(item_price(entries[2].after.as_ref().unwrap()), 2.49);
+        assert_eq!(item_price(entries[3].before.as_ref().unwrap()), 2.49);
+        assert!(entries[3].after.is_none());
+
+        info!("verifying the most recent entries can be retrieved");
+        let entries = inventory
+            .get_audit_log(audit_log(1))
+            .await?
+            .into_inner()
+            .entries;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, "Remove");
+
+        info!("verifying removing a missing item isn't audited");
+        let request = remove_request("AUDITED");
+        inventory.remove(request).await?;
+        let entries = inventory
+            .get_audit_log(audit_log(0))
+            .await?
+            .into_inner()
+            .entries;
+        assert_eq!(entries.len(), 4);
+
+        info!("verifying the audit log requires admin credentials");
+        let request = Request::new(AuditLogRequest { limit: 0 });
+        let status = inventory.get_audit_log(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn audit_log_batches() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_admin_token("secret");
+        let admin = |mut request: Request<_>| {
+            let authorization = "Bearer secret".parse().unwrap();
+            request
+                .metadata_mut()
+                .insert("authorization", authorization);
+            request
+        };
+        let audited = |inventory: &StoreInventory| {
+            inventory
+                .audit
+                .recent(0)
+                .into_iter()
+                .map(|entry| (entry.operation, entry.sku, entry.before, entry.after))
+                .collect::<Vec<_>>()
+        };
+        let item = |sku: &str, quantity: u32| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
         };
-        let request = Request::new(bad_item);
-        let response = client.add(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::NO_STOCK_ERR);
+        let operation = |operation| Operation {
+            operation: Some(operation),
+        };
+        inventory.add(Request::new(item("FIRST", 42))).await?;
+        inventory.add(Request::new(item("SECOND", 42))).await?;
 
-        info!("verifying that duplicate items are rejected");
-        let request = Request::new(item.clone());
-        let response = client.add(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::DUP_ITEM_ERR);
+        info!("verifying each item a batch changes is audited");
+        let request = Request::new(ApplyBatchRequest {
+            operations: vec![
+                operation(BatchOperation::Add(item("THIRD", 3))),
+                operation(BatchOperation::UpdateQuantity(QuantityChangeRequest {
+                    sku: "FIRST".into(),
+                    change: -2,
+                })),
+                operation(BatchOperation::Remove(ItemIdentifier {
+                    sku: "SECOND".into(),
+                })),
+            ],
+        });
+        inventory.apply_batch(request).await?;
+        let mut entries = audited(&inventory).split_off(2);
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(entries.len(), 3);
+        let (operation, sku, before, after) = &entries[0];
+        assert_eq!((operation.as_str(), sku.as_str()), ("ApplyBatch", "FIRST"));
+        assert_eq!(item_quantity(before.as_ref().unwrap()), 42);
+        assert_eq!(item_quantity(after.as_ref().unwrap()), 40);
+        let (_, sku, before, after) = &entries[1];
+        assert_eq!(sku, "SECOND");
+        assert!(before.is_some() && after.is_none());
+        let (_, sku, before, after) = &entries[2];
+        assert_eq!(sku, "THIRD");
+        assert!(before.is_none());
+        assert_eq!(item_quantity(after.as_ref().unwrap()), 3);
 
-        info!("adding a 1000 generic items to the inventory");
-        for i in 1000..2000 {
-            let item_id = ItemIdentifier {
-                sku: format!("SKU{}", i),
-            };
-            let item = Item {
-                identifier: Some(item_id),
-                stock: Some(item_stock.clone()),
-                information: None,
+        info!("verifying each item a snapshot adds, replaces or removes is audited");
+        let request = admin(Request::new(LoadSnapshotRequest {
+            items: vec![item("FIRST", 7), item("FOURTH", 4)],
+        }));
+        inventory.load_snapshot(request).await?;
+        let mut entries = audited(&inventory).split_off(5);
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+        let skus: Vec<&str> = entries.iter().map(|entry| entry.1.as_str()).collect();
+        assert_eq!(skus, ["FIRST", "FOURTH", "THIRD"]);
+        assert!(entries.iter().all(|entry| entry.0 == "LoadSnapshot"));
+        assert_eq!(item_quantity(entries[0].2.as_ref().unwrap()), 40);
+        assert_eq!(item_quantity(entries[0].3.as_ref().unwrap()), 7);
+        assert!(entries[1].2.is_none());
+        assert!(entries[2].2.is_some() && entries[2].3.is_none());
+
+        info!("verifying batches of removals are audited");
+        let request = Request::new(BatchRemoveRequest {
+            skus: vec!["FIRST".into(), "MISSING".into()],
+        });
+        inventory.batch_remove(request).await?;
+        let entries = audited(&inventory).split_off(8);
+        assert_eq!(entries.len(), 1);
+        let (operation, sku, before, after) = &entries[0];
+        assert_eq!((operation.as_str(), sku.as_str()), ("BatchRemove", "FIRST"));
+        assert_eq!(item_quantity(before.as_ref().unwrap()), 7);
+        assert!(after.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tail_logs() -> Result<(), Error> {
+        let inventory = StoreInventory::default().with_admin_token("secret");
+        let tail_logs = |backlog: u32, token: Option<&str>| {
+            let mut request = Request::new(TailLogsRequest { backlog });
+            if let Some(token) = token {
+                let authorization = format!("Bearer {}", token).parse().unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", authorization);
+            }
+            request
+        };
+        let line = |message: &str| LogLine {
+            message: message.into(),
+            ..Default::default()
+        };
+
+        info!("verifying logs can't be tailed without admin credentials");
+        for token in [None, Some("wrong")] {
+            let status = match inventory.tail_logs(tail_logs(1, token)).await {
+                Ok(_) => panic!("logs were tailed without admin credentials"),
+                Err(status) => status,
             };
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        }
 
-            let request = Request::new(item);
-            let response = client.add(request).await?;
-            assert_eq!(response.into_inner().status, "success");
+        info!("tailing recent and live log lines");
+        inventory.logs().publish(line("old"));
+        inventory.logs().publish(line("recent"));
+        let mut stream = inventory
+            .tail_logs(tail_logs(1, Some("secret")))
+            .await?
+            .into_inner();
+        inventory.logs().publish(line("live"));
+        for expected in ["recent", "live"] {
+            let line = stream.next().await.unwrap()?;
+            assert_eq!(line.message, expected);
         }
 
-        // ---------------------------------------------------------------------
-        // test updating an item's quantity
-        // ---------------------------------------------------------------------
+        Ok(())
+    }
 
-        info!("reducing item inventory by 35 units");
+    #[tokio::test]
+    async fn sqlite_storage() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("store.db");
+        let inventory = StoreInventory::with_storage(SqliteStorage::open(&path)?);
+        for sku in ["WATCHED", "KEPT"] {
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: sku.into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            inventory.add(Request::new(item)).await?;
+        }
+        let timeout = Duration::from_millis(100);
+
+        info!("verifying changes to items in the database are watched");
+        let request = watch_request("WATCHED");
+        let mut stream = inventory.watch(request).await?.into_inner();
         let request = Request::new(QuantityChangeRequest {
-            sku: sku.clone(),
-            change: -35,
+            sku: "WATCHED".into(),
+            change: 1,
         });
-        let response = client.update_quantity(request).await?;
-        assert_eq!(response.into_inner().status, "success");
+        inventory.update_quantity(request).await?;
+        let changed = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()?;
+        assert_eq!(item_quantity(&watched_item(changed)), 43);
+        inventory.remove(remove_request("WATCHED")).await?;
+        let status = tokio::time::timeout(timeout, stream.next())
+            .await?
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            InventoryError::from_status(&status),
+            Some(InventoryError::NoItem)
+        );
 
-        info!("verifying quantity change");
-        let request = Request::new(ItemIdentifier { sku: sku.clone() });
-        let quantity = item_quantity(&client.get(request).await?.into_inner());
-        assert_eq!(quantity, 7);
+        info!("verifying the items survive the inventory being reopened");
+        drop(inventory);
+        let inventory = StoreInventory::with_storage(SqliteStorage::open(&path)?);
+        let kept = inventory.get(get_request("KEPT")).await?.into_inner();
+        assert_eq!(item_quantity(&kept), 42);
+        let status = inventory.get(get_request("WATCHED")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
 
-        info!("increasing item inventory by 7 units");
-        let request = Request::new(QuantityChangeRequest {
-            sku: sku.clone(),
-            change: 7,
-        });
-        let response = client.update_quantity(request).await?;
-        assert_eq!(response.into_inner().status, "success");
+        Ok(())
+    }
 
-        info!("verifying quantity updates for no-SKU items are rejected");
-        let request = Request::new(QuantityChangeRequest {
-            sku: "".into(),
-            change: 1024,
-        });
-        let response = client.update_quantity(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::EMPTY_SKU_ERR);
+    // storage which fails to store items under SKUs starting with FAIL.
+    #[derive(Debug, Default)]
+    struct FailingStorage(MemoryStorage);
 
-        info!("verifying quantity updates that introduce no change are rejected");
-        let request = Request::new(QuantityChangeRequest {
-            sku: sku.clone(),
-            change: 0,
-        });
-        let response = client.update_quantity(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::EMPTY_QUANT_ERR);
+    #[tonic::async_trait]
+    impl Storage for FailingStorage {
+        async fn insert(
+            &mut self,
+            sku: String,
+            item: Item,
+        ) -> Result<Option<Item>, InventoryError> {
+            if sku.starts_with("FAIL") {
+                return Err(InventoryError::StorageFailure);
+            }
+            self.0.insert(sku, item).await
+        }
 
-        info!("verifying quantity updates for non-existent items are rejected");
-        let request = Request::new(QuantityChangeRequest {
-            sku: "DOESNTEXIST".into(),
-            change: 4098,
-        });
-        let response = client.update_quantity(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::NO_ITEM_ERR);
+        async fn get(&self, sku: &str) -> Result<Option<Item>, InventoryError> {
+            self.0.get(sku).await
+        }
 
-        info!("verifying quantity updates that would reduce below 0 are rejected");
-        let request = Request::new(QuantityChangeRequest {
-            sku: sku.clone(),
-            change: -15,
-        });
-        let response = client.update_quantity(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::UNSUFF_INV_ERR);
+        async fn remove(&mut self, sku: &str) -> Result<Option<Item>, InventoryError> {
+            self.0.remove(sku).await
+        }
 
-        info!("verifying current item quantity");
-        let request = Request::new(ItemIdentifier { sku: sku.clone() });
-        let quantity = item_quantity(&client.get(request).await?.into_inner());
-        assert_eq!(quantity, 14);
+        async fn update(&mut self, sku: &str, item: Item) -> Result<Option<Item>, InventoryError> {
+            self.0.update(sku, item).await
+        }
 
-        // ---------------------------------------------------------------------
-        // test updating an item's price
-        // ---------------------------------------------------------------------
+        async fn all(&self) -> Result<Vec<(String, Item)>, InventoryError> {
+            self.0.all().await
+        }
+    }
 
-        info!("increasing the price of an item to $2.49");
-        let request = Request::new(PriceChangeRequest {
-            sku: item_id.sku.clone(),
-            price: 2.49,
-        });
-        let response = client.update_price(request).await?;
-        assert_eq!(response.into_inner().status, "success");
+    #[tokio::test]
+    async fn failed_commits() -> Result<(), Error> {
+        let inventory =
+            StoreInventory::with_storage(FailingStorage::default()).with_admin_token("secret");
+        let item = |sku: &str| Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let failed = |status: Status| {
+            assert_eq!(
+                InventoryError::from_status(&status),
+                Some(InventoryError::StorageFailure)
+            );
+        };
+        for sku in ["KEPT", "MOVED"] {
+            inventory.add(Request::new(item(sku))).await?;
+        }
+        let snapshot = inventory.list(Request::new(ListRequest::default())).await?;
+        let mut changes = inventory.changes.subscribe();
 
-        info!("verifying price updates for items with no SKU are rejected");
-        let request = Request::new(PriceChangeRequest {
-            sku: "".into(),
-            price: 9.99,
-        });
-        let response = client.update_price(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::EMPTY_SKU_ERR);
+        info!("verifying items which fail to be stored aren't added");
+        let status = inventory.add(Request::new(item("FAILED"))).await;
+        failed(status.unwrap_err());
+        let status = inventory.upsert(Request::new(item("FAILED"))).await;
+        failed(status.unwrap_err());
 
-        info!("verifying price updates to $0.00 are rejected");
-        let request = Request::new(PriceChangeRequest {
-            sku: sku.clone(),
-            price: 0.00,
-        });
-        let response = client.update_price(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::BAD_PRICE_ERR);
+        info!("verifying a batch which fails to commit changes nothing");
+        let operations = vec![
+            BatchOperation::Remove(ItemIdentifier {
+                sku: "MOVED".into(),
+            }),
+            BatchOperation::Add(item("FAILED")),
+        ];
+        let operations = operations
+            .into_iter()
+            .map(|operation| Operation {
+                operation: Some(operation),
+            })
+            .collect();
+        let request = Request::new(ApplyBatchRequest { operations });
+        failed(inventory.apply_batch(request).await.unwrap_err());
 
-        info!("verifying price updates to a negative value are rejected");
-        let request = Request::new(PriceChangeRequest {
-            sku: sku.clone(),
-            price: -8096.64,
+        info!("verifying a rename which fails to commit leaves the item where it was");
+        let request = Request::new(RenameSkuRequest {
+            old_sku: "MOVED".into(),
+            new_sku: "FAILED".into(),
         });
-        let response = client.update_price(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::BAD_PRICE_ERR);
+        failed(inventory.rename_sku(request).await.unwrap_err());
 
-        info!("verifying price updates to a non-existent item are rejected");
-        let request = Request::new(PriceChangeRequest {
-            sku: "DOESNTEXIST".into(),
-            price: 299.99,
+        info!("verifying a snapshot which fails to load leaves the inventory as it was");
+        let mut request = Request::new(LoadSnapshotRequest {
+            items: vec![item("NEW"), item("FAILED")],
         });
-        let response = client.update_price(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::NO_ITEM_ERR);
+        let authorization = "Bearer secret".parse().unwrap();
+        request
+            .metadata_mut()
+            .insert("authorization", authorization);
+        failed(inventory.load_snapshot(request).await.unwrap_err());
 
-        info!("verifying price updates to the price already set are rejected");
-        let request = Request::new(PriceChangeRequest {
-            sku: sku.clone(),
-            price: 2.49,
-        });
-        let response = client.update_price(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::DUP_PRICE_ERR);
+        info!("verifying none of the failed changes were published, audited or kept");
+        assert!(matches!(
+            changes.try_recv(),
+            Err(broadcast::error::TryRecvError::Empty)
+        ));
+        let operations: Vec<String> = inventory
+            .audit
+            .recent(0)
+            .into_iter()
+            .map(|entry| entry.operation)
+            .collect();
+        assert_eq!(operations, ["Add", "Add"]);
+        let after = inventory.list(Request::new(ListRequest::default())).await?;
+        assert_eq!(after.into_inner(), snapshot.into_inner());
+        let request = Request::new(ChangedSinceRequest::default());
+        let changed = inventory.list_changed_since(request).await?.into_inner();
+        assert!(changed.removed_skus.is_empty());
 
-        info!("verifying current item price");
-        let request = Request::new(ItemIdentifier { sku: sku.clone() });
-        let price = item_price(&client.get(request).await?.into_inner());
-        assert_eq!(price, 2.49);
+        Ok(())
+    }
 
-        // ---------------------------------------------------------------------
-        // test retrieving items
-        // ---------------------------------------------------------------------
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn concurrent_reads() -> Result<(), Error> {
+        let inventory = Arc::new(StoreInventory::default());
 
-        info!("verifying that retrievals of items with no SKU are rejected");
-        let request = Request::new(ItemIdentifier { sku: "".into() });
-        let response = client.get(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::EMPTY_SKU_ERR);
+        info!("adding an item to read");
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: "READ".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
 
-        info!("verifying that retrievals of items which don't exist are rejected");
-        let request = Request::new(ItemIdentifier {
-            sku: "DOESNTEXIST".into(),
-        });
-        let response = client.get(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::NO_ITEM_ERR);
+        info!("verifying gets don't wait for a read already holding the item's shard");
+        let held = inventory.shard("READ").read().await;
+        let mut getters = Vec::new();
+        for _ in 0..64 {
+            let inventory = inventory.clone();
+            getters.push(tokio::spawn(async move {
+                let request = get_request("READ");
+                inventory.get(request).await.map(Response::into_inner)
+            }));
+        }
+        for getter in getters {
+            let item = tokio::time::timeout(Duration::from_secs(5), getter).await???;
+            assert_eq!(item_quantity(&item), 42);
+        }
 
-        // ---------------------------------------------------------------------
-        // test watching items
-        // ---------------------------------------------------------------------
+        info!("verifying changes still wait for reads to finish");
+        let updater = {
+            let inventory = inventory.clone();
+            tokio::spawn(async move {
+                let request = Request::new(QuantityChangeRequest {
+                    sku: "READ".into(),
+                    change: 1,
+                });
+                inventory.update_quantity(request).await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!updater.is_finished());
+        drop(held);
+        updater.await??;
+        let item = inventory.get(get_request("READ")).await?.into_inner();
+        assert_eq!(item_quantity(&item), 43);
 
-        // TODO
+        Ok(())
+    }
 
-        // ---------------------------------------------------------------------
-        // test removing items
-        // ---------------------------------------------------------------------
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn coalesced_gets() -> Result<(), Error> {
+        let inventory = Arc::new(StoreInventory::default().with_coalesced_gets());
 
-        info!("removing all added items");
-        let request = Request::new(item_id.clone());
-        let response = client.remove(request).await?;
-        assert_eq!(response.into_inner().status, "success: item was removed");
-        for i in 1000..2000 {
-            let item_id = ItemIdentifier {
-                sku: format!("SKU{}", i),
+        info!("adding a hot item");
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: "HOT".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+
+        info!("verifying coalesced gets never observe an item older than their request");
+        for expected in 1..=100 {
+            let request = Request::new(QuantityChangeRequest {
+                sku: "HOT".into(),
+                change: 1,
+            });
+            inventory.update_quantity(request).await?;
+
+            let mut getters = Vec::new();
+            for _ in 0..16 {
+                let inventory = inventory.clone();
+                getters.push(tokio::spawn(async move {
+                    let request = get_request("HOT");
+                    inventory.get(request).await.unwrap().into_inner()
+                }));
+            }
+            for getter in getters {
+                assert!(item_quantity(&getter.await?) >= expected);
+            }
+        }
+
+        info!("verifying coalesced gets for missing items are rejected");
+        let request = get_request("DOESNTEXIST");
+        let response = inventory.get(request).await;
+        assert_eq!(
+            InventoryError::from_status(&response.unwrap_err()),
+            Some(InventoryError::NoItem)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cancelled_coalesced_gets() -> Result<(), Error> {
+        let inventory = Arc::new(StoreInventory::default().with_coalesced_gets());
+        let item = Item {
+            identifier: Some(ItemIdentifier { sku: "HOT".into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity: 42,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        inventory.add(Request::new(item)).await?;
+
+        info!("cancelling a get while it waits for the shard lock");
+        let held = inventory.shard("HOT").write().await;
+        let timeout = Duration::from_millis(20);
+        let get = tokio::time::timeout(timeout, inventory.get(get_request("HOT"))).await;
+        assert!(get.is_err());
+        drop(held);
+
+        info!("verifying the shard can still be locked and the item read");
+        let timeout = Duration::from_secs(1);
+        let request = Request::new(SetQuantityRequest {
+            sku: "HOT".into(),
+            quantity: 7,
+        });
+        tokio::time::timeout(timeout, inventory.set_quantity(request)).await??;
+        let item = tokio::time::timeout(timeout, inventory.get(get_request("HOT")))
+            .await??
+            .into_inner();
+        assert_eq!(item_quantity(&item), 7);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    #[ignore = "benchmark, run with: cargo test --release -- --ignored --nocapture"]
+    async fn coalesced_hot_key_throughput() -> Result<(), Error> {
+        const READERS: usize = 64;
+        const READS: usize = 10_000;
+
+        for coalesced in [false, true] {
+            let mut inventory = StoreInventory::default();
+            if coalesced {
+                inventory = inventory.with_coalesced_gets();
+            }
+            let inventory = Arc::new(inventory);
+            let item = Item {
+                identifier: Some(ItemIdentifier { sku: "HOT".into() }),
+                stock: Some(ItemStock {
+                    price: 1.79,
+                    quantity: 42,
+                    ..Default::default()
+                }),
+                information: Some(ItemInformation {
+                    name: Some("hot item".into()),
+                    description: Some("x".repeat(4096)),
+                }),
+                ..Default::default()
             };
-            let request = Request::new(item_id);
-            let response = client.remove(request).await?;
-            assert_eq!(response.into_inner().status, "success: item was removed");
+            inventory.add(Request::new(item)).await?;
+
+            let start = Instant::now();
+            let mut readers = Vec::new();
+            for _ in 0..READERS {
+                let inventory = inventory.clone();
+                readers.push(tokio::spawn(async move {
+                    for _ in 0..READS {
+                        let request = get_request("HOT");
+                        inventory.get(request).await.unwrap();
+                    }
+                }));
+            }
+            for reader in readers {
+                reader.await?;
+            }
+
+            let elapsed = start.elapsed().as_secs_f64();
+            let throughput = (READERS * READS) as f64 / elapsed;
+            info!("coalesced={}: {:.0} gets/sec", coalesced, throughput);
         }
 
-        info!("verifying removing items with no SKU is rejected");
-        let request = Request::new(ItemIdentifier { sku: "".into() });
-        let response = client.remove(request).await;
-        assert!(response.is_err());
-        assert_eq!(response.err().unwrap().message(), server::EMPTY_SKU_ERR);
+        Ok(())
+    }
 
-        info!("verifying removing non-existent items succeeds, but is reported");
-        let request = Request::new(item_id.clone());
-        let response = client.remove(request).await?;
-        assert_eq!(response.into_inner().status, "success: item didn't exist");
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    #[ignore = "benchmark, run with: cargo test --release -- --ignored --nocapture"]
+    async fn sharded_write_throughput() -> Result<(), Error> {
+        const WRITERS: usize = 64;
+        const WRITES: usize = 10_000;
+
+        // a single shard is equivalent to one global lock over the inventory
+        for shards in [1, server::DEFAULT_SHARDS] {
+            let inventory = Arc::new(StoreInventory::with_shards(shards));
+            for i in 0..WRITERS {
+                let item = Item {
+                    identifier: Some(ItemIdentifier {
+                        sku: format!("SKU{}", i),
+                    }),
+                    stock: Some(ItemStock {
+                        price: 1.79,
+                        quantity: 0,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                };
+                inventory.add(Request::new(item)).await?;
+            }
+
+            info!("measuring concurrent writes to {} SKUs", WRITERS);
+            let start = Instant::now();
+            let mut writers = Vec::new();
+            for i in 0..WRITERS {
+                let inventory = inventory.clone();
+                writers.push(tokio::spawn(async move {
+                    for _ in 0..WRITES {
+                        let request = Request::new(QuantityChangeRequest {
+                            sku: format!("SKU{}", i),
+                            change: 1,
+                        });
+                        inventory.update_quantity(request).await.unwrap();
+                    }
+                }));
+            }
+            for writer in writers {
+                writer.await?;
+            }
+
+            let elapsed = start.elapsed().as_secs_f64();
+            let throughput = (WRITERS * WRITES) as f64 / elapsed;
+            info!("{} shard(s): {:.0} writes/sec", shards, throughput);
+        }
 
         Ok(())
     }
@@ -610,6 +8068,27 @@ mod tests {
     // Helper Functions
     // -------------------------------------------------------------------------
 
+    fn get_request(sku: impl Into<String>) -> Request<GetRequest> {
+        Request::new(GetRequest {
+            sku: sku.into(),
+            include_reservations: false,
+        })
+    }
+
+    fn watch_request(sku: impl Into<String>) -> Request<WatchRequest> {
+        Request::new(WatchRequest {
+            sku: sku.into(),
+            from_version: None,
+        })
+    }
+
+    fn remove_request(sku: impl Into<String>) -> Request<RemoveRequest> {
+        Request::new(RemoveRequest {
+            sku: sku.into(),
+            strict: false,
+        })
+    }
+
     fn item_quantity(item: &Item) -> u32 {
         item.stock.as_ref().unwrap().quantity
     }
@@ -617,4 +8096,31 @@ mod tests {
     fn item_price(item: &Item) -> f32 {
         item.stock.as_ref().unwrap().price
     }
+
+    // retrieves the item sent by a watch, panicking on heartbeats.
+    fn watched_item(event: WatchEvent) -> Item {
+        match event.event {
+            Some(WatchedEvent::Item(item)) => item,
+            event => panic!("expected an item, got {:?}", event),
+        }
+    }
+
+    fn item_sku(item: &Item) -> String {
+        item.identifier.as_ref().unwrap().sku.clone()
+    }
+
+    fn image_chunks(sku: &str, content_type: &str, data: &[u8], size: usize) -> Vec<ImageChunk> {
+        data.chunks(size)
+            .enumerate()
+            .map(|(i, data)| ImageChunk {
+                sku: if i == 0 { sku.into() } else { String::new() },
+                content_type: if i == 0 {
+                    content_type.into()
+                } else {
+                    String::new()
+                },
+                data: data.to_vec(),
+            })
+            .collect()
+    }
 }
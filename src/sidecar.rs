@@ -0,0 +1,277 @@
+use hyper::header::{ALLOW, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::rest::{authorized, http_status, unauthorized};
+use crate::server::StoreInventory;
+use crate::store::inventory_server::Inventory;
+use crate::store::{GetRequest, ListRequest};
+
+// -----------------------------------------------------------------------------
+// HTTP Sidecar
+// -----------------------------------------------------------------------------
+
+// the number of items read from the inventory at a time when streaming all of
+// them, which is the largest page List allows.
+const LIST_PAGE_SIZE: u32 = 1000;
+
+/// Serves read-only HTTP access to the inventory for integrations which can't
+/// speak gRPC. Items are served as JSON, one per line:
+///
+///  - `GET /items` streams every Item in ascending SKU order.
+///  - `GET /items/{sku}` retrieves a single Item, or responds 404.
+#[derive(Debug, Clone)]
+pub struct Sidecar {
+    inventory: Arc<StoreInventory>,
+    api_key: Option<String>,
+    anonymous_reads: bool,
+}
+
+impl Sidecar {
+    /// Creates a sidecar which reads from the given inventory.
+    pub fn new(inventory: Arc<StoreInventory>) -> Self {
+        Sidecar {
+            inventory,
+            api_key: None,
+            anonymous_reads: false,
+        }
+    }
+
+    /// Requires every request to carry the given API key as a bearer token in
+    /// its authorization header, as the gRPC server does.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Accepts requests without the API key, as the sidecar only offers
+    /// reads.
+    pub fn with_anonymous_reads(mut self, anonymous_reads: bool) -> Self {
+        self.anonymous_reads = anonymous_reads;
+        self
+    }
+
+    /// Serves the sidecar on the given address.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), hyper::Error> {
+        let sidecar = Arc::new(self);
+        let make_service = make_service_fn(move |_| {
+            let sidecar = sidecar.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let sidecar = sidecar.clone();
+                    async move { Ok::<_, Infallible>(sidecar.handle(request).await) }
+                }))
+            }
+        });
+
+        hyper::Server::bind(&addr).serve(make_service).await
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        if !authorized(&request, self.api_key.as_deref(), self.anonymous_reads) {
+            return unauthorized();
+        }
+
+        let sku = match request.uri().path().strip_prefix("/items") {
+            Some("" | "/") => None,
+            Some(sku) => match sku.strip_prefix('/') {
+                Some(sku) if !sku.contains('/') => {
+                    Some(percent_decode_str(sku).decode_utf8_lossy())
+                }
+                _ => return error(StatusCode::NOT_FOUND, "not found"),
+            },
+            None => return error(StatusCode::NOT_FOUND, "not found"),
+        };
+
+        // the sidecar never changes the inventory
+        if request.method() != Method::GET {
+            let mut response = error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+            response.headers_mut().insert(ALLOW, "GET".parse().unwrap());
+            return response;
+        }
+
+        match sku {
+            Some(sku) => get_item(&self.inventory, sku.into_owned()).await,
+            None => list_items(self.inventory.clone()),
+        }
+    }
+}
+
+async fn get_item(inventory: &StoreInventory, sku: String) -> Response<Body> {
+    let request = tonic::Request::new(GetRequest {
+        sku,
+        ..Default::default()
+    });
+    match inventory.get(request).await {
+        Ok(item) => json(StatusCode::OK, &item.into_inner()),
+        Err(status) => error(http_status(status.code()), status.message()),
+    }
+}
+
+// streams the whole inventory a page at a time, so that it's never all held
+// in memory at once. Items are seen as they were when their page was read.
+fn list_items(inventory: Arc<StoreInventory>) -> Response<Body> {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let mut page_token = String::new();
+        loop {
+            let request = tonic::Request::new(ListRequest {
+                page_size: LIST_PAGE_SIZE,
+                page_token,
+            });
+            let page = match inventory.list(request).await {
+                Ok(page) => page.into_inner(),
+                Err(_) => return sender.abort(),
+            };
+
+            let mut lines = Vec::new();
+            for item in &page.items {
+                serde_json::to_writer(&mut lines, item).expect("items encode as JSON");
+                lines.push(b'\n');
+            }
+            // stop once the client goes away
+            if sender.send_data(lines.into()).await.is_err() {
+                return;
+            }
+
+            if page.next_page_token.is_empty() {
+                return;
+            }
+            page_token = page.next_page_token;
+        }
+    });
+
+    Response::builder()
+        .header(CONTENT_TYPE, "application/x-ndjson")
+        .body(body)
+        .unwrap()
+}
+
+fn json(code: StatusCode, message: &impl Serialize) -> Response<Body> {
+    let mut json = serde_json::to_vec(message).expect("messages encode as JSON");
+    json.push(b'\n');
+    Response::builder()
+        .status(code)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(json))
+        .unwrap()
+}
+
+fn error(code: StatusCode, message: &str) -> Response<Body> {
+    json(code, &serde_json::json!({ "error": message }))
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use hyper::header::AUTHORIZATION;
+    use hyper::{Body, Method, Request, StatusCode};
+    use std::println as info;
+    use std::sync::Arc;
+
+    use crate::server::StoreInventory;
+    use crate::sidecar::Sidecar;
+    use crate::store::inventory_server::Inventory;
+    use crate::store::{Item, ItemIdentifier, ItemStock};
+
+    fn item(sku: &str, quantity: u32) -> Item {
+        Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            stock: Some(ItemStock {
+                price: 1.79,
+                quantity,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn request(sidecar: &Sidecar, method: Method, path: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        let response = sidecar.handle(request).await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn items() -> Result<(), Error> {
+        let inventory = Arc::new(StoreInventory::default());
+        for (sku, quantity) in [("B", 2), ("A", 1), ("C/D", 3)] {
+            let request = tonic::Request::new(item(sku, quantity));
+            inventory.add(request).await?;
+        }
+        let sidecar = Sidecar::new(inventory);
+
+        info!("listing every item as newline-delimited JSON");
+        let (status, body) = request(&sidecar, Method::GET, "/items").await;
+        assert_eq!(status, StatusCode::OK);
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with(r#"{"identifier":{"sku":"A"}"#));
+        assert!(lines[2].starts_with(r#"{"identifier":{"sku":"C/D"}"#));
+
+        info!("retrieving single items, with percent encoded SKUs");
+        let (status, body) = request(&sidecar, Method::GET, "/items/B").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.starts_with(
+            r#"{"identifier":{"sku":"B"},"stock":{"price":1.79,"quantity":2,"currency":"USD"}"#
+        ));
+        let (status, body) = request(&sidecar, Method::GET, "/items/C%2FD").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains(r#""quantity":3"#));
+
+        info!("verifying missing items and unknown paths are not found");
+        for path in ["/items/MISSING", "/items/C/D", "/other"] {
+            let (status, _) = request(&sidecar, Method::GET, path).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", path);
+        }
+
+        info!("verifying the inventory can't be changed");
+        for method in [Method::POST, Method::PUT, Method::DELETE] {
+            let (status, _) = request(&sidecar, method, "/items/A").await;
+            assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        }
+        let (_, body) = request(&sidecar, Method::GET, "/items").await;
+        assert_eq!(body.lines().count(), 3);
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn api_key() {
+        let sidecar = Sidecar::new(Arc::new(StoreInventory::default())).with_api_key("secret");
+
+        info!("verifying requests without the API key are rejected");
+        for path in ["/items", "/items/A"] {
+            let (status, _) = request(&sidecar, Method::GET, path).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+
+        info!("verifying requests with the API key are served");
+        let get = Request::builder()
+            .uri("/items/A")
+            .header(AUTHORIZATION, "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+        let response = sidecar.handle(get).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        info!("verifying reads are served without it when allowed");
+        let sidecar = sidecar.with_anonymous_reads(true);
+        let (status, _) = request(&sidecar, Method::GET, "/items/A").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,554 @@
+use prost::Message;
+use rusqlite::{params, Connection, Row};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use crate::error::InventoryError;
+use crate::store::Item;
+
+// -----------------------------------------------------------------------------
+// Storage
+// -----------------------------------------------------------------------------
+
+/// Where the items of a shard of the inventory are kept, keyed by SKU.
+/// StoreInventory holds a lock around each shard's storage for as long as an
+/// operation needs the items it reads to stay as they are, so storage doesn't
+/// need to synchronize access itself, and operations spanning several items
+/// stay atomic whatever the storage.
+///
+/// Storage which can fail (e.g. because a disk is full) reports it with
+/// InventoryError::StorageFailure, after logging the underlying error.
+///
+/// Only the required methods need implementing, the rest are built on them
+/// and can be overridden where the storage can do better (e.g. an in-memory
+/// map which can be scanned without copying its items).
+#[tonic::async_trait]
+pub trait Storage: fmt::Debug + Send + Sync {
+    /// Stores an item under the SKU, returning the item it replaced if there
+    /// was one.
+    async fn insert(&mut self, sku: String, item: Item) -> Result<Option<Item>, InventoryError>;
+
+    /// Retrieves a copy of the item stored under the SKU.
+    async fn get(&self, sku: &str) -> Result<Option<Item>, InventoryError>;
+
+    /// Removes the item stored under the SKU, returning it.
+    async fn remove(&mut self, sku: &str) -> Result<Option<Item>, InventoryError>;
+
+    /// Replaces the item stored under the SKU, returning the item as it was.
+    /// Nothing is stored if there's no item under the SKU.
+    async fn update(&mut self, sku: &str, item: Item) -> Result<Option<Item>, InventoryError>;
+
+    /// Retrieves copies of every item along with the SKU it's stored under,
+    /// in ascending SKU order.
+    async fn all(&self) -> Result<Vec<(String, Item)>, InventoryError>;
+
+    /// Reports whether there's an item stored under the SKU.
+    async fn contains(&self, sku: &str) -> Result<bool, InventoryError> {
+        Ok(self.get(sku).await?.is_some())
+    }
+
+    /// The number of items stored.
+    async fn len(&self) -> Result<usize, InventoryError> {
+        Ok(self.all().await?.len())
+    }
+
+    /// Reports whether there are no items stored.
+    async fn is_empty(&self) -> Result<bool, InventoryError> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Visits every item along with the SKU it's stored under, in ascending
+    /// SKU order.
+    async fn scan(
+        &self,
+        visit: &mut (dyn for<'i> FnMut(&'i str, &'i Item) + Send),
+    ) -> Result<(), InventoryError> {
+        for (sku, item) in self.all().await? {
+            visit(&sku, &item);
+        }
+        Ok(())
+    }
+
+    /// Retrieves copies of up to the given number of items stored under SKUs
+    /// after the given one (or from the start), in ascending SKU order.
+    async fn range(&self, after: Option<&str>, limit: usize) -> Result<Vec<Item>, InventoryError> {
+        let items = self.all().await?.into_iter();
+        Ok(items
+            .filter(|(sku, _)| after.is_none_or(|after| sku.as_str() > after))
+            .take(limit)
+            .map(|(_, item)| item)
+            .collect())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Memory Storage
+// -----------------------------------------------------------------------------
+
+/// Keeps items in memory, ordered by SKU so that listings can resume from any
+/// point. This is the storage the inventory uses unless told otherwise.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    items: BTreeMap<String, Item>,
+}
+
+#[tonic::async_trait]
+impl Storage for MemoryStorage {
+    async fn insert(&mut self, sku: String, item: Item) -> Result<Option<Item>, InventoryError> {
+        Ok(self.items.insert(sku, item))
+    }
+
+    async fn get(&self, sku: &str) -> Result<Option<Item>, InventoryError> {
+        Ok(self.items.get(sku).cloned())
+    }
+
+    async fn remove(&mut self, sku: &str) -> Result<Option<Item>, InventoryError> {
+        Ok(self.items.remove(sku))
+    }
+
+    async fn update(&mut self, sku: &str, item: Item) -> Result<Option<Item>, InventoryError> {
+        let stored = self.items.get_mut(sku);
+        Ok(stored.map(|stored| std::mem::replace(stored, item)))
+    }
+
+    async fn all(&self) -> Result<Vec<(String, Item)>, InventoryError> {
+        let items = self.items.iter();
+        Ok(items
+            .map(|(sku, item)| (sku.clone(), item.clone()))
+            .collect())
+    }
+
+    async fn contains(&self, sku: &str) -> Result<bool, InventoryError> {
+        Ok(self.items.contains_key(sku))
+    }
+
+    async fn len(&self) -> Result<usize, InventoryError> {
+        Ok(self.items.len())
+    }
+
+    async fn scan(
+        &self,
+        visit: &mut (dyn for<'i> FnMut(&'i str, &'i Item) + Send),
+    ) -> Result<(), InventoryError> {
+        for (sku, item) in &self.items {
+            visit(sku, item);
+        }
+        Ok(())
+    }
+
+    async fn range(&self, after: Option<&str>, limit: usize) -> Result<Vec<Item>, InventoryError> {
+        let start = match after {
+            Some(after) => Bound::Excluded(after),
+            None => Bound::Unbounded,
+        };
+        let items = self.items.range::<str, _>((start, Bound::Unbounded));
+        Ok(items.take(limit).map(|(_, item)| item.clone()).collect())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// SQLite Storage
+// -----------------------------------------------------------------------------
+
+// the migrations which bring a database's tables up to date, in the order
+// they're applied. The number of migrations a database has had applied is
+// kept as its user_version, so that only newer ones are applied on startup.
+const MIGRATIONS: &[&str] = &["CREATE TABLE items (
+        sku         TEXT PRIMARY KEY NOT NULL,
+        price       REAL,
+        quantity    INTEGER,
+        name        TEXT,
+        description TEXT,
+        item        BLOB NOT NULL
+    ) WITHOUT ROWID"];
+
+// the number of rows a scan reads ahead of the items it has visited.
+const SCAN_BUFFER: usize = 64;
+
+/// Keeps items in a SQLite database, so that the inventory survives restarts
+/// and isn't limited by memory. Items are kept in the items table keyed by
+/// SKU, with columns for their price, quantity, name and description so that
+/// the database can be queried by other tools. Each row also holds the whole
+/// item encoded as protobuf, which is what's read back.
+///
+/// Only items are kept in the database, reservations, images and tombstones
+/// of removed items are still only kept in memory.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    // queries block while the database is read and written, so they're run
+    // on tokio's blocking threads rather than the runtime's workers, which
+    // would otherwise stall every other call they're serving. Connections
+    // can't be shared between threads, so those queries take turns.
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    /// Opens the database at the given path, creating it if it doesn't exist,
+    /// and brings its tables up to date.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        SqliteStorage::migrate(Connection::open(path)?)
+    }
+
+    /// Opens a database which is only kept in memory, and is lost once the
+    /// storage is dropped.
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        SqliteStorage::migrate(Connection::open_in_memory()?)
+    }
+
+    // applies the migrations the database hasn't had yet, all of them for a
+    // new database. They're applied in a single transaction, so a database is
+    // never left part way through a migration.
+    fn migrate(mut connection: Connection) -> rusqlite::Result<Self> {
+        let applied: i64 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        let transaction = connection.transaction()?;
+        for migration in MIGRATIONS.iter().skip(applied as usize) {
+            transaction.execute_batch(migration)?;
+        }
+        if applied < MIGRATIONS.len() as i64 {
+            transaction.pragma_update(None, "user_version", MIGRATIONS.len() as i64)?;
+        }
+        transaction.commit()?;
+
+        Ok(SqliteStorage {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    // runs a query against the connection on a blocking thread.
+    async fn query<T, F>(&self, query: F) -> Result<T, InventoryError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, InventoryError> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || query(&mut connection.lock().unwrap()))
+            .await
+            .map_err(failed)?
+    }
+}
+
+#[tonic::async_trait]
+impl Storage for SqliteStorage {
+    async fn insert(&mut self, sku: String, item: Item) -> Result<Option<Item>, InventoryError> {
+        self.query(move |connection| {
+            let transaction = connection.transaction().map_err(failed)?;
+            let previous = select_item(&transaction, &sku)?;
+            store_item(&transaction, &sku, &item)?;
+            transaction.commit().map_err(failed)?;
+            Ok(previous)
+        })
+        .await
+    }
+
+    async fn get(&self, sku: &str) -> Result<Option<Item>, InventoryError> {
+        let sku = sku.to_string();
+        self.query(move |connection| select_item(connection, &sku))
+            .await
+    }
+
+    async fn remove(&mut self, sku: &str) -> Result<Option<Item>, InventoryError> {
+        let sku = sku.to_string();
+        self.query(move |connection| {
+            let transaction = connection.transaction().map_err(failed)?;
+            let previous = select_item(&transaction, &sku)?;
+            transaction
+                .execute("DELETE FROM items WHERE sku = ?1", [&sku])
+                .map_err(failed)?;
+            transaction.commit().map_err(failed)?;
+            Ok(previous)
+        })
+        .await
+    }
+
+    async fn update(&mut self, sku: &str, item: Item) -> Result<Option<Item>, InventoryError> {
+        let sku = sku.to_string();
+        self.query(move |connection| {
+            let transaction = connection.transaction().map_err(failed)?;
+            let previous = select_item(&transaction, &sku)?;
+            if previous.is_some() {
+                store_item(&transaction, &sku, &item)?;
+            }
+            transaction.commit().map_err(failed)?;
+            Ok(previous)
+        })
+        .await
+    }
+
+    async fn all(&self) -> Result<Vec<(String, Item)>, InventoryError> {
+        let mut items = Vec::new();
+        self.scan(&mut |sku, item| items.push((sku.to_string(), item.clone())))
+            .await?;
+        Ok(items)
+    }
+
+    async fn contains(&self, sku: &str) -> Result<bool, InventoryError> {
+        let sku = sku.to_string();
+        self.query(move |connection| {
+            let query = "SELECT EXISTS (SELECT 1 FROM items WHERE sku = ?1)";
+            connection
+                .query_row(query, [sku], |row| row.get(0))
+                .map_err(failed)
+        })
+        .await
+    }
+
+    async fn len(&self) -> Result<usize, InventoryError> {
+        self.query(|connection| {
+            let query = "SELECT COUNT(*) FROM items";
+            let count: i64 = connection
+                .query_row(query, [], |row| row.get(0))
+                .map_err(failed)?;
+            Ok(count as usize)
+        })
+        .await
+    }
+
+    // rows are sent over as they're read and visited as they arrive, so
+    // scans of a large inventory don't need to hold all of it in memory. The
+    // read stops early if the scan is dropped part way through.
+    async fn scan(
+        &self,
+        visit: &mut (dyn for<'i> FnMut(&'i str, &'i Item) + Send),
+    ) -> Result<(), InventoryError> {
+        let (rows_tx, mut rows_rx) = mpsc::channel(SCAN_BUFFER);
+        let read = self.query(move |connection| {
+            let mut statement = connection
+                .prepare("SELECT sku, item FROM items ORDER BY sku")
+                .map_err(failed)?;
+            let mut rows = statement.query([]).map_err(failed)?;
+            while let Some(row) = rows.next().map_err(failed)? {
+                let sku: String = row.get(0).map_err(failed)?;
+                if rows_tx.blocking_send((sku, decode_item(row, 1)?)).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        let visited = async {
+            while let Some((sku, item)) = rows_rx.recv().await {
+                visit(&sku, &item);
+            }
+        };
+        let (read, ()) = tokio::join!(read, visited);
+        read
+    }
+
+    async fn range(&self, after: Option<&str>, limit: usize) -> Result<Vec<Item>, InventoryError> {
+        let after = after.map(str::to_string);
+        self.query(move |connection| {
+            let mut statement = connection
+                .prepare(
+                    "SELECT item FROM items WHERE ?1 IS NULL OR sku > ?1 ORDER BY sku LIMIT ?2",
+                )
+                .map_err(failed)?;
+            let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+            let mut rows = statement.query(params![after, limit]).map_err(failed)?;
+            let mut items = Vec::new();
+            while let Some(row) = rows.next().map_err(failed)? {
+                items.push(decode_item(row, 0)?);
+            }
+            Ok(items)
+        })
+        .await
+    }
+}
+
+// retrieves the item stored under the SKU from the database.
+fn select_item(connection: &Connection, sku: &str) -> Result<Option<Item>, InventoryError> {
+    let mut statement = connection
+        .prepare_cached("SELECT item FROM items WHERE sku = ?1")
+        .map_err(failed)?;
+    let mut rows = statement.query([sku]).map_err(failed)?;
+    match rows.next().map_err(failed)? {
+        Some(row) => decode_item(row, 0).map(Some),
+        None => Ok(None),
+    }
+}
+
+// stores the item under the SKU in the database, replacing the item stored
+// under it if there is one.
+fn store_item(connection: &Connection, sku: &str, item: &Item) -> Result<(), InventoryError> {
+    let stock = item.stock.as_ref();
+    let information = item.information.as_ref();
+    connection
+        .prepare_cached(
+            "INSERT OR REPLACE INTO items (sku, price, quantity, name, description, item)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .and_then(|mut statement| {
+            statement.execute(params![
+                sku,
+                stock.map(|stock| stock.price),
+                stock.map(|stock| stock.quantity),
+                information.and_then(|information| information.name.as_deref()),
+                information.and_then(|information| information.description.as_deref()),
+                item.encode_to_vec(),
+            ])
+        })
+        .map_err(failed)?;
+    Ok(())
+}
+
+// decodes the item held in the given column of a row.
+fn decode_item(row: &Row<'_>, column: usize) -> Result<Item, InventoryError> {
+    let encoded = row.get_ref(column).and_then(|value| Ok(value.as_blob()?));
+    Item::decode(encoded.map_err(failed)?).map_err(failed)
+}
+
+// logs why the database failed, which clients are only told happened.
+fn failed(err: impl fmt::Display) -> InventoryError {
+    tracing::error!(error = %err, "the SQLite storage failed");
+    InventoryError::StorageFailure
+}
+
+// -----------------------------------------------------------------------------
+// Testing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::error::InventoryError;
+    use crate::storage::{MemoryStorage, SqliteStorage, Storage, MIGRATIONS};
+    use crate::store::{Item, ItemIdentifier, ItemInformation, ItemStock};
+
+    // storage which only implements the required methods, to exercise the
+    // provided ones.
+    #[derive(Debug, Default)]
+    struct MinimalStorage(MemoryStorage);
+
+    #[tonic::async_trait]
+    impl Storage for MinimalStorage {
+        async fn insert(
+            &mut self,
+            sku: String,
+            item: Item,
+        ) -> Result<Option<Item>, InventoryError> {
+            self.0.insert(sku, item).await
+        }
+
+        async fn get(&self, sku: &str) -> Result<Option<Item>, InventoryError> {
+            self.0.get(sku).await
+        }
+
+        async fn remove(&mut self, sku: &str) -> Result<Option<Item>, InventoryError> {
+            self.0.remove(sku).await
+        }
+
+        async fn update(&mut self, sku: &str, item: Item) -> Result<Option<Item>, InventoryError> {
+            self.0.update(sku, item).await
+        }
+
+        async fn all(&self) -> Result<Vec<(String, Item)>, InventoryError> {
+            self.0.all().await
+        }
+    }
+
+    fn item(sku: &str) -> Item {
+        Item {
+            identifier: Some(ItemIdentifier { sku: sku.into() }),
+            ..Default::default()
+        }
+    }
+
+    async fn exercise(storage: &mut dyn Storage) -> Result<(), InventoryError> {
+        assert!(storage.is_empty().await?);
+        for sku in ["C", "A", "B"] {
+            assert_eq!(storage.insert(sku.into(), item(sku)).await?, None);
+        }
+        assert_eq!(storage.len().await?, 3);
+        assert!(storage.contains("A").await?);
+        assert_eq!(storage.get("B").await?, Some(item("B")));
+
+        // items are kept in SKU order, and ranges resume after a SKU
+        let mut visited = Vec::new();
+        storage
+            .scan(&mut |sku, _| visited.push(sku.to_string()))
+            .await?;
+        assert_eq!(visited, ["A", "B", "C"]);
+        let range = storage.range(Some("A"), 1).await?;
+        assert_eq!(range, [item("B")]);
+        assert_eq!(storage.range(None, 10).await?.len(), 3);
+
+        // updates only replace items which are there
+        let mut updated = item("A");
+        updated.last_updated = 1;
+        assert_eq!(storage.update("A", updated.clone()).await?, Some(item("A")));
+        assert_eq!(storage.get("A").await?, Some(updated));
+        assert_eq!(storage.update("D", item("D")).await?, None);
+        assert!(!storage.contains("D").await?);
+
+        assert_eq!(storage.remove("C").await?, Some(item("C")));
+        assert_eq!(storage.remove("C").await?, None);
+        assert_eq!(storage.all().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memory_storage() -> Result<(), InventoryError> {
+        exercise(&mut MemoryStorage::default()).await
+    }
+
+    #[tokio::test]
+    async fn provided_methods() -> Result<(), InventoryError> {
+        exercise(&mut MinimalStorage::default()).await
+    }
+
+    #[tokio::test]
+    async fn sqlite_storage() -> Result<(), InventoryError> {
+        exercise(&mut SqliteStorage::open_in_memory().unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn sqlite_storage_persists() -> Result<(), InventoryError> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.db");
+        let mut stored = item("PERSISTED");
+        stored.stock = Some(ItemStock {
+            price: 1.5,
+            quantity: 3,
+            reorder_threshold: Some(1),
+            ..Default::default()
+        });
+        stored.information = Some(ItemInformation {
+            name: Some("name".into()),
+            description: None,
+        });
+        stored.tags = vec!["tag".into()];
+
+        let mut storage = SqliteStorage::open(&path).unwrap();
+        storage.insert("PERSISTED".into(), stored.clone()).await?;
+        drop(storage);
+
+        // the item survives the database being reopened, which doesn't apply
+        // the migrations again
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(storage.get("PERSISTED").await?, Some(stored));
+        drop(storage);
+
+        // the columns hold the item's fields for other tools to query
+        let connection = Connection::open(&path).unwrap();
+        let version: i64 = connection
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len() as i64);
+        let query = "SELECT price, quantity, name, description FROM items WHERE sku = ?1";
+        let row = connection
+            .query_row(query, ["PERSISTED"], |row| {
+                Ok((
+                    row.get::<_, f64>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })
+            .unwrap();
+        assert_eq!(row, (1.5, 3, Some("name".into()), None));
+        Ok(())
+    }
+}
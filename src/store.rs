@@ -1,34 +1,209 @@
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ItemIdentifier {
     #[prost(string, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub sku: ::prost::alloc::string::String,
 }
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ItemStock {
+    /// prices are kept in whole cents, the server rounds prices with fractions
+    /// of a cent to the nearest cent.
     #[prost(float, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub price: f32,
     #[prost(uint32, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub quantity: u32,
+    /// when set, the Item is listed by ListLowStock once its quantity falls to
+    /// this threshold or below, so that it can be reordered.
+    #[prost(uint32, optional, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub reorder_threshold: ::core::option::Option<u32>,
+    /// the ISO 4217 code of the currency the price is in, which is USD when
+    /// it's empty. The server stores it in upper case.
+    #[prost(string, tag = "4")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub currency: ::prost::alloc::string::String,
 }
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ItemInformation {
     #[prost(string, optional, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub name: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(string, optional, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub description: ::core::option::Option<::prost::alloc::string::String>,
 }
+/// ItemPhysical describes the size and weight of a single unit of an Item,
+/// e.g. for calculating shipping. None of them can be negative.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ItemPhysical {
+    #[prost(float, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub weight_grams: f32,
+    #[prost(float, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub length_mm: f32,
+    #[prost(float, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub width_mm: f32,
+    #[prost(float, tag = "4")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub height_mm: f32,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Item {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub identifier: ::core::option::Option<ItemIdentifier>,
     #[prost(message, optional, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub stock: ::core::option::Option<ItemStock>,
     #[prost(message, optional, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub information: ::core::option::Option<ItemInformation>,
+    #[prost(uint64, tag = "4")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub last_updated: u64,
+    /// the Item's image, which is set by the server once one is uploaded with
+    /// UploadImage. It's ignored when sent by clients.
+    #[prost(message, optional, tag = "5")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub image: ::core::option::Option<ItemImage>,
+    /// only reported when requested, see ConvertedPrice. It's ignored when
+    /// sent by clients.
+    #[prost(message, optional, tag = "6")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub converted: ::core::option::Option<ConvertedPrice>,
+    /// only reported when requested, see Availability. It's ignored when
+    /// sent by clients.
+    #[prost(message, optional, tag = "7")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub availability: ::core::option::Option<Availability>,
+    /// arbitrary metadata about the Item (e.g. brand or weight), keyed by name.
+    #[prost(map = "string, string", tag = "8")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(serialize_with = "crate::json::sorted")]
+    pub attributes: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    /// when the Item expires (in unix epoch milliseconds), expired Items are
+    /// removed from the inventory by the server's periodic sweep.
+    #[prost(uint64, optional, tag = "9")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::optional_uint64")]
+    pub expires_at: ::core::option::Option<u64>,
+    /// labels grouping the Item into categories (e.g. "produce"), which Items
+    /// can be listed by.
+    #[prost(string, repeated, tag = "10")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// a counter incremented by the server every time the Item is changed,
+    /// starting from 1 when it's added.
+    #[prost(uint64, tag = "11")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub version: u64,
+    #[prost(message, optional, tag = "12")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub physical: ::core::option::Option<ItemPhysical>,
+}
+/// GetRequest identifies the Item to retrieve. The SKU has the same field
+/// number as in ItemIdentifier, so that the two are compatible on the wire.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRequest {
+    #[prost(string, tag = "2")]
+    pub sku: ::prost::alloc::string::String,
+    /// whether to report the Item's availability, accounting for reservations.
+    #[prost(bool, tag = "3")]
+    pub include_reservations: bool,
+}
+/// WatchRequest identifies the Item to watch, and is compatible with
+/// ItemIdentifier on the wire like GetRequest.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchRequest {
+    #[prost(string, tag = "2")]
+    pub sku: ::prost::alloc::string::String,
+    /// the version of the Item the client last saw, e.g. before reconnecting.
+    /// The Item is sent first when its version differs (it's been changed, or
+    /// removed and added again since), as the changes in between aren't kept.
+    #[prost(uint64, optional, tag = "3")]
+    pub from_version: ::core::option::Option<u64>,
+}
+/// RemoveRequest identifies the Item to remove, and is compatible with
+/// ItemIdentifier on the wire like GetRequest.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RemoveRequest {
+    #[prost(string, tag = "2")]
+    pub sku: ::prost::alloc::string::String,
+    /// whether removing an Item which isn't in the inventory fails with
+    /// NOT_FOUND, rather than succeeding.
+    #[prost(bool, tag = "3")]
+    pub strict: bool,
+}
+/// Availability is an Item's stock quantity accounting for the reservations
+/// of it. It's only reported when requested.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Availability {
+    #[prost(uint32, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub total: u32,
+    #[prost(uint32, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub reserved: u32,
+    #[prost(uint32, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub available: u32,
+}
+/// ConvertedPrice is an Item's price converted into the currency requested via
+/// the "x-currency" metadata header. It's for display only, and never stored.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConvertedPrice {
+    #[prost(string, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub currency: ::prost::alloc::string::String,
+    #[prost(float, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub price: f32,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ItemImage {
+    #[prost(string, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub content_type: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub size: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -40,331 +215,3596 @@ pub struct QuantityChangeRequest {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetQuantityRequest {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub quantity: u32,
+}
+/// ReorderThresholdRequest sets the reorder threshold of an Item, or clears it
+/// when no threshold is given.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReorderThresholdRequest {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(uint32, optional, tag = "2")]
+    pub threshold: ::core::option::Option<u32>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PriceChangeRequest {
     #[prost(string, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub sku: ::prost::alloc::string::String,
     #[prost(float, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub price: f32,
+    /// the price the Item must currently be at for the change to be applied,
+    /// compared in cents.
+    #[prost(float, optional, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub expected_price: ::core::option::Option<f32>,
+    /// the currency the price is in, which must be the Item's currency when
+    /// given. Prices are taken to be in the Item's currency otherwise.
+    #[prost(string, optional, tag = "4")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub currency: ::core::option::Option<::prost::alloc::string::String>,
 }
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct InventoryChangeResponse {
     #[prost(string, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub status: ::prost::alloc::string::String,
+    /// the item as it was stored, including the fields the server sets (e.g.
+    /// last_updated). Only set by Add and Upsert.
+    #[prost(message, optional, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub item: ::core::option::Option<Item>,
+    /// whether an existing Item was replaced. Only set by Upsert.
+    #[prost(bool, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub replaced: bool,
+    /// whether the Item existed, so that it was removed. Only set by Remove,
+    /// which clients should rely on rather than the status, which is written
+    /// for people.
+    #[prost(bool, tag = "4")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub existed: bool,
 }
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct InventoryUpdateResponse {
     #[prost(string, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub status: ::prost::alloc::string::String,
     #[prost(float, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub price: f32,
     #[prost(uint32, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
     pub quantity: u32,
 }
-/// Generated client implementations.
-pub mod inventory_client {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    use tonic::codegen::http::Uri;
-    #[derive(Debug, Clone)]
-    pub struct InventoryClient<T> {
-        inner: tonic::client::Grpc<T>,
-    }
-    impl InventoryClient<tonic::transport::Channel> {
-        /// Attempt to create a new client by connecting to a given endpoint.
-        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
-        where
-            D: std::convert::TryInto<tonic::transport::Endpoint>,
-            D::Error: Into<StdError>,
-        {
-            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
-            Ok(Self::new(conn))
-        }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChangedSinceRequest {
+    #[prost(uint64, tag = "1")]
+    pub since: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChangedSinceResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+    #[prost(string, repeated, tag = "2")]
+    pub removed_skus: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// ImageChunk is a piece of an Item's image. When uploading, the SKU and
+/// content type are taken from the first chunk and ignored in the rest.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImageChunk {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub content_type: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetManyRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub skus: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// GetManyResult is the outcome of retrieving a single SKU, the Item is only
+/// present when it was found.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetManyResult {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(enumeration = "get_many_result::Outcome", tag = "2")]
+    pub outcome: i32,
+    #[prost(message, optional, tag = "3")]
+    pub item: ::core::option::Option<Item>,
+}
+/// Nested message and enum types in `GetManyResult`.
+pub mod get_many_result {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Outcome {
+        Found = 0,
+        NotFound = 1,
+        Invalid = 2,
     }
-    impl<T> InventoryClient<T>
-    where
-        T: tonic::client::GrpcService<tonic::body::BoxBody>,
-        T::Error: Into<StdError>,
-        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
-        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
-    {
-        pub fn new(inner: T) -> Self {
-            let inner = tonic::client::Grpc::new(inner);
-            Self { inner }
-        }
-        pub fn with_origin(inner: T, origin: Uri) -> Self {
-            let inner = tonic::client::Grpc::with_origin(inner, origin);
-            Self { inner }
-        }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InventoryClient<InterceptedService<T, F>>
-        where
-            F: tonic::service::Interceptor,
-            T::ResponseBody: Default,
-            T: tonic::codegen::Service<
-                http::Request<tonic::body::BoxBody>,
-                Response = http::Response<
-                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
-                >,
-            >,
-            <T as tonic::codegen::Service<
-                http::Request<tonic::body::BoxBody>,
-            >>::Error: Into<StdError> + Send + Sync,
-        {
-            InventoryClient::new(InterceptedService::new(inner, interceptor))
-        }
-        /// Compress requests with the given encoding.
+    impl Outcome {
+        /// String value of the enum field names used in the ProtoBuf definition.
         ///
-        /// This requires the server to support it otherwise it might respond with an
-        /// error.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.inner = self.inner.send_compressed(encoding);
-            self
-        }
-        /// Enable decompressing responses.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.inner = self.inner.accept_compressed(encoding);
-            self
-        }
-        /// Add inserts a new Item into the inventory.
-        pub async fn add(
-            &mut self,
-            request: impl tonic::IntoRequest<super::Item>,
-        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Add");
-            self.inner.unary(request.into_request(), path, codec).await
-        }
-        /// Remove removes Items from the inventory.
-        pub async fn remove(
-            &mut self,
-            request: impl tonic::IntoRequest<super::ItemIdentifier>,
-        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Remove");
-            self.inner.unary(request.into_request(), path, codec).await
-        }
-        /// Get retrieves Item information.
-        pub async fn get(
-            &mut self,
-            request: impl tonic::IntoRequest<super::ItemIdentifier>,
-        ) -> Result<tonic::Response<super::Item>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Get");
-            self.inner.unary(request.into_request(), path, codec).await
-        }
-        /// UpdateQuantity increases or decreases the stock quantity of an Item.
-        pub async fn update_quantity(
-            &mut self,
-            request: impl tonic::IntoRequest<super::QuantityChangeRequest>,
-        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/store.Inventory/UpdateQuantity",
-            );
-            self.inner.unary(request.into_request(), path, codec).await
-        }
-        /// UpdatePrice increases or decreases the price of an Item.
-        pub async fn update_price(
-            &mut self,
-            request: impl tonic::IntoRequest<super::PriceChangeRequest>,
-        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/store.Inventory/UpdatePrice",
-            );
-            self.inner.unary(request.into_request(), path, codec).await
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Outcome::Found => "FOUND",
+                Outcome::NotFound => "NOT_FOUND",
+                Outcome::Invalid => "INVALID",
+            }
         }
-        /// Watch streams Item updates from the inventory.
-        pub async fn watch(
-            &mut self,
-            request: impl tonic::IntoRequest<super::ItemIdentifier>,
-        ) -> Result<
-            tonic::Response<tonic::codec::Streaming<super::Item>>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Watch");
-            self.inner.server_streaming(request.into_request(), path, codec).await
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "FOUND" => Some(Self::Found),
+                "NOT_FOUND" => Some(Self::NotFound),
+                "INVALID" => Some(Self::Invalid),
+                _ => None,
+            }
         }
     }
 }
-/// Generated server implementations.
-pub mod inventory_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with InventoryServer.
-    #[async_trait]
-    pub trait Inventory: Send + Sync + 'static {
-        /// Add inserts a new Item into the inventory.
-        async fn add(
-            &self,
-            request: tonic::Request<super::Item>,
-        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
-        /// Remove removes Items from the inventory.
-        async fn remove(
-            &self,
-            request: tonic::Request<super::ItemIdentifier>,
-        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
-        /// Get retrieves Item information.
-        async fn get(
-            &self,
-            request: tonic::Request<super::ItemIdentifier>,
-        ) -> Result<tonic::Response<super::Item>, tonic::Status>;
-        /// UpdateQuantity increases or decreases the stock quantity of an Item.
-        async fn update_quantity(
-            &self,
-            request: tonic::Request<super::QuantityChangeRequest>,
-        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status>;
-        /// UpdatePrice increases or decreases the price of an Item.
-        async fn update_price(
-            &self,
-            request: tonic::Request<super::PriceChangeRequest>,
-        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status>;
-        /// Server streaming response type for the Watch method.
-        type WatchStream: futures_core::Stream<Item = Result<super::Item, tonic::Status>>
-            + Send
-            + 'static;
-        /// Watch streams Item updates from the inventory.
-        async fn watch(
-            &self,
-            request: tonic::Request<super::ItemIdentifier>,
-        ) -> Result<tonic::Response<Self::WatchStream>, tonic::Status>;
+/// GetManyResponse holds a result for each requested SKU, in the order they
+/// were requested.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetManyResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<GetManyResult>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RenameSkuRequest {
+    #[prost(string, tag = "1")]
+    pub old_sku: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub new_sku: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SwapStockRequest {
+    #[prost(string, tag = "1")]
+    pub first_sku: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub second_sku: ::prost::alloc::string::String,
+}
+/// WatchEvent is a message sent by Watch: either the Item after a change to
+/// it, or a heartbeat which only keeps the stream alive. The stream ends with
+/// NOT_FOUND when the Item is removed.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchEvent {
+    #[prost(oneof = "watch_event::Event", tags = "1, 2")]
+    pub event: ::core::option::Option<watch_event::Event>,
+}
+/// Nested message and enum types in `WatchEvent`.
+pub mod watch_event {
+    #[allow(clippy::large_enum_variant)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Event {
+        #[prost(message, tag = "1")]
+        Item(super::Item),
+        #[prost(message, tag = "2")]
+        Heartbeat(super::Heartbeat),
     }
-    #[derive(Debug)]
-    pub struct InventoryServer<T: Inventory> {
-        inner: _Inner<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
+}
+/// Heartbeat is sent by a Watch which has sent nothing else for the server's
+/// heartbeat interval.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Heartbeat {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchAllRequest {}
+/// ItemEvent is a change to an Item reported by WatchAll.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ItemEvent {
+    /// the Item as it is after the change. Only the identifier is set when the
+    /// Item was removed.
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub item: ::core::option::Option<Item>,
+    #[prost(bool, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub removed: bool,
+}
+/// ReservationRequest reserves or releases some quantity of an Item's stock.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReservationRequest {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub quantity: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadOnlyRequest {
+    #[prost(bool, tag = "1")]
+    pub read_only: bool,
+}
+/// ListRequest requests a page of Items. Each page resumes strictly after the
+/// last SKU of the previous page, which gives these guarantees when the
+/// inventory changes between pages:
+///
+///   - Items present for the whole listing appear exactly once.
+///   - Items added during the listing appear only if their SKU sorts after the
+///     page being listed when they were added.
+///   - Items removed during the listing are missing if they were removed
+///     before their page was listed.
+///   - Items appear as they were when their page was listed.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRequest {
+    /// the maximum number of Items in the page, defaults to 100 when 0 and is
+    /// capped at 1000.
+    #[prost(uint32, tag = "1")]
+    pub page_size: u32,
+    /// the next_page_token of the previous page, empty for the first page.
+    #[prost(string, tag = "2")]
+    pub page_token: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+    /// the token to list the next page with, empty when this was the last.
+    #[prost(string, tag = "2")]
+    pub next_page_token: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SampleRequest {
+    #[prost(uint32, tag = "1")]
+    pub count: u32,
+    #[prost(enumeration = "sample_request::Weighting", tag = "2")]
+    pub weighting: i32,
+    /// seeds the random selection, so that sampling the same inventory with
+    /// the same seed always chooses the same Items.
+    #[prost(uint64, optional, tag = "3")]
+    pub seed: ::core::option::Option<u64>,
+}
+/// Nested message and enum types in `SampleRequest`.
+pub mod sample_request {
+    /// Weighting determines how likely each Item is to be chosen.
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Weighting {
+        Uniform = 0,
+        Quantity = 1,
+        Value = 2,
     }
-    struct _Inner<T>(Arc<T>);
-    impl<T: Inventory> InventoryServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            let inner = _Inner(inner);
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
+    impl Weighting {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Weighting::Uniform => "UNIFORM",
+                Weighting::Quantity => "QUANTITY",
+                Weighting::Value => "VALUE",
             }
         }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "UNIFORM" => Some(Self::Uniform),
+                "QUANTITY" => Some(Self::Quantity),
+                "VALUE" => Some(Self::Value),
+                _ => None,
+            }
         }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SampleResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MetricsRequest {}
+/// MethodMetrics describes the requests served for a method. Latencies are in
+/// microseconds, and for streaming methods only cover opening the stream.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MethodMetrics {
+    #[prost(string, tag = "1")]
+    pub method: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub requests: u64,
+    #[prost(uint64, tag = "3")]
+    pub errors: u64,
+    #[prost(uint64, tag = "4")]
+    pub mean_latency_micros: u64,
+    #[prost(uint64, tag = "5")]
+    pub max_latency_micros: u64,
+}
+/// Watcher describes a Watch stream which is currently open.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Watcher {
+    #[prost(string, tag = "1")]
+    pub stream_id: ::prost::alloc::string::String,
+    /// the SKU being watched, empty for WatchAll streams.
+    #[prost(string, tag = "2")]
+    pub sku: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MetricsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub methods: ::prost::alloc::vec::Vec<MethodMetrics>,
+    #[prost(uint64, tag = "2")]
+    pub items: u64,
+    #[prost(uint64, tag = "3")]
+    pub active_watchers: u64,
+    /// the open Watch streams, in the order they were opened.
+    #[prost(message, repeated, tag = "4")]
+    pub watchers: ::prost::alloc::vec::Vec<Watcher>,
+}
+/// Operation is a single change to the inventory within a batch.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Operation {
+    #[prost(oneof = "operation::Operation", tags = "1, 2, 3, 4")]
+    pub operation: ::core::option::Option<operation::Operation>,
+}
+/// Nested message and enum types in `Operation`.
+pub mod operation {
+    #[allow(clippy::large_enum_variant)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Operation {
+        #[prost(message, tag = "1")]
+        Add(super::Item),
+        #[prost(message, tag = "2")]
+        Remove(super::ItemIdentifier),
+        #[prost(message, tag = "3")]
+        UpdatePrice(super::PriceChangeRequest),
+        #[prost(message, tag = "4")]
+        UpdateQuantity(super::QuantityChangeRequest),
+    }
+}
+/// ApplyBatchRequest holds operations which are applied in order, each of them
+/// sees the changes of those before it. When an operation fails the index of it
+/// is reported in the "x-failed-operation" metadata of the error.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplyBatchRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub operations: ::prost::alloc::vec::Vec<Operation>,
+}
+/// BatchRemoveRequest holds the SKUs of the Items to remove. The batch is
+/// rejected as a whole if any of the SKUs is invalid.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchRemoveRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub skus: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchRemoveResponse {
+    /// the number of Items which were removed.
+    #[prost(uint32, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub removed: u32,
+    /// the number of SKUs which had no Item to remove, SKUs listed more than
+    /// once only have an Item to remove the first time.
+    #[prost(uint32, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub missing: u32,
+}
+/// BatchGetRequest holds the SKUs of the Items to retrieve. The batch is
+/// rejected as a whole if any of the SKUs is invalid.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchGetRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub skus: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// BatchGetResponse holds the Items which were found and the SKUs which
+/// weren't, both in the order they were requested. SKUs listed more than once
+/// are only reported once.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchGetResponse {
+    #[prost(message, repeated, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+    #[prost(string, repeated, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub missing: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TailLogsRequest {
+    /// the number of recent log lines to send before the live ones.
+    #[prost(uint32, tag = "1")]
+    pub backlog: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuditLogRequest {
+    /// the number of recent entries to retrieve, all of those kept when 0.
+    #[prost(uint32, tag = "1")]
+    pub limit: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuditLogResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub entries: ::prost::alloc::vec::Vec<AuditEntry>,
+}
+/// AuditEntry records a change made to an Item, along with the Item before
+/// and after it. The Item before is unset for additions, and the Item after
+/// is unset for removals.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuditEntry {
+    /// the time the change was made, in unix epoch milliseconds.
+    #[prost(uint64, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub timestamp: u64,
+    /// the method which made the change, e.g. "UpdatePrice", or "Expire" for
+    /// Items removed once they expired.
+    #[prost(string, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub operation: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "4")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub before: ::core::option::Option<Item>,
+    #[prost(message, optional, tag = "5")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub after: ::core::option::Option<Item>,
+}
+/// LogLine is a line logged by the server. Clients which fall behind miss the
+/// oldest lines rather than holding up logging, in which case the next line
+/// they receive reports how many were dropped.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LogLine {
+    /// the time the line was logged, in unix epoch milliseconds.
+    #[prost(uint64, tag = "1")]
+    pub timestamp: u64,
+    #[prost(string, tag = "2")]
+    pub level: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub target: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub message: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "5")]
+    pub dropped: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AccessStatsRequest {
+    /// the maximum number of Items to report, defaults to 10 when 0 and is
+    /// capped at 1000.
+    #[prost(uint32, tag = "1")]
+    pub limit: u32,
+}
+/// ItemAccesses reports how many times an Item was fetched since it was added.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ItemAccesses {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub accesses: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AccessStatsResponse {
+    /// the most fetched Items, in descending order of accesses.
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<ItemAccesses>,
+}
+/// PriceBucketsRequest defines price ranges by the boundaries between them,
+/// which must be strictly ascending. N boundaries make N+1 buckets: one below
+/// the first boundary, one between each pair, and one from the last boundary
+/// up. Buckets include their lower boundary and exclude their upper one, so an
+/// Item priced exactly on a boundary is counted in the bucket above it.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PriceBucketsRequest {
+    #[prost(float, repeated, tag = "1")]
+    pub boundaries: ::prost::alloc::vec::Vec<f32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PriceBucket {
+    /// the lower boundary of the bucket (inclusive), absent for the first.
+    #[prost(float, optional, tag = "1")]
+    pub min: ::core::option::Option<f32>,
+    /// the upper boundary of the bucket (exclusive), absent for the last.
+    #[prost(float, optional, tag = "2")]
+    pub max: ::core::option::Option<f32>,
+    #[prost(uint64, tag = "3")]
+    pub count: u64,
+    /// the total value (price times quantity) of the Items in the bucket.
+    #[prost(double, tag = "4")]
+    pub total_value: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PriceBucketsResponse {
+    /// the buckets in ascending price order, including those which are empty.
+    #[prost(message, repeated, tag = "1")]
+    pub buckets: ::prost::alloc::vec::Vec<PriceBucket>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsistencyRequest {
+    /// repairs the issues which can be repaired, rather than only reporting
+    /// them. Repairs are refused in read-only maintenance mode.
+    #[prost(bool, tag = "1")]
+    pub repair: bool,
+}
+/// ConsistencyIssue describes an Item which broke an invariant.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsistencyIssue {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub description: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub repaired: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsistencyReport {
+    /// the issues found, ordered by SKU.
+    #[prost(message, repeated, tag = "1")]
+    pub issues: ::prost::alloc::vec::Vec<ConsistencyIssue>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadSnapshotRequest {
+    /// the Items the inventory will hold, each SKU may only appear once.
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadSnapshotResponse {
+    /// the number of Items loaded from the snapshot.
+    #[prost(uint32, tag = "1")]
+    pub loaded: u32,
+    /// the number of Items the inventory held before they were replaced.
+    #[prost(uint32, tag = "2")]
+    pub replaced: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LatencySummaryRequest {}
+/// MethodLatency describes the latency of the requests served for a method
+/// within the window, in microseconds. Percentiles are accurate to within
+/// 1/8th of their value.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MethodLatency {
+    #[prost(string, tag = "1")]
+    pub method: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub requests: u64,
+    #[prost(uint64, tag = "3")]
+    pub p50_micros: u64,
+    #[prost(uint64, tag = "4")]
+    pub p90_micros: u64,
+    #[prost(uint64, tag = "5")]
+    pub p99_micros: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LatencySummaryResponse {
+    /// the methods which served requests within the window, ordered by method.
+    #[prost(message, repeated, tag = "1")]
+    pub methods: ::prost::alloc::vec::Vec<MethodLatency>,
+    /// how far back the summaries look.
+    #[prost(uint64, tag = "2")]
+    pub window_seconds: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BulkPriceRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub changes: ::prost::alloc::vec::Vec<PriceChangeRequest>,
+}
+/// PriceChangeResult is the outcome of a single change of a bulk price update.
+/// The price is the Item's price after the change, and is only set when the
+/// Item was found. The error is only set when the change failed.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PriceChangeResult {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(enumeration = "price_change_result::Outcome", tag = "2")]
+    pub outcome: i32,
+    #[prost(float, tag = "3")]
+    pub price: f32,
+    #[prost(string, tag = "4")]
+    pub error: ::prost::alloc::string::String,
+}
+/// Nested message and enum types in `PriceChangeResult`.
+pub mod price_change_result {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Outcome {
+        Updated = 0,
+        Unchanged = 1,
+        Failed = 2,
+    }
+    impl Outcome {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Outcome::Updated => "UPDATED",
+                Outcome::Unchanged => "UNCHANGED",
+                Outcome::Failed => "FAILED",
+            }
         }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "UPDATED" => Some(Self::Updated),
+                "UNCHANGED" => Some(Self::Unchanged),
+                "FAILED" => Some(Self::Failed),
+                _ => None,
+            }
         }
     }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for InventoryServer<T>
-    where
-        T: Inventory,
-        B: Body + Send + 'static,
-        B::Error: Into<StdError> + Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
-            &mut self,
-            _cx: &mut Context<'_>,
-        ) -> Poll<Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
-        }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            let inner = self.inner.clone();
-            match req.uri().path() {
-                "/store.Inventory/Add" => {
+}
+/// BulkPriceResponse holds a result for each requested change, in the order
+/// they were requested.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BulkPriceResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<PriceChangeResult>,
+}
+/// InformationChangeRequest changes the information of an Item. The name and
+/// description are only changed when they're set, and each attribute is set to
+/// its value, or removed from the Item if its value is empty.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InformationChangeRequest {
+    #[prost(string, tag = "1")]
+    pub sku: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub information: ::core::option::Option<ItemInformation>,
+    #[prost(map = "string, string", tag = "3")]
+    pub attributes: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttributeFilter {
+    #[prost(string, tag = "1")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListByAttributeResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TagFilter {
+    #[prost(string, tag = "1")]
+    pub tag: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListByTagResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WeightRangeRequest {
+    /// the lightest weight (in grams) of the Items to list.
+    #[prost(float, tag = "1")]
+    pub min_grams: f32,
+    /// the heaviest weight (in grams) of the Items to list, which is unbounded
+    /// when it isn't set.
+    #[prost(float, optional, tag = "2")]
+    pub max_grams: ::core::option::Option<f32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListByWeightRangeResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LowStockRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListLowStockResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchRequest {
+    #[prost(string, tag = "1")]
+    pub query: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<Item>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InventoryStatsRequest {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InventoryStatsResponse {
+    #[prost(uint64, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub items: u64,
+    #[prost(uint64, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub units: u64,
+    /// the sum of price * quantity over every Item, in the currency below.
+    /// Summed exactly in cents, the total only loses precision beyond about
+    /// 90 trillion.
+    #[prost(double, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub total_value: f64,
+    /// the currency the total value is in: the Items' currency when they all
+    /// share one, and the base currency otherwise.
+    #[prost(string, tag = "4")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub currency: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CountRequest {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CountResponse {
+    #[prost(uint64, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub count: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ServerInfoRequest {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ServerInfo {
+    /// the version of the server's crate, e.g. "0.1.0".
+    #[prost(string, tag = "1")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub version: ::prost::alloc::string::String,
+    /// when the server was built, in unix epoch milliseconds.
+    #[prost(uint64, tag = "2")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub built_at: u64,
+    /// the git commit the server was built from, which is empty when it wasn't
+    /// built from a git checkout.
+    #[prost(string, tag = "3")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    pub git_sha: ::prost::alloc::string::String,
+    /// how long the server has been up, in milliseconds.
+    #[prost(uint64, tag = "4")]
+    #[serde(skip_serializing_if = "crate::json::is_default")]
+    #[serde(with = "crate::json::uint64")]
+    pub uptime_ms: u64,
+}
+/// Generated client implementations.
+pub mod inventory_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct InventoryClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl InventoryClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: std::convert::TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> InventoryClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InventoryClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            InventoryClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Add inserts a new Item into the inventory.
+        pub async fn add(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Item>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Add");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// BatchAdd inserts a stream of new Items into the inventory, reporting how
+        /// many were added and how many failed.
+        pub async fn batch_add(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::Item>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/BatchAdd");
+            self.inner
+                .client_streaming(request.into_streaming_request(), path, codec)
+                .await
+        }
+        /// Upsert inserts an Item, replacing the Item with the same SKU if there
+        /// is one. A replaced Item loses its image and reservations, as if it was
+        /// removed and added again.
+        pub async fn upsert(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Item>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Upsert");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// ApplyBatch applies a list of operations to the inventory atomically,
+        /// either all of them are applied or none are.
+        pub async fn apply_batch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ApplyBatchRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/ApplyBatch",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Remove removes Items from the inventory. Removing an Item which isn't
+        /// in the inventory succeeds, reporting that it didn't exist, unless the
+        /// request is strict.
+        pub async fn remove(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RemoveRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Remove");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// BatchRemove removes a list of Items from the inventory at once,
+        /// reporting how many were removed and how many didn't exist.
+        pub async fn batch_remove(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BatchRemoveRequest>,
+        ) -> Result<tonic::Response<super::BatchRemoveResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/BatchRemove",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Get retrieves Item information. Items stored without stock (which Add
+        /// rejects, but storage written by other means might hold) are returned
+        /// without it, and changes to their stock fail with FAILED_PRECONDITION.
+        pub async fn get(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetRequest>,
+        ) -> Result<tonic::Response<super::Item>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Get");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// UpdateQuantity increases or decreases the stock quantity of an Item.
+        /// Decreasing it below the stock reserved of the Item fails with
+        /// FAILED_PRECONDITION.
+        pub async fn update_quantity(
+            &mut self,
+            request: impl tonic::IntoRequest<super::QuantityChangeRequest>,
+        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/UpdateQuantity",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// SetQuantity sets the stock quantity of an Item to an exact count, which
+        /// unlike UpdateQuantity doesn't depend on the quantity it had. As with
+        /// UpdateQuantity, it can't be set below the stock which is reserved.
+        pub async fn set_quantity(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetQuantityRequest>,
+        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/SetQuantity",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// SetReorderThreshold sets or clears the quantity at which an Item is
+        /// listed by ListLowStock.
+        pub async fn set_reorder_threshold(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReorderThresholdRequest>,
+        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/SetReorderThreshold",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// UpdatePrice increases or decreases the price of an Item. Updating an
+        /// Item to the price it's already at succeeds without changing it, with
+        /// the status "success: no change". When an expected price is given the
+        /// update is only applied if the Item is still at that price, and fails
+        /// with FAILED_PRECONDITION otherwise, so that concurrent updates based on
+        /// a stale read aren't lost. Updates can't change the currency an Item is
+        /// priced in, those which would fail with FAILED_PRECONDITION.
+        pub async fn update_price(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PriceChangeRequest>,
+        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/UpdatePrice",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// BulkUpdatePrice changes the prices of many Items, reporting an outcome
+        /// for each change rather than failing them all. Items already at the
+        /// requested price are reported as unchanged.
+        pub async fn bulk_update_price(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BulkPriceRequest>,
+        ) -> Result<tonic::Response<super::BulkPriceResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/BulkUpdatePrice",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// UpdateInformation changes the descriptive information and attributes
+        /// of an Item. Updates which leave the Item as it was succeed without
+        /// changing it, with the status "success: no change".
+        pub async fn update_information(
+            &mut self,
+            request: impl tonic::IntoRequest<super::InformationChangeRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/UpdateInformation",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Watch streams Item updates from the inventory. Clients can identify the
+        /// stream with the "x-stream-id" metadata header, e.g. to correlate the
+        /// streams they reconnect with in logs. The id is echoed in the response
+        /// metadata, and generated when the client didn't supply one. Servers can
+        /// be configured to send heartbeats while the Item is unchanged, so that
+        /// proxies don't close streams which are idle. Clients resuming a watch
+        /// after reconnecting can send the version of the Item they last saw, and
+        /// are sent the Item as it is now first if it has changed since.
+        pub async fn watch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchRequest>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::WatchEvent>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Watch");
+            self.inner.server_streaming(request.into_request(), path, codec).await
+        }
+        /// GetAndWatch streams the Item as it is now, followed by its updates as
+        /// Watch does. The first Item is a consistent snapshot: every change made
+        /// after it is streamed, and none made before it are.
+        pub async fn get_and_watch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ItemIdentifier>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::WatchEvent>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/GetAndWatch",
+            );
+            self.inner.server_streaming(request.into_request(), path, codec).await
+        }
+        /// WatchAll streams changes to every Item in the inventory as they're made,
+        /// including Items being added and removed. Streams are identified like
+        /// Watch streams. Streams which fall too far behind end with DATA_LOSS, as
+        /// the changes they missed can't be recovered.
+        pub async fn watch_all(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchAllRequest>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::ItemEvent>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/WatchAll");
+            self.inner.server_streaming(request.into_request(), path, codec).await
+        }
+        /// ListChangedSince retrieves the Items updated after a point in time, and
+        /// the SKUs of Items removed after it. Removals are only remembered for the
+        /// server's tombstone retention (a day by default), so syncs from before a
+        /// forgotten removal fail with FAILED_PRECONDITION, and clients must then
+        /// sync everything again with a since of 0.
+        pub async fn list_changed_since(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ChangedSinceRequest>,
+        ) -> Result<tonic::Response<super::ChangedSinceResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/ListChangedSince",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// UploadImage stores an image for an Item, streamed in chunks.
+        pub async fn upload_image(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::ImageChunk>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/UploadImage",
+            );
+            self.inner
+                .client_streaming(request.into_streaming_request(), path, codec)
+                .await
+        }
+        /// GetImage streams the image stored for an Item in chunks.
+        pub async fn get_image(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ItemIdentifier>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::ImageChunk>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/GetImage");
+            self.inner.server_streaming(request.into_request(), path, codec).await
+        }
+        /// GetMany retrieves multiple Items as they were at a single moment,
+        /// reporting the outcome for each SKU.
+        pub async fn get_many(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetManyRequest>,
+        ) -> Result<tonic::Response<super::GetManyResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/GetMany");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// BatchGet retrieves multiple Items as GetMany does, but lists the Items
+        /// which were found and the SKUs without an Item as missing. Unlike
+        /// GetMany, the batch is rejected as a whole if any of the SKUs is invalid.
+        pub async fn batch_get(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BatchGetRequest>,
+        ) -> Result<tonic::Response<super::BatchGetResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/BatchGet");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// SwapStock atomically exchanges the stock of two Items.
+        pub async fn swap_stock(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SwapStockRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/SwapStock",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// RenameSku moves an Item to a new SKU, keeping its stock, information,
+        /// image and reservations. Watches of the old SKU see the Item removed,
+        /// and the renamed Item is returned.
+        pub async fn rename_sku(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RenameSkuRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/RenameSku",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Reserve sets aside some of an Item's stock (e.g. for a pending order)
+        /// without removing it, so that it's no longer reported as available. The
+        /// reserved quantity can't exceed the Item's quantity.
+        pub async fn reserve(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReservationRequest>,
+        ) -> Result<tonic::Response<super::Availability>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Reserve");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Release returns previously reserved stock of an Item, so that it's
+        /// available again.
+        pub async fn release(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReservationRequest>,
+        ) -> Result<tonic::Response<super::Availability>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Release");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// SetReadOnly enables or disables read-only maintenance mode, in which
+        /// the inventory can't be changed. Requires admin credentials.
+        pub async fn set_read_only(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReadOnlyRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/SetReadOnly",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// List retrieves Items in ascending SKU order, a page at a time.
+        pub async fn list(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListRequest>,
+        ) -> Result<tonic::Response<super::ListResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/List");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Sample retrieves randomly chosen Items, optionally weighted.
+        pub async fn sample(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SampleRequest>,
+        ) -> Result<tonic::Response<super::SampleResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Sample");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// GetMetrics retrieves the current values of the server's metrics.
+        pub async fn get_metrics(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MetricsRequest>,
+        ) -> Result<tonic::Response<super::MetricsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/GetMetrics",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// TailLogs streams recent log lines of the server followed by live ones.
+        /// Requires admin credentials.
+        pub async fn tail_logs(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TailLogsRequest>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::LogLine>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/TailLogs");
+            self.inner.server_streaming(request.into_request(), path, codec).await
+        }
+        /// GetAuditLog retrieves the most recent changes made to Items, oldest
+        /// first, including those made by batches, snapshots and expiry. Requires
+        /// admin credentials.
+        pub async fn get_audit_log(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AuditLogRequest>,
+        ) -> Result<tonic::Response<super::AuditLogResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/GetAuditLog",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// GetAccessStats retrieves the Items which were fetched the most, either
+        /// by Get or by opening a Watch.
+        pub async fn get_access_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AccessStatsRequest>,
+        ) -> Result<tonic::Response<super::AccessStatsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/GetAccessStats",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// GroupByPriceBucket counts the Items, and their total value, in each of
+        /// the price ranges between the requested boundaries.
+        pub async fn group_by_price_bucket(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PriceBucketsRequest>,
+        ) -> Result<tonic::Response<super::PriceBucketsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/GroupByPriceBucket",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// CheckConsistency verifies that the structures kept alongside the Items
+        /// agree with them, optionally repairing those which don't. Requires admin
+        /// credentials.
+        pub async fn check_consistency(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ConsistencyRequest>,
+        ) -> Result<tonic::Response<super::ConsistencyReport>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/CheckConsistency",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// LoadSnapshot replaces the entire inventory with the provided Items, or
+        /// changes nothing if any of them is invalid. Requires admin credentials.
+        pub async fn load_snapshot(
+            &mut self,
+            request: impl tonic::IntoRequest<super::LoadSnapshotRequest>,
+        ) -> Result<tonic::Response<super::LoadSnapshotResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/LoadSnapshot",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// ListByAttribute retrieves the Items with an attribute of the given
+        /// value, in ascending SKU order.
+        pub async fn list_by_attribute(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AttributeFilter>,
+        ) -> Result<tonic::Response<super::ListByAttributeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/ListByAttribute",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// ListByTag retrieves the Items carrying the given tag, in ascending SKU
+        /// order.
+        pub async fn list_by_tag(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TagFilter>,
+        ) -> Result<tonic::Response<super::ListByTagResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/ListByTag",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// ListLowStock retrieves the Items whose quantity is at or below their
+        /// reorder threshold, in ascending SKU order. Items without a threshold
+        /// are never listed.
+        pub async fn list_low_stock(
+            &mut self,
+            request: impl tonic::IntoRequest<super::LowStockRequest>,
+        ) -> Result<tonic::Response<super::ListLowStockResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/ListLowStock",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// GetLatencySummary retrieves latency percentiles for each method which
+        /// served requests within the recent window.
+        pub async fn get_latency_summary(
+            &mut self,
+            request: impl tonic::IntoRequest<super::LatencySummaryRequest>,
+        ) -> Result<tonic::Response<super::LatencySummaryResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/GetLatencySummary",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Search retrieves the Items whose name or description contains the
+        /// query, ignoring case, in ascending SKU order. Items without any
+        /// information never match.
+        pub async fn search(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SearchRequest>,
+        ) -> Result<tonic::Response<super::SearchResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Search");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// InventoryStats reports totals across the whole inventory, taken at a
+        /// single moment: the number of Items, their units in stock and the total
+        /// value of those units. Items priced in different currencies are valued
+        /// in the base currency using the server's exchange rates, and the stats
+        /// fail with FAILED_PRECONDITION when a currency has no rate.
+        pub async fn inventory_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<super::InventoryStatsRequest>,
+        ) -> Result<tonic::Response<super::InventoryStatsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/InventoryStats",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Count reports the number of Items in the inventory, without any of
+        /// their data.
+        pub async fn count(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CountRequest>,
+        ) -> Result<tonic::Response<super::CountResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Count");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Validate checks an Item as Add would without adding it, failing with
+        /// the same INVALID_ARGUMENT errors Add would. A valid Item is returned as
+        /// Add would store it, e.g. with its price rounded to the cent. Whether an
+        /// Item with the SKU is already in the inventory isn't checked.
+        pub async fn validate(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Item>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Inventory/Validate");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// GetServerInfo reports which build of the server is running and how
+        /// long it has been up, e.g. to tell which build is serving behind a load
+        /// balancer.
+        pub async fn get_server_info(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ServerInfoRequest>,
+        ) -> Result<tonic::Response<super::ServerInfo>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/GetServerInfo",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// ListByWeightRange retrieves the Items weighing between the given
+        /// bounds (both inclusive), in ascending SKU order, e.g. for slotting them
+        /// in a warehouse. Items without physical attributes are never listed.
+        pub async fn list_by_weight_range(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WeightRangeRequest>,
+        ) -> Result<tonic::Response<super::ListByWeightRangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Inventory/ListByWeightRange",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod inventory_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with InventoryServer.
+    #[async_trait]
+    pub trait Inventory: Send + Sync + 'static {
+        /// Add inserts a new Item into the inventory.
+        async fn add(
+            &self,
+            request: tonic::Request<super::Item>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// BatchAdd inserts a stream of new Items into the inventory, reporting how
+        /// many were added and how many failed.
+        async fn batch_add(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::Item>>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// Upsert inserts an Item, replacing the Item with the same SKU if there
+        /// is one. A replaced Item loses its image and reservations, as if it was
+        /// removed and added again.
+        async fn upsert(
+            &self,
+            request: tonic::Request<super::Item>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// ApplyBatch applies a list of operations to the inventory atomically,
+        /// either all of them are applied or none are.
+        async fn apply_batch(
+            &self,
+            request: tonic::Request<super::ApplyBatchRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// Remove removes Items from the inventory. Removing an Item which isn't
+        /// in the inventory succeeds, reporting that it didn't exist, unless the
+        /// request is strict.
+        async fn remove(
+            &self,
+            request: tonic::Request<super::RemoveRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// BatchRemove removes a list of Items from the inventory at once,
+        /// reporting how many were removed and how many didn't exist.
+        async fn batch_remove(
+            &self,
+            request: tonic::Request<super::BatchRemoveRequest>,
+        ) -> Result<tonic::Response<super::BatchRemoveResponse>, tonic::Status>;
+        /// Get retrieves Item information. Items stored without stock (which Add
+        /// rejects, but storage written by other means might hold) are returned
+        /// without it, and changes to their stock fail with FAILED_PRECONDITION.
+        async fn get(
+            &self,
+            request: tonic::Request<super::GetRequest>,
+        ) -> Result<tonic::Response<super::Item>, tonic::Status>;
+        /// UpdateQuantity increases or decreases the stock quantity of an Item.
+        /// Decreasing it below the stock reserved of the Item fails with
+        /// FAILED_PRECONDITION.
+        async fn update_quantity(
+            &self,
+            request: tonic::Request<super::QuantityChangeRequest>,
+        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status>;
+        /// SetQuantity sets the stock quantity of an Item to an exact count, which
+        /// unlike UpdateQuantity doesn't depend on the quantity it had. As with
+        /// UpdateQuantity, it can't be set below the stock which is reserved.
+        async fn set_quantity(
+            &self,
+            request: tonic::Request<super::SetQuantityRequest>,
+        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status>;
+        /// SetReorderThreshold sets or clears the quantity at which an Item is
+        /// listed by ListLowStock.
+        async fn set_reorder_threshold(
+            &self,
+            request: tonic::Request<super::ReorderThresholdRequest>,
+        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status>;
+        /// UpdatePrice increases or decreases the price of an Item. Updating an
+        /// Item to the price it's already at succeeds without changing it, with
+        /// the status "success: no change". When an expected price is given the
+        /// update is only applied if the Item is still at that price, and fails
+        /// with FAILED_PRECONDITION otherwise, so that concurrent updates based on
+        /// a stale read aren't lost. Updates can't change the currency an Item is
+        /// priced in, those which would fail with FAILED_PRECONDITION.
+        async fn update_price(
+            &self,
+            request: tonic::Request<super::PriceChangeRequest>,
+        ) -> Result<tonic::Response<super::InventoryUpdateResponse>, tonic::Status>;
+        /// BulkUpdatePrice changes the prices of many Items, reporting an outcome
+        /// for each change rather than failing them all. Items already at the
+        /// requested price are reported as unchanged.
+        async fn bulk_update_price(
+            &self,
+            request: tonic::Request<super::BulkPriceRequest>,
+        ) -> Result<tonic::Response<super::BulkPriceResponse>, tonic::Status>;
+        /// UpdateInformation changes the descriptive information and attributes
+        /// of an Item. Updates which leave the Item as it was succeed without
+        /// changing it, with the status "success: no change".
+        async fn update_information(
+            &self,
+            request: tonic::Request<super::InformationChangeRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// Server streaming response type for the Watch method.
+        type WatchStream: futures_core::Stream<
+                Item = Result<super::WatchEvent, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Watch streams Item updates from the inventory. Clients can identify the
+        /// stream with the "x-stream-id" metadata header, e.g. to correlate the
+        /// streams they reconnect with in logs. The id is echoed in the response
+        /// metadata, and generated when the client didn't supply one. Servers can
+        /// be configured to send heartbeats while the Item is unchanged, so that
+        /// proxies don't close streams which are idle. Clients resuming a watch
+        /// after reconnecting can send the version of the Item they last saw, and
+        /// are sent the Item as it is now first if it has changed since.
+        async fn watch(
+            &self,
+            request: tonic::Request<super::WatchRequest>,
+        ) -> Result<tonic::Response<Self::WatchStream>, tonic::Status>;
+        /// Server streaming response type for the GetAndWatch method.
+        type GetAndWatchStream: futures_core::Stream<
+                Item = Result<super::WatchEvent, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// GetAndWatch streams the Item as it is now, followed by its updates as
+        /// Watch does. The first Item is a consistent snapshot: every change made
+        /// after it is streamed, and none made before it are.
+        async fn get_and_watch(
+            &self,
+            request: tonic::Request<super::ItemIdentifier>,
+        ) -> Result<tonic::Response<Self::GetAndWatchStream>, tonic::Status>;
+        /// Server streaming response type for the WatchAll method.
+        type WatchAllStream: futures_core::Stream<
+                Item = Result<super::ItemEvent, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// WatchAll streams changes to every Item in the inventory as they're made,
+        /// including Items being added and removed. Streams are identified like
+        /// Watch streams. Streams which fall too far behind end with DATA_LOSS, as
+        /// the changes they missed can't be recovered.
+        async fn watch_all(
+            &self,
+            request: tonic::Request<super::WatchAllRequest>,
+        ) -> Result<tonic::Response<Self::WatchAllStream>, tonic::Status>;
+        /// ListChangedSince retrieves the Items updated after a point in time, and
+        /// the SKUs of Items removed after it. Removals are only remembered for the
+        /// server's tombstone retention (a day by default), so syncs from before a
+        /// forgotten removal fail with FAILED_PRECONDITION, and clients must then
+        /// sync everything again with a since of 0.
+        async fn list_changed_since(
+            &self,
+            request: tonic::Request<super::ChangedSinceRequest>,
+        ) -> Result<tonic::Response<super::ChangedSinceResponse>, tonic::Status>;
+        /// UploadImage stores an image for an Item, streamed in chunks.
+        async fn upload_image(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::ImageChunk>>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// Server streaming response type for the GetImage method.
+        type GetImageStream: futures_core::Stream<
+                Item = Result<super::ImageChunk, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// GetImage streams the image stored for an Item in chunks.
+        async fn get_image(
+            &self,
+            request: tonic::Request<super::ItemIdentifier>,
+        ) -> Result<tonic::Response<Self::GetImageStream>, tonic::Status>;
+        /// GetMany retrieves multiple Items as they were at a single moment,
+        /// reporting the outcome for each SKU.
+        async fn get_many(
+            &self,
+            request: tonic::Request<super::GetManyRequest>,
+        ) -> Result<tonic::Response<super::GetManyResponse>, tonic::Status>;
+        /// BatchGet retrieves multiple Items as GetMany does, but lists the Items
+        /// which were found and the SKUs without an Item as missing. Unlike
+        /// GetMany, the batch is rejected as a whole if any of the SKUs is invalid.
+        async fn batch_get(
+            &self,
+            request: tonic::Request<super::BatchGetRequest>,
+        ) -> Result<tonic::Response<super::BatchGetResponse>, tonic::Status>;
+        /// SwapStock atomically exchanges the stock of two Items.
+        async fn swap_stock(
+            &self,
+            request: tonic::Request<super::SwapStockRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// RenameSku moves an Item to a new SKU, keeping its stock, information,
+        /// image and reservations. Watches of the old SKU see the Item removed,
+        /// and the renamed Item is returned.
+        async fn rename_sku(
+            &self,
+            request: tonic::Request<super::RenameSkuRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// Reserve sets aside some of an Item's stock (e.g. for a pending order)
+        /// without removing it, so that it's no longer reported as available. The
+        /// reserved quantity can't exceed the Item's quantity.
+        async fn reserve(
+            &self,
+            request: tonic::Request<super::ReservationRequest>,
+        ) -> Result<tonic::Response<super::Availability>, tonic::Status>;
+        /// Release returns previously reserved stock of an Item, so that it's
+        /// available again.
+        async fn release(
+            &self,
+            request: tonic::Request<super::ReservationRequest>,
+        ) -> Result<tonic::Response<super::Availability>, tonic::Status>;
+        /// SetReadOnly enables or disables read-only maintenance mode, in which
+        /// the inventory can't be changed. Requires admin credentials.
+        async fn set_read_only(
+            &self,
+            request: tonic::Request<super::ReadOnlyRequest>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// List retrieves Items in ascending SKU order, a page at a time.
+        async fn list(
+            &self,
+            request: tonic::Request<super::ListRequest>,
+        ) -> Result<tonic::Response<super::ListResponse>, tonic::Status>;
+        /// Sample retrieves randomly chosen Items, optionally weighted.
+        async fn sample(
+            &self,
+            request: tonic::Request<super::SampleRequest>,
+        ) -> Result<tonic::Response<super::SampleResponse>, tonic::Status>;
+        /// GetMetrics retrieves the current values of the server's metrics.
+        async fn get_metrics(
+            &self,
+            request: tonic::Request<super::MetricsRequest>,
+        ) -> Result<tonic::Response<super::MetricsResponse>, tonic::Status>;
+        /// Server streaming response type for the TailLogs method.
+        type TailLogsStream: futures_core::Stream<
+                Item = Result<super::LogLine, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// TailLogs streams recent log lines of the server followed by live ones.
+        /// Requires admin credentials.
+        async fn tail_logs(
+            &self,
+            request: tonic::Request<super::TailLogsRequest>,
+        ) -> Result<tonic::Response<Self::TailLogsStream>, tonic::Status>;
+        /// GetAuditLog retrieves the most recent changes made to Items, oldest
+        /// first, including those made by batches, snapshots and expiry. Requires
+        /// admin credentials.
+        async fn get_audit_log(
+            &self,
+            request: tonic::Request<super::AuditLogRequest>,
+        ) -> Result<tonic::Response<super::AuditLogResponse>, tonic::Status>;
+        /// GetAccessStats retrieves the Items which were fetched the most, either
+        /// by Get or by opening a Watch.
+        async fn get_access_stats(
+            &self,
+            request: tonic::Request<super::AccessStatsRequest>,
+        ) -> Result<tonic::Response<super::AccessStatsResponse>, tonic::Status>;
+        /// GroupByPriceBucket counts the Items, and their total value, in each of
+        /// the price ranges between the requested boundaries.
+        async fn group_by_price_bucket(
+            &self,
+            request: tonic::Request<super::PriceBucketsRequest>,
+        ) -> Result<tonic::Response<super::PriceBucketsResponse>, tonic::Status>;
+        /// CheckConsistency verifies that the structures kept alongside the Items
+        /// agree with them, optionally repairing those which don't. Requires admin
+        /// credentials.
+        async fn check_consistency(
+            &self,
+            request: tonic::Request<super::ConsistencyRequest>,
+        ) -> Result<tonic::Response<super::ConsistencyReport>, tonic::Status>;
+        /// LoadSnapshot replaces the entire inventory with the provided Items, or
+        /// changes nothing if any of them is invalid. Requires admin credentials.
+        async fn load_snapshot(
+            &self,
+            request: tonic::Request<super::LoadSnapshotRequest>,
+        ) -> Result<tonic::Response<super::LoadSnapshotResponse>, tonic::Status>;
+        /// ListByAttribute retrieves the Items with an attribute of the given
+        /// value, in ascending SKU order.
+        async fn list_by_attribute(
+            &self,
+            request: tonic::Request<super::AttributeFilter>,
+        ) -> Result<tonic::Response<super::ListByAttributeResponse>, tonic::Status>;
+        /// ListByTag retrieves the Items carrying the given tag, in ascending SKU
+        /// order.
+        async fn list_by_tag(
+            &self,
+            request: tonic::Request<super::TagFilter>,
+        ) -> Result<tonic::Response<super::ListByTagResponse>, tonic::Status>;
+        /// ListLowStock retrieves the Items whose quantity is at or below their
+        /// reorder threshold, in ascending SKU order. Items without a threshold
+        /// are never listed.
+        async fn list_low_stock(
+            &self,
+            request: tonic::Request<super::LowStockRequest>,
+        ) -> Result<tonic::Response<super::ListLowStockResponse>, tonic::Status>;
+        /// GetLatencySummary retrieves latency percentiles for each method which
+        /// served requests within the recent window.
+        async fn get_latency_summary(
+            &self,
+            request: tonic::Request<super::LatencySummaryRequest>,
+        ) -> Result<tonic::Response<super::LatencySummaryResponse>, tonic::Status>;
+        /// Search retrieves the Items whose name or description contains the
+        /// query, ignoring case, in ascending SKU order. Items without any
+        /// information never match.
+        async fn search(
+            &self,
+            request: tonic::Request<super::SearchRequest>,
+        ) -> Result<tonic::Response<super::SearchResponse>, tonic::Status>;
+        /// InventoryStats reports totals across the whole inventory, taken at a
+        /// single moment: the number of Items, their units in stock and the total
+        /// value of those units. Items priced in different currencies are valued
+        /// in the base currency using the server's exchange rates, and the stats
+        /// fail with FAILED_PRECONDITION when a currency has no rate.
+        async fn inventory_stats(
+            &self,
+            request: tonic::Request<super::InventoryStatsRequest>,
+        ) -> Result<tonic::Response<super::InventoryStatsResponse>, tonic::Status>;
+        /// Count reports the number of Items in the inventory, without any of
+        /// their data.
+        async fn count(
+            &self,
+            request: tonic::Request<super::CountRequest>,
+        ) -> Result<tonic::Response<super::CountResponse>, tonic::Status>;
+        /// Validate checks an Item as Add would without adding it, failing with
+        /// the same INVALID_ARGUMENT errors Add would. A valid Item is returned as
+        /// Add would store it, e.g. with its price rounded to the cent. Whether an
+        /// Item with the SKU is already in the inventory isn't checked.
+        async fn validate(
+            &self,
+            request: tonic::Request<super::Item>,
+        ) -> Result<tonic::Response<super::InventoryChangeResponse>, tonic::Status>;
+        /// GetServerInfo reports which build of the server is running and how
+        /// long it has been up, e.g. to tell which build is serving behind a load
+        /// balancer.
+        async fn get_server_info(
+            &self,
+            request: tonic::Request<super::ServerInfoRequest>,
+        ) -> Result<tonic::Response<super::ServerInfo>, tonic::Status>;
+        /// ListByWeightRange retrieves the Items weighing between the given
+        /// bounds (both inclusive), in ascending SKU order, e.g. for slotting them
+        /// in a warehouse. Items without physical attributes are never listed.
+        async fn list_by_weight_range(
+            &self,
+            request: tonic::Request<super::WeightRangeRequest>,
+        ) -> Result<tonic::Response<super::ListByWeightRangeResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct InventoryServer<T: Inventory> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Inventory> InventoryServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for InventoryServer<T>
+    where
+        T: Inventory,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/store.Inventory/Add" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::Item>
+                    for AddSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Item>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).add(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/BatchAdd" => {
+                    #[allow(non_camel_case_types)]
+                    struct BatchAddSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::ClientStreamingService<super::Item>
+                    for BatchAddSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<tonic::Streaming<super::Item>>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).batch_add(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BatchAddSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Upsert" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpsertSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::Item>
+                    for UpsertSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Item>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).upsert(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpsertSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/ApplyBatch" => {
+                    #[allow(non_camel_case_types)]
+                    struct ApplyBatchSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::ApplyBatchRequest>
+                    for ApplyBatchSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ApplyBatchRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).apply_batch(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ApplyBatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Remove" => {
+                    #[allow(non_camel_case_types)]
+                    struct RemoveSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::RemoveRequest>
+                    for RemoveSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RemoveRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).remove(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RemoveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/BatchRemove" => {
+                    #[allow(non_camel_case_types)]
+                    struct BatchRemoveSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::BatchRemoveRequest>
+                    for BatchRemoveSvc<T> {
+                        type Response = super::BatchRemoveResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BatchRemoveRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).batch_remove(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BatchRemoveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Get" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::GetRequest>
+                    for GetSvc<T> {
+                        type Response = super::Item;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/UpdateQuantity" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateQuantitySvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::QuantityChangeRequest>
+                    for UpdateQuantitySvc<T> {
+                        type Response = super::InventoryUpdateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::QuantityChangeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).update_quantity(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateQuantitySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/SetQuantity" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetQuantitySvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::SetQuantityRequest>
+                    for SetQuantitySvc<T> {
+                        type Response = super::InventoryUpdateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetQuantityRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).set_quantity(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetQuantitySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/SetReorderThreshold" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetReorderThresholdSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::ReorderThresholdRequest>
+                    for SetReorderThresholdSvc<T> {
+                        type Response = super::InventoryUpdateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReorderThresholdRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).set_reorder_threshold(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetReorderThresholdSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/UpdatePrice" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdatePriceSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::PriceChangeRequest>
+                    for UpdatePriceSvc<T> {
+                        type Response = super::InventoryUpdateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PriceChangeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).update_price(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdatePriceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/BulkUpdatePrice" => {
+                    #[allow(non_camel_case_types)]
+                    struct BulkUpdatePriceSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::BulkPriceRequest>
+                    for BulkUpdatePriceSvc<T> {
+                        type Response = super::BulkPriceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BulkPriceRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).bulk_update_price(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BulkUpdatePriceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/UpdateInformation" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateInformationSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::InformationChangeRequest>
+                    for UpdateInformationSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InformationChangeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).update_information(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateInformationSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Watch" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::ServerStreamingService<super::WatchRequest>
+                    for WatchSvc<T> {
+                        type Response = super::WatchEvent;
+                        type ResponseStream = T::WatchStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).watch(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/GetAndWatch" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAndWatchSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::ServerStreamingService<super::ItemIdentifier>
+                    for GetAndWatchSvc<T> {
+                        type Response = super::WatchEvent;
+                        type ResponseStream = T::GetAndWatchStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ItemIdentifier>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_and_watch(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAndWatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/WatchAll" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchAllSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::ServerStreamingService<super::WatchAllRequest>
+                    for WatchAllSvc<T> {
+                        type Response = super::ItemEvent;
+                        type ResponseStream = T::WatchAllStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchAllRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).watch_all(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchAllSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/ListChangedSince" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListChangedSinceSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::ChangedSinceRequest>
+                    for ListChangedSinceSvc<T> {
+                        type Response = super::ChangedSinceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ChangedSinceRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).list_changed_since(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListChangedSinceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/UploadImage" => {
+                    #[allow(non_camel_case_types)]
+                    struct UploadImageSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::ClientStreamingService<super::ImageChunk>
+                    for UploadImageSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<tonic::Streaming<super::ImageChunk>>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).upload_image(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UploadImageSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/GetImage" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetImageSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::ServerStreamingService<super::ItemIdentifier>
+                    for GetImageSvc<T> {
+                        type Response = super::ImageChunk;
+                        type ResponseStream = T::GetImageStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ItemIdentifier>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_image(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetImageSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/GetMany" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetManySvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::GetManyRequest>
+                    for GetManySvc<T> {
+                        type Response = super::GetManyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetManyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_many(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetManySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/BatchGet" => {
+                    #[allow(non_camel_case_types)]
+                    struct BatchGetSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::BatchGetRequest>
+                    for BatchGetSvc<T> {
+                        type Response = super::BatchGetResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BatchGetRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).batch_get(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BatchGetSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/SwapStock" => {
+                    #[allow(non_camel_case_types)]
+                    struct SwapStockSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::SwapStockRequest>
+                    for SwapStockSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SwapStockRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).swap_stock(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SwapStockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/RenameSku" => {
+                    #[allow(non_camel_case_types)]
+                    struct RenameSkuSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::RenameSkuRequest>
+                    for RenameSkuSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RenameSkuRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).rename_sku(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RenameSkuSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Reserve" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReserveSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::ReservationRequest>
+                    for ReserveSvc<T> {
+                        type Response = super::Availability;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReservationRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).reserve(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReserveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Release" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReleaseSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::ReservationRequest>
+                    for ReleaseSvc<T> {
+                        type Response = super::Availability;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReservationRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).release(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReleaseSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/SetReadOnly" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetReadOnlySvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::ReadOnlyRequest>
+                    for SetReadOnlySvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReadOnlyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).set_read_only(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetReadOnlySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/List" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::ListRequest>
+                    for ListSvc<T> {
+                        type Response = super::ListResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).list(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Sample" => {
+                    #[allow(non_camel_case_types)]
+                    struct SampleSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::SampleRequest>
+                    for SampleSvc<T> {
+                        type Response = super::SampleResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SampleRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).sample(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SampleSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/GetMetrics" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetMetricsSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::MetricsRequest>
+                    for GetMetricsSvc<T> {
+                        type Response = super::MetricsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MetricsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_metrics(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetMetricsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/TailLogs" => {
+                    #[allow(non_camel_case_types)]
+                    struct TailLogsSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::ServerStreamingService<super::TailLogsRequest>
+                    for TailLogsSvc<T> {
+                        type Response = super::LogLine;
+                        type ResponseStream = T::TailLogsStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TailLogsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).tail_logs(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TailLogsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/GetAuditLog" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAuditLogSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::AuditLogRequest>
+                    for GetAuditLogSvc<T> {
+                        type Response = super::AuditLogResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AuditLogRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_audit_log(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAuditLogSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/GetAccessStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAccessStatsSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::AccessStatsRequest>
+                    for GetAccessStatsSvc<T> {
+                        type Response = super::AccessStatsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AccessStatsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_access_stats(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAccessStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/GroupByPriceBucket" => {
+                    #[allow(non_camel_case_types)]
+                    struct GroupByPriceBucketSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::PriceBucketsRequest>
+                    for GroupByPriceBucketSvc<T> {
+                        type Response = super::PriceBucketsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PriceBucketsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).group_by_price_bucket(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GroupByPriceBucketSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/CheckConsistency" => {
                     #[allow(non_camel_case_types)]
-                    struct AddSvc<T: Inventory>(pub Arc<T>);
-                    impl<T: Inventory> tonic::server::UnaryService<super::Item>
-                    for AddSvc<T> {
-                        type Response = super::InventoryChangeResponse;
+                    struct CheckConsistencySvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::ConsistencyRequest>
+                    for CheckConsistencySvc<T> {
+                        type Response = super::ConsistencyReport;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Item>,
+                            request: tonic::Request<super::ConsistencyRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).add(request).await };
+                            let fut = async move {
+                                (*inner).check_consistency(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -373,7 +3813,7 @@ pub mod inventory_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = AddSvc(inner);
+                        let method = CheckConsistencySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -385,22 +3825,26 @@ pub mod inventory_server {
                     };
                     Box::pin(fut)
                 }
-                "/store.Inventory/Remove" => {
+                "/store.Inventory/LoadSnapshot" => {
                     #[allow(non_camel_case_types)]
-                    struct RemoveSvc<T: Inventory>(pub Arc<T>);
-                    impl<T: Inventory> tonic::server::UnaryService<super::ItemIdentifier>
-                    for RemoveSvc<T> {
-                        type Response = super::InventoryChangeResponse;
+                    struct LoadSnapshotSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::LoadSnapshotRequest>
+                    for LoadSnapshotSvc<T> {
+                        type Response = super::LoadSnapshotResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ItemIdentifier>,
+                            request: tonic::Request<super::LoadSnapshotRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).remove(request).await };
+                            let fut = async move {
+                                (*inner).load_snapshot(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -409,7 +3853,7 @@ pub mod inventory_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = RemoveSvc(inner);
+                        let method = LoadSnapshotSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -421,22 +3865,26 @@ pub mod inventory_server {
                     };
                     Box::pin(fut)
                 }
-                "/store.Inventory/Get" => {
+                "/store.Inventory/ListByAttribute" => {
                     #[allow(non_camel_case_types)]
-                    struct GetSvc<T: Inventory>(pub Arc<T>);
-                    impl<T: Inventory> tonic::server::UnaryService<super::ItemIdentifier>
-                    for GetSvc<T> {
-                        type Response = super::Item;
+                    struct ListByAttributeSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::AttributeFilter>
+                    for ListByAttributeSvc<T> {
+                        type Response = super::ListByAttributeResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ItemIdentifier>,
+                            request: tonic::Request<super::AttributeFilter>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).get(request).await };
+                            let fut = async move {
+                                (*inner).list_by_attribute(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -445,7 +3893,7 @@ pub mod inventory_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetSvc(inner);
+                        let method = ListByAttributeSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -457,25 +3905,61 @@ pub mod inventory_server {
                     };
                     Box::pin(fut)
                 }
-                "/store.Inventory/UpdateQuantity" => {
+                "/store.Inventory/ListByTag" => {
                     #[allow(non_camel_case_types)]
-                    struct UpdateQuantitySvc<T: Inventory>(pub Arc<T>);
+                    struct ListByTagSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::TagFilter>
+                    for ListByTagSvc<T> {
+                        type Response = super::ListByTagResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TagFilter>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).list_by_tag(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListByTagSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/ListLowStock" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListLowStockSvc<T: Inventory>(pub Arc<T>);
                     impl<
                         T: Inventory,
-                    > tonic::server::UnaryService<super::QuantityChangeRequest>
-                    for UpdateQuantitySvc<T> {
-                        type Response = super::InventoryUpdateResponse;
+                    > tonic::server::UnaryService<super::LowStockRequest>
+                    for ListLowStockSvc<T> {
+                        type Response = super::ListLowStockResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::QuantityChangeRequest>,
+                            request: tonic::Request<super::LowStockRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).update_quantity(request).await
+                                (*inner).list_low_stock(request).await
                             };
                             Box::pin(fut)
                         }
@@ -485,7 +3969,7 @@ pub mod inventory_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = UpdateQuantitySvc(inner);
+                        let method = ListLowStockSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -497,25 +3981,25 @@ pub mod inventory_server {
                     };
                     Box::pin(fut)
                 }
-                "/store.Inventory/UpdatePrice" => {
+                "/store.Inventory/GetLatencySummary" => {
                     #[allow(non_camel_case_types)]
-                    struct UpdatePriceSvc<T: Inventory>(pub Arc<T>);
+                    struct GetLatencySummarySvc<T: Inventory>(pub Arc<T>);
                     impl<
                         T: Inventory,
-                    > tonic::server::UnaryService<super::PriceChangeRequest>
-                    for UpdatePriceSvc<T> {
-                        type Response = super::InventoryUpdateResponse;
+                    > tonic::server::UnaryService<super::LatencySummaryRequest>
+                    for GetLatencySummarySvc<T> {
+                        type Response = super::LatencySummaryResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PriceChangeRequest>,
+                            request: tonic::Request<super::LatencySummaryRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).update_price(request).await
+                                (*inner).get_latency_summary(request).await
                             };
                             Box::pin(fut)
                         }
@@ -525,7 +4009,7 @@ pub mod inventory_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = UpdatePriceSvc(inner);
+                        let method = GetLatencySummarySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -537,25 +4021,62 @@ pub mod inventory_server {
                     };
                     Box::pin(fut)
                 }
-                "/store.Inventory/Watch" => {
+                "/store.Inventory/Search" => {
                     #[allow(non_camel_case_types)]
-                    struct WatchSvc<T: Inventory>(pub Arc<T>);
+                    struct SearchSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::SearchRequest>
+                    for SearchSvc<T> {
+                        type Response = super::SearchResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SearchRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).search(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SearchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/InventoryStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct InventoryStatsSvc<T: Inventory>(pub Arc<T>);
                     impl<
                         T: Inventory,
-                    > tonic::server::ServerStreamingService<super::ItemIdentifier>
-                    for WatchSvc<T> {
-                        type Response = super::Item;
-                        type ResponseStream = T::WatchStream;
+                    > tonic::server::UnaryService<super::InventoryStatsRequest>
+                    for InventoryStatsSvc<T> {
+                        type Response = super::InventoryStatsResponse;
                         type Future = BoxFuture<
-                            tonic::Response<Self::ResponseStream>,
+                            tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ItemIdentifier>,
+                            request: tonic::Request<super::InventoryStatsRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).watch(request).await };
+                            let fut = async move {
+                                (*inner).inventory_stats(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -564,14 +4085,166 @@ pub mod inventory_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = WatchSvc(inner);
+                        let method = InventoryStatsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
                                 send_compression_encodings,
                             );
-                        let res = grpc.server_streaming(method, req).await;
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Count" => {
+                    #[allow(non_camel_case_types)]
+                    struct CountSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::CountRequest>
+                    for CountSvc<T> {
+                        type Response = super::CountResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CountRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).count(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CountSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/Validate" => {
+                    #[allow(non_camel_case_types)]
+                    struct ValidateSvc<T: Inventory>(pub Arc<T>);
+                    impl<T: Inventory> tonic::server::UnaryService<super::Item>
+                    for ValidateSvc<T> {
+                        type Response = super::InventoryChangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Item>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).validate(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ValidateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/GetServerInfo" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetServerInfoSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::ServerInfoRequest>
+                    for GetServerInfoSvc<T> {
+                        type Response = super::ServerInfo;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ServerInfoRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_server_info(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetServerInfoSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Inventory/ListByWeightRange" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListByWeightRangeSvc<T: Inventory>(pub Arc<T>);
+                    impl<
+                        T: Inventory,
+                    > tonic::server::UnaryService<super::WeightRangeRequest>
+                    for ListByWeightRangeSvc<T> {
+                        type Response = super::ListByWeightRangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WeightRangeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).list_by_weight_range(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListByWeightRangeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
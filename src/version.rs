@@ -0,0 +1,19 @@
+// -----------------------------------------------------------------------------
+// Build Information
+// -----------------------------------------------------------------------------
+
+/// The version of the server's crate.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The git commit the server was built from, if it was built from a git
+/// checkout.
+pub const GIT_SHA: Option<&str> = option_env!("STORE_GIT_SHA");
+
+// when the server was built, in unix epoch milliseconds, as the build script
+// recorded it.
+const BUILT_AT: &str = env!("STORE_BUILT_AT");
+
+/// Reports when the server was built, in unix epoch milliseconds.
+pub fn built_at() -> u64 {
+    BUILT_AT.parse().unwrap_or_default()
+}